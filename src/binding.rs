@@ -0,0 +1,277 @@
+use error::Error;
+use program::{Argument, Parameter};
+
+// how a single parameter receives its value at a particular call site.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Binding {
+    Argument(usize), // index into the call's argument list
+    Default,
+    Star(Vec<usize>),       // surplus positional arguments
+    DoubleStar(Vec<usize>), // surplus keyword arguments
+}
+
+// matches the arguments of a call to `name` on `line` against its
+// parameters, the way python 2.5 does when a function is entered. returns
+// one binding per parameter, in the order the parameters were declared. the
+// error messages are the ones cpython raises as TypeErrors.
+pub fn bind(
+    name: &str,
+    params: &[Parameter],
+    args: &[Argument],
+    line: usize,
+) -> Result<Vec<Binding>, Error> {
+    let named: Vec<&Parameter> = params
+        .iter()
+        .filter(|param| matches!(param, Parameter::Positional(_) | Parameter::Default(..)))
+        .collect();
+    let defaults = named
+        .iter()
+        .filter(|param| matches!(param, Parameter::Default(..)))
+        .count();
+    let star = params
+        .iter()
+        .any(|param| matches!(param, Parameter::Star(_)));
+    let double_star = params
+        .iter()
+        .any(|param| matches!(param, Parameter::DoubleStar(_)));
+
+    let mut positional = Vec::new();
+    let mut keywords = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        match arg {
+            Argument::Positional(_) => positional.push(i),
+            Argument::Keyword(keyword, _) => keywords.push((i, keyword)),
+        }
+    }
+
+    if positional.len() > named.len() && !star {
+        let qualifier = if defaults > 0 { "at most" } else { "exactly" };
+        return Err(arity_error(
+            name,
+            qualifier,
+            named.len(),
+            !keywords.is_empty(),
+            positional.len(),
+            line,
+        ));
+    }
+
+    let mut bindings: Vec<Option<Binding>> = vec![None; named.len()];
+    for (slot, &i) in bindings.iter_mut().zip(positional.iter()) {
+        *slot = Some(Binding::Argument(i));
+    }
+    let surplus = positional.iter().skip(named.len()).cloned().collect();
+
+    let mut surplus_keywords = Vec::new();
+    for &(i, keyword) in keywords.iter() {
+        match named.iter().position(|param| param.name() == keyword) {
+            Some(slot) => {
+                if bindings[slot].is_some() {
+                    return Err(Error::TypeError {
+                        message: format!(
+                            "{}() got multiple values for keyword argument '{}'",
+                            name, keyword
+                        ),
+                        line,
+                    });
+                }
+                bindings[slot] = Some(Binding::Argument(i));
+            }
+            None if double_star => surplus_keywords.push(i),
+            None => {
+                return Err(Error::TypeError {
+                    message: format!(
+                        "{}() got an unexpected keyword argument '{}'",
+                        name, keyword
+                    ),
+                    line,
+                })
+            }
+        }
+    }
+
+    let required = named.len() - defaults;
+    for (slot, binding) in bindings
+        .iter()
+        .enumerate()
+        .take(required)
+        .skip(positional.len())
+    {
+        if binding.is_none() {
+            let qualifier = if star || defaults > 0 {
+                "at least"
+            } else {
+                "exactly"
+            };
+            return Err(arity_error(
+                name,
+                qualifier,
+                required,
+                !keywords.is_empty(),
+                slot,
+                line,
+            ));
+        }
+    }
+
+    let mut bindings: Vec<Binding> = bindings
+        .into_iter()
+        .map(|binding| binding.unwrap_or(Binding::Default))
+        .collect();
+    if star {
+        bindings.push(Binding::Star(surplus));
+    }
+    if double_star {
+        bindings.push(Binding::DoubleStar(surplus_keywords));
+    }
+    Ok(bindings)
+}
+
+fn arity_error(
+    name: &str,
+    qualifier: &str,
+    expected: usize,
+    keywords: bool,
+    given: usize,
+    line: usize,
+) -> Error {
+    Error::TypeError {
+        message: format!(
+            "{}() takes {} {} {}argument{} ({} given)",
+            name,
+            qualifier,
+            expected,
+            if keywords { "non-keyword " } else { "" },
+            if expected == 1 { "" } else { "s" },
+            given
+        ),
+        line,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use program::*;
+    use testing::*;
+
+    // binds the call in `call` against the first def in `text`.
+    fn bind_call(text: &str, call: &str) -> Result<Vec<Binding>, Error> {
+        let program = parse(&format!("{}\nprint {}", text, call)).unwrap();
+        let params = match &program.body.statements[0] {
+            Statement::Def { params, .. } => params.clone(),
+            _ => panic!("expected def"),
+        };
+        match &program.body.statements[1] {
+            Statement::Print { items, .. } => match &items[0] {
+                Expression::Call {
+                    name,
+                    params: args,
+                    line,
+                } => bind(name, &params, args, *line),
+                _ => panic!("expected call"),
+            },
+            _ => panic!("expected call"),
+        }
+    }
+
+    macro_rules! bind_test {
+        (name: $name:ident,def: $def:expr,call: $call:expr,bindings: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let expected = $expected.to_vec();
+                assert_eq!(bind_call($def, $call).unwrap(), expected);
+            }
+        };
+    }
+
+    macro_rules! error_test {
+        (name: $name:ident,def: $def:expr,call: $call:expr,message: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let expected = Error::TypeError {
+                    message: $expected.to_owned(),
+                    line: 3,
+                };
+                assert_eq!(bind_call($def, $call).unwrap_err(), expected);
+            }
+        };
+    }
+
+    bind_test! {
+        name: positional,
+        def: "def f(a, b):\n  return a",
+        call: "f(1, 2)",
+        bindings: [Binding::Argument(0), Binding::Argument(1)],
+    }
+
+    bind_test! {
+        name: default,
+        def: "def f(a, b=2):\n  return a",
+        call: "f(1)",
+        bindings: [Binding::Argument(0), Binding::Default],
+    }
+
+    bind_test! {
+        name: keywords,
+        def: "def f(a, b):\n  return a",
+        call: "f(b=3, a=1)",
+        bindings: [Binding::Argument(1), Binding::Argument(0)],
+    }
+
+    bind_test! {
+        name: star,
+        def: "def f(a, *rest):\n  return a",
+        call: "f(1, 2, 3)",
+        bindings: [Binding::Argument(0), Binding::Star(vec![1, 2])],
+    }
+
+    bind_test! {
+        name: double_star,
+        def: "def f(a, **rest):\n  return a",
+        call: "f(1, b=2, c=3)",
+        bindings: [Binding::Argument(0), Binding::DoubleStar(vec![1, 2])],
+    }
+
+    error_test! {
+        name: too_few,
+        def: "def f(a, b):\n  return a",
+        call: "f(1)",
+        message: "f() takes exactly 2 arguments (1 given)",
+    }
+
+    error_test! {
+        name: too_many,
+        def: "def f(a, b=2):\n  return a",
+        call: "f(1, 2, 3)",
+        message: "f() takes at most 2 arguments (3 given)",
+    }
+
+    error_test! {
+        name: too_few_with_defaults,
+        def: "def f(a, b=2):\n  return a",
+        call: "f()",
+        message: "f() takes at least 1 argument (0 given)",
+    }
+
+    error_test! {
+        name: too_few_non_keyword,
+        def: "def f(a, b):\n  return a",
+        call: "f(b=1)",
+        message: "f() takes exactly 2 non-keyword arguments (0 given)",
+    }
+
+    error_test! {
+        name: multiple_values,
+        def: "def f(a, b):\n  return a",
+        call: "f(1, a=2)",
+        message: "f() got multiple values for keyword argument 'a'",
+    }
+
+    error_test! {
+        name: unexpected_keyword,
+        def: "def f(a):\n  return a",
+        call: "f(1, c=2)",
+        message: "f() got an unexpected keyword argument 'c'",
+    }
+}
@@ -0,0 +1,548 @@
+use binding;
+use classes::Classes;
+use error::Error;
+use exception::Exception;
+use program::*;
+use std::collections::{HashMap, HashSet};
+
+// resolves every call against the functions defined at the top level of the
+// program, so that a call with the wrong arguments is reported against the
+// python source instead of surfacing as a type mismatch in the generated wat.
+// so are calls that make instances of the program's classes, and calls of
+// their methods through the class.
+pub struct Checker<'a> {
+    program: &'a Program,
+    // parameters of each top-level function, by name
+    defs: HashMap<&'a str, &'a [Parameter]>,
+    classes: Classes,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(program: &'a Program) -> Checker<'a> {
+        let mut defs = HashMap::new();
+        for stmt in program.body.statements.iter() {
+            if let Statement::Def { name, params, .. } = stmt {
+                defs.insert(name.as_str(), params.as_slice());
+            }
+        }
+        let classes = Classes::new(program);
+        Checker {
+            program,
+            defs,
+            classes,
+        }
+    }
+
+    pub fn check(&self) -> Result<(), Error> {
+        self.check_bases()?;
+        self.check_body(&self.program.body, true)
+    }
+
+    // a class can only derive from a class that is known before the program
+    // runs.
+    fn check_bases(&self) -> Result<(), Error> {
+        let bases = self
+            .program
+            .body
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Class { name, base, .. } => Some((name, base)),
+                _ => None,
+            });
+        for (class, (name, base)) in self.classes.iter().zip(bases) {
+            if let Some(Expression::Simple(Value::Variable(base, line))) = base {
+                if base != "object" && class.base.is_none() {
+                    return Err(Error::TypeError {
+                        message: format!(
+                            "{} can only derive from object or a class defined before it",
+                            name
+                        ),
+                        line: *line,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // `top` is whether the body is the module's own, where functions can be
+    // defined.
+    fn check_body(&self, body: &Body, top: bool) -> Result<(), Error> {
+        if top {
+            Self::check_redefinitions(body)?;
+        }
+        for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+            self.check_statement(stmt, *line, top)?;
+        }
+        Ok(())
+    }
+
+    fn check_statement(&self, stmt: &Statement, line: usize, top: bool) -> Result<(), Error> {
+        match stmt {
+            Statement::Return(e)
+            | Statement::Assign(_, e)
+            | Statement::Expression(e)
+            | Statement::AugAssign(e)
+            | Statement::Del(e) => self.check_expression(e),
+            Statement::SetItem {
+                object,
+                index,
+                value,
+            } => {
+                self.check_expression(value)?;
+                self.check_expression(object)?;
+                self.check_expression(index)
+            }
+            Statement::Print { .. }
+            | Statement::SetAttr { .. }
+            | Statement::Unpack(..)
+            | Statement::Assert(..)
+            | Statement::Raise(_) => {
+                for e in stmt.expressions() {
+                    self.check_expression(e)?;
+                }
+                Ok(())
+            }
+            Statement::Global(_) | Statement::Import(_) | Statement::Pass => Ok(()),
+            Statement::If {
+                condition,
+                body,
+                elif,
+                else_body,
+            } => {
+                self.check_expression(condition)?;
+                self.check_body(body, false)?;
+                for (condition, body) in elif.iter() {
+                    self.check_expression(condition)?;
+                    self.check_body(body, false)?;
+                }
+                if let Some(body) = else_body {
+                    self.check_body(body, false)?;
+                }
+                Ok(())
+            }
+            Statement::Try { .. } => {
+                for e in stmt.expressions() {
+                    self.check_expression(e)?;
+                }
+                for body in stmt.bodies() {
+                    self.check_body(body, false)?;
+                }
+                Ok(())
+            }
+            Statement::Def { name, params, body } => {
+                // only the functions defined at the top level are compiled.
+                if !top {
+                    return Err(Error::Unsupported {
+                        message: format!(
+                            "defining '{}' anywhere but the top level of the module",
+                            name
+                        ),
+                        line,
+                    });
+                }
+                for param in params.iter() {
+                    if let Parameter::Default(_, default) = param {
+                        self.check_expression(default)?;
+                    }
+                }
+                self.check_body(body, false)
+            }
+            Statement::Class { body, .. } => {
+                Self::check_redefinitions(body)?;
+                Self::check_class_reads(body)?;
+                for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+                    self.check_statement(stmt, *line, top)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn check_expression(&self, expr: &Expression) -> Result<(), Error> {
+        for operand in expr.operands() {
+            self.check_expression(operand)?;
+        }
+        if let Expression::MethodCall {
+            object,
+            name,
+            params,
+        } = expr
+        {
+            if let Expression::Simple(Value::Variable(class, line)) = &**object {
+                let method = self
+                    .classes
+                    .find(class)
+                    .filter(|_| !self.defs.contains_key(class.as_str()))
+                    .and_then(|class| self.classes.resolve(class, name));
+                if let Some((_, method_params)) = method {
+                    binding::bind(name, method_params, params, *line)?;
+                }
+            }
+        }
+        if let Expression::Call { name, params, line } = expr {
+            match self.defs.get(name.as_str()) {
+                Some(def_params) => {
+                    binding::bind(name, def_params, params, *line)?;
+                }
+                None if self.classes.find(name).is_some() => {
+                    self.check_constructor(name, params, *line)?
+                }
+                None if name == "len" => Self::check_builtin(name, params, *line)?,
+                None if name == "isinstance" => Self::check_isinstance(params, *line)?,
+                None if Exception::from_name(name).is_some()
+                    && params
+                        .iter()
+                        .any(|arg| matches!(arg, Argument::Keyword(..))) =>
+                {
+                    return Err(Error::TypeError {
+                        message: format!("{} does not take keyword arguments", name),
+                        line: *line,
+                    });
+                }
+                None => (),
+            }
+        }
+        Ok(())
+    }
+
+    // making an instance calls __init__, if its class has one, with the new
+    // instance as the first argument. without one, there can't be any.
+    fn check_constructor(&self, name: &str, args: &[Argument], line: usize) -> Result<(), Error> {
+        let class = self.classes.find(name).unwrap();
+        if let Some((_, params)) = self.classes.resolve(class, "__init__") {
+            let mut args = args.to_vec();
+            args.insert(
+                0,
+                Argument::Positional(Expression::Simple(Value::Integer(0))),
+            );
+            binding::bind("__init__", params, &args, line)?;
+        } else if !args.is_empty() {
+            let message = if self.classes.get(class).new_style {
+                "object() takes no parameters"
+            } else {
+                "this constructor takes no arguments"
+            };
+            return Err(Error::TypeError {
+                message: message.to_owned(),
+                line,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_isinstance(args: &[Argument], line: usize) -> Result<(), Error> {
+        let message = if args.iter().any(|arg| matches!(arg, Argument::Keyword(..))) {
+            "isinstance() takes no keyword arguments".to_owned()
+        } else if args.len() != 2 {
+            format!("isinstance expected 2 arguments, got {}", args.len())
+        } else {
+            return Ok(());
+        };
+        Err(Error::TypeError { message, line })
+    }
+
+    // each def is compiled to a function of its own, so a body can only
+    // define a name once.
+    fn check_redefinitions(body: &Body) -> Result<(), Error> {
+        let mut defined = HashSet::new();
+        for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+            if let Statement::Def { name, .. } = stmt {
+                if !defined.insert(name) {
+                    return Err(Error::Unsupported {
+                        message: format!("defining '{}' more than once", name),
+                        line: *line,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // once a class's body has set an attribute, the body reads it from the
+    // class, where neither it nor a method can be called, and a method is
+    // no value.
+    fn check_class_reads(body: &Body) -> Result<(), Error> {
+        fn check(
+            expr: &Expression,
+            attributes: &HashSet<&str>,
+            methods: &HashSet<&str>,
+        ) -> Result<(), Error> {
+            for operand in expr.operands() {
+                check(operand, attributes, methods)?;
+            }
+            match expr {
+                Expression::Call { name, line, .. }
+                    if attributes.contains(name.as_str()) || methods.contains(name.as_str()) =>
+                {
+                    Err(Error::Unsupported {
+                        message: format!("calling '{}' in the body of its class", name),
+                        line: *line,
+                    })
+                }
+                Expression::Simple(Value::Variable(name, line))
+                    if methods.contains(name.as_str()) =>
+                {
+                    Err(Error::Unsupported {
+                        message: format!("using the method '{}' as a value", name),
+                        line: *line,
+                    })
+                }
+                _ => Ok(()),
+            }
+        }
+        let mut attributes = HashSet::new();
+        let mut methods = HashSet::new();
+        for stmt in body.statements.iter() {
+            for e in stmt.expressions() {
+                check(e, &attributes, &methods)?;
+            }
+            match stmt {
+                Statement::Assign(attribute, _) => {
+                    attributes.insert(attribute.as_str());
+                }
+                Statement::Def { name, .. } => {
+                    methods.insert(name.as_str());
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    // the builtin functions take a single positional argument, and word
+    // their errors the way python's own builtins do.
+    fn check_builtin(name: &str, args: &[Argument], line: usize) -> Result<(), Error> {
+        let message = if args.iter().any(|arg| matches!(arg, Argument::Keyword(..))) {
+            format!("{}() takes no keyword arguments", name)
+        } else if args.len() != 1 {
+            format!(
+                "{}() takes exactly one argument ({} given)",
+                name,
+                args.len()
+            )
+        } else {
+            return Ok(());
+        };
+        Err(Error::TypeError { message, line })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing::*;
+
+    macro_rules! check_test {
+        (name: $name:ident,text: $text:expr,) => {
+            #[test]
+            fn $name() {
+                let program = parse($text).unwrap();
+                Checker::new(&program).check().unwrap();
+            }
+        };
+    }
+
+    macro_rules! error_test {
+        (name: $name:ident,text: $text:expr,error: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let program = parse($text).unwrap();
+                let error = Checker::new(&program).check().unwrap_err();
+                assert_eq!(error, $expected);
+            }
+        };
+    }
+
+    check_test! {
+        name: matching_call,
+        text: "def f(a, b):\n  return a + b\nprint f(1, 2)",
+    }
+
+    check_test! {
+        name: unknown_function,
+        text: "print f(1, 2)",
+    }
+
+    error_test! {
+        name: too_few_arguments,
+        text: "def f(a, b):\n  return a + b\nprint f(1)",
+        error: Error::TypeError {
+            message: "f() takes exactly 2 arguments (1 given)".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: call_before_def,
+        text: "print 1\nprint f(1, 2, 3)\ndef f(a, b):\n  return a + b",
+        error: Error::TypeError {
+            message: "f() takes exactly 2 arguments (3 given)".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: call_in_function_body,
+        text: "def g(n):\n  if n < 1:\n    return 0\n  else:\n    return f(n)\n\
+               def f(a, b):\n  return a",
+        error: Error::TypeError {
+            message: "f() takes exactly 2 arguments (1 given)".to_owned(),
+            line: 5,
+        },
+    }
+
+    error_test! {
+        name: call_in_argument,
+        text: "def f(a):\n  return a\nprint f(f())",
+        error: Error::TypeError {
+            message: "f() takes exactly 1 argument (0 given)".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: call_in_default,
+        text: "def f(a):\n  return a\ndef g(b=f(1, 2)):\n  return b",
+        error: Error::TypeError {
+            message: "f() takes exactly 1 argument (2 given)".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: len_arity,
+        text: "xs = [1]\nprint len(xs, xs)",
+        error: Error::TypeError {
+            message: "len() takes exactly one argument (2 given)".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: len_keyword,
+        text: "print len(x=[])",
+        error: Error::TypeError {
+            message: "len() takes no keyword arguments".to_owned(),
+            line: 1,
+        },
+    }
+
+    error_test! {
+        name: call_in_handler,
+        text: "def f(a):\n  return a\ntry: pass\nexcept KeyError: raise ValueError(x=f(1, 2))",
+        error: Error::TypeError {
+            message: "f() takes exactly 1 argument (2 given)".to_owned(),
+            line: 4,
+        },
+    }
+
+    error_test! {
+        name: exception_keyword_argument,
+        text: "raise ValueError(message='m')",
+        error: Error::TypeError {
+            message: "ValueError does not take keyword arguments".to_owned(),
+            line: 1,
+        },
+    }
+
+    error_test! {
+        name: constructor_arity,
+        text: "class A:\n  def __init__(self, x):\n    self.x = x\nprint A()",
+        error: Error::TypeError {
+            message: "__init__() takes exactly 2 arguments (1 given)".to_owned(),
+            line: 4,
+        },
+    }
+
+    error_test! {
+        name: constructor_without_init,
+        text: "class A(object): pass\nclass B(A): pass\nb = B(1)",
+        error: Error::TypeError {
+            message: "object() takes no parameters".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: method_through_class,
+        text: "class A:\n  def f(self, x): pass\nclass B(A): pass\nB.f(B(), 1, 2)",
+        error: Error::TypeError {
+            message: "f() takes exactly 2 arguments (3 given)".to_owned(),
+            line: 4,
+        },
+    }
+
+    error_test! {
+        name: base_defined_later,
+        text: "class A(B): pass\nclass B: pass",
+        error: Error::TypeError {
+            message: "A can only derive from object or a class defined before it".to_owned(),
+            line: 1,
+        },
+    }
+
+    error_test! {
+        name: isinstance_arity,
+        text: "class A: pass\nprint isinstance(A())",
+        error: Error::TypeError {
+            message: "isinstance expected 2 arguments, got 1".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: nested_def,
+        text: "def f():\n  x = 1\n  def g():\n    return x\n  return g()",
+        error: Error::Unsupported {
+            message: "defining 'g' anywhere but the top level of the module".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: def_in_block,
+        text: "if 1:\n  def f():\n    return 1\nprint f()",
+        error: Error::Unsupported {
+            message: "defining 'f' anywhere but the top level of the module".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: def_repeated,
+        text: "def f(a):\n  return a\nprint f(1)\ndef f():\n  return 2\nprint f()",
+        error: Error::Unsupported {
+            message: "defining 'f' more than once".to_owned(),
+            line: 4,
+        },
+    }
+
+    error_test! {
+        name: method_repeated,
+        text: "class A:\n  def m(self):\n    return 1\n  def m(self):\n    return 2",
+        error: Error::Unsupported {
+            message: "defining 'm' more than once".to_owned(),
+            line: 4,
+        },
+    }
+
+    error_test! {
+        name: class_attribute_called,
+        text: "def f():\n  return 1\nclass A:\n  f = 2\n  g = f()",
+        error: Error::Unsupported {
+            message: "calling 'f' in the body of its class".to_owned(),
+            line: 5,
+        },
+    }
+
+    error_test! {
+        name: method_read_in_class,
+        text: "class A:\n  def m(self):\n    return 1\n  n = m",
+        error: Error::Unsupported {
+            message: "using the method 'm' as a value".to_owned(),
+            line: 4,
+        },
+    }
+}
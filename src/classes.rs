@@ -0,0 +1,146 @@
+use program::*;
+
+// a class defined at the top level of the module. the runtime tells classes
+// apart by their numbers, which count them in the order they are defined.
+#[derive(Debug, PartialEq)]
+pub struct Class {
+    pub name: String,
+    // the number of the class it derives from, unless that is object or
+    // nothing
+    pub base: Option<usize>,
+    // whether it derives from object, directly or through its base, which
+    // changes how it and its instances are written out
+    pub new_style: bool,
+    // the methods its body defines, with their parameters
+    methods: Vec<(String, Vec<Parameter>)>,
+}
+
+// every class the module defines. a class's methods are known before the
+// program runs, so a method call only has to find out which class its
+// object is an instance of.
+#[derive(Debug, PartialEq)]
+pub struct Classes {
+    classes: Vec<Class>,
+}
+
+impl Classes {
+    pub fn new(program: &Program) -> Classes {
+        let mut classes: Vec<Class> = vec![];
+        for stmt in program.body.statements.iter() {
+            if let Statement::Class { name, base, body } = stmt {
+                let (base, new_style) = match base {
+                    Some(Expression::Simple(Value::Variable(base, _))) if base == "object" => {
+                        (None, true)
+                    }
+                    Some(Expression::Simple(Value::Variable(base, _))) => {
+                        match classes.iter().rposition(|class| class.name == *base) {
+                            Some(i) => (Some(i), classes[i].new_style),
+                            None => (None, false),
+                        }
+                    }
+                    _ => (None, false),
+                };
+                let methods = body
+                    .statements
+                    .iter()
+                    .filter_map(|stmt| match stmt {
+                        Statement::Def { name, params, .. } => Some((name.clone(), params.clone())),
+                        _ => None,
+                    })
+                    .collect();
+                classes.push(Class {
+                    name: name.clone(),
+                    base,
+                    new_style,
+                    methods,
+                });
+            }
+        }
+        Classes { classes }
+    }
+
+    pub fn get(&self, class: usize) -> &Class {
+        &self.classes[class]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Class> {
+        self.classes.iter()
+    }
+
+    // the number of the last class defined with `name`.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.classes.iter().rposition(|class| class.name == name)
+    }
+
+    // the method `name` of the instances of `class`, from the class itself
+    // or the nearest class it derives from that defines it, as the name of
+    // its function and its parameters.
+    pub fn resolve(&self, class: usize, name: &str) -> Option<(String, &[Parameter])> {
+        let mut class = Some(class);
+        while let Some(i) = class {
+            let c = &self.classes[i];
+            if let Some((_, params)) = c.methods.iter().find(|(method, _)| method == name) {
+                return Some((format!("{}.{}", c.name, name), params));
+            }
+            class = c.base;
+        }
+        None
+    }
+
+    // the first of the methods `names` that the instances of `class` have.
+    pub fn resolve_first(&self, class: usize, names: &[&str]) -> Option<(String, &[Parameter])> {
+        names.iter().find_map(|name| self.resolve(class, name))
+    }
+
+    // the functions a call of the method `name` can end up in, each with
+    // the numbers of the classes whose instances it is called for.
+    pub fn dispatch(&self, name: &str) -> Vec<(String, Vec<usize>)> {
+        self.dispatch_first(&[name])
+    }
+
+    // the same for the first of `names` that each class has a method for.
+    pub fn dispatch_first(&self, names: &[&str]) -> Vec<(String, Vec<usize>)> {
+        let mut targets: Vec<(String, Vec<usize>)> = vec![];
+        for class in 0..self.classes.len() {
+            if let Some((function, _)) = self.resolve_first(class, names) {
+                match targets.iter_mut().find(|(f, _)| *f == function) {
+                    Some((_, classes)) => classes.push(class),
+                    None => targets.push((function, vec![class])),
+                }
+            }
+        }
+        targets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing::*;
+
+    #[test]
+    fn method_resolution() {
+        let program = parse(
+            "class A:\n  def f(self): pass\n  def g(self): pass\n\
+             class B(A):\n  def g(self, x): pass\nclass C(object): pass",
+        )
+        .unwrap();
+        let classes = Classes::new(&program);
+        assert_eq!(classes.find("B"), Some(1));
+        assert_eq!(classes.get(1).base, Some(0));
+        assert!(!classes.get(1).new_style && classes.get(2).new_style);
+        assert_eq!(classes.resolve(1, "f").unwrap().0, "A.f");
+        assert_eq!(classes.resolve(1, "g").unwrap().1.len(), 2);
+        assert_eq!(classes.resolve(2, "f"), None);
+        assert_eq!(
+            classes.dispatch("g"),
+            vec![("A.g".to_owned(), vec![0]), ("B.g".to_owned(), vec![1])]
+        );
+        assert_eq!(classes.dispatch("f"), vec![("A.f".to_owned(), vec![0, 1])]);
+        assert_eq!(
+            classes.dispatch_first(&["g", "f"]),
+            vec![("A.g".to_owned(), vec![0]), ("B.g".to_owned(), vec![1])]
+        );
+        assert_eq!(classes.resolve_first(2, &["g", "f"]), None);
+    }
+}
@@ -1,9 +1,81 @@
+use binding::{self, Binding};
+use classes::Classes;
+use error::Error;
+use exception::{Exception, Handling};
 use program::*;
+use runtime;
+use scope::{Scope, SymbolTable};
+use std::collections::{HashMap, HashSet};
+use types::{self, Type, TypeTable};
+use unbound::{self, Unbound};
 use wexp::Wexp::{self, *};
 
 pub struct CodeGenerator {
     program: Program,
-    // functions, etc.
+    // parameters of each top-level function, by name
+    defs: HashMap<String, Vec<Parameter>>,
+    symbols: SymbolTable,
+    types: TypeTable,
+    // the variables that can be read before they are assigned
+    unbound: Unbound,
+    // the function being generated, or None for the module itself
+    function: Option<String>,
+    // functions whose def is sure to have run by the current point
+    defined: HashSet<String>,
+    // functions whose calls check that their def has run
+    checked: HashSet<String>,
+    // the line of the statement being generated, and whether the runtime
+    // has been told it since the last call
+    line: usize,
+    line_set: bool,
+    // scratch locals needed by the function currently being generated
+    temps: Vec<(String, Type)>,
+    // whether the collector can ever run, which it can only do inside a call
+    collects: bool,
+    // the globals that can point to objects, which have fixed slots at the
+    // bottom of the shadow stack
+    statics: Vec<String>,
+    // the variables of the function being generated that can point to
+    // objects, if it keeps them in a frame on the shadow stack
+    frame: Option<Vec<String>>,
+    // whether the statement being generated has put temporaries on the
+    // shadow stack
+    rooted: bool,
+    handling: Handling,
+    // how many blocks have been labelled for try statements so far
+    labels: usize,
+    // how many values the try statements being generated keep on the
+    // shadow stack, above the frame
+    held: usize,
+    // the temporaries holding the exceptions being handled, innermost last
+    handled: Vec<String>,
+    // the try statements with finally clauses that the code being generated
+    // is inside of, innermost last
+    finally: Vec<Finally>,
+    // the temporaries that hold what a function returns while its finally
+    // clauses run
+    returns: Option<Vec<(String, Type)>>,
+    // where the name of each function is kept for tracebacks, and of each
+    // class for writing it out
+    names: HashMap<String, usize>,
+    classes: Classes,
+    // the names of the attributes the program uses, numbered by where they
+    // are in it
+    attributes: Vec<String>,
+    // the runtime helpers that have been replaced by functions that try the
+    // special methods of the program's classes first
+    specials: Vec<String>,
+    // the temp holding the class whose body is being generated, and the
+    // attributes the body has set so far
+    class_body: Option<(String, HashSet<String>)>,
+}
+
+// a try statement whose finally clause has to run before a return leaves
+// the function. the return branches out to the block labelled `label`,
+// setting the `returning` temporary.
+struct Finally {
+    label: String,
+    returning: Option<String>,
 }
 
 macro_rules! wasm {
@@ -17,99 +89,939 @@ macro_rules! wasm {
 }
 
 impl CodeGenerator {
-    pub fn new(program: Program) -> CodeGenerator {
-        CodeGenerator { program }
+    pub fn new(
+        program: Program,
+        symbols: SymbolTable,
+        types: TypeTable,
+        handling: Handling,
+    ) -> CodeGenerator {
+        let mut defs = HashMap::new();
+        for stmt in program.body.statements.iter() {
+            if let Statement::Def { name, params, .. } = stmt {
+                defs.insert(name.clone(), params.clone());
+            }
+        }
+        let classes = Classes::new(&program);
+        let unbound = unbound::analyze(&program, &symbols);
+        CodeGenerator {
+            program,
+            defs,
+            symbols,
+            types,
+            unbound,
+            function: None,
+            defined: HashSet::new(),
+            checked: HashSet::new(),
+            line: 0,
+            line_set: false,
+            temps: Vec::new(),
+            collects: false,
+            statics: Vec::new(),
+            frame: None,
+            rooted: false,
+            handling,
+            labels: 0,
+            held: 0,
+            handled: Vec::new(),
+            finally: Vec::new(),
+            returns: None,
+            names: HashMap::new(),
+            classes,
+            attributes: Vec::new(),
+            specials: Vec::new(),
+            class_body: None,
+        }
     }
 
-    pub fn codegen(mut self) -> Wexp {
-        let print = List(vec![
-            wasm!(func),
-            Atom("$print".to_string()),
-            List(vec![wasm!(import), wasm!("\"host\""), wasm!("\"print\"")]),
-            List(vec![wasm!(param), wasm!(i32)]),
-        ]);
-        let mut module = vec![wasm!(module), print];
-        module.extend(self.codegen_defs(&self.program.body));
+    pub fn codegen(mut self) -> Result<Wexp, Error> {
+        let body = self.program.body.clone();
+        self.collects = body
+            .statements
+            .iter()
+            .any(|stmt| self.statement_collects(stmt));
+        if self.collects {
+            self.statics = self.statics(&body);
+        }
+        // no function can run before the first top-level statement that
+        // calls something, so the defs ahead of it are always in place.
+        self.defined = self.defined_before_first_call(&body);
+        let names = self.codegen_names(&body);
+        let defs = self.codegen_defs(&body)?;
+        // top-level code sees functions defined one def at a time.
+        self.defined.clear();
         let mut main = vec![wasm!(func), List(vec![wasm!(export), wasm!("\"main\"")])];
-        main.extend(self.codegen_body(&self.program.body));
+        let mut main_body = vec![];
+        if !self.statics.is_empty() {
+            main_body.extend(vec![
+                wasm!("i32.const"),
+                Atom(self.statics.len().to_string()),
+                wasm!(call),
+                wasm!("$rt.enter"),
+                wasm!(drop),
+            ]);
+        }
+        let mut statements = vec![];
+        for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+            statements.extend(self.codegen_statement(stmt, *line)?);
+            if let Statement::Def { name, .. } = stmt {
+                self.defined.insert(name.clone());
+            }
+        }
+        // an exception that nothing catches is reported to the host. with
+        // status handling, the linker checks for one after each call.
+        match self.handling {
+            Handling::Status => main_body.extend(statements),
+            Handling::Wasm => {
+                main_body.extend(self.codegen_catching(statements));
+                main_body.extend(vec![
+                    wasm!(get_global),
+                    wasm!("$exception"),
+                    wasm!("i64.const"),
+                    wasm!("0"),
+                    wasm!("i64.ne"),
+                    wasm!("if"),
+                    wasm!(call),
+                    wasm!("$rt.uncaught"),
+                    wasm!("end"),
+                ]);
+            }
+        }
+        if body.statements.iter().any(Self::leaves_line_open) {
+            main_body.extend(vec![wasm!(call), wasm!("$rt.exit")]);
+        }
+        main.extend(self.codegen_temps());
+        main.extend(main_body);
+        // the runtime writes an instance in a container with its class's
+        // __repr__, if it has one.
+        if self.has_special("repr") {
+            self.helper("repr");
+        }
+        let mut specials = vec![];
+        for operation in self.specials.clone().iter() {
+            specials.push(self.codegen_special(operation));
+        }
+        let mut module = vec![wasm!(module)];
+        module.extend(self.codegen_globals(&body));
+        module.extend(self.codegen_name_data(names)?);
+        module.extend(defs);
         module.push(List(main));
-        List(module)
+        module.extend(specials);
+        Ok(List(module))
     }
 
-    pub fn codegen_defs(&self, body: &Body) -> Vec<Wexp> {
-        body.statements
-            .iter()
-            .filter_map(|stmt| {
-                if let Statement::Def { .. } = stmt {
-                    Some(self.codegen_def(stmt))
-                } else {
-                    None
+    // module variables are wasm globals, so that every function can reach
+    // them, along with the flags of the ones that can be read before they
+    // are assigned. default parameter values are evaluated once, when the def
+    // statement runs, and kept in a global until a call needs them. so is a
+    // flag recording that the def has run, if any call needs to check it.
+    pub fn codegen_globals(&self, body: &Body) -> Vec<Wexp> {
+        let mut globals = vec![];
+        for variable in self.symbols.variables().iter() {
+            let t = self.types.variable(variable).unwrap();
+            globals.push(List(vec![
+                wasm!(global),
+                Atom(Self::prepend_dollar(variable)),
+                List(vec![wasm!(mut), wasm!(t.wasm())]),
+                List(Self::zero(t)),
+            ]));
+        }
+        for variable in self.unbound.globals() {
+            globals.push(List(vec![
+                wasm!(global),
+                Atom(Self::defined_flag(variable)),
+                List(vec![wasm!(mut), wasm!(i32)]),
+                List(vec![wasm!("i32.const"), wasm!("0")]),
+            ]));
+        }
+        for Function { name, params, .. } in body.functions() {
+            for param in params.iter() {
+                if let Parameter::Default(param, _) = param {
+                    let t = self.types.child(&name).unwrap().variable(param).unwrap();
+                    globals.push(List(vec![
+                        wasm!(global),
+                        Atom(Self::default_global(&name, param)),
+                        List(vec![wasm!(mut), wasm!(t.wasm())]),
+                        List(Self::zero(t)),
+                    ]));
                 }
-            })
-            .collect()
+            }
+            if self.checked.contains(&name) {
+                globals.push(List(vec![
+                    wasm!(global),
+                    Atom(Self::defined_flag(&name)),
+                    List(vec![wasm!(mut), wasm!(i32)]),
+                    List(vec![wasm!("i32.const"), wasm!("0")]),
+                ]));
+            }
+        }
+        globals
     }
 
-    pub fn codegen_body(&self, body: &Body) -> Vec<Wexp> {
-        body.statements
-            .iter()
-            .filter(|stmt| {
-                if let Statement::Def { .. } = stmt {
-                    false
-                } else {
-                    true
-                }
-            })
-            .flat_map(|stmt| self.codegen_statement(stmt))
-            .collect()
+    // the names of the functions, which the entries they push on the call
+    // stack point to, and of the classes. a method goes by its own name.
+    fn codegen_names(&mut self, body: &Body) -> Vec<u8> {
+        let mut names = vec![];
+        for function in body.functions() {
+            self.names
+                .insert(function.name.clone(), runtime::NAMES + names.len());
+            let name = function.name.rsplit('.').next().unwrap();
+            names.extend(runtime::name(name));
+        }
+        for class in self.classes.iter() {
+            if !self.names.contains_key(&class.name) {
+                self.names
+                    .insert(class.name.clone(), runtime::NAMES + names.len());
+                names.extend(runtime::name(&class.name));
+            }
+        }
+        names
     }
 
-    pub fn codegen_def(&self, stmt: &Statement) -> Wexp {
-        let mut def_wexp: Vec<Wexp> = vec![wasm!("func")];
-        // TODO: is there a better way to destructure Def variant?
-        if let Statement::Def { name, params, body } = stmt {
-            let mut n = Self::prepend_dollar(name);
-            def_wexp.push(Atom(n));
-            for param in params.iter() {
-                let mut p = Self::prepend_dollar(param);
-                let mut param_wexp = vec![wasm!("param")];
-                param_wexp.push(Atom(p));
-                param_wexp.push(wasm!(i32));
-                def_wexp.push(List(param_wexp));
+    // the data of the `names`, followed by those of the attributes, which
+    // the runtime's messages name, and a table of 2 byte addresses of them
+    // by number. they have to fit below the heap.
+    fn codegen_name_data(&self, mut names: Vec<u8>) -> Result<Vec<Wexp>, Error> {
+        let mut data = vec![];
+        if !self.attributes.is_empty() {
+            let mut table = vec![];
+            for attribute in self.attributes.iter() {
+                let address = runtime::NAMES + names.len();
+                table.extend(vec![address as u8, (address >> 8) as u8]);
+                names.extend(runtime::name(attribute));
             }
-            // TODO: don't assume that all functions return integers.
-            let return_type = List(vec![wasm!("result"), wasm!("i32")]);
-            def_wexp.push(return_type);
-            // TODO: resolve this.
-            // this disallows functions inside functions I suppose, since
-            // codegen_body skips function definitions.
-            let b = self.codegen_body(body);
-            def_wexp.extend(b);
+            data.push(List(vec![
+                wasm!(global),
+                wasm!("$attributes"),
+                wasm!(i32),
+                List(vec![
+                    wasm!("i32.const"),
+                    Atom((runtime::NAMES + names.len()).to_string()),
+                ]),
+            ]));
+            names.extend(table);
+        }
+        if names.is_empty() {
+            return Ok(data);
+        }
+        if runtime::NAMES + names.len() > runtime::HEAP {
+            return Err(Error::TooManyNames(names.len()));
+        }
+        data.insert(
+            0,
+            List(vec![
+                wasm!(data),
+                List(vec![wasm!("i32.const"), Atom(runtime::NAMES.to_string())]),
+                Atom(runtime::string(&names)),
+            ]),
+        );
+        Ok(data)
+    }
+
+    pub fn codegen_defs(&mut self, body: &Body) -> Result<Vec<Wexp>, Error> {
+        let mut defs = vec![];
+        for function in body.functions() {
+            defs.push(self.codegen_def(&function.name, function.params, function.body)?);
+        }
+        Ok(defs)
+    }
+
+    pub fn codegen_body(&mut self, body: &Body) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+            atoms.extend(self.codegen_statement(stmt, *line)?);
+        }
+        Ok(atoms)
+    }
+
+    pub fn codegen_def(
+        &mut self,
+        name: &str,
+        params: &[Parameter],
+        body: &Body,
+    ) -> Result<Wexp, Error> {
+        let mut def_wexp: Vec<Wexp> = vec![wasm!("func")];
+        let types = self.types.child(name).unwrap();
+        let result = types.result();
+        let n = Self::prepend_dollar(name);
+        def_wexp.push(Atom(n));
+        for param in params.iter() {
+            // surplus arguments come packed in a tuple and a dict.
+            let param = param.name();
+            let p = Self::prepend_dollar(param);
+            let mut param_wexp = vec![wasm!("param")];
+            param_wexp.push(Atom(p));
+            param_wexp.push(wasm!(types.variable(param).unwrap().wasm()));
+            def_wexp.push(List(param_wexp));
+        }
+        let mut return_type = vec![wasm!("result")];
+        if types.results().is_empty() {
+            return_type.push(wasm!(result.wasm()));
+        } else {
+            return_type.extend(types.results().iter().map(|t| wasm!(t.wasm())));
+        }
+        def_wexp.push(List(return_type));
+        // functions are never nested, the checker sees to that.
+        self.function = Some(name.to_owned());
+        self.frame = self.frame(body);
+        self.returns = None;
+        let b = self.codegen_body(body)?;
+        let catches = body.statements.iter().any(Self::catches);
+        def_wexp.extend(self.codegen_locals(params));
+        if catches {
+            def_wexp.push(List(vec![wasm!(local), wasm!("$calls"), wasm!(i32)]));
+        }
+        def_wexp.extend(self.codegen_temps());
+        def_wexp.extend(self.codegen_enter(params, catches));
+        for param in params.iter() {
+            def_wexp.extend(self.codegen_defined(param.name(), true));
+        }
+        def_wexp.extend(b);
+        // falling off the end of a function returns None. a body that
+        // can't still needs something on the stack for the validator.
+        if types::can_fall_off(body) {
+            def_wexp.extend(self.codegen_leave());
+            def_wexp.extend(Self::constant(runtime::boxed(runtime::NONE, 0)));
+        } else if !matches!(body.statements.last(), Some(Statement::Return(_))) {
+            def_wexp.push(wasm!(unreachable));
+        }
+        self.frame = None;
+        self.function = None;
+        Ok(List(def_wexp))
+    }
+
+    pub fn codegen_statement(&mut self, stmt: &Statement, line: usize) -> Result<Vec<Wexp>, Error> {
+        self.line = line;
+        self.line_set = false;
+        let rooted = self.rooted;
+        self.rooted = false;
+        let mut atoms = self.codegen_statement_kind(stmt)?;
+        // temporaries are only kept alive by the statement that made them.
+        // a return drops the whole frame anyway.
+        let returns = matches!(stmt, Statement::Return(_));
+        if self.rooted && !returns {
+            atoms.extend(self.codegen_unroot());
         }
-        List(def_wexp)
+        self.rooted = rooted;
+        Ok(atoms)
     }
 
-    pub fn codegen_statement(&self, stmt: &Statement) -> Vec<Wexp> {
+    fn codegen_statement_kind(&mut self, stmt: &Statement) -> Result<Vec<Wexp>, Error> {
         let mut atoms = vec![];
         match stmt {
-            Statement::Print(e) => {
-                let expr = self.codegen_expression(e);
-                atoms.extend(expr);
-                atoms.extend(vec![wasm!(call), Atom("$print".to_owned())]);
+            Statement::Print {
+                stream: Stream::Stdout,
+                items,
+                newline: true,
+            } if items.len() == 1 => {
+                atoms.extend(self.codegen_printed(&items[0])?);
+                atoms.extend(vec![wasm!(call), Atom("$rt.print".to_owned())]);
+            }
+            Statement::Print {
+                stream,
+                items,
+                newline,
+            } => {
+                let stream = (*stream as i32).to_string();
+                for item in items.iter() {
+                    atoms.extend(self.codegen_printed(item)?);
+                    atoms.extend(vec![
+                        wasm!("i32.const"),
+                        Atom(stream.clone()),
+                        wasm!(call),
+                        wasm!("$rt.print_item"),
+                    ]);
+                }
+                if *newline {
+                    atoms.extend(vec![
+                        wasm!("i32.const"),
+                        Atom(stream),
+                        wasm!(call),
+                        wasm!("$rt.print_newline"),
+                    ]);
+                }
             }
             Statement::Return(e) => {
-                let expr = self.codegen_expression(e);
-                atoms.extend(expr);
+                let function = self.function.clone().unwrap();
+                let types = self.types.child(&function).unwrap();
+                let (result, results) = (types.result(), types.results().to_vec());
+                match e {
+                    // a function with several results leaves each value on
+                    // the stack.
+                    Expression::Tuple(items) if !results.is_empty() => {
+                        for (i, (item, t)) in items.iter().zip(results).enumerate() {
+                            atoms.extend(self.codegen_coerced(item, t)?);
+                            let rest: Vec<&Expression> = items[i + 1..].iter().collect();
+                            atoms.extend(self.codegen_root(item, t, &rest));
+                        }
+                    }
+                    _ => atoms.extend(self.codegen_coerced(e, result)?),
+                }
+                if self.finally.is_empty() {
+                    atoms.extend(self.codegen_leave());
+                    atoms.push(wasm!(return));
+                } else {
+                    for (temp, _) in self.return_temps().iter().rev() {
+                        atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+                    }
+                    atoms.extend(self.codegen_return());
+                }
+            }
+            Statement::Assign(name, e) => {
+                let t = self.variable_type(name);
+                atoms.extend(self.codegen_coerced(e, t)?);
+                atoms.extend(self.codegen_assign(name));
+            }
+            Statement::SetItem {
+                object,
+                index,
+                value,
+            } => {
+                atoms.extend(self.codegen_coerced(value, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(value, Type::Dynamic, &[object, index]));
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(object, Type::Dynamic, &[index]));
+                atoms.extend(self.codegen_coerced(index, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper("setitem"))]);
             }
+            Statement::Unpack(targets, value) => {
+                atoms.extend(self.codegen_unpack(targets, value)?);
+            }
+            Statement::AugAssign(e) => {
+                atoms.extend(self.codegen_aug_assign(e)?);
+            }
+            Statement::Expression(e) => {
+                atoms.extend(self.codegen_expression(e)?);
+                atoms.push(wasm!(drop));
+            }
+            Statement::Del(Expression::Subscript(object, index)) => {
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(object, Type::Dynamic, &[index]));
+                atoms.extend(self.codegen_coerced(index, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.delitem")]);
+            }
+            // a deleted name is unbound again, and lets go of what it held.
+            Statement::Del(Expression::Simple(Value::Variable(name, line))) => {
+                atoms.extend(self.codegen_bound(name, *line));
+                atoms.extend(Self::zero(self.variable_type(name)));
+                atoms.extend(self.codegen_set(name));
+                atoms.extend(self.codegen_defined(name, false));
+            }
+            Statement::Del(Expression::Attribute(object, name)) => {
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.delattr")]);
+            }
+            // the parser only lets names, items and attributes be deleted.
+            Statement::Del(_) => unreachable!(),
+            // the message becomes the AssertionError's only arg.
+            Statement::Assert(condition, message) => {
+                atoms.extend(self.codegen_expression(condition)?);
+                atoms.extend(self.codegen_truthy(self.expression_type(condition)));
+                atoms.extend(vec![wasm!("i32.eqz"), wasm!("if")]);
+                let line_set = self.line_set;
+                match message {
+                    Some(message) => {
+                        let args = [Argument::Positional(message.clone())];
+                        atoms.extend(self.codegen_new_exception(Exception::AssertionError, &args)?);
+                        atoms.extend(self.codegen_line());
+                        atoms.extend(vec![wasm!(call), wasm!("$rt.raise_exception")]);
+                        atoms.push(wasm!(unreachable));
+                    }
+                    None => {
+                        atoms.extend(self.codegen_line());
+                        atoms.extend(Self::codegen_throw(Exception::AssertionError));
+                    }
+                }
+                atoms.push(wasm!("end"));
+                self.line_set = line_set;
+            }
+            // a class by itself is raised with no args, and a bare raise
+            // raises the exception being handled again.
+            Statement::Raise(Some(Expression::Simple(Value::Variable(name, _))))
+                if self.exception_class(name).is_some() =>
+            {
+                let exception = self.exception_class(name).unwrap();
+                atoms.extend(self.codegen_line());
+                atoms.extend(Self::codegen_throw(exception));
+            }
+            Statement::Raise(Some(e)) => {
+                atoms.extend(self.codegen_coerced(e, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![
+                    wasm!(call),
+                    wasm!("$rt.raise_exception"),
+                    wasm!(unreachable),
+                ]);
+            }
+            Statement::Raise(None) => match self.handled.last() {
+                Some(caught) => atoms.extend(vec![
+                    wasm!(get_local),
+                    Atom(caught.clone()),
+                    wasm!(call),
+                    wasm!("$rt.throw"),
+                    wasm!(unreachable),
+                ]),
+                None => {
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(Self::codegen_throw_message(
+                        Exception::TypeError,
+                        "exceptions must be old-style classes or derived from \
+                         BaseException, not NoneType",
+                    ));
+                }
+            },
+            Statement::Try { .. } => {
+                atoms.extend(self.codegen_try(stmt)?);
+            }
+            Statement::Global(_) | Statement::Import(_) | Statement::Pass => (),
             Statement::If { .. } => {
-                let if_wexp = self.codegen_if(stmt);
+                let if_wexp = self.codegen_if(stmt)?;
                 atoms.extend(if_wexp);
             }
-            _ => unimplemented!(),
+            Statement::Def { name, params, .. } => {
+                atoms.extend(self.codegen_defaults(name, params)?);
+                if self.checked.contains(name) {
+                    atoms.extend(vec![wasm!("i32.const"), wasm!("1"), wasm!(set_global)]);
+                    atoms.push(Atom(Self::defined_flag(name)));
+                }
+            }
+            Statement::Class { .. } => {
+                atoms.extend(self.codegen_class(stmt)?);
+            }
+            Statement::SetAttr {
+                object,
+                name,
+                value,
+            } => {
+                atoms.extend(self.codegen_coerced(value, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(value, Type::Dynamic, &[object]));
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.setattr")]);
+            }
+        }
+        Ok(atoms)
+    }
+
+    // evaluates the default values of a function's parameters into their
+    // globals.
+    fn codegen_defaults(
+        &mut self,
+        function: &str,
+        params: &[Parameter],
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        for param in params.iter() {
+            if let Parameter::Default(param, default) = param {
+                let t = self.types.child(function).unwrap().variable(param).unwrap();
+                atoms.extend(self.codegen_coerced(default, t)?);
+                atoms.push(wasm!(set_global));
+                atoms.push(Atom(Self::default_global(function, param)));
+                atoms.extend(self.codegen_slot(&Self::default_global(function, param), true));
+            }
+        }
+        Ok(atoms)
+    }
+
+    // a class statement makes the class, and then runs its body, which sets
+    // its attributes and the defaults of its methods, before assigning it
+    // to its name.
+    fn codegen_class(&mut self, stmt: &Statement) -> Result<Vec<Wexp>, Error> {
+        let (name, base, body) = match stmt {
+            Statement::Class { name, base, body } => (name, base, body),
+            _ => unreachable!(),
+        };
+        let class = self.classes.find(name).unwrap();
+        let mut atoms = match base {
+            Some(Expression::Simple(Value::Variable(base, _))) if base == "object" => {
+                Self::constant(runtime::boxed(runtime::NONE, 0))
+            }
+            Some(base) => self.codegen_coerced(base, Type::Dynamic)?,
+            None => Self::constant(runtime::boxed(runtime::NONE, 0)),
+        };
+        atoms.extend(vec![
+            wasm!("i32.const"),
+            Atom(class.to_string()),
+            wasm!("i32.const"),
+            Atom(self.names[name].to_string()),
+            wasm!("i32.const"),
+            Atom((self.classes.get(class).new_style as i32).to_string()),
+        ]);
+        atoms.extend(self.codegen_line());
+        atoms.extend(vec![wasm!(call), wasm!("$rt.new_class")]);
+        let temp = self.temp(Type::Dynamic);
+        atoms.extend(self.codegen_keep(Type::Dynamic, true));
+        atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+        self.class_body = Some((temp.clone(), HashSet::new()));
+        for (stmt, line) in body.statements.iter().zip(body.lines.iter()) {
+            self.line = *line;
+            self.line_set = false;
+            match stmt {
+                Statement::Assign(attribute, e) => {
+                    atoms.extend(self.codegen_coerced(e, Type::Dynamic)?);
+                    atoms.extend(vec![wasm!(get_local), Atom(temp.clone())]);
+                    atoms.extend(self.codegen_attribute(attribute));
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.setattr")]);
+                    if let Some((_, attributes)) = &mut self.class_body {
+                        attributes.insert(attribute.clone());
+                    }
+                }
+                Statement::Def {
+                    name: method,
+                    params,
+                    ..
+                } => {
+                    let function = format!("{}.{}", name, method);
+                    atoms.extend(self.codegen_defaults(&function, params)?);
+                }
+                Statement::Expression(e) => {
+                    atoms.extend(self.codegen_expression(e)?);
+                    atoms.push(wasm!(drop));
+                }
+                // the parser lets nothing else into a class's body.
+                _ => (),
+            }
+        }
+        self.class_body = None;
+        atoms.extend(vec![wasm!(get_local), Atom(temp)]);
+        atoms.extend(self.codegen_assign(name));
+        Ok(atoms)
+    }
+
+    // the number of the attribute `name`, numbering it if it is new.
+    fn codegen_attribute(&mut self, name: &str) -> Vec<Wexp> {
+        let id = match self
+            .attributes
+            .iter()
+            .position(|attribute| attribute == name)
+        {
+            Some(id) => id,
+            None => {
+                self.attributes.push(name.to_owned());
+                self.attributes.len() - 1
+            }
+        };
+        vec![wasm!("i32.const"), Atom(id.to_string())]
+    }
+
+    // an item of a print statement. an instance is printed as the str its
+    // class's __str__, or else its __repr__, gives back.
+    fn codegen_printed(&mut self, item: &Expression) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = self.codegen_coerced(item, Type::Dynamic)?;
+        if self.expression_type(item) == Type::Dynamic && self.has_special("str") {
+            atoms.extend(self.codegen_line());
+            atoms.extend(vec![wasm!(call), Atom(self.helper("str"))]);
+        }
+        Ok(atoms)
+    }
+
+    // sets the variable `name` to the value on the stack, which already has
+    // the variable's type.
+    fn codegen_assign(&mut self, name: &str) -> Vec<Wexp> {
+        let mut atoms = self.codegen_set(name);
+        atoms.extend(self.codegen_defined(name, true));
+        atoms
+    }
+
+    fn codegen_set(&mut self, name: &str) -> Vec<Wexp> {
+        let global = self.scope(name) == Scope::Global;
+        let mut atoms = vec![if global {
+            wasm!(set_global)
+        } else {
+            wasm!(set_local)
+        }];
+        atoms.push(Atom(Self::prepend_dollar(name)));
+        atoms.extend(self.codegen_slot(&Self::prepend_dollar(name), global));
+        atoms
+    }
+
+    // records whether the variable `name` is assigned, if anything checks.
+    fn codegen_defined(&self, name: &str, defined: bool) -> Vec<Wexp> {
+        if !self.can_be_unbound(name) {
+            return vec![];
+        }
+        vec![
+            wasm!("i32.const"),
+            Atom((defined as u8).to_string()),
+            if self.scope(name) == Scope::Global {
+                wasm!(set_global)
+            } else {
+                wasm!(set_local)
+            },
+            Atom(Self::defined_flag(name)),
+        ]
+    }
+
+    // whether the variable `name` has a flag saying if it is assigned.
+    fn can_be_unbound(&self, name: &str) -> bool {
+        match self.function {
+            Some(ref function) if self.scope(name) == Scope::Local => {
+                self.unbound.local(function, name)
+            }
+            _ => self.scope(name) == Scope::Global && self.unbound.global(name),
+        }
+    }
+
+    // raises the error python does unless the variable `name` has been
+    // assigned.
+    fn codegen_bound(&mut self, name: &str, line: usize) -> Vec<Wexp> {
+        if !self.can_be_unbound(name) {
+            return vec![];
+        }
+        let global = self.scope(name) == Scope::Global;
+        let (exception, message) = if global {
+            (Exception::NameError, self.undefined(name))
+        } else {
+            (
+                Exception::UnboundLocalError,
+                format!("local variable '{}' referenced before assignment", name),
+            )
+        };
+        let mut atoms = vec![
+            if global {
+                wasm!(get_global)
+            } else {
+                wasm!(get_local)
+            },
+            Atom(Self::defined_flag(name)),
+            wasm!("i32.eqz"),
+            wasm!("if"),
+        ];
+        atoms.extend(self.codegen_raise(exception, &message, line));
+        atoms.push(wasm!("end"));
+        atoms
+    }
+
+    // how python words the NameError for a global `name` that isn't bound.
+    fn undefined(&self, name: &str) -> String {
+        match self.function {
+            Some(_) => format!("global name '{}' is not defined", name),
+            None => format!("name '{}' is not defined", name),
+        }
+    }
+
+    // an augmented assignment evaluates the container and key of an item
+    // once, for both getting and setting it. adding to a list extends it in
+    // place.
+    fn codegen_aug_assign(&mut self, expr: &Expression) -> Result<Vec<Wexp>, Error> {
+        let (operator, left, right) = Self::binary(expr);
+        let operator = if operator == "add" { "iadd" } else { operator };
+        let mut atoms = vec![];
+        match left {
+            Expression::Simple(Value::Variable(name, _)) => {
+                let t = self.variable_type(name);
+                if operator == "iadd"
+                    && self.expression_type(expr) == Type::Dynamic
+                    && !(self.is_int(left) && self.is_int(right))
+                {
+                    atoms.extend(self.codegen_coerced(left, Type::Dynamic)?);
+                    atoms.extend(self.codegen_root(left, Type::Dynamic, &[right]));
+                    atoms.extend(self.codegen_coerced(right, Type::Dynamic)?);
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), Atom(self.helper("iadd"))]);
+                    atoms.extend(Self::coerce(Type::Dynamic, t));
+                } else {
+                    atoms.extend(self.codegen_coerced(expr, t)?);
+                }
+                atoms.extend(self.codegen_assign(name));
+            }
+            Expression::Subscript(object, index) => {
+                let temps = [self.temp(Type::Dynamic), self.temp(Type::Dynamic)];
+                for (e, temp) in [object, index].iter().zip(temps.iter()) {
+                    // an int key is boxed, but nothing can collect it.
+                    let t = self.expression_type(e);
+                    atoms.extend(self.codegen_coerced(e, Type::Dynamic)?);
+                    atoms.extend(self.codegen_keep(t, true));
+                    atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+                }
+                for temp in temps.iter() {
+                    atoms.extend(vec![wasm!(get_local), Atom(temp.clone())]);
+                }
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper("getitem"))]);
+                atoms.extend(self.codegen_root(left, Type::Dynamic, &[right]));
+                atoms.extend(self.codegen_coerced(right, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper(operator))]);
+                for temp in temps.iter() {
+                    atoms.extend(vec![wasm!(get_local), Atom(temp.clone())]);
+                }
+                atoms.extend(vec![wasm!(call), Atom(self.helper("setitem"))]);
+            }
+            Expression::Attribute(object, name) => {
+                let temp = self.temp(Type::Dynamic);
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_keep(Type::Dynamic, true));
+                atoms.extend(vec![wasm!(tee_local), Atom(temp.clone())]);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.getattr")]);
+                atoms.extend(self.codegen_root(left, Type::Dynamic, &[right]));
+                atoms.extend(self.codegen_coerced(right, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper(operator))]);
+                atoms.extend(vec![wasm!(get_local), Atom(temp)]);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(vec![wasm!(call), wasm!("$rt.setattr")]);
+            }
+            // the parser only lets names, items and attributes be updated.
+            _ => unreachable!(),
+        }
+        Ok(atoms)
+    }
+
+    // the runtime helper for a binary operator, and its operands.
+    fn binary(expr: &Expression) -> (&'static str, &Expression, &Expression) {
+        match expr {
+            Expression::Add(l, r) => ("add", l, r),
+            Expression::Sub(l, r) => ("sub", l, r),
+            Expression::Mult(l, r) => ("mul", l, r),
+            Expression::Div(l, r) => ("div", l, r),
+            Expression::Mod(l, r) => ("mod", l, r),
+            Expression::Power(l, r) => ("pow", l, r),
+            Expression::BitAnd(l, r) => ("and", l, r),
+            Expression::BitOr(l, r) => ("or", l, r),
+            Expression::BitXor(l, r) => ("xor", l, r),
+            Expression::LShift(l, r) => ("shl", l, r),
+            Expression::RShift(l, r) => ("shr", l, r),
+            _ => unreachable!(),
+        }
+    }
+
+    // unpacking evaluates the whole value, and then assigns its values to
+    // the targets from left to right. a tuple display, or a call to a
+    // function with as many results as there are targets, gives its values
+    // without making a tuple. anything else has to be a list or tuple of
+    // the right length.
+    fn codegen_unpack(
+        &mut self,
+        targets: &[Target],
+        value: &Expression,
+    ) -> Result<Vec<Wexp>, Error> {
+        let n = targets.len();
+        let types = self.types.unpacked(self.function.as_deref(), value, n);
+        let temps: Vec<String> = types.iter().map(|t| self.temp(*t)).collect();
+        let later = self.targets_collect(targets);
+        let mut atoms = vec![];
+        match value {
+            Expression::Tuple(items) if items.len() == n => {
+                for (i, item) in items.iter().enumerate() {
+                    atoms.extend(self.codegen_expression(item)?);
+                    let later = later || items[i + 1..].iter().any(|e| self.expression_collects(e));
+                    atoms.extend(self.codegen_keep(types[i], later));
+                    atoms.extend(vec![wasm!(set_local), Atom(temps[i].clone())]);
+                }
+            }
+            Expression::Call { name, params, line } if self.results(name).len() == n => {
+                atoms.extend(self.codegen_call(name, params, *line)?);
+                for (temp, t) in temps.iter().zip(types.iter()).rev() {
+                    atoms.extend(self.codegen_keep(*t, later));
+                    atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+                }
+            }
+            _ => {
+                atoms.extend(self.codegen_coerced(value, Type::Dynamic)?);
+                atoms.extend(self.codegen_elements(&temps, later));
+            }
+        }
+        for ((target, temp), t) in targets.iter().zip(temps.iter()).zip(types) {
+            atoms.extend(self.codegen_store(target, temp, t)?);
+        }
+        Ok(atoms)
+    }
+
+    // moves the items of the list or tuple on the stack into `temps`,
+    // raising a ValueError if it doesn't have that many.
+    fn codegen_elements(&mut self, temps: &[String], later: bool) -> Vec<Wexp> {
+        let address = self.temp(Type::Int);
+        let mut atoms = vec![wasm!("i32.const"), Atom(temps.len().to_string())];
+        atoms.extend(self.codegen_line());
+        atoms.extend(vec![
+            wasm!(call),
+            wasm!("$rt.unpack"),
+            wasm!(set_local),
+            Atom(address.clone()),
+        ]);
+        for (i, temp) in temps.iter().enumerate() {
+            atoms.extend(vec![
+                wasm!(get_local),
+                Atom(address.clone()),
+                wasm!("i64.load"),
+                Atom(format!("offset={}", 8 * i)),
+            ]);
+            atoms.extend(self.codegen_keep(Type::Dynamic, later));
+            atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+        }
+        atoms
+    }
+
+    // assigns the value of type `t` in `temp` to `target`.
+    fn codegen_store(&mut self, target: &Target, temp: &str, t: Type) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![wasm!(get_local), Atom(temp.to_owned())];
+        match target {
+            Target::Name(name) => {
+                atoms.extend(Self::coerce(t, self.variable_type(name)));
+                atoms.extend(self.codegen_assign(name));
+            }
+            Target::Item(object, index) => {
+                atoms.extend(Self::coerce(t, Type::Dynamic));
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(object, Type::Dynamic, &[index]));
+                atoms.extend(self.codegen_coerced(index, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper("setitem"))]);
+            }
+            Target::Attribute(object, name) => {
+                atoms.extend(Self::coerce(t, Type::Dynamic));
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.setattr")]);
+            }
+            Target::Tuple(targets) => {
+                atoms.extend(Self::coerce(t, Type::Dynamic));
+                let temps: Vec<String> = targets.iter().map(|_| self.temp(Type::Dynamic)).collect();
+                let later = self.targets_collect(targets);
+                atoms.extend(self.codegen_elements(&temps, later));
+                for (target, temp) in targets.iter().zip(temps.iter()) {
+                    atoms.extend(self.codegen_store(target, temp, Type::Dynamic)?);
+                }
+            }
+        }
+        Ok(atoms)
+    }
+
+    // packs the values a call to a function with several results left on
+    // the stack into a tuple.
+    fn codegen_pack(&mut self, results: &[Type]) -> Vec<Wexp> {
+        let temps: Vec<String> = results.iter().map(|t| self.temp(*t)).collect();
+        let mut atoms = vec![];
+        for (temp, t) in temps.iter().zip(results.iter()).rev() {
+            atoms.extend(self.codegen_keep(*t, true));
+            atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+        }
+        atoms.extend(vec![wasm!("i32.const"), Atom(results.len().to_string())]);
+        atoms.extend(self.codegen_line());
+        atoms.extend(vec![wasm!(call), wasm!("$rt.new_tuple")]);
+        for (i, (temp, t)) in temps.iter().zip(results.iter()).enumerate() {
+            atoms.extend(vec![
+                wasm!("i32.const"),
+                Atom(i.to_string()),
+                wasm!(get_local),
+                Atom(temp.clone()),
+            ]);
+            atoms.extend(Self::coerce(*t, Type::Dynamic));
+            atoms.extend(vec![wasm!(call), wasm!("$rt.init_item")]);
         }
         atoms
     }
 
-    pub fn codegen_if(&self, stmt: &Statement) -> Vec<Wexp> {
+    pub fn codegen_if(&mut self, stmt: &Statement) -> Result<Vec<Wexp>, Error> {
         let mut if_wexp = Vec::new();
         if let Statement::If {
             condition,
@@ -118,13 +1030,15 @@ impl CodeGenerator {
             else_body,
         } = stmt
         {
-            let cond_wexp = self.codegen_expression(condition);
+            let line = self.line;
+            let cond_wexp = self.codegen_expression(condition)?;
             if_wexp.extend(cond_wexp);
+            if_wexp.extend(self.codegen_truthy(self.expression_type(condition)));
             if_wexp.push(wasm!("if"));
-            // TODO: don't assume that all if/else return integers.
-            let return_type = List(vec![wasm!("result"), wasm!("i32")]);
-            if_wexp.push(return_type);
-            let body_wexp = self.codegen_body(body);
+            let body_wexp = self.codegen_body(body)?;
+            // elif conditions are reported against the if statement.
+            self.line = line;
+            self.line_set = false;
             if_wexp.extend(body_wexp);
             if !elif.is_empty() {
                 let mut elif_clone = elif.clone();
@@ -137,159 +1051,2334 @@ impl CodeGenerator {
                     else_body: else_clone,
                 };
                 let mut elif_wexp = vec![wasm!("else")];
-                elif_wexp.extend(self.codegen_if(&elif_stmt));
+                elif_wexp.extend(self.codegen_if(&elif_stmt)?);
                 if_wexp.extend(elif_wexp);
             } else {
                 if let Some(b) = else_body {
                     if_wexp.push(wasm!("else"));
-                    let else_wexp = self.codegen_body(b);
+                    let else_wexp = self.codegen_body(b)?;
                     if_wexp.extend(else_wexp);
                 }
             }
             if_wexp.push(wasm!("end"));
         }
-        if_wexp
+        Ok(if_wexp)
+    }
+
+    // a try statement runs its body catching whatever it raises. then it
+    // runs its else clause if nothing was raised, or else the first handler
+    // that matches, with the exception kept on the shadow stack, or raises
+    // it again if none does. a finally clause runs after all that, catching
+    // whatever was raised there, and then raises it again, or carries on
+    // with a return that branched out to it.
+    fn codegen_try(&mut self, stmt: &Statement) -> Result<Vec<Wexp>, Error> {
+        let (body, handlers, else_body, finally) = match stmt {
+            Statement::Try {
+                body,
+                handlers,
+                else_body,
+                finally,
+            } => (body, handlers, else_body, finally),
+            _ => unreachable!(),
+        };
+        let line = self.line;
+        if finally.is_some() {
+            self.labels += 1;
+            self.finally.push(Finally {
+                label: format!("$finally.{}", self.labels),
+                returning: None,
+            });
+        }
+        let body = self.codegen_body(body)?;
+        let mut atoms = self.codegen_catching(body);
+        atoms.extend(self.codegen_unroot());
+        if !handlers.is_empty() {
+            let mut handling = vec![wasm!(get_global), wasm!("$exception")];
+            match else_body {
+                Some(else_body) => {
+                    handling.extend(vec![wasm!("i64.eqz"), wasm!("if")]);
+                    handling.extend(self.codegen_body(else_body)?);
+                    handling.push(wasm!("else"));
+                }
+                None => handling.extend(vec![
+                    wasm!("i64.const"),
+                    wasm!("0"),
+                    wasm!("i64.ne"),
+                    wasm!("if"),
+                ]),
+            }
+            let caught = self.temp(Type::Dynamic);
+            handling.extend(self.codegen_hold(&caught));
+            self.handled.push(caught.clone());
+            let mut tests = 0;
+            for handler in handlers.iter() {
+                self.line = line;
+                self.line_set = false;
+                if let Some(exception) = &handler.exception {
+                    handling.extend(self.codegen_matches(exception, &caught)?);
+                    handling.push(wasm!("if"));
+                    tests += 1;
+                }
+                if let Some(name) = &handler.name {
+                    handling.extend(vec![wasm!(get_local), Atom(caught.clone())]);
+                    handling.extend(Self::coerce(Type::Dynamic, self.variable_type(name)));
+                    handling.extend(self.codegen_assign(name));
+                }
+                handling.extend(self.codegen_body(&handler.body)?);
+                if handler.exception.is_some() {
+                    handling.push(wasm!("else"));
+                }
+            }
+            if handlers.last().unwrap().exception.is_some() {
+                handling.extend(vec![
+                    wasm!(get_local),
+                    Atom(caught),
+                    wasm!(call),
+                    wasm!("$rt.throw"),
+                ]);
+            }
+            handling.extend((0..tests).map(|_| wasm!("end")));
+            self.handled.pop();
+            self.held -= 1;
+            handling.push(wasm!("end"));
+            handling.extend(self.codegen_unroot());
+            match finally {
+                Some(_) => atoms.extend(self.codegen_catching(handling)),
+                None => atoms.extend(handling),
+            }
+        }
+        let finally = match finally {
+            Some(finally) => finally,
+            None => return Ok(atoms),
+        };
+        let Finally { label, returning } = self.finally.pop().unwrap();
+        let mut atoms = {
+            let mut block = vec![wasm!(block), Atom(label)];
+            block.extend(atoms);
+            block.push(wasm!("end"));
+            block
+        };
+        atoms.extend(self.codegen_unroot());
+        let held = self.held;
+        let pending = self.temp(Type::Dynamic);
+        atoms.extend(self.codegen_hold(&pending));
+        if returning.is_some() {
+            for (temp, t) in self.return_temps() {
+                if t.boxed() {
+                    atoms.extend(vec![
+                        wasm!(get_local),
+                        Atom(temp),
+                        wasm!(call),
+                        wasm!("$rt.root"),
+                        wasm!(drop),
+                    ]);
+                    self.held += 1;
+                }
+            }
+        }
+        atoms.extend(self.codegen_body(finally)?);
+        self.held = held;
+        atoms.extend(self.codegen_unroot());
+        atoms.extend(vec![
+            wasm!(get_local),
+            Atom(pending.clone()),
+            wasm!("i64.const"),
+            wasm!("0"),
+            wasm!("i64.ne"),
+            wasm!("if"),
+            wasm!(get_local),
+            Atom(pending),
+            wasm!(call),
+            wasm!("$rt.throw"),
+            wasm!("end"),
+        ]);
+        if let Some(returning) = returning {
+            atoms.extend(vec![wasm!(get_local), Atom(returning), wasm!("if")]);
+            atoms.extend(self.codegen_return());
+            atoms.push(wasm!("end"));
+        }
+        Ok(atoms)
+    }
+
+    // runs the code in `body`, leaving whatever it raises in $exception
+    // instead of letting it propagate, or 0 if it raises nothing.
+    fn codegen_catching(&mut self, body: Vec<Wexp>) -> Vec<Wexp> {
+        self.labels += 1;
+        let mut atoms = match self.handling {
+            Handling::Status => vec![wasm!(block), Atom(format!("$catch.{}", self.labels))],
+            Handling::Wasm => {
+                let label = format!("$caught.{}", self.labels);
+                vec![
+                    wasm!(block),
+                    Atom(label.clone()),
+                    List(vec![wasm!(result), wasm!(i64)]),
+                    wasm!(try_table),
+                    List(vec![wasm!(catch), wasm!("$python"), Atom(label)]),
+                ]
+            }
+        };
+        atoms.extend(body);
+        atoms.push(wasm!("end"));
+        if self.handling == Handling::Wasm {
+            atoms.extend(vec![
+                wasm!("i64.const"),
+                wasm!("0"),
+                wasm!("end"),
+                wasm!(set_global),
+                wasm!("$exception"),
+            ]);
+        }
+        // the functions an exception came out of never popped their entries.
+        match self.function {
+            Some(_) => atoms.extend(vec![wasm!(get_local), wasm!("$calls")]),
+            None => atoms.extend(vec![wasm!("i32.const"), Atom(runtime::CALLS.to_string())]),
+        }
+        atoms.extend(vec![wasm!(set_global), wasm!("$calls.top")]);
+        atoms
+    }
+
+    // moves the exception in $exception to `temp`, keeping it on the shadow
+    // stack until the try statement is done with it.
+    fn codegen_hold(&mut self, temp: &str) -> Vec<Wexp> {
+        self.held += 1;
+        vec![
+            wasm!(get_global),
+            wasm!("$exception"),
+            wasm!(call),
+            wasm!("$rt.root"),
+            wasm!(set_local),
+            Atom(temp.to_owned()),
+            wasm!("i64.const"),
+            wasm!("0"),
+            wasm!(set_global),
+            wasm!("$exception"),
+        ]
+    }
+
+    // whether the exception in `caught` matches an except clause's classes.
+    // anything that isn't a builtin exception class is evaluated, and never
+    // matches.
+    fn codegen_matches(
+        &mut self,
+        exception: &Expression,
+        caught: &str,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        match exception {
+            Expression::Simple(Value::Complex(e)) => {
+                atoms.extend(self.codegen_matches(e, caught)?);
+            }
+            Expression::Tuple(items) => {
+                atoms.extend(vec![wasm!("i32.const"), wasm!("0")]);
+                for item in items.iter() {
+                    atoms.extend(self.codegen_matches(item, caught)?);
+                    atoms.push(wasm!("i32.or"));
+                }
+            }
+            Expression::Simple(Value::Variable(name, _))
+                if self.exception_class(name).is_some() =>
+            {
+                let exception = self.exception_class(name).unwrap();
+                atoms.extend(vec![
+                    wasm!(get_local),
+                    Atom(caught.to_owned()),
+                    wasm!("i32.const"),
+                    Atom(exception.code().to_string()),
+                    wasm!(call),
+                    wasm!("$rt.matches"),
+                ]);
+            }
+            e => {
+                atoms.extend(self.codegen_expression(e)?);
+                atoms.extend(vec![wasm!(drop), wasm!("i32.const"), wasm!("0")]);
+            }
+        }
+        Ok(atoms)
+    }
+
+    // a new exception of a builtin class, with `args` as its args.
+    fn codegen_new_exception(
+        &mut self,
+        exception: Exception,
+        args: &[Argument],
+    ) -> Result<Vec<Wexp>, Error> {
+        let items = args.iter().map(|arg| arg.expression().clone()).collect();
+        let mut atoms = self.codegen_expression(&Expression::Tuple(items))?;
+        atoms.extend(vec![
+            wasm!("i32.const"),
+            Atom(exception.code().to_string()),
+            wasm!(call),
+            wasm!("$rt.new_exception"),
+        ]);
+        Ok(atoms)
+    }
+
+    // the temporaries a return inside a try statement with a finally clause
+    // leaves its values in.
+    fn return_temps(&mut self) -> Vec<(String, Type)> {
+        if self.returns.is_none() {
+            let function = self.function.clone().unwrap();
+            let types = self.types.child(&function).unwrap();
+            let results = match types.results() {
+                [] => vec![types.result()],
+                results => results.to_vec(),
+            };
+            let temps = results.into_iter().map(|t| (self.temp(t), t)).collect();
+            self.returns = Some(temps);
+        }
+        self.returns.clone().unwrap()
+    }
+
+    // returns the values in the return temporaries, branching out to the
+    // innermost finally clause first if there is one.
+    fn codegen_return(&mut self) -> Vec<Wexp> {
+        if self.finally.is_empty() {
+            let mut atoms = vec![];
+            for (temp, _) in self.return_temps() {
+                atoms.extend(vec![wasm!(get_local), Atom(temp)]);
+            }
+            atoms.extend(self.codegen_leave());
+            atoms.push(wasm!(return));
+            return atoms;
+        }
+        let returning = match self.finally.last().unwrap().returning {
+            Some(ref returning) => returning.clone(),
+            None => self.temp(Type::Bool),
+        };
+        let finally = self.finally.last_mut().unwrap();
+        finally.returning = Some(returning.clone());
+        vec![
+            wasm!("i32.const"),
+            wasm!("1"),
+            wasm!(set_local),
+            Atom(returning),
+            wasm!(br),
+            Atom(finally.label.clone()),
+        ]
     }
 
-    pub fn codegen_expression(&self, expr: &Expression) -> Vec<Wexp> {
+    pub fn codegen_expression(&mut self, expr: &Expression) -> Result<Vec<Wexp>, Error> {
         let mut atoms = vec![];
         match expr {
             Expression::Simple(v) => {
-                let val = self.codegen_value(v);
+                let val = self.codegen_value(v)?;
                 atoms.extend(val);
             }
             Expression::Add(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.add".to_owned()));
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "add")?);
             }
             Expression::Sub(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.sub".to_owned()));
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "sub")?);
             }
             Expression::Mult(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.mul".to_owned()));
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "mul")?);
             }
             Expression::Div(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.div_s".to_owned()));
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "div")?);
             }
             Expression::Mod(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.rem_s".to_owned()));
-            }
-            Expression::Lt(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.lt_s".to_owned()));
-            }
-            Expression::Gt(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.gt_s".to_owned()));
-            }
-            Expression::Leq(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.le_s".to_owned()));
-            }
-            Expression::Geq(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.ge_s".to_owned()));
-            }
-            Expression::EqEq(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.eq".to_owned()));
-            }
-            Expression::Ne(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.ne".to_owned()));
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "mod")?);
+            }
+            Expression::Power(ref v, ref e) => {
+                atoms.extend(self.codegen_arithmetic(expr, v, e, "pow")?);
+            }
+            Expression::Lt(..)
+            | Expression::Gt(..)
+            | Expression::Leq(..)
+            | Expression::Geq(..)
+            | Expression::EqEq(..)
+            | Expression::Ne(..)
+            | Expression::In(..)
+            | Expression::NotIn(..)
+            | Expression::Is(..)
+            | Expression::IsNot(..) => {
+                atoms.extend(self.codegen_link(expr, &mut None, false)?);
+            }
+            Expression::Chain(links) => atoms.extend(self.codegen_chain(links)?),
+            Expression::Neg(e) => atoms.extend(self.codegen_negation(expr, e)?),
+            Expression::BitAnd(ref v, ref e) => {
+                atoms.extend(self.codegen_bitwise(expr, v, e, "and")?);
+            }
+            Expression::BitOr(ref v, ref e) => {
+                atoms.extend(self.codegen_bitwise(expr, v, e, "or")?);
+            }
+            Expression::BitXor(ref v, ref e) => {
+                atoms.extend(self.codegen_bitwise(expr, v, e, "xor")?);
+            }
+            Expression::LShift(ref v, ref e) => {
+                atoms.extend(self.codegen_bitwise(expr, v, e, "shl")?);
+            }
+            Expression::RShift(ref v, ref e) => {
+                atoms.extend(self.codegen_bitwise(expr, v, e, "shr")?);
+            }
+            Expression::Invert(e) => {
+                let t = self.expression_type(expr);
+                atoms.extend(self.codegen_coerced(e, t)?);
+                if t == Type::Int {
+                    atoms.extend(vec![wasm!("i32.const"), wasm!("-1"), wasm!("i32.xor")]);
+                } else {
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.invert")]);
+                }
             }
             Expression::And(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.and".to_owned()));
+                atoms.extend(self.codegen_short_circuit(expr, v, e, true)?);
             }
             Expression::Or(ref v, ref e) => {
-                let expr_l = self.codegen_expression(v);
-                let expr_r = self.codegen_expression(e);
-                atoms.extend(expr_l);
-                atoms.extend(expr_r);
-                atoms.push(Atom("i32.or".to_owned()));
+                atoms.extend(self.codegen_short_circuit(expr, v, e, false)?);
+            }
+            Expression::Conditional(condition, a, b) => {
+                atoms.extend(self.codegen_conditional(expr, condition, a, b)?);
+            }
+            Expression::Call { name, params, .. }
+                if name == "len" && self.scope(name) == Scope::Builtin =>
+            {
+                atoms.extend(self.codegen_coerced(params[0].expression(), Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper("len"))]);
+            }
+            Expression::Call { name, params, .. }
+                if name == "isinstance" && self.scope(name) == Scope::Builtin =>
+            {
+                atoms.extend(self.codegen_isinstance(&params[0], &params[1])?);
             }
-            Expression::Call { name, params } => {
-                for param in params {
-                    atoms.extend(self.codegen_expression(param));
+            Expression::Call { name, params, .. } if self.exception_class(name).is_some() => {
+                let exception = self.exception_class(name).unwrap();
+                atoms.extend(self.codegen_new_exception(exception, params)?);
+            }
+            Expression::Call { name, params, line } if self.class(name).is_some() => {
+                atoms.extend(self.codegen_new_instance(name, params, *line)?);
+            }
+            Expression::Call { name, params, line } => {
+                atoms.extend(self.codegen_call(name, params, *line)?);
+                let results = self.results(name).to_vec();
+                if !results.is_empty() {
+                    atoms.extend(self.codegen_pack(&results));
                 }
-                atoms.push(wasm!("call"));
-                atoms.push(wasm!(&Self::prepend_dollar(name)));
             }
-            _ => unimplemented!(),
-        }
-        atoms
-    }
-
-    pub fn codegen_value(&self, value: &Value) -> Vec<Wexp> {
-        let mut atoms = vec![];
-        match value {
-            Value::Integer(i) => {
-                atoms.push(Atom("i32.const".to_owned()));
-                atoms.push(Atom(i.to_string()));
+            Expression::MethodCall {
+                object,
+                name,
+                params,
+            } => {
+                atoms.extend(self.codegen_method_call(object, name, params)?);
             }
-            Value::Variable(v) => {
-                atoms.push(Atom("get_local".to_owned()));
-                let value = Self::prepend_dollar(v);
-                atoms.push(Atom(value));
+            Expression::Attribute(object, name) => {
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.getattr")]);
             }
-            _ => {
-                unimplemented!();
+            Expression::List(items) | Expression::Tuple(items) => {
+                let constructor = match expr {
+                    Expression::List(_) => "$rt.new_list",
+                    _ => "$rt.new_tuple",
+                };
+                atoms.extend(vec![wasm!("i32.const"), Atom(items.len().to_string())]);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(constructor.to_owned())]);
+                let items: Vec<&Expression> = items.iter().collect();
+                atoms.extend(self.codegen_root(expr, Type::Dynamic, &items));
+                for (i, item) in items.iter().enumerate() {
+                    atoms.extend(vec![wasm!("i32.const"), Atom(i.to_string())]);
+                    atoms.extend(self.codegen_coerced(item, Type::Dynamic)?);
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.init_item")]);
+                }
+            }
+            Expression::Dict(entries) => {
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.new_dict")]);
+                let operands = expr.operands();
+                atoms.extend(self.codegen_root(expr, Type::Dynamic, &operands));
+                for (key, value) in entries.iter() {
+                    atoms.extend(self.codegen_coerced(value, Type::Dynamic)?);
+                    atoms.extend(self.codegen_root(value, Type::Dynamic, &[key]));
+                    atoms.extend(self.codegen_coerced(key, Type::Dynamic)?);
+                    // hashing the key can raise.
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.init_entry")]);
+                }
+            }
+            Expression::Subscript(object, index) => {
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                atoms.extend(self.codegen_root(object, Type::Dynamic, &[index]));
+                atoms.extend(self.codegen_coerced(index, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(self.helper("getitem"))]);
+            }
+            Expression::Slice(object, lower, upper) => {
+                let bounds = [lower, upper];
+                atoms.extend(self.codegen_coerced(object, Type::Dynamic)?);
+                let rest: Vec<&Expression> = bounds.iter().filter_map(|b| b.as_deref()).collect();
+                atoms.extend(self.codegen_root(object, Type::Dynamic, &rest));
+                for (i, bound) in bounds.iter().enumerate() {
+                    match bound {
+                        Some(bound) => {
+                            atoms.extend(self.codegen_coerced(bound, Type::Dynamic)?);
+                            let rest: Vec<&Expression> = bounds[i + 1..]
+                                .iter()
+                                .filter_map(|b| b.as_deref())
+                                .collect();
+                            atoms.extend(self.codegen_root(bound, Type::Dynamic, &rest));
+                        }
+                        // a missing bound runs to the end of the list.
+                        None => atoms.extend(Self::constant(runtime::boxed(runtime::NONE, 0))),
+                    }
+                }
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.getslice")]);
             }
         }
-        atoms
+        Ok(atoms)
     }
 
-    fn prepend_dollar(name: &str) -> String {
-        let mut s = String::from("$");
-        s.push_str(name);
+    // a method of one of the program's classes called through the class is
+    // a plain call of its function. anywhere else, the method that is
+    // called depends on the class of the object, which is looked up in a
+    // table of the classes that have one of that name. objects of the
+    // builtin types, and instances of the other classes, fall through to
+    // the builtin methods.
+    fn codegen_method_call(
+        &mut self,
+        object: &Expression,
+        name: &str,
+        args: &[Argument],
+    ) -> Result<Vec<Wexp>, Error> {
+        if let Expression::Simple(Value::Variable(class, line)) = object {
+            let method = self
+                .class(class)
+                .and_then(|class| self.classes.resolve(class, name))
+                .map(|(function, params)| (function, params.to_vec()));
+            if let Some((function, params)) = method {
+                let mut atoms =
+                    self.codegen_arguments(&function, name, &params, None, args, *line)?;
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), Atom(Self::prepend_dollar(&function))]);
+                return Ok(atoms);
+            }
+        }
+        let targets = self.classes.dispatch(name);
+        if targets.is_empty() {
+            if Self::builtin_method(name).is_none() {
+                let mut atoms = self.codegen_coerced(object, Type::Dynamic)?;
+                atoms.extend(self.codegen_line());
+                atoms.extend(self.codegen_attribute(name));
+                atoms.extend(vec![
+                    wasm!(call),
+                    wasm!("$rt.no_attribute"),
+                    wasm!(unreachable),
+                ]);
+                return Ok(atoms);
+            }
+            let mut atoms = self.codegen_coerced(object, Type::Dynamic)?;
+            atoms.extend(self.codegen_root(object, Type::Dynamic, &Self::expressions(args)));
+            atoms.extend(self.codegen_builtin_method(name, args)?);
+            return Ok(atoms);
+        }
+        let receiver = self.temp(Type::Dynamic);
+        let mut atoms = self.codegen_coerced(object, Type::Dynamic)?;
+        atoms.extend(self.codegen_keep(Type::Dynamic, true));
+        atoms.extend(vec![wasm!(set_local), Atom(receiver.clone())]);
+        self.labels += 1;
+        let done = format!("$method.{}", self.labels);
+        let labels: Vec<String> = (0..targets.len())
+            .map(|i| format!("{}.{}", done, i))
+            .collect();
+        let default = format!("{}.default", done);
+        atoms.extend(vec![
+            wasm!(block),
+            Atom(done.clone()),
+            List(vec![wasm!(result), wasm!(i64)]),
+            wasm!(block),
+            Atom(default.clone()),
+        ]);
+        for label in labels.iter().rev() {
+            atoms.extend(vec![wasm!(block), Atom(label.clone())]);
+        }
+        atoms.extend(vec![
+            wasm!(get_local),
+            Atom(receiver.clone()),
+            wasm!(call),
+            wasm!("$rt.class_id"),
+            wasm!(br_table),
+        ]);
+        for class in 0..self.classes.iter().count() {
+            let label = targets
+                .iter()
+                .position(|(_, classes)| classes.contains(&class))
+                .map_or(&default, |i| &labels[i]);
+            atoms.push(Atom(label.clone()));
+        }
+        atoms.push(Atom(default.clone()));
+        let line = self.line;
+        for (function, classes) in targets.iter() {
+            atoms.push(wasm!("end"));
+            self.line_set = false;
+            let params = self.classes.resolve(classes[0], name).unwrap().1.to_vec();
+            match self.codegen_arguments(function, name, &params, Some(&receiver), args, line) {
+                Ok(arguments) => {
+                    atoms.extend(arguments);
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), Atom(Self::prepend_dollar(function))]);
+                }
+                // the arguments don't fit this method, which only matters
+                // if it is the one that is called.
+                Err(Error::TypeError { message, line }) => {
+                    for arg in args.iter() {
+                        atoms.extend(self.codegen_expression(arg.expression())?);
+                        atoms.push(wasm!(drop));
+                    }
+                    atoms.extend(self.codegen_raise(Exception::TypeError, &message, line));
+                    atoms.push(wasm!(unreachable));
+                }
+                Err(error) => return Err(error),
+            }
+            atoms.extend(vec![wasm!(br), Atom(done.clone())]);
+        }
+        atoms.push(wasm!("end"));
+        self.line_set = false;
+        atoms.extend(vec![wasm!(get_local), Atom(receiver)]);
+        if Self::builtin_method(name).is_some() {
+            atoms.extend(self.codegen_builtin_method(name, args)?);
+        } else {
+            atoms.extend(self.codegen_line());
+            atoms.extend(self.codegen_attribute(name));
+            atoms.extend(vec![
+                wasm!(call),
+                wasm!("$rt.no_attribute"),
+                wasm!(unreachable),
+            ]);
+        }
+        atoms.push(wasm!("end"));
+        self.line_set = false;
+        Ok(atoms)
+    }
+
+    // how many arguments each method of the builtin types requires, and
+    // how many more it can take.
+    fn builtin_method(name: &str) -> Option<(usize, usize)> {
+        match name {
+            "append" => Some((1, 0)),
+            "keys" | "values" | "items" => Some((0, 0)),
+            "get" => Some((1, 1)),
+            _ => None,
+        }
+    }
+
+    // the methods of the builtin types are runtime helpers that take the
+    // object, which is on the stack, and then the arguments, and check the
+    // kind of the object. optional arguments that are left out are passed
+    // as None. passing the wrong number of arguments is a TypeError once
+    // they have been evaluated, with python's message.
+    fn codegen_builtin_method(
+        &mut self,
+        name: &str,
+        args: &[Argument],
+    ) -> Result<Vec<Wexp>, Error> {
+        let (required, optional) = Self::builtin_method(name).unwrap();
+        let rest = Self::expressions(args);
+        let mut atoms = vec![];
+        for (i, arg) in rest.iter().enumerate() {
+            atoms.extend(self.codegen_coerced(arg, Type::Dynamic)?);
+            atoms.extend(self.codegen_root(arg, Type::Dynamic, &rest[i + 1..]));
+        }
+        atoms.extend(self.codegen_line());
+        let keywords = args.iter().any(|arg| matches!(arg, Argument::Keyword(..)));
+        let given = args.len();
+        if keywords {
+            let message = format!("{}() takes no keyword arguments", name);
+            atoms.extend(Self::codegen_throw_message(Exception::TypeError, &message));
+        } else if given < required || given > required + optional {
+            // python words it the way the method takes its arguments.
+            let message = match (required, optional) {
+                (0, 0) => format!("{}() takes no arguments ({} given)", name, given),
+                (1, 0) => format!("{}() takes exactly one argument ({} given)", name, given),
+                _ if given < required => format!(
+                    "{} expected at least {} arguments, got {}",
+                    name, required, given
+                ),
+                _ => format!(
+                    "{} expected at most {} arguments, got {}",
+                    name,
+                    required + optional,
+                    given
+                ),
+            };
+            atoms.extend(Self::codegen_throw_message(Exception::TypeError, &message));
+        } else {
+            for _ in args.len()..required + optional {
+                atoms.extend(Self::constant(runtime::boxed(runtime::NONE, 0)));
+            }
+            atoms.extend(vec![wasm!(call), Atom(format!("$rt.{}", name))]);
+        }
+        Ok(atoms)
+    }
+
+    // making an instance of one of the program's classes calls its
+    // __init__, if it has one, with the new instance, and gives the
+    // instance.
+    fn codegen_new_instance(
+        &mut self,
+        name: &str,
+        args: &[Argument],
+        line: usize,
+    ) -> Result<Vec<Wexp>, Error> {
+        let class = self.class(name).unwrap();
+        let mut atoms = self.codegen_value(&Value::Variable(name.to_owned(), line))?;
+        atoms.extend(self.codegen_line());
+        atoms.extend(vec![wasm!(call), wasm!("$rt.new_instance")]);
+        let instance = self.temp(Type::Dynamic);
+        atoms.extend(self.codegen_keep(Type::Dynamic, true));
+        atoms.extend(vec![wasm!(set_local), Atom(instance.clone())]);
+        let init = self
+            .classes
+            .resolve(class, "__init__")
+            .map(|(function, params)| (function, params.to_vec()));
+        if let Some((function, params)) = init {
+            let arguments = self.codegen_arguments(
+                &function,
+                "__init__",
+                &params,
+                Some(&instance),
+                args,
+                line,
+            )?;
+            atoms.extend(arguments);
+            atoms.extend(self.codegen_line());
+            atoms.extend(vec![
+                wasm!(call),
+                Atom(Self::prepend_dollar(&function)),
+                wasm!(call),
+                wasm!("$rt.check_init"),
+            ]);
+        }
+        atoms.extend(vec![wasm!(get_local), Atom(instance)]);
+        Ok(atoms)
+    }
+
+    // isinstance with a builtin exception class is the test an except
+    // clause makes, and everything is an instance of object.
+    fn codegen_isinstance(
+        &mut self,
+        value: &Argument,
+        class: &Argument,
+    ) -> Result<Vec<Wexp>, Error> {
+        let (value, class) = (value.expression(), class.expression());
+        let mut atoms = self.codegen_coerced(value, Type::Dynamic)?;
+        match class {
+            Expression::Simple(Value::Variable(name, _))
+                if self.exception_class(name).is_some() =>
+            {
+                let exception = self.exception_class(name).unwrap();
+                atoms.extend(vec![
+                    wasm!("i32.const"),
+                    Atom(exception.code().to_string()),
+                    wasm!(call),
+                    wasm!("$rt.matches"),
+                ]);
+            }
+            Expression::Simple(Value::Variable(name, _))
+                if name == "object" && self.scope(name) == Scope::Builtin =>
+            {
+                atoms.extend(vec![wasm!(drop), wasm!("i32.const"), wasm!("1")]);
+            }
+            _ => {
+                atoms.extend(self.codegen_root(value, Type::Dynamic, &[class]));
+                atoms.extend(self.codegen_coerced(class, Type::Dynamic)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.isinstance")]);
+            }
+        }
+        Ok(atoms)
+    }
+
+    // the number of the class `name` refers to, if it is one of the
+    // program's classes and nothing else of the module's.
+    fn class(&self, name: &str) -> Option<usize> {
+        if self.scope(name) != Scope::Global || self.defs.contains_key(name) {
+            return None;
+        }
+        self.classes.find(name)
+    }
+
+    // the special methods that take over the runtime helper `operation` for
+    // instances of the program's classes: the ones looked for on the first
+    // operand, in order, and the reflected one looked for on the second.
+    fn special_methods(operation: &str) -> (&'static [&'static str], Option<&'static str>) {
+        match operation {
+            "add" | "iadd" => (&["__add__"], Some("__radd__")),
+            "eq" => (&["__eq__"], Some("__eq__")),
+            "lt" => (&["__lt__"], Some("__gt__")),
+            "getitem" => (&["__getitem__"], None),
+            "setitem" => (&["__setitem__"], None),
+            "len" => (&["__len__"], None),
+            "str" => (&["__str__", "__repr__"], None),
+            "repr" => (&["__repr__"], None),
+            _ => (&[], None),
+        }
+    }
+
+    fn has_special(&self, operation: &str) -> bool {
+        let (methods, reflected) = Self::special_methods(operation);
+        let methods: Vec<&str> = methods.iter().chain(reflected.iter()).cloned().collect();
+        !self.classes.dispatch_first(&methods).is_empty()
+    }
+
+    // the function that does `operation`: the runtime helper of that name,
+    // unless one of the program's classes has a special method for it.
+    fn helper(&mut self, operation: &str) -> String {
+        if !self.has_special(operation) {
+            return format!("$rt.{}", operation);
+        }
+        // `+=` on an instance is the same as `+`.
+        let needed = if operation == "iadd" {
+            vec!["add", "iadd"]
+        } else {
+            vec![operation]
+        };
+        for operation in needed {
+            if !self.specials.iter().any(|special| special == operation) {
+                self.specials.push(operation.to_owned());
+            }
+        }
+        format!("$special.{}", operation)
+    }
+
+    // a function that takes the same arguments as the runtime helper
+    // `operation`, and calls the special method for it if the first operand
+    // is an instance whose class has one. if there was none, or it gave back
+    // NotImplemented, a binary operator then tries the reflected method of
+    // the second operand. a comparison gives whether what the method gave
+    // back is true. anything else goes to the helper.
+    fn codegen_special(&mut self, operation: &str) -> Wexp {
+        let (params, result): (&[&str], Option<&str>) = match operation {
+            "setitem" => (&["$value", "$object", "$index"], None),
+            "getitem" => (&["$object", "$index"], Some("i64")),
+            "len" => (&["$value"], Some("i32")),
+            "str" | "repr" => (&["$value"], Some("i64")),
+            "eq" | "lt" => (&["$a", "$b"], Some("i32")),
+            _ => (&["$a", "$b"], Some("i64")),
+        };
+        let mut func = vec![wasm!(func), Atom(format!("$special.{}", operation))];
+        for param in params.iter() {
+            func.push(List(vec![
+                wasm!(param),
+                Atom(param.to_string()),
+                wasm!(i64),
+            ]));
+        }
+        if let Some(t) = result {
+            func.push(List(vec![wasm!(result), Atom(t.to_string())]));
+        }
+        if operation == "iadd" {
+            func.extend(vec![
+                wasm!(get_local),
+                wasm!("$a"),
+                wasm!(call),
+                wasm!("$rt.is_instance"),
+                wasm!(get_local),
+                wasm!("$b"),
+                wasm!(call),
+                wasm!("$rt.is_instance"),
+                wasm!("i32.or"),
+                wasm!("if"),
+                List(vec![wasm!(result), wasm!(i64)]),
+                wasm!(get_local),
+                wasm!("$a"),
+                wasm!(get_local),
+                wasm!("$b"),
+                wasm!(call),
+                wasm!("$special.add"),
+                wasm!("else"),
+                wasm!(get_local),
+                wasm!("$a"),
+                wasm!(get_local),
+                wasm!("$b"),
+                wasm!(call),
+                wasm!("$rt.iadd"),
+                wasm!("end"),
+            ]);
+            return List(func);
+        }
+        let (methods, reflected) = Self::special_methods(operation);
+        if reflected.is_some() {
+            func.push(List(vec![wasm!(local), wasm!("$result"), wasm!(i64)]));
+        }
+        // the operands have to outlive the first method called, which can
+        // start a collection.
+        let rooted = reflected.is_some() && self.collects;
+        let restore = || {
+            if rooted {
+                vec![
+                    wasm!(get_local),
+                    wasm!("$top"),
+                    wasm!(set_global),
+                    wasm!("$shadow.top"),
+                ]
+            } else {
+                vec![]
+            }
+        };
+        if rooted {
+            func.push(List(vec![wasm!(local), wasm!("$top"), wasm!(i32)]));
+            func.extend(vec![
+                wasm!(get_global),
+                wasm!("$shadow.top"),
+                wasm!(set_local),
+                wasm!("$top"),
+            ]);
+            for param in params.iter() {
+                func.extend(vec![
+                    wasm!(get_local),
+                    Atom(param.to_string()),
+                    wasm!(call),
+                    wasm!("$rt.root"),
+                    wasm!(drop),
+                ]);
+            }
+        }
+        // what is done with what the method gives back.
+        let found = || match operation {
+            "add" | "eq" | "lt" => {
+                let mut atoms = vec![wasm!(tee_local), wasm!("$result")];
+                atoms.extend(Self::constant(runtime::boxed(runtime::NONE, 1)));
+                atoms.extend(vec![
+                    wasm!("i64.ne"),
+                    wasm!("if"),
+                    wasm!(get_local),
+                    wasm!("$result"),
+                ]);
+                if operation != "add" {
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.truthy")]);
+                }
+                atoms.extend(restore());
+                atoms.extend(vec![wasm!(return), wasm!("end")]);
+                atoms
+            }
+            "setitem" => vec![wasm!(drop), wasm!(return)],
+            "len" => vec![wasm!(call), wasm!("$rt.check_len"), wasm!(return)],
+            "str" | "repr" => vec![
+                wasm!("i32.const"),
+                Atom(((operation == "repr") as u8).to_string()),
+                wasm!(call),
+                wasm!("$rt.check_str"),
+                wasm!(return),
+            ],
+            _ => vec![wasm!(return)],
+        };
+        let args: Vec<&str> = match operation {
+            "setitem" => vec!["$object", "$index", "$value"],
+            _ => params.to_vec(),
+        };
+        func.extend(self.codegen_dispatch(operation, methods, &args, &found));
+        if let Some(reflected) = reflected {
+            let args: Vec<&str> = args.iter().rev().cloned().collect();
+            let label = format!("{}.reflected", operation);
+            let dispatch = self.codegen_dispatch(&label, &[reflected], &args, &found);
+            // comparisons try it either way.
+            if operation == "add" && !dispatch.is_empty() {
+                func.extend(vec![
+                    wasm!(get_local),
+                    wasm!("$a"),
+                    wasm!(get_local),
+                    wasm!("$b"),
+                    wasm!(call),
+                    wasm!("$rt.same_class"),
+                    wasm!("i32.eqz"),
+                    wasm!("if"),
+                ]);
+                func.extend(dispatch);
+                func.push(wasm!("end"));
+            } else {
+                func.extend(dispatch);
+            }
+        }
+        func.extend(restore());
+        if operation == "str" || operation == "repr" {
+            func.extend(vec![wasm!(get_local), wasm!("$value")]);
+        } else {
+            for param in params.iter() {
+                func.extend(vec![wasm!(get_local), Atom(param.to_string())]);
+            }
+            func.extend(vec![wasm!(call), Atom(format!("$rt.{}", operation))]);
+        }
+        List(func)
+    }
+
+    // calls the first of the methods `names` that the class of the instance
+    // in the local `args[0]` has, with the locals `args`, and runs `found` on
+    // what it gives back. anything else carries on after it, and so does
+    // `found` if it doesn't return.
+    fn codegen_dispatch(
+        &self,
+        label: &str,
+        names: &[&str],
+        args: &[&str],
+        found: &dyn Fn() -> Vec<Wexp>,
+    ) -> Vec<Wexp> {
+        let targets = self.classes.dispatch_first(names);
+        if targets.is_empty() {
+            return vec![];
+        }
+        let done = format!("$dispatch.{}", label);
+        let labels: Vec<String> = (0..targets.len())
+            .map(|i| format!("{}.{}", done, i))
+            .collect();
+        let mut atoms = vec![wasm!(block), Atom(done.clone())];
+        for label in labels.iter().rev() {
+            atoms.extend(vec![wasm!(block), Atom(label.clone())]);
+        }
+        atoms.extend(vec![
+            wasm!(get_local),
+            wasm!(args[0]),
+            wasm!(call),
+            wasm!("$rt.class_id"),
+            wasm!(br_table),
+        ]);
+        for class in 0..self.classes.iter().count() {
+            let label = targets
+                .iter()
+                .position(|(_, classes)| classes.contains(&class))
+                .map_or(&done, |i| &labels[i]);
+            atoms.push(Atom(label.clone()));
+        }
+        atoms.push(Atom(done.clone()));
+        for (function, classes) in targets.iter() {
+            atoms.push(wasm!("end"));
+            let params = self.classes.resolve_first(classes[0], names).unwrap().1;
+            match Self::special_arguments(function, params, args) {
+                Ok(arguments) => {
+                    atoms.extend(arguments);
+                    atoms.extend(vec![wasm!(call), Atom(Self::prepend_dollar(function))]);
+                    atoms.extend(found());
+                }
+                Err(Some(message)) => {
+                    atoms.extend(Self::codegen_throw_message(Exception::TypeError, &message))
+                }
+                Err(None) => atoms.extend(Self::codegen_throw(Exception::TypeError)),
+            }
+            atoms.extend(vec![wasm!(br), Atom(done.clone())]);
+        }
+        atoms.push(wasm!("end"));
+        atoms
+    }
+
+    // the locals `args` bound to the parameters of the method `function`,
+    // or else python's message for why they don't fit them. there is none
+    // for parameters that take the rest, which a special method isn't
+    // passed.
+    fn special_arguments(
+        function: &str,
+        params: &[Parameter],
+        args: &[&str],
+    ) -> Result<Vec<Wexp>, Option<String>> {
+        let bound: Vec<Argument> = args
+            .iter()
+            .map(|_| Argument::Positional(Expression::Simple(Value::Integer(0))))
+            .collect();
+        let name = function.rsplit('.').next().unwrap();
+        let bindings = match binding::bind(name, params, &bound, 0) {
+            Ok(bindings) => bindings,
+            Err(Error::TypeError { message, .. }) => return Err(Some(message)),
+            Err(_) => return Err(None),
+        };
+        let mut atoms = vec![];
+        for (param, binding) in params.iter().zip(bindings.iter()) {
+            match binding {
+                Binding::Argument(i) => atoms.extend(vec![wasm!(get_local), wasm!(args[*i])]),
+                Binding::Default => atoms.extend(vec![
+                    wasm!(get_global),
+                    Atom(Self::default_global(function, param.name())),
+                ]),
+                Binding::Star(_) | Binding::DoubleStar(_) => return Err(None),
+            }
+        }
+        Ok(atoms)
+    }
+
+    pub fn codegen_call(
+        &mut self,
+        name: &str,
+        args: &[Argument],
+        line: usize,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        match self.def(name).cloned() {
+            Some(params) => {
+                // calling a function before its def has run is a NameError.
+                if !self.defined.contains(name) {
+                    self.checked.insert(name.to_owned());
+                    atoms.push(wasm!(get_global));
+                    atoms.push(Atom(Self::defined_flag(name)));
+                    atoms.extend(vec![wasm!("i32.eqz"), wasm!("if")]);
+                    let message = self.undefined(name);
+                    atoms.extend(self.codegen_raise(Exception::NameError, &message, line));
+                    atoms.push(wasm!("end"));
+                }
+                atoms.extend(self.codegen_arguments(name, name, &params, None, args, line)?);
+            }
+            // nothing else can be called, but what is called and its
+            // arguments are still evaluated first.
+            None => {
+                let callee = Expression::Simple(Value::Variable(name.to_owned(), line));
+                let temp = self.temp(Type::Dynamic);
+                atoms.extend(self.codegen_coerced(&callee, Type::Dynamic)?);
+                let rest = Self::expressions(args);
+                atoms.extend(self.codegen_root(&callee, Type::Dynamic, &rest));
+                atoms.extend(vec![wasm!(set_local), Atom(temp.clone())]);
+                for arg in args.iter() {
+                    atoms.extend(self.codegen_expression(arg.expression())?);
+                    atoms.push(wasm!(drop));
+                }
+                atoms.extend(vec![wasm!(get_local), Atom(temp)]);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![
+                    wasm!(call),
+                    wasm!("$rt.not_callable"),
+                    wasm!(unreachable),
+                ]);
+                return Ok(atoms);
+            }
+        }
+        // the callee keeps the line it was called from for tracebacks, and
+        // puts it back when it returns.
+        atoms.extend(self.codegen_line());
+        atoms.push(wasm!("call"));
+        atoms.push(wasm!(&Self::prepend_dollar(name)));
+        Ok(atoms)
+    }
+
+    // the arguments of a call to `function`, called `name` in errors, bound
+    // to its parameters. a method called on an object is given the object
+    // in `receiver` as its first argument.
+    fn codegen_arguments(
+        &mut self,
+        function: &str,
+        name: &str,
+        params: &[Parameter],
+        receiver: Option<&str>,
+        args: &[Argument],
+        line: usize,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        let mut bound = args.to_vec();
+        if receiver.is_some() {
+            bound.insert(
+                0,
+                Argument::Positional(Expression::Simple(Value::Integer(0))),
+            );
+        }
+        let skipped = bound.len() - args.len();
+        let bindings = binding::bind(name, params, &bound, line)?;
+        let order: Vec<usize> = bindings
+            .iter()
+            .flat_map(|binding| match binding {
+                Binding::Argument(i) => vec![*i],
+                Binding::Default => vec![],
+                Binding::Star(surplus) | Binding::DoubleStar(surplus) => surplus.clone(),
+            })
+            .collect();
+        // surplus arguments are packed after the rest have been evaluated.
+        let packs: Vec<Expression> = bindings
+            .iter()
+            .filter_map(|binding| match binding {
+                Binding::Star(surplus) => Some(Expression::Tuple(
+                    surplus
+                        .iter()
+                        .map(|i| bound[*i].expression().clone())
+                        .collect(),
+                )),
+                Binding::DoubleStar(surplus) => Some(Expression::Dict(
+                    surplus
+                        .iter()
+                        .map(|i| match bound[*i] {
+                            Argument::Keyword(ref keyword, ref value) => (
+                                Expression::Simple(Value::String(keyword.as_bytes().to_vec())),
+                                value.clone(),
+                            ),
+                            Argument::Positional(_) => unreachable!(),
+                        })
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        // arguments are evaluated in the order they are written. if
+        // keywords put them out of parameter order, stash them in
+        // temporaries first.
+        let temps = if order.windows(2).all(|w| w[0] < w[1]) {
+            None
+        } else {
+            let mut temps: Vec<(String, Type)> = receiver
+                .iter()
+                .map(|receiver| (receiver.to_string(), Type::Dynamic))
+                .collect();
+            for (i, arg) in args.iter().enumerate() {
+                let t = self.expression_type(arg.expression());
+                let temp = self.temp(t);
+                atoms.extend(self.codegen_expression(arg.expression())?);
+                if packs.is_empty() {
+                    let rest = Self::expressions(&args[i + 1..]);
+                    atoms.extend(self.codegen_root(arg.expression(), t, &rest));
+                } else {
+                    atoms.extend(self.codegen_keep(t, true));
+                }
+                atoms.push(wasm!(set_local));
+                atoms.push(Atom(temp.clone()));
+                temps.push((temp, t));
+            }
+            Some(temps)
+        };
+        let mut packs = packs.iter();
+        for (param, binding) in params.iter().zip(bindings.iter()) {
+            let t = self
+                .types
+                .child(function)
+                .unwrap()
+                .variable(param.name())
+                .unwrap();
+            match binding {
+                Binding::Argument(i) => match temps {
+                    Some(ref temps) => {
+                        let (ref temp, from) = temps[*i];
+                        atoms.push(wasm!(get_local));
+                        atoms.push(Atom(temp.clone()));
+                        atoms.extend(Self::coerce(from, t));
+                    }
+                    None if *i < skipped => {
+                        atoms.push(wasm!(get_local));
+                        atoms.push(Atom(receiver.unwrap().to_owned()));
+                        atoms.extend(Self::coerce(Type::Dynamic, t));
+                    }
+                    None => {
+                        let arg = args[*i - skipped].expression();
+                        atoms.extend(self.codegen_coerced(arg, t)?);
+                        let mut rest = Self::expressions(&args[*i - skipped + 1..]);
+                        rest.extend(packs.clone());
+                        atoms.extend(self.codegen_root(arg, t, &rest));
+                    }
+                },
+                Binding::Default => {
+                    atoms.push(wasm!(get_global));
+                    atoms.push(Atom(Self::default_global(function, param.name())));
+                }
+                Binding::Star(surplus) | Binding::DoubleStar(surplus) => {
+                    let pack = packs.next().unwrap();
+                    let later = packs.len() > 0;
+                    match temps {
+                        Some(ref temps) => {
+                            atoms.extend(self.codegen_packed(pack, surplus, temps, later)?)
+                        }
+                        None => {
+                            atoms.extend(self.codegen_expression(pack)?);
+                            let rest: Vec<&Expression> = packs.clone().collect();
+                            atoms.extend(self.codegen_root(pack, Type::Dynamic, &rest));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(atoms)
+    }
+
+    // the tuple or dict `pack` of surplus arguments, made from the
+    // temporaries they were stashed in. `later` is whether anything that
+    // can collect is still to come.
+    fn codegen_packed(
+        &mut self,
+        pack: &Expression,
+        surplus: &[usize],
+        temps: &[(String, Type)],
+        later: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        match pack {
+            Expression::Tuple(_) => {
+                atoms.extend(vec![wasm!("i32.const"), Atom(surplus.len().to_string())]);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.new_tuple")]);
+                atoms.extend(self.codegen_keep(Type::Dynamic, later));
+                for (j, i) in surplus.iter().enumerate() {
+                    let (ref temp, from) = temps[*i];
+                    atoms.extend(vec![wasm!("i32.const"), Atom(j.to_string())]);
+                    atoms.extend(vec![wasm!(get_local), Atom(temp.clone())]);
+                    atoms.extend(Self::coerce(from, Type::Dynamic));
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.init_item")]);
+                }
+            }
+            Expression::Dict(entries) => {
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.new_dict")]);
+                // the keys are new strs.
+                atoms.extend(self.codegen_keep(Type::Dynamic, later || !surplus.is_empty()));
+                for ((key, _), i) in entries.iter().zip(surplus.iter()) {
+                    let (ref temp, from) = temps[*i];
+                    atoms.extend(vec![wasm!(get_local), Atom(temp.clone())]);
+                    atoms.extend(Self::coerce(from, Type::Dynamic));
+                    atoms.extend(self.codegen_expression(key)?);
+                    atoms.extend(self.codegen_line());
+                    atoms.extend(vec![wasm!(call), wasm!("$rt.init_entry")]);
+                }
+            }
+            _ => unreachable!(),
+        }
+        Ok(atoms)
+    }
+
+    // arithmetic on operands of known types is done inline, or with the
+    // runtime's unboxed helpers for the operators that can raise. anything
+    // else goes through the boxed helper, which checks the operand kinds.
+    fn codegen_arithmetic(
+        &mut self,
+        expr: &Expression,
+        left: &Expression,
+        right: &Expression,
+        operator: &str,
+    ) -> Result<Vec<Wexp>, Error> {
+        let t = self.expression_type(expr);
+        // ints are added, subtracted and multiplied in 64 bits, where they
+        // can't overflow, and the result is boxed as an int or a long.
+        if t == Type::Dynamic
+            && ["add", "sub", "mul"].contains(&operator)
+            && self.is_int(left)
+            && self.is_int(right)
+        {
+            let mut atoms = self.codegen_widened(left)?;
+            atoms.extend(self.codegen_widened(right)?);
+            atoms.push(Atom(format!("i64.{}", operator)));
+            atoms.extend(self.codegen_line());
+            atoms.extend(vec![wasm!(call), wasm!("$rt.int64")]);
+            return Ok(atoms);
+        }
+        let mut atoms = self.codegen_coerced(left, t)?;
+        atoms.extend(self.codegen_root(left, t, &[right]));
+        atoms.extend(self.codegen_coerced(right, t)?);
+        let instruction = match (t, operator) {
+            // only a remainder of ints is known to be an int.
+            (Type::Int, _) => format!("$rt.{}_int", operator),
+            (Type::Float, "div") | (Type::Float, "mod") | (Type::Float, "pow") => {
+                format!("$rt.{}_float", operator)
+            }
+            (Type::Float, _) => format!("f64.{}", operator),
+            _ => self.helper(operator),
+        };
+        if instruction.starts_with('$') {
+            atoms.extend(self.codegen_line());
+            atoms.push(wasm!(call));
+        }
+        atoms.push(Atom(instruction));
+        Ok(atoms)
+    }
+
+    // bitwise operators on known ints and bools are done inline. so are
+    // right shifts by a constant that wasm's shift instructions get right,
+    // and any other shift goes through the runtime, which checks the count.
+    fn codegen_bitwise(
+        &mut self,
+        expr: &Expression,
+        left: &Expression,
+        right: &Expression,
+        operator: &str,
+    ) -> Result<Vec<Wexp>, Error> {
+        let t = self.expression_type(expr);
+        let mut atoms = self.codegen_coerced(left, t)?;
+        atoms.extend(self.codegen_root(left, t, &[right]));
+        atoms.extend(self.codegen_coerced(right, t)?);
+        let constant = matches!(right, Expression::Simple(Value::Integer(n)) if *n < 32);
+        let instruction = match (t, operator) {
+            (Type::Dynamic, _) => format!("$rt.{}", operator),
+            (_, "shr") if !constant => "$rt.shr_int".to_owned(),
+            (_, "shr") => "i32.shr_s".to_owned(),
+            _ => format!("i32.{}", operator),
+        };
+        if instruction.starts_with('$') {
+            atoms.extend(self.codegen_line());
+            atoms.push(wasm!(call));
+        }
+        atoms.push(Atom(instruction));
+        Ok(atoms)
+    }
+
+    // a negated number is folded into a constant, and any other int is
+    // negated in 64 bits like it is subtracted.
+    fn codegen_negation(&mut self, expr: &Expression, e: &Expression) -> Result<Vec<Wexp>, Error> {
+        let t = self.expression_type(expr);
+        Ok(match (t, e) {
+            (Type::Int, Expression::Simple(Value::Integer(i))) => {
+                vec![
+                    wasm!("i32.const"),
+                    Atom((i.wrapping_neg() as i32).to_string()),
+                ]
+            }
+            (Type::Float, Expression::Simple(Value::Float(f))) => {
+                vec![wasm!("f64.const"), Atom(format!("{:?}", -f))]
+            }
+            (Type::Dynamic, _) if self.is_int(e) => {
+                let mut atoms = vec![wasm!("i64.const"), wasm!("0")];
+                atoms.extend(self.codegen_widened(e)?);
+                atoms.push(wasm!("i64.sub"));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.int64")]);
+                atoms
+            }
+            (Type::Float, _) => {
+                let mut atoms = self.codegen_coerced(e, t)?;
+                atoms.push(wasm!("f64.neg"));
+                atoms
+            }
+            _ => {
+                let mut atoms = self.codegen_coerced(e, t)?;
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.neg")]);
+                atoms
+            }
+        })
+    }
+
+    fn is_int(&self, expr: &Expression) -> bool {
+        matches!(self.expression_type(expr), Type::Int | Type::Bool)
+    }
+
+    // an int or bool as an i64.
+    fn codegen_widened(&mut self, expr: &Expression) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = self.codegen_coerced(expr, Type::Int)?;
+        atoms.push(wasm!("i64.extend_i32_s"));
+        Ok(atoms)
+    }
+
+    // a comparison. in a chain of them, its left operand can be the one the
+    // comparison before it kept, and it can keep its right one for the next.
+    fn codegen_link(
+        &mut self,
+        link: &Expression,
+        kept: &mut Option<String>,
+        keep: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        match link {
+            Expression::Lt(v, e)
+            | Expression::Gt(v, e)
+            | Expression::Leq(v, e)
+            | Expression::Geq(v, e)
+            | Expression::EqEq(v, e)
+            | Expression::Ne(v, e) => {
+                let operator = match link {
+                    Expression::Lt(..) => "lt",
+                    Expression::Gt(..) => "gt",
+                    Expression::Leq(..) => "le",
+                    Expression::Geq(..) => "ge",
+                    Expression::EqEq(..) => "eq",
+                    _ => "ne",
+                };
+                atoms.extend(self.codegen_comparison(v, e, operator, kept, keep)?);
+            }
+            Expression::In(item, container) | Expression::NotIn(item, container) => {
+                atoms.extend(self.codegen_operands(item, container, Type::Dynamic, kept, keep)?);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.contains")]);
+                if let Expression::NotIn(..) = link {
+                    atoms.push(wasm!("i32.eqz"));
+                }
+            }
+            Expression::Is(left, right) | Expression::IsNot(left, right) => {
+                atoms.extend(self.codegen_identity(left, right, kept, keep)?);
+                if let Expression::IsNot(..) = link {
+                    atoms.push(wasm!("i32.eqz"));
+                }
+            }
+            _ => unreachable!("{:?} is no comparison", link),
+        }
+        Ok(atoms)
+    }
+
+    // a chain of comparisons holds if all of them do, and stops at the first
+    // that doesn't.
+    fn codegen_chain(&mut self, links: &[Expression]) -> Result<Vec<Wexp>, Error> {
+        let mut kept = None;
+        let mut atoms = self.codegen_link(&links[0], &mut kept, true)?;
+        // the comparisons after the first might not run, so whatever line
+        // they set can't be relied on afterwards.
+        let line_set = self.line_set;
+        for (i, link) in links.iter().enumerate().skip(1) {
+            atoms.push(wasm!("if"));
+            atoms.push(List(vec![wasm!(result), wasm!("i32")]));
+            atoms.extend(self.codegen_link(link, &mut kept, i + 1 < links.len())?);
+        }
+        for _ in 1..links.len() {
+            atoms.extend(vec![
+                wasm!("else"),
+                wasm!("i32.const"),
+                wasm!("0"),
+                wasm!("end"),
+            ]);
+        }
+        self.line_set = line_set && self.line_set;
+        Ok(atoms)
+    }
+
+    // the operands of a comparison, both converted to `t`. the left one is
+    // the value in `kept` if there is one, and `keep` puts the right one in
+    // a temporary for the comparison after this one.
+    fn codegen_operands(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        t: Type,
+        kept: &mut Option<String>,
+        keep: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = match kept.take() {
+            Some(temp) => {
+                let mut atoms = vec![wasm!(get_local), Atom(temp)];
+                atoms.extend(Self::coerce(self.expression_type(left), t));
+                atoms
+            }
+            None => {
+                let mut atoms = self.codegen_coerced(left, t)?;
+                atoms.extend(self.codegen_root(left, t, &[right]));
+                atoms
+            }
+        };
+        if keep {
+            let r = self.expression_type(right);
+            let temp = self.temp(r);
+            atoms.extend(self.codegen_expression(right)?);
+            atoms.extend(self.codegen_keep(r, true));
+            atoms.extend(vec![wasm!(tee_local), Atom(temp.clone())]);
+            atoms.extend(Self::coerce(r, t));
+            *kept = Some(temp);
+        } else {
+            atoms.extend(self.codegen_coerced(right, t)?);
+        }
+        Ok(atoms)
+    }
+
+    // comparisons always give a bool, and only raise in the special methods
+    // of the program's classes.
+    fn codegen_comparison(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        operator: &str,
+        kept: &mut Option<String>,
+        keep: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let l = self.expression_type(left);
+        let r = self.expression_type(right);
+        let t = l.arithmetic(r);
+        let mut atoms = self.codegen_operands(left, right, t, kept, keep)?;
+        match (t, operator) {
+            (Type::Int, "eq") | (Type::Int, "ne") => atoms.push(Atom(format!("i32.{}", operator))),
+            (Type::Int, _) => atoms.push(Atom(format!("i32.{}_s", operator))),
+            (Type::Float, _) => atoms.push(Atom(format!("f64.{}", operator))),
+            _ => {
+                let helper = self.helper(operator);
+                if !helper.starts_with("$rt.") {
+                    atoms.extend(self.codegen_line());
+                }
+                atoms.extend(vec![wasm!(call), Atom(helper)]);
+            }
+        }
+        Ok(atoms)
+    }
+
+    // two values are the same object if they are boxed to the same bits. so
+    // equal ints, bools and floats are always identical, and None is only
+    // ever itself. values of the same unboxed type are compared as they are.
+    fn codegen_identity(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        kept: &mut Option<String>,
+        keep: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let l = self.expression_type(left);
+        let r = self.expression_type(right);
+        let t = match (l, r) {
+            (Type::Int, Type::Int) | (Type::Bool, Type::Bool) => l,
+            _ => Type::Dynamic,
+        };
+        // the left object can't be freed, and its address reused, while the
+        // right one is made.
+        let mut atoms = self.codegen_operands(left, right, t, kept, keep)?;
+        atoms.push(if t == Type::Dynamic {
+            wasm!("i64.eq")
+        } else {
+            wasm!("i32.eq")
+        });
+        Ok(atoms)
+    }
+
+    // `and` and `or` evaluate to one of their operands, and only evaluate
+    // the right one if the left one doesn't decide the result.
+    fn codegen_short_circuit(
+        &mut self,
+        expr: &Expression,
+        left: &Expression,
+        right: &Expression,
+        and: bool,
+    ) -> Result<Vec<Wexp>, Error> {
+        let t = self.expression_type(expr);
+        let temp = self.temp(t);
+        let mut atoms = self.codegen_coerced(left, t)?;
+        atoms.extend(self.codegen_root(left, t, &[right]));
+        atoms.extend(vec![wasm!(tee_local), Atom(temp.clone())]);
+        atoms.extend(self.codegen_truthy(t));
+        if !and {
+            atoms.push(wasm!("i32.eqz"));
+        }
+        atoms.push(wasm!("if"));
+        atoms.push(List(vec![wasm!(result), wasm!(t.wasm())]));
+        // the right operand might not run, so whatever line it sets can't be
+        // relied on afterwards.
+        let line_set = self.line_set;
+        atoms.extend(self.codegen_coerced(right, t)?);
+        self.line_set = line_set && self.line_set;
+        atoms.extend(vec![
+            wasm!("else"),
+            wasm!(get_local),
+            Atom(temp),
+            wasm!("end"),
+        ]);
+        Ok(atoms)
+    }
+
+    // only the value that is chosen is evaluated, so the line is only known
+    // afterwards if both of them set it.
+    fn codegen_conditional(
+        &mut self,
+        expr: &Expression,
+        condition: &Expression,
+        a: &Expression,
+        b: &Expression,
+    ) -> Result<Vec<Wexp>, Error> {
+        let t = self.expression_type(expr);
+        let mut atoms = self.codegen_expression(condition)?;
+        atoms.extend(self.codegen_truthy(self.expression_type(condition)));
+        atoms.push(wasm!("if"));
+        atoms.push(List(vec![wasm!(result), wasm!(t.wasm())]));
+        let line_set = self.line_set;
+        atoms.extend(self.codegen_coerced(a, t)?);
+        let a_set = self.line_set;
+        self.line_set = line_set;
+        atoms.push(wasm!("else"));
+        atoms.extend(self.codegen_coerced(b, t)?);
+        self.line_set = a_set && self.line_set;
+        atoms.push(wasm!("end"));
+        Ok(atoms)
+    }
+
+    // generates `expr` and converts its value to the representation of `t`.
+    fn codegen_coerced(&mut self, expr: &Expression, t: Type) -> Result<Vec<Wexp>, Error> {
+        let from = self.expression_type(expr);
+        let mut atoms = self.codegen_expression(expr)?;
+        atoms.extend(Self::coerce(from, t));
+        Ok(atoms)
+    }
+
+    // the conversions between representations. inference only ever widens
+    // a type to dynamic, or an int or bool to a float in arithmetic.
+    fn coerce(from: Type, to: Type) -> Vec<Wexp> {
+        match (from, to) {
+            (from, to) if from == to => vec![],
+            (Type::Bool, Type::Int) => vec![],
+            // a str is boxed already, and a value that can be anything is one
+            // wherever inference found it has to be.
+            (Type::Str, Type::Dynamic) | (Type::Dynamic, Type::Str) => vec![],
+            (Type::Int, Type::Float) | (Type::Bool, Type::Float) => {
+                vec![wasm!("f64.convert_i32_s")]
+            }
+            (Type::Int, Type::Dynamic) => vec![wasm!(call), wasm!("$rt.int")],
+            (Type::Bool, Type::Dynamic) => vec![wasm!(call), wasm!("$rt.bool")],
+            (Type::Float, Type::Dynamic) => vec![wasm!(call), wasm!("$rt.float")],
+            (from, to) => panic!("can't convert {} to {}", from, to),
+        }
+    }
+
+    // leaves an i32 that is nonzero if the value of type `t` on the stack is
+    // true.
+    fn codegen_truthy(&self, t: Type) -> Vec<Wexp> {
+        match t {
+            Type::Int | Type::Bool => vec![],
+            Type::Float => vec![wasm!("f64.const"), wasm!("0"), wasm!("f64.ne")],
+            _ => vec![wasm!(call), wasm!("$rt.truthy")],
+        }
+    }
+
+    pub fn codegen_value(&mut self, value: &Value) -> Result<Vec<Wexp>, Error> {
+        let mut atoms = vec![];
+        match value {
+            Value::Integer(i) => atoms.extend(vec![wasm!("i32.const"), Atom(i.to_string())]),
+            Value::Float(f) => atoms.extend(vec![wasm!("f64.const"), Atom(format!("{:?}", f))]),
+            Value::String(bytes) => {
+                atoms.extend(self.codegen_line());
+                atoms.extend(Self::codegen_str(bytes));
+            }
+            // the limbs of a long are stored from constants, two at a time.
+            Value::Long(limbs) => {
+                atoms.extend(vec![wasm!("i32.const"), Atom(limbs.len().to_string())]);
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.new_long")]);
+                for (i, pair) in limbs.chunks(2).enumerate() {
+                    let word = pair
+                        .iter()
+                        .rev()
+                        .fold(0, |word, &limb| word << 32 | u64::from(limb));
+                    atoms.extend(vec![
+                        wasm!("i32.const"),
+                        Atom((8 * i).to_string()),
+                        wasm!("i64.const"),
+                        Atom(format!("{:#x}", word)),
+                        wasm!(call),
+                        wasm!("$rt.init_bytes"),
+                    ]);
+                }
+            }
+            // an attribute the class's body has already set.
+            Value::Variable(v, _) if self.class_attribute(v).is_some() => {
+                let class = self.class_attribute(v).unwrap();
+                atoms.extend(vec![wasm!(get_local), Atom(class)]);
+                atoms.extend(self.codegen_attribute(v));
+                atoms.extend(self.codegen_line());
+                atoms.extend(vec![wasm!(call), wasm!("$rt.getattr")]);
+            }
+            Value::Variable(v, line) => match self.scope(v) {
+                Scope::Local if self.is_local(v) => {
+                    atoms.extend(self.codegen_bound(v, *line));
+                    atoms.push(Atom("get_local".to_owned()));
+                    let value = Self::prepend_dollar(v);
+                    atoms.push(Atom(value));
+                }
+                Scope::Global if self.is_global(v) => {
+                    atoms.extend(self.codegen_bound(v, *line));
+                    atoms.push(Atom("get_global".to_owned()));
+                    let value = Self::prepend_dollar(v);
+                    atoms.push(Atom(value));
+                }
+                Scope::Builtin => match v.as_str() {
+                    "True" => atoms.extend(vec![wasm!("i32.const"), wasm!("1")]),
+                    "False" => atoms.extend(vec![wasm!("i32.const"), wasm!("0")]),
+                    // NotImplemented is told apart from None by its payload.
+                    "NotImplemented" => {
+                        atoms.extend(Self::constant(runtime::boxed(runtime::NONE, 1)))
+                    }
+                    "None" => atoms.extend(Self::constant(runtime::boxed(runtime::NONE, 0))),
+                    _ => {
+                        return Err(Error::Unsupported {
+                            message: format!("using the builtin '{}' as a value", v),
+                            line: *line,
+                        })
+                    }
+                },
+                // functions can only be called.
+                _ => {
+                    return Err(Error::Unsupported {
+                        message: format!("using the function '{}' as a value", v),
+                        line: *line,
+                    })
+                }
+            },
+            Value::Complex(e) => atoms.extend(self.codegen_expression(e)?),
+        }
+        Ok(atoms)
+    }
+
+    // the builtin exception class that `name` refers to, unless the program
+    // binds it to something else.
+    fn exception_class(&self, name: &str) -> Option<Exception> {
+        match self.table().scope(name) {
+            Some(Scope::Builtin) => Exception::from_name(name),
+            _ => None,
+        }
+    }
+
+    fn table(&self) -> &SymbolTable {
+        match self.function {
+            Some(ref function) => self.symbols.child(function).unwrap(),
+            None => &self.symbols,
+        }
+    }
+
+    fn scope(&self, name: &str) -> Scope {
+        self.table().scope(name).unwrap()
+    }
+
+    fn expression_type(&self, expr: &Expression) -> Type {
+        self.types.expression(self.function.as_deref(), expr)
+    }
+
+    fn variable_type(&self, name: &str) -> Type {
+        self.types.lookup(self.function.as_deref(), name)
+    }
+
+    // the types of the values a function returns as separate results, if
+    // `name` is one that does.
+    fn results(&self, name: &str) -> &[Type] {
+        match self.types.child(name) {
+            Some(types) if self.def(name).is_some() => types.results(),
+            _ => &[],
+        }
+    }
+
+    // the parameters of the top-level def that calling `name` runs, unless
+    // the name is bound to something else where it is called.
+    fn def(&self, name: &str) -> Option<&Vec<Parameter>> {
+        if self.scope(name) != Scope::Global {
+            return None;
+        }
+        self.defs.get(name)
+    }
+
+    // the temp holding the class whose body has set the attribute `name`.
+    fn class_attribute(&self, name: &str) -> Option<String> {
+        match &self.class_body {
+            Some((class, attributes)) if attributes.contains(name) => Some(class.clone()),
+            _ => None,
+        }
+    }
+
+    // whether `name` is a local of the wasm function being generated.
+    fn is_local(&self, name: &str) -> bool {
+        self.table()
+            .variables()
+            .iter()
+            .any(|variable| variable == name)
+    }
+
+    // whether `name` is one of the module's variables.
+    fn is_global(&self, name: &str) -> bool {
+        self.symbols
+            .variables()
+            .iter()
+            .any(|variable| variable == name)
+    }
+
+    // locals for the variables that aren't already parameters, for the
+    // address of the function's frame, and for the flags of the variables
+    // that can be read before they are assigned.
+    fn codegen_locals(&self, params: &[Parameter]) -> Vec<Wexp> {
+        let frame = self
+            .frame
+            .as_ref()
+            .map(|_| List(vec![wasm!(local), wasm!("$frame"), wasm!(i32)]));
+        self.table()
+            .variables()
+            .iter()
+            .filter(|variable| params.iter().all(|param| param.name() != variable.as_str()))
+            .map(|variable| {
+                List(vec![
+                    wasm!(local),
+                    Atom(Self::prepend_dollar(variable)),
+                    wasm!(self.variable_type(variable).wasm()),
+                ])
+            })
+            .chain(frame)
+            .chain(
+                self.unbound
+                    .locals(self.function.as_ref().unwrap())
+                    .into_iter()
+                    .map(|local| {
+                        List(vec![
+                            wasm!(local),
+                            Atom(Self::defined_flag(local)),
+                            wasm!(i32),
+                        ])
+                    }),
+            )
+            .collect()
+    }
+
+    // tells the runtime which line is running, ahead of something that can
+    // raise an exception.
+    fn codegen_line(&mut self) -> Vec<Wexp> {
+        if self.line_set {
+            return vec![];
+        }
+        self.line_set = true;
+        vec![
+            wasm!("i32.const"),
+            Atom(self.line.to_string()),
+            wasm!(set_global),
+            wasm!("$line"),
+        ]
+    }
+
+    // a new str of `bytes`, which are stored 8 at a time, from constants.
+    fn codegen_str(bytes: &[u8]) -> Vec<Wexp> {
+        let mut atoms = vec![
+            wasm!("i32.const"),
+            Atom(bytes.len().to_string()),
+            wasm!(call),
+            wasm!("$rt.new_str"),
+        ];
+        for (i, chunk) in bytes.chunks(8).enumerate() {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            atoms.extend(vec![
+                wasm!("i32.const"),
+                Atom((8 * i).to_string()),
+                wasm!("i64.const"),
+                Atom(format!("{:#x}", u64::from_le_bytes(word))),
+                wasm!(call),
+                wasm!("$rt.init_bytes"),
+            ]);
+        }
+        atoms
+    }
+
+    // raises a new `exception` from `line`, with `message` as its arg.
+    fn codegen_raise(&mut self, exception: Exception, message: &str, line: usize) -> Vec<Wexp> {
+        let mut atoms = vec![
+            wasm!("i32.const"),
+            Atom(line.to_string()),
+            wasm!(set_global),
+            wasm!("$line"),
+        ];
+        atoms.extend(Self::codegen_str(message.as_bytes()));
+        atoms.extend(vec![
+            wasm!("i32.const"),
+            Atom(exception.code().to_string()),
+            wasm!(call),
+            wasm!("$rt.raise_arg"),
+        ]);
+        atoms
+    }
+
+    // raises `exception` at the line already given to the runtime. the
+    // validator takes whatever is on the stack as the value that would have
+    // been produced.
+    fn codegen_throw(exception: Exception) -> Vec<Wexp> {
+        vec![
+            wasm!("i32.const"),
+            Atom(exception.code().to_string()),
+            wasm!(call),
+            wasm!("$rt.raise"),
+            wasm!(unreachable),
+        ]
+    }
+
+    // raises `exception` with `message` as its arg, at the line already
+    // given to the runtime.
+    fn codegen_throw_message(exception: Exception, message: &str) -> Vec<Wexp> {
+        let mut atoms = Self::codegen_str(message.as_bytes());
+        atoms.extend(vec![
+            wasm!("i32.const"),
+            Atom(exception.code().to_string()),
+            wasm!(call),
+            wasm!("$rt.raise_arg"),
+            wasm!(unreachable),
+        ]);
+        atoms
+    }
+
+    fn constant(value: u64) -> Vec<Wexp> {
+        vec![wasm!("i64.const"), Atom(runtime::constant(value))]
+    }
+
+    // what a variable of type `t` holds before it is assigned.
+    fn zero(t: Type) -> Vec<Wexp> {
+        match t {
+            Type::Int | Type::Bool => vec![wasm!("i32.const"), wasm!("0")],
+            Type::Float => vec![wasm!("f64.const"), wasm!("0")],
+            _ => Self::constant(runtime::boxed(runtime::NONE, 0)),
+        }
+    }
+
+    fn defined_before_first_call(&self, body: &Body) -> HashSet<String> {
+        let mut defined = HashSet::new();
+        for stmt in body.statements.iter() {
+            if self.statement_calls(stmt) {
+                break;
+            }
+            if let Statement::Def { name, .. } = stmt {
+                defined.insert(name.clone());
+            }
+        }
+        defined
+    }
+
+    // whether running `stmt` can call a function. a def only runs its
+    // default expressions.
+    fn statement_calls(&self, stmt: &Statement) -> bool {
+        self.statement_any(stmt, &|expr| {
+            matches!(
+                expr,
+                Expression::Call { .. } | Expression::MethodCall { .. }
+            )
+        })
+    }
+
+    // whether running `stmt` can start a collection, which happens when the
+    // runtime allocates an object, or in a call.
+    fn statement_collects(&self, stmt: &Statement) -> bool {
+        Self::statement_stores(stmt)
+            || Self::catches(stmt)
+            || self.has_special("str") && Self::prints(stmt)
+            || self.statement_any(stmt, &|expr| self.allocates(expr))
+    }
+
+    // whether `stmt` has a print statement, which can call the __str__ of
+    // an instance.
+    fn prints(stmt: &Statement) -> bool {
+        matches!(stmt, Statement::Print { .. })
+            || stmt
+                .bodies()
+                .iter()
+                .any(|body| body.statements.iter().any(Self::prints))
+    }
+
+    // whether `stmt` has a try statement, which needs the variables it can
+    // see kept on the shadow stack, since making the exceptions it catches
+    // can start a collection.
+    fn catches(stmt: &Statement) -> bool {
+        matches!(stmt, Statement::Try { .. })
+            || stmt
+                .bodies()
+                .iter()
+                .any(|body| body.statements.iter().any(Self::catches))
+    }
+
+    fn expression_collects(&self, expr: &Expression) -> bool {
+        Self::expression_any(expr, &|expr| self.allocates(expr))
+    }
+
+    fn allocates(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Call { .. }
+            | Expression::MethodCall { .. }
+            | Expression::List(_)
+            | Expression::Tuple(_)
+            | Expression::Dict(_)
+            | Expression::Slice(..)
+            | Expression::Simple(Value::String(_))
+            | Expression::Simple(Value::Long(_)) => true,
+            // an item of a str is a new str.
+            Expression::Subscript(..) => true,
+            // adding lists makes a new one, and ints that overflow, like any
+            // arithmetic on longs, make a new long.
+            Expression::Add(..)
+            | Expression::Sub(..)
+            | Expression::Mult(..)
+            | Expression::Div(..)
+            | Expression::Mod(..)
+            | Expression::Power(..)
+            | Expression::Neg(..)
+            | Expression::BitAnd(..)
+            | Expression::BitOr(..)
+            | Expression::BitXor(..)
+            | Expression::LShift(..)
+            | Expression::RShift(..)
+            | Expression::Invert(..) => self.expression_type(expr).boxed(),
+            // so can the special methods a comparison calls.
+            Expression::EqEq(..) => self.has_special("eq"),
+            Expression::Lt(..) => self.has_special("lt"),
+            Expression::Chain(links) => links.iter().any(|link| self.allocates(link)),
+            _ => false,
+        }
+    }
+
+    // whether `f` holds for any expression that running `stmt` evaluates.
+    fn statement_any(&self, stmt: &Statement, f: &dyn Fn(&Expression) -> bool) -> bool {
+        self.types
+            .evaluated(self.function.as_deref(), stmt)
+            .iter()
+            .any(|e| Self::expression_any(e, f))
+            || stmt.bodies().iter().any(|body| {
+                body.statements
+                    .iter()
+                    .any(|stmt| self.statement_any(stmt, f))
+            })
+    }
+
+    // whether `stmt` has a print to stdout with a trailing comma, whose line
+    // python ends when the program exits.
+    fn leaves_line_open(stmt: &Statement) -> bool {
+        let bodies = match stmt {
+            Statement::Print {
+                stream: Stream::Stdout,
+                newline: false,
+                ..
+            } => return true,
+            Statement::Def { body, .. } => vec![body],
+            _ => stmt.bodies(),
+        };
+        bodies
+            .iter()
+            .any(|body| body.statements.iter().any(Self::leaves_line_open))
+    }
+
+    // whether running `stmt` stores an item or an attribute, which can make
+    // a dict's table bigger. so can making a class.
+    fn statement_stores(stmt: &Statement) -> bool {
+        let stores = match stmt {
+            Statement::SetItem { .. } | Statement::SetAttr { .. } | Statement::Class { .. } => true,
+            Statement::AugAssign(e) => matches!(
+                e.operands()[0],
+                Expression::Subscript(..) | Expression::Attribute(..)
+            ),
+            Statement::Unpack(targets, _) => targets.iter().any(Self::target_stores),
+            _ => false,
+        };
+        stores
+            || stmt
+                .bodies()
+                .iter()
+                .any(|body| body.statements.iter().any(Self::statement_stores))
+    }
+
+    fn target_stores(target: &Target) -> bool {
+        match target {
+            Target::Name(_) => false,
+            Target::Item(..) | Target::Attribute(..) => true,
+            Target::Tuple(targets) => targets.iter().any(Self::target_stores),
+        }
+    }
+
+    // whether storing into `targets` can start a collection.
+    fn targets_collect(&self, targets: &[Target]) -> bool {
+        targets.iter().any(|target| {
+            Self::target_stores(target)
+                || target
+                    .expressions()
+                    .iter()
+                    .any(|e| self.expression_collects(e))
+        })
+    }
+
+    fn expression_any(expr: &Expression, f: &dyn Fn(&Expression) -> bool) -> bool {
+        f(expr)
+            || expr
+                .operands()
+                .iter()
+                .any(|operand| Self::expression_any(operand, f))
+    }
+
+    // the globals that get slots at the bottom of the shadow stack.
+    fn statics(&self, body: &Body) -> Vec<String> {
+        let mut statics: Vec<String> = self
+            .symbols
+            .variables()
+            .iter()
+            .filter(|variable| self.types.variable(variable).is_some_and(Type::boxed))
+            .map(|variable| Self::prepend_dollar(variable))
+            .collect();
+        for Function { name, params, .. } in body.functions() {
+            let types = self.types.child(&name).unwrap();
+            for param in params.iter() {
+                if let Parameter::Default(param, _) = param {
+                    if types.variable(param).is_some_and(Type::boxed) {
+                        statics.push(Self::default_global(&name, param));
+                    }
+                }
+            }
+        }
+        statics
+    }
+
+    // the variables that get slots in the frame of the function being
+    // generated. a function only needs a frame if the collector can run
+    // while it is holding something that can point to an object.
+    fn frame(&self, body: &Body) -> Option<Vec<String>> {
+        if !self.collects
+            || !body
+                .statements
+                .iter()
+                .any(|stmt| self.statement_collects(stmt))
+        {
+            return None;
+        }
+        let slots: Vec<String> = self
+            .table()
+            .variables()
+            .iter()
+            .filter(|variable| self.variable_type(variable).boxed())
+            .cloned()
+            .collect();
+        let function = self.function.as_deref();
+        if slots.is_empty()
+            && !self.types.body_dynamic(function, body)
+            && !body.statements.iter().any(Self::catches)
+        {
+            return None;
+        }
+        Some(slots)
+    }
+
+    // pushes the function being generated on the call stack, keeping where
+    // its entry ends if it catches exceptions, then reserves its frame, with
+    // its parameters in their slots.
+    fn codegen_enter(&self, params: &[Parameter], catches: bool) -> Vec<Wexp> {
+        let function = self.function.as_ref().unwrap();
+        let mut atoms = vec![
+            wasm!("i32.const"),
+            Atom(self.names[function].to_string()),
+            wasm!(call),
+            wasm!("$rt.call"),
+        ];
+        if catches {
+            atoms.extend(vec![
+                wasm!(get_global),
+                wasm!("$calls.top"),
+                wasm!(set_local),
+                wasm!("$calls"),
+            ]);
+        }
+        let slots = match self.frame {
+            Some(ref slots) => slots,
+            None => return atoms,
+        };
+        atoms.extend(vec![
+            wasm!("i32.const"),
+            Atom(slots.len().to_string()),
+            wasm!(call),
+            wasm!("$rt.enter"),
+            wasm!(set_local),
+            wasm!("$frame"),
+        ]);
+        for param in params.iter() {
+            atoms.extend(self.codegen_slot(&Self::prepend_dollar(param.name()), false));
+        }
+        atoms
+    }
+
+    // pops the frame of the function being generated, and its entry on the
+    // call stack, before it returns.
+    fn codegen_leave(&self) -> Vec<Wexp> {
+        let mut atoms = match self.frame {
+            Some(_) => vec![
+                wasm!(get_local),
+                wasm!("$frame"),
+                wasm!(set_global),
+                wasm!("$shadow.top"),
+            ],
+            None => vec![],
+        };
+        atoms.extend(vec![wasm!(call), wasm!("$rt.return")]);
+        atoms
+    }
+
+    // copies a variable that has just been set into its slot on the shadow
+    // stack, if it has one.
+    fn codegen_slot(&self, variable: &str, global: bool) -> Vec<Wexp> {
+        if global {
+            match self.statics.iter().position(|name| name == variable) {
+                Some(i) => vec![
+                    wasm!("i32.const"),
+                    Atom((runtime::SHADOW_BASE + 8 * i).to_string()),
+                    wasm!(get_global),
+                    Atom(variable.to_owned()),
+                    wasm!("i64.store"),
+                ],
+                None => vec![],
+            }
+        } else {
+            let slots = match self.frame {
+                Some(ref slots) => slots,
+                None => return vec![],
+            };
+            match slots
+                .iter()
+                .position(|name| Self::prepend_dollar(name) == variable)
+            {
+                Some(i) => vec![
+                    wasm!(get_local),
+                    wasm!("$frame"),
+                    wasm!(get_local),
+                    Atom(variable.to_owned()),
+                    wasm!("i64.store"),
+                    Atom(format!("offset={}", 8 * i)),
+                ],
+                None => vec![],
+            }
+        }
+    }
+
+    // keeps the value of `expr`, converted to `t` and on top of the stack,
+    // alive while `rest`, the operands evaluated after it, run. a local in
+    // the frame is already safe, since nothing else can change it.
+    fn codegen_root(&mut self, expr: &Expression, t: Type, rest: &[&Expression]) -> Vec<Wexp> {
+        if !t.boxed() || !self.collects || !rest.iter().any(|e| self.expression_collects(e)) {
+            return vec![];
+        }
+        if let Expression::Simple(Value::Variable(name, _)) = expr {
+            if self.frame.is_some() && self.scope(name) == Scope::Local {
+                return vec![];
+            }
+        }
+        self.rooted = true;
+        vec![wasm!(call), wasm!("$rt.root")]
+    }
+
+    // keeps the value of type `t` on top of the stack, which is about to be
+    // put in a temporary, alive if the collector can run before it is used.
+    fn codegen_keep(&mut self, t: Type, later: bool) -> Vec<Wexp> {
+        if !t.boxed() || !self.collects || !later {
+            return vec![];
+        }
+        self.rooted = true;
+        vec![wasm!(call), wasm!("$rt.root")]
+    }
+
+    // drops the temporaries the current statement put on the shadow stack.
+    fn codegen_unroot(&self) -> Vec<Wexp> {
+        let mut atoms = match self.frame {
+            Some(ref slots) => vec![
+                wasm!(get_local),
+                wasm!("$frame"),
+                wasm!("i32.const"),
+                Atom((8 * (slots.len() + self.held)).to_string()),
+                wasm!("i32.add"),
+            ],
+            None => vec![
+                wasm!("i32.const"),
+                Atom((runtime::SHADOW_BASE + 8 * (self.statics.len() + self.held)).to_string()),
+            ],
+        };
+        atoms.extend(vec![wasm!(set_global), wasm!("$shadow.top")]);
+        atoms
+    }
+
+    fn expressions(args: &[Argument]) -> Vec<&Expression> {
+        args.iter().map(Argument::expression).collect()
+    }
+
+    fn temp(&mut self, t: Type) -> String {
+        let temp = format!("$tmp.{}", self.temps.len());
+        self.temps.push((temp.clone(), t));
+        temp
+    }
+
+    fn codegen_temps(&mut self) -> Vec<Wexp> {
+        self.temps
+            .drain(..)
+            .map(|(temp, t)| List(vec![wasm!(local), Atom(temp), wasm!(t.wasm())]))
+            .collect()
+    }
+
+    // the flag that says whether the def of a function has run, or whether
+    // a variable has been assigned.
+    fn defined_flag(name: &str) -> String {
+        format!("${}.defined", name)
+    }
+
+    fn default_global(function: &str, param: &str) -> String {
+        format!("${}.default.{}", function, param)
+    }
+
+    fn prepend_dollar(name: &str) -> String {
+        let mut s = String::from("$");
+        s.push_str(name);
         s
     }
 }
 
 #[cfg(test)]
 mod test {
+    use common::*;
+    use exception::Handling;
+    use scope;
     use testing::*;
+    use types;
 
     macro_rules! codegen_test {
         (name: $name:ident,text: $text:expr,wat: $expected:expr,) => {
@@ -301,196 +3390,277 @@ mod test {
                 assert_eq!(wexp.to_string(), expected);
             }
         };
+        (name: $name:ident,handling: $handling:expr,text: $text:expr,wat: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let wexp = codegen_handling($text, $handling);
+                assert_eq!(wexp, $expected);
+            }
+        };
     }
 
     codegen_test! {
         name: empty_program,
         text: "",
-        wat: "(module (func $print (import \"host\" \"print\") (param i32)) \
-        (func (export \"main\")))",
+        wat: "(module \
+         (func (export \"main\")))",
     }
 
     codegen_test! {
         name: print_int,
         text: "print 24",
-        wat: "(module (func $print (import \"host\" \"print\") \
-         (param i32)) (func (export \"main\") i32.const 24 call $print))",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 24 \
+         call $rt.int \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: print_float,
+        text: "print 1.5",
+        wat: "(module \
+         (func (export \"main\") \
+         f64.const 1.5 \
+         call $rt.float \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: print_none,
+        text: "print None",
+        wat: "(module \
+         (func (export \"main\") \
+         i64.const 0xfffb_000000000000 \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: print_leq,
         text: "print 8 <= 8",
-        wat: "(module (func $print (import \"host\" \"print\") \
-         (param i32)) (func (export \"main\") i32.const 8 i32.const 8 \
-         i32.le_s call $print))",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 8 \
+         i32.const 8 \
+         i32.le_s \
+         call $rt.bool \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: print_ne,
         text: "print 8 != 8",
-        wat: "(module (func $print (import \"host\" \"print\") \
-         (param i32)) (func (export \"main\") i32.const 8 i32.const 8 \
-         i32.ne call $print))",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 8 \
+         i32.const 8 \
+         i32.ne \
+         call $rt.bool \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: add_int,
         text: "print 1 + 2",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
          (func (export \"main\") \
          i32.const 1 \
+         i64.extend_i32_s \
          i32.const 2 \
-         i32.add \
-         call $print\
-         ))",
+         i64.extend_i32_s \
+         i64.add \
+         i32.const 1 \
+         set_global $line \
+         call $rt.int64 \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: sub_int,
         text: "print 2 - 1",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
          (func (export \"main\") \
          i32.const 2 \
+         i64.extend_i32_s \
          i32.const 1 \
-         i32.sub \
-         call $print\
-         ))",
+         i64.extend_i32_s \
+         i64.sub \
+         i32.const 1 \
+         set_global $line \
+         call $rt.int64 \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: div_int,
         text: "print 9 / 3",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
          (func (export \"main\") \
          i32.const 9 \
+         call $rt.int \
          i32.const 3 \
-         i32.div_s \
-         call $print\
-         ))",
+         call $rt.int \
+         i32.const 1 \
+         set_global $line \
+         call $rt.div \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: mod_int,
         text: "print 13 % 7",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
          (func (export \"main\") \
          i32.const 13 \
          i32.const 7 \
-         i32.rem_s \
-         call $print\
-         ))",
+         i32.const 1 \
+         set_global $line \
+         call $rt.mod_int \
+         call $rt.int \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: print_and,
         text: "print 1>=2 and 2<7",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
-         (func (export \"main\") \
+         (func (export \"main\") (local $tmp.0 i32) \
          i32.const 1 \
          i32.const 2 \
          i32.ge_s \
+         tee_local $tmp.0 \
+         if (result i32) \
          i32.const 2 \
          i32.const 7 \
          i32.lt_s \
-         i32.and \
-         call $print\
-         ))",
+         else \
+         get_local $tmp.0 \
+         end \
+         call $rt.bool \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: add_and_sub_int,
         text: "print 2 + 2 - 3",
         wat: "(module \
-         (func $print (import \"host\" \"print\") (param i32)) \
          (func (export \"main\") \
          i32.const 2 \
+         i64.extend_i32_s \
          i32.const 2 \
+         i64.extend_i32_s \
+         i64.add \
+         i32.const 1 \
+         set_global $line \
+         call $rt.int64 \
          i32.const 3 \
-         i32.sub \
-         i32.add \
-         call $print))",
+         call $rt.int \
+         call $rt.sub \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: codegen_def,
         text: "def f():\n  return 8",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f \
-            (result i32) \
-            i32.const 8) \
-            (func (export \"main\")\
-            ))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 8 \
+         call $rt.return \
+         return) \
+         (func (export \"main\")))",
     }
 
     codegen_test! {
         name: def_param,
         text: "def f(n):\n  return n",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f \
-            (param $n i32) \
-            (result i32) \
-            get_local $n) \
-            (func (export \"main\")\
-            ))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $n i64) (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $n \
+         call $rt.return \
+         return) \
+         (func (export \"main\")))",
     }
 
     codegen_test! {
         name: def_params,
         text: "def f(m, n, o, p):\n  return p",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f \
-            (param $m i32) \
-            (param $n i32) \
-            (param $o i32) \
-            (param $p i32) \
-            (result i32) \
-            get_local $p) \
-            (func (export \"main\")\
-            ))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $m i64) (param $n i64) (param $o i64) (param $p i64) (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $p \
+         call $rt.return \
+         return) \
+         (func (export \"main\")))",
     }
 
     codegen_test! {
         name: function_call,
         text: "def f(a, b):\n  return a + b\nprint f(2, 3)",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f (param $a i32) (param $b i32) (result i32) \
-            get_local $a \
-            get_local $b \
-            i32.add) \
-            (func (export \"main\") \
-            i32.const 2 \
-            i32.const 3 \
-            call $f \
-            call $print))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $b i32) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $a \
+         i64.extend_i32_s \
+         get_local $b \
+         i64.extend_i32_s \
+         i64.add \
+         i32.const 2 \
+         set_global $line \
+         call $rt.int64 \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 2 \
+         i32.const 3 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
     }
 
     codegen_test! {
         name: if_else,
         text: "def f(a):\n  if a < 5:\n    return 0\n  else:\n    return 1\nprint f(1)",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f (param $a i32) (result i32) \
-            get_local $a \
-            i32.const 5 \
-            i32.lt_s \
-            if (result i32) \
-            i32.const 0 \
-            else \
-            i32.const 1 \
-            end) \
-            (func (export \"main\") \
-            i32.const 1 \
-            call $f \
-            call $print))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $a \
+         i32.const 5 \
+         i32.lt_s \
+         if \
+         i32.const 0 \
+         call $rt.return \
+         return \
+         else \
+         i32.const 1 \
+         call $rt.return \
+         return \
+         end \
+         unreachable) \
+         (func (export \"main\") \
+         i32.const 1 \
+         i32.const 6 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print))",
     }
 
     codegen_test! {
@@ -498,33 +3668,51 @@ mod test {
         text: "def f(n):\n if n < 5:\n  return 0\n elif n < 10:\n  return 1\n else:\
         \n  return 2\nprint f(4)\nprint f(8)\nprint f(11)",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f (param $n i32) (result i32) \
-                get_local $n \
-                i32.const 5 \
-                i32.lt_s \
-                if (result i32) \
-                    i32.const 0 \
-                else \
-                    get_local $n \
-                    i32.const 10 \
-                    i32.lt_s \
-                    if (result i32) \
-                        i32.const 1 \
-                    else \
-                        i32.const 2 \
-                    end \
-                end) \
-            (func (export \"main\") \
-                i32.const 4 \
-                call $f \
-                call $print \
-                i32.const 8 \
-                call $f \
-                call $print \
-                i32.const 11 \
-                call $f \
-                call $print))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $n i32) (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $n \
+         i32.const 5 \
+         i32.lt_s \
+         if \
+         i32.const 0 \
+         call $rt.return \
+         return \
+         else \
+         get_local $n \
+         i32.const 10 \
+         i32.lt_s \
+         if \
+         i32.const 1 \
+         call $rt.return \
+         return \
+         else \
+         i32.const 2 \
+         call $rt.return \
+         return \
+         end \
+         end \
+         unreachable) \
+         (func (export \"main\") \
+         i32.const 4 \
+         i32.const 8 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print \
+         i32.const 8 \
+         i32.const 9 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print \
+         i32.const 11 \
+         i32.const 10 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print))",
     }
 
     codegen_test! {
@@ -532,40 +3720,60 @@ mod test {
         text: "def f(n):\n if n < 5:\n  return 0\n elif n < 10:\n  return 1\n elif n < 15:\
         \n  return 2  \n else:\n  return 3\nprint f(4)\nprint f(8)\nprint f(81)",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $f (param $n i32) (result i32) \
-                get_local $n \
-                i32.const 5 \
-                i32.lt_s \
-                if (result i32) \
-                    i32.const 0 \
-                else \
-                    get_local $n \
-                    i32.const 10 \
-                    i32.lt_s \
-                    if (result i32) \
-                        i32.const 1 \
-                    else \
-                        get_local $n \
-                        i32.const 15 \
-                        i32.lt_s \
-                        if (result i32) \
-                            i32.const 2 \
-                        else \
-                            i32.const 3 \
-                        end \
-                    end \
-                end) \
-            (func (export \"main\") \
-                i32.const 4 \
-                call $f \
-                call $print \
-                i32.const 8 \
-                call $f \
-                call $print \
-                i32.const 81 \
-                call $f \
-                call $print))",
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $n i32) (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $n \
+         i32.const 5 \
+         i32.lt_s \
+         if \
+         i32.const 0 \
+         call $rt.return \
+         return \
+         else \
+         get_local $n \
+         i32.const 10 \
+         i32.lt_s \
+         if \
+         i32.const 1 \
+         call $rt.return \
+         return \
+         else \
+         get_local $n \
+         i32.const 15 \
+         i32.lt_s \
+         if \
+         i32.const 2 \
+         call $rt.return \
+         return \
+         else \
+         i32.const 3 \
+         call $rt.return \
+         return \
+         end \
+         end \
+         end \
+         unreachable) \
+         (func (export \"main\") \
+         i32.const 4 \
+         i32.const 10 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print \
+         i32.const 8 \
+         i32.const 11 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print \
+         i32.const 81 \
+         i32.const 12 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print))",
     }
 
     codegen_test! {
@@ -573,27 +3781,1060 @@ mod test {
         text: "def fib(n):\n  if n < 2:\n    return n\n  else:\n    return fib(n - 2) + fib(n - 1)\
         \nprint fib(4)",
         wat: "(module \
-            (func $print (import \"host\" \"print\") (param i32)) \
-            (func $fib (param $n i32) (result i32) \
-            get_local $n \
-            i32.const 2 \
-            i32.lt_s \
-            if (result i32) \
-            get_local $n \
-            else \
-            get_local $n \
-            i32.const 2 \
-            i32.sub \
-            call $fib \
-            get_local $n \
-            i32.const 1 \
-            i32.sub \
-            call $fib \
-            i32.add \
-            end) \
-            (func (export \"main\") \
-            i32.const 4 \
-            call $fib \
-            call $print))",
+         (data (i32.const 49536) \"\\03\\66\\69\\62\") \
+         (func $fib (param $n i64) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 1 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $frame \
+         get_local $n \
+         i64.store offset=0 \
+         get_local $n \
+         i32.const 2 \
+         call $rt.int \
+         call $rt.lt \
+         if \
+         get_local $n \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return \
+         else \
+         get_local $n \
+         i32.const 2 \
+         call $rt.int \
+         i32.const 5 \
+         set_global $line \
+         call $rt.sub \
+         call $fib \
+         call $rt.root \
+         get_local $n \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.sub \
+         call $fib \
+         call $rt.add \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return \
+         end \
+         unreachable) \
+         (func (export \"main\") \
+         i32.const 4 \
+         call $rt.int \
+         i32.const 6 \
+         set_global $line \
+         call $fib \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: default_param,
+        text: "def f(a, b=2):\n  return a + b\nprint f(1)",
+        wat: "(module \
+         (global $f.default.b (mut i32) (i32.const 0)) \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $b i32) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $a \
+         i64.extend_i32_s \
+         get_local $b \
+         i64.extend_i32_s \
+         i64.add \
+         i32.const 2 \
+         set_global $line \
+         call $rt.int64 \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 2 \
+         set_global $f.default.b \
+         i32.const 1 \
+         get_global $f.default.b \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: keyword_arguments_in_order,
+        text: "def f(a, b):\n  return a - b\nprint f(a=2, b=1)",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $b i32) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $a \
+         i64.extend_i32_s \
+         get_local $b \
+         i64.extend_i32_s \
+         i64.sub \
+         i32.const 2 \
+         set_global $line \
+         call $rt.int64 \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 2 \
+         i32.const 1 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: keyword_arguments_out_of_order,
+        text: "def f(a, b):\n  return a - b\nprint f(b=1, a=2)",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $b i32) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $a \
+         i64.extend_i32_s \
+         get_local $b \
+         i64.extend_i32_s \
+         i64.sub \
+         i32.const 2 \
+         set_global $line \
+         call $rt.int64 \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") (local $tmp.0 i32) (local $tmp.1 i32) \
+         i32.const 1 \
+         set_local $tmp.0 \
+         i32.const 2 \
+         set_local $tmp.1 \
+         get_local $tmp.1 \
+         get_local $tmp.0 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: variables,
+        text: "def f(a):\n  b = a + 1\n  return b\nc = True\nprint c",
+        wat: "(module \
+         (global $c (mut i32) (i32.const 0)) \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i64) (result i64) (local $b i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $a \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 2 \
+         set_global $line \
+         call $rt.add \
+         set_local $b \
+         get_local $b \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 1 \
+         set_global $c \
+         get_global $c \
+         call $rt.bool \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: global_statement,
+        text: "def f():\n  global n\n  n = n + 1\n  return n",
+        wat: "(module \
+         (global $n (mut i64) (i64.const 0xfffb_000000000000)) \
+         (global $n.defined (mut i32) (i32.const 0)) \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_global $n.defined \
+         i32.eqz \
+         if \
+         i32.const 3 \
+         set_global $line \
+         i32.const 30 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x6e206c61626f6c67 \
+         call $rt.init_bytes \
+         i32.const 8 \
+         i64.const 0x20276e2720656d61 \
+         call $rt.init_bytes \
+         i32.const 16 \
+         i64.const 0x6420746f6e207369 \
+         call $rt.init_bytes \
+         i32.const 24 \
+         i64.const 0x64656e696665 \
+         call $rt.init_bytes \
+         i32.const 1 \
+         call $rt.raise_arg \
+         end \
+         get_global $n \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 3 \
+         set_global $line \
+         call $rt.add \
+         set_global $n \
+         i32.const 1 \
+         set_global $n.defined \
+         get_global $n.defined \
+         i32.eqz \
+         if \
+         i32.const 4 \
+         set_global $line \
+         i32.const 30 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x6e206c61626f6c67 \
+         call $rt.init_bytes \
+         i32.const 8 \
+         i64.const 0x20276e2720656d61 \
+         call $rt.init_bytes \
+         i32.const 16 \
+         i64.const 0x6420746f6e207369 \
+         call $rt.init_bytes \
+         i32.const 24 \
+         i64.const 0x64656e696665 \
+         call $rt.init_bytes \
+         i32.const 1 \
+         call $rt.raise_arg \
+         end \
+         get_global $n \
+         call $rt.return \
+         return) \
+         (func (export \"main\")))",
+    }
+
+    codegen_test! {
+        name: call_before_def,
+        text: "print f()\ndef f():\n  return 1",
+        wat: "(module \
+         (global $f.defined (mut i32) (i32.const 0)) \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 1 \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         get_global $f.defined \
+         i32.eqz \
+         if \
+         i32.const 1 \
+         set_global $line \
+         i32.const 23 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x27662720656d616e \
+         call $rt.init_bytes \
+         i32.const 8 \
+         i64.const 0x20746f6e20736920 \
+         call $rt.init_bytes \
+         i32.const 16 \
+         i64.const 0x64656e69666564 \
+         call $rt.init_bytes \
+         i32.const 1 \
+         call $rt.raise_arg \
+         end \
+         i32.const 1 \
+         set_global $line \
+         call $f \
+         call $rt.int \
+         call $rt.print \
+         i32.const 1 \
+         set_global $f.defined))",
+    }
+
+    codegen_test! {
+        name: float_arithmetic,
+        text: "print 1.5 * 2",
+        wat: "(module \
+         (func (export \"main\") \
+         f64.const 1.5 \
+         i32.const 2 \
+         f64.convert_i32_s \
+         f64.mul \
+         call $rt.float \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: dynamic_variable,
+        text: "x = 1\nx = None\nprint x + 1",
+        wat: "(module \
+         (global $x (mut i64) (i64.const 0xfffb_000000000000)) \
+         (func (export \"main\") \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         i32.const 1 \
+         call $rt.int \
+         set_global $x \
+         i32.const 512 \
+         get_global $x \
+         i64.store \
+         i64.const 0xfffb_000000000000 \
+         set_global $x \
+         i32.const 512 \
+         get_global $x \
+         i64.store \
+         get_global $x \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 3 \
+         set_global $line \
+         call $rt.add \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: list_items,
+        text: "xs = [1, 2.5]\nprint xs[0]",
+        wat: "(module \
+         (global $xs (mut i64) (i64.const 0xfffb_000000000000)) \
+         (func (export \"main\") \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         i32.const 2 \
+         i32.const 1 \
+         set_global $line \
+         call $rt.new_list \
+         i32.const 0 \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.init_item \
+         i32.const 1 \
+         f64.const 2.5 \
+         call $rt.float \
+         call $rt.init_item \
+         set_global $xs \
+         i32.const 512 \
+         get_global $xs \
+         i64.store \
+         get_global $xs \
+         i32.const 0 \
+         call $rt.int \
+         i32.const 2 \
+         set_global $line \
+         call $rt.getitem \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: dict_display,
+        text: "d = {'key': 1}\nprint 'key' in d",
+        wat: "(module \
+         (global $d (mut i64) (i64.const 0xfffb_000000000000)) \
+         (func (export \"main\") \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         i32.const 1 \
+         set_global $line \
+         call $rt.new_dict \
+         call $rt.root \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.root \
+         i32.const 3 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x79656b \
+         call $rt.init_bytes \
+         call $rt.init_entry \
+         set_global $d \
+         i32.const 512 \
+         get_global $d \
+         i64.store \
+         i32.const 520 \
+         set_global $shadow.top \
+         i32.const 2 \
+         set_global $line \
+         i32.const 3 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x79656b \
+         call $rt.init_bytes \
+         get_global $d \
+         call $rt.contains \
+         call $rt.bool \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: shadow_stack,
+        text: "def f(a):\n  return a\ndef g(a):\n  b = f(a)\n  return a + f(b)\
+               \nx = g(1.5)\nprint x + g(1)",
+        wat: "(module \
+         (global $x (mut i64) (i64.const 0xfffb_000000000000)) \
+         (data (i32.const 49536) \"\\01\\66\\01\\67\") \
+         (func $f (param $a i64) (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $a \
+         call $rt.return \
+         return) \
+         (func $g (param $a i64) (result i64) (local $b i64) (local $frame i32) \
+         i32.const 49538 \
+         call $rt.call \
+         i32.const 2 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $frame \
+         get_local $a \
+         i64.store offset=0 \
+         get_local $a \
+         i32.const 4 \
+         set_global $line \
+         call $f \
+         set_local $b \
+         get_local $frame \
+         get_local $b \
+         i64.store offset=8 \
+         get_local $a \
+         get_local $b \
+         i32.const 5 \
+         set_global $line \
+         call $f \
+         call $rt.add \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         f64.const 1.5 \
+         call $rt.float \
+         i32.const 6 \
+         set_global $line \
+         call $g \
+         set_global $x \
+         i32.const 512 \
+         get_global $x \
+         i64.store \
+         get_global $x \
+         call $rt.root \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 7 \
+         set_global $line \
+         call $g \
+         call $rt.add \
+         call $rt.print \
+         i32.const 520 \
+         set_global $shadow.top))",
+    }
+
+    #[test]
+    fn arity_error() {
+        let program = parse("def f(a, b):\n  return a\nprint f(1)").unwrap();
+        let symbols = scope::analyze(&program).unwrap();
+        let types = types::infer(&program, &symbols);
+        let error = CodeGenerator::new(program, symbols, types, Handling::Status)
+            .codegen()
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            Error::TypeError {
+                message: "f() takes exactly 2 arguments (1 given)".to_owned(),
+                line: 3,
+            }
+        );
+    }
+
+    codegen_test! {
+        name: multiple_results,
+        text: "def f(a):\n  return a + 1, a * 2.5\nx, y = f(1)\nt = f(x)",
+        wat: "(module \
+         (global $x (mut i64) (i64.const 0xfffb_000000000000)) \
+         (global $y (mut i64) (i64.const 0xfffb_000000000000)) \
+         (global $t (mut i64) (i64.const 0xfffb_000000000000)) \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i64) (result i64 i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 1 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $frame \
+         get_local $a \
+         i64.store offset=0 \
+         get_local $a \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 2 \
+         set_global $line \
+         call $rt.add \
+         call $rt.root \
+         get_local $a \
+         f64.const 2.5 \
+         call $rt.float \
+         call $rt.mul \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") (local $tmp.0 i64) (local $tmp.1 i64) (local $tmp.2 i64) (local $tmp.3 i64) \
+         i32.const 3 \
+         call $rt.enter \
+         drop \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         set_local $tmp.1 \
+         set_local $tmp.0 \
+         get_local $tmp.0 \
+         set_global $x \
+         i32.const 512 \
+         get_global $x \
+         i64.store \
+         get_local $tmp.1 \
+         set_global $y \
+         i32.const 520 \
+         get_global $y \
+         i64.store \
+         get_global $x \
+         i32.const 4 \
+         set_global $line \
+         call $f \
+         call $rt.root \
+         set_local $tmp.3 \
+         call $rt.root \
+         set_local $tmp.2 \
+         i32.const 2 \
+         call $rt.new_tuple \
+         i32.const 0 \
+         get_local $tmp.2 \
+         call $rt.init_item \
+         i32.const 1 \
+         get_local $tmp.3 \
+         call $rt.init_item \
+         set_global $t \
+         i32.const 528 \
+         get_global $t \
+         i64.store \
+         i32.const 536 \
+         set_global $shadow.top))",
+    }
+
+    codegen_test! {
+        name: identity,
+        text: "def f(a):\n  return a is None\nprint f(1) is not True",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (result i32) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $a \
+         call $rt.int \
+         i64.const 0xfffb_000000000000 \
+         i64.eq \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 1 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         i32.const 1 \
+         i32.eq \
+         i32.eqz \
+         call $rt.bool \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: bitwise_operators,
+        text: "def f(a, n):\n  return ~(a & 3) | a << 2 ^ a >> n\nprint f(1, 1)",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $n i32) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         get_local $a \
+         i32.const 3 \
+         i32.and \
+         i32.const -1 \
+         i32.xor \
+         call $rt.int \
+         call $rt.root \
+         get_local $a \
+         call $rt.int \
+         i32.const 2 \
+         call $rt.int \
+         i32.const 2 \
+         set_global $line \
+         call $rt.shl \
+         get_local $a \
+         get_local $n \
+         call $rt.shr_int \
+         call $rt.int \
+         call $rt.xor \
+         call $rt.or \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 1 \
+         i32.const 1 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: power,
+        text: "def f(a, x):\n  return [a ** 2, x ** a]\nprint f(3, 1.5)",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $a i32) (param $x f64) (result i64) (local $frame i32) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 0 \
+         call $rt.enter \
+         set_local $frame \
+         i32.const 2 \
+         i32.const 2 \
+         set_global $line \
+         call $rt.new_list \
+         call $rt.root \
+         i32.const 0 \
+         get_local $a \
+         call $rt.int \
+         i32.const 2 \
+         call $rt.int \
+         call $rt.pow \
+         call $rt.init_item \
+         i32.const 1 \
+         get_local $x \
+         get_local $a \
+         f64.convert_i32_s \
+         call $rt.pow_float \
+         call $rt.float \
+         call $rt.init_item \
+         get_local $frame \
+         set_global $shadow.top \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 3 \
+         f64.const 1.5 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: augmented_assignment,
+        text: "n = 1\nn -= 2\nxs = [n]\nxs[0] += 1",
+        wat: "(module \
+         (global $n (mut i64) (i64.const 0xfffb_000000000000)) \
+         (global $xs (mut i64) (i64.const 0xfffb_000000000000)) \
+         (func (export \"main\") (local $tmp.0 i64) (local $tmp.1 i64) \
+         i32.const 2 \
+         call $rt.enter \
+         drop \
+         i32.const 1 \
+         call $rt.int \
+         set_global $n \
+         i32.const 512 \
+         get_global $n \
+         i64.store \
+         get_global $n \
+         i32.const 2 \
+         call $rt.int \
+         i32.const 2 \
+         set_global $line \
+         call $rt.sub \
+         set_global $n \
+         i32.const 512 \
+         get_global $n \
+         i64.store \
+         i32.const 1 \
+         i32.const 3 \
+         set_global $line \
+         call $rt.new_list \
+         i32.const 0 \
+         get_global $n \
+         call $rt.init_item \
+         set_global $xs \
+         i32.const 520 \
+         get_global $xs \
+         i64.store \
+         get_global $xs \
+         call $rt.root \
+         set_local $tmp.0 \
+         i32.const 0 \
+         call $rt.int \
+         set_local $tmp.1 \
+         get_local $tmp.0 \
+         get_local $tmp.1 \
+         i32.const 4 \
+         set_global $line \
+         call $rt.getitem \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.iadd \
+         get_local $tmp.0 \
+         get_local $tmp.1 \
+         call $rt.setitem \
+         i32.const 528 \
+         set_global $shadow.top))",
+    }
+
+    codegen_test! {
+        name: conditional_expression,
+        text: "def f(n):\n  return 1 if n else 2.5\nprint f(0)",
+        wat: "(module \
+         (data (i32.const 49536) \"\\01\\66\") \
+         (func $f (param $n i32) (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         get_local $n \
+         if (result i64) \
+         i32.const 1 \
+         call $rt.int \
+         else \
+         f64.const 2.5 \
+         call $rt.float \
+         end \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         i32.const 0 \
+         i32.const 3 \
+         set_global $line \
+         call $f \
+         call $rt.print))",
+    }
+
+    codegen_test! {
+        name: assert_statement,
+        text: "assert 1 < 2, 'm'",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 1 \
+         i32.const 2 \
+         i32.lt_s \
+         i32.eqz \
+         if \
+         i32.const 1 \
+         i32.const 1 \
+         set_global $line \
+         call $rt.new_tuple \
+         call $rt.root \
+         i32.const 0 \
+         i32.const 1 \
+         call $rt.new_str \
+         i32.const 0 \
+         i64.const 0x6d \
+         call $rt.init_bytes \
+         call $rt.init_item \
+         i32.const 9 \
+         call $rt.new_exception \
+         call $rt.raise_exception \
+         unreachable \
+         end \
+         i32.const 512 \
+         set_global $shadow.top))",
+    }
+
+    codegen_test! {
+        name: print_statement,
+        text: "print >> sys.stderr, 1, 2.5,\nprint",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 2 \
+         call $rt.print_item \
+         f64.const 2.5 \
+         call $rt.float \
+         i32.const 2 \
+         call $rt.print_item \
+         i32.const 1 \
+         call $rt.print_newline))",
+    }
+
+    codegen_test! {
+        name: print_leaving_line_open,
+        text: "if 1:\n  print 1,",
+        wat: "(module \
+         (func (export \"main\") \
+         i32.const 1 \
+         if \
+         i32.const 1 \
+         call $rt.int \
+         i32.const 1 \
+         call $rt.print_item \
+         end \
+         call $rt.exit))",
+    }
+
+    codegen_test! {
+        name: special_method,
+        text: "class A:\n  def __len__(self):\n    return 1\nprint len(A())",
+        wat: "(module \
+         (global $A (mut i64) (i64.const 0xfffb_000000000000)) \
+         (data (i32.const 49536) \"\\07\\5f\\5f\\6c\\65\\6e\\5f\\5f\\01\\41\") \
+         (func $A.__len__ (param $self i64) (result i64) \
+         i32.const 49536 \
+         call $rt.call \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.return \
+         return) \
+         (func (export \"main\") \
+         (local $tmp.0 i64) \
+         (local $tmp.1 i64) \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         i64.const 0xfffb_000000000000 \
+         i32.const 0 \
+         i32.const 49544 \
+         i32.const 0 \
+         i32.const 1 \
+         set_global $line \
+         call $rt.new_class \
+         call $rt.root \
+         set_local $tmp.0 \
+         get_local $tmp.0 \
+         set_global $A \
+         i32.const 512 \
+         get_global $A \
+         i64.store \
+         i32.const 520 \
+         set_global $shadow.top \
+         get_global $A \
+         i32.const 4 \
+         set_global $line \
+         call $rt.new_instance \
+         call $rt.root \
+         set_local $tmp.1 \
+         get_local $tmp.1 \
+         call $special.len \
+         call $rt.int \
+         call $rt.print \
+         i32.const 520 \
+         set_global $shadow.top) \
+         (func $special.len (param $value i64) (result i32) \
+         block $dispatch.len \
+         block $dispatch.len.0 \
+         get_local $value \
+         call $rt.class_id \
+         br_table $dispatch.len.0 $dispatch.len \
+         end \
+         get_local $value \
+         call $A.__len__ \
+         call $rt.check_len \
+         return \
+         br $dispatch.len \
+         end \
+         get_local $value \
+         call $rt.len))",
+    }
+
+    codegen_test! {
+        name: try_statement,
+        text: "try:\n  print 1\nexcept ValueError:\n  print 2",
+        wat: "(module \
+         (func (export \"main\") (local $tmp.0 i64) \
+         block $catch.1 \
+         i32.const 1 \
+         call $rt.int \
+         call $rt.print \
+         end \
+         i32.const 32768 \
+         set_global $calls.top \
+         i32.const 512 \
+         set_global $shadow.top \
+         get_global $exception \
+         i64.const 0 \
+         i64.ne \
+         if \
+         get_global $exception \
+         call $rt.root \
+         set_local $tmp.0 \
+         i64.const 0 \
+         set_global $exception \
+         get_local $tmp.0 \
+         i32.const 8 \
+         call $rt.matches \
+         if \
+         i32.const 2 \
+         call $rt.int \
+         call $rt.print \
+         else \
+         get_local $tmp.0 \
+         call $rt.throw \
+         end \
+         end \
+         i32.const 512 \
+         set_global $shadow.top))",
+    }
+
+    // with wasm handling, each try clause runs inside a try_table that
+    // leaves what it caught in $exception, and so does main.
+    codegen_test! {
+        name: try_finally_wasm,
+        handling: Handling::Wasm,
+        text: "try:\n  raise ValueError\nexcept ValueError, e:\n  print e\nfinally:\n  print 2",
+        wat: "(module \
+         (global $e (mut i64) (i64.const 0xfffb_000000000000)) \
+         (func (export \"main\") (local $tmp.0 i64) (local $tmp.1 i64) \
+         i32.const 1 \
+         call $rt.enter \
+         drop \
+         block $caught.4 (result i64) \
+         try_table (catch $python $caught.4) \
+         block $finally.1 \
+         block $caught.2 (result i64) \
+         try_table (catch $python $caught.2) \
+         i32.const 2 \
+         set_global $line \
+         i32.const 8 \
+         call $rt.raise \
+         unreachable \
+         end \
+         i64.const 0 \
+         end \
+         set_global $exception \
+         i32.const 32768 \
+         set_global $calls.top \
+         i32.const 520 \
+         set_global $shadow.top \
+         block $caught.3 (result i64) \
+         try_table (catch $python $caught.3) \
+         get_global $exception \
+         i64.const 0 \
+         i64.ne \
+         if \
+         get_global $exception \
+         call $rt.root \
+         set_local $tmp.0 \
+         i64.const 0 \
+         set_global $exception \
+         get_local $tmp.0 \
+         i32.const 8 \
+         call $rt.matches \
+         if \
+         get_local $tmp.0 \
+         set_global $e \
+         i32.const 512 \
+         get_global $e \
+         i64.store \
+         get_global $e \
+         call $rt.print \
+         else \
+         get_local $tmp.0 \
+         call $rt.throw \
+         end \
+         end \
+         i32.const 520 \
+         set_global $shadow.top \
+         end \
+         i64.const 0 \
+         end \
+         set_global $exception \
+         i32.const 32768 \
+         set_global $calls.top \
+         end \
+         i32.const 520 \
+         set_global $shadow.top \
+         get_global $exception \
+         call $rt.root \
+         set_local $tmp.1 \
+         i64.const 0 \
+         set_global $exception \
+         i32.const 2 \
+         call $rt.int \
+         call $rt.print \
+         i32.const 520 \
+         set_global $shadow.top \
+         get_local $tmp.1 \
+         i64.const 0 \
+         i64.ne \
+         if \
+         get_local $tmp.1 \
+         call $rt.throw \
+         end \
+         end \
+         i64.const 0 \
+         end \
+         set_global $exception \
+         i32.const 32768 \
+         set_global $calls.top \
+         get_global $exception \
+         i64.const 0 \
+         i64.ne \
+         if \
+         call $rt.uncaught \
+         end))",
+    }
+
+    codegen_test! {
+        name: uncaught_raise_wasm,
+        handling: Handling::Wasm,
+        text: "raise KeyError",
+        wat: "(module \
+         (func (export \"main\") \
+         block $caught.1 (result i64) \
+         try_table (catch $python $caught.1) \
+         i32.const 1 \
+         set_global $line \
+         i32.const 7 \
+         call $rt.raise \
+         unreachable \
+         end \
+         i64.const 0 \
+         end \
+         set_global $exception \
+         i32.const 32768 \
+         set_global $calls.top \
+         get_global $exception \
+         i64.const 0 \
+         i64.ne \
+         if \
+         call $rt.uncaught \
+         end))",
     }
 }
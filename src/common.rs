@@ -2,6 +2,4 @@ pub use codegen::CodeGenerator;
 pub use error::Error;
 pub use lexer::Lexer;
 pub use parser::Parser;
-pub use program::Program;
-pub use token::Token;
 pub use wexp::Wexp;
@@ -1,106 +1,130 @@
+use checker::Checker;
 use common::*;
-use regex::Regex;
+use exception::Handling;
+use program::Program;
+use runtime;
+use scope::{self, SymbolTable};
+use types::{self, TypeTable};
 
-lazy_static! {
-    static ref PRINT_RE: Regex = Regex::new(r"^called host host.print[(]([^)]*)[)] =>$").unwrap();
+// settings from the command line that change the generated module.
+pub struct Options {
+    pub heap_size: u32,
+    // -O leaves out assert statements
+    pub optimize: bool,
+    pub exceptions: Handling,
+    // the file tracebacks say the program is in
+    pub filename: String,
 }
 
-pub fn compile(text: &str) -> Result<Wexp, Error> {
-    let lexer = Lexer::new(&text);
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            heap_size: runtime::DEFAULT_HEAP_SIZE,
+            optimize: false,
+            exceptions: Handling::Status,
+            filename: "<stdin>".to_owned(),
+        }
+    }
+}
+
+// lexes, parses and checks the program, with its asserts taken out under
+// -O, up to the point where its types can be inferred.
+fn analyze(text: &str, options: &Options) -> Result<(Program, SymbolTable), Error> {
+    let lexer = Lexer::new(text);
     let tokens = lexer.lex()?;
     let parser = Parser::new(tokens);
-    let program = parser.parse_program()?;
-    let codegenerator = CodeGenerator::new(program);
-    Ok(codegenerator.codegen())
+    let mut program = parser.parse_program()?;
+    if options.optimize {
+        program.body.remove_asserts();
+    }
+    let symbols = scope::analyze(&program)?;
+    Checker::new(&program).check()?;
+    Ok((program, symbols))
+}
+
+pub fn compile(text: &str, options: &Options) -> Result<Wexp, Error> {
+    let (program, symbols) = analyze(text, options)?;
+    let types = types::infer(&program, &symbols);
+    let codegenerator = CodeGenerator::new(program, symbols, types, options.exceptions);
+    Ok(runtime::link(
+        codegenerator.codegen()?,
+        options.heap_size,
+        options.exceptions,
+        &options.filename,
+    ))
+}
+
+// the types that compile would give the program's variables and functions.
+pub fn infer_types(text: &str, options: &Options) -> Result<TypeTable, Error> {
+    let (program, symbols) = analyze(text, options)?;
+    Ok(types::infer(&program, &symbols))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use testing::{run_wat, run_wat_exceptions};
 
-    use tempfile::Builder;
-
-    use std::process::Command;
-    use std::{fs, str};
-
-    fn run(text: &str) -> Vec<String> {
+    fn run(text: &str, options: &Options) -> Vec<String> {
         println!("compiling program: {}", text);
-
-        let wat = compile(text).expect("Compilation failed").to_string();
-
-        println!("compiled wat: {}", wat);
-
-        let watfile = Builder::new()
-            .prefix("compyler-test-input")
-            .suffix(".wat")
-            .tempfile()
-            .expect("Failed to create tempfile");
-
-        fs::write(&watfile, &wat).expect("Failed to write WAT.");
-
-        let wasm = Builder::new()
-            .prefix("compyler-test-wasm")
-            .suffix(".wasm")
-            .tempfile()
-            .expect("Failed to create tempfile");
-
-        let wat2wasm_status = Command::new("wat2wasm")
-            .arg("-o")
-            .arg(wasm.path())
-            .arg(watfile.path())
-            .status()
-            .expect("wat2wasm failed.");
-
-        assert!(wat2wasm_status.success());
-
-        let wasm_interp_output = Command::new("wasm-interp")
-            .arg("--host-print")
-            .arg("--run-all-exports")
-            .arg(wasm.path())
-            .output()
-            .expect("Failed to execute wasm-interp");
-
-        if !wasm_interp_output.status.success() {
-            let stdout = str::from_utf8(&wasm_interp_output.stdout)
-                .unwrap()
-                .to_string();
-            let stderr = str::from_utf8(&wasm_interp_output.stderr)
-                .unwrap()
-                .to_string();
-            println!("{}", stdout);
-            println!("{}", stderr);
-            panic!();
+        let wat = compile(text, options)
+            .expect("Compilation failed")
+            .to_string();
+        match options.exceptions {
+            Handling::Status => run_wat(&wat),
+            Handling::Wasm => run_wat_exceptions(&wat),
         }
-
-        let stdout =
-            str::from_utf8(&wasm_interp_output.stdout).expect("wasm-interp output was not UTF-8");
-
-        println!("wasm-interp:  {:?}", stdout.trim());
-
-        stdout
-            .lines()
-            .flat_map(|line| {
-                let captures = PRINT_RE.captures(line);
-                if let Some(captures) = captures {
-                    return Some(captures[1].to_string());
-                }
-
-                if line != "main() => " {
-                    return None;
-                }
-
-                panic!("unexpected line in output: {}", line);
-            })
-            .collect()
     }
 
     macro_rules! test {
         (name: $name:ident,input: $input:expr,output: $output:expr,) => {
+            test! {
+                name: $name,
+                options: Options::default(),
+                input: $input,
+                output: $output,
+            }
+        };
+        (name: $name:ident,heap_size: $heap_size:expr,input: $input:expr,output: $output:expr,) => {
+            test! {
+                name: $name,
+                options: Options {
+                    heap_size: $heap_size,
+                    ..Options::default()
+                },
+                input: $input,
+                output: $output,
+            }
+        };
+        (name: $name:ident,optimize: $optimize:expr,input: $input:expr,output: $output:expr,) => {
+            test! {
+                name: $name,
+                options: Options {
+                    optimize: $optimize,
+                    ..Options::default()
+                },
+                input: $input,
+                output: $output,
+            }
+        };
+        (name: $name:ident,exceptions: $exceptions:expr,input: $input:expr,output: $output:expr,) => {
+            test! {
+                name: $name,
+                options: Options {
+                    exceptions: $exceptions,
+                    ..Options::default()
+                },
+                input: $input,
+                output: $output,
+            }
+        };
+        (name: $name:ident,options: $options:expr,input: $input:expr,output: $output:expr,) => {
             #[test]
             fn $name() {
                 let input = $input;
                 let expected: &[&str] = &$output;
-                let actual = run(input);
+                let options = $options;
+                let actual = run(input, &options);
                 if actual != expected {
                     println!("expected:     {}", expected.join(", "));
                     println!("actual:       {}", actual.join(", "));
@@ -110,6 +134,18 @@ mod test {
         };
     }
 
+    macro_rules! error_test {
+        (name: $name:ident,input: $input:expr,error: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                match compile($input, &Options::default()) {
+                    Ok(_) => panic!("compiled"),
+                    Err(error) => assert_eq!(error, $expected),
+                }
+            }
+        };
+    }
+
     // test! {
     //     name:   empty,
     //     input:  r#"
@@ -131,67 +167,1281 @@ mod test {
     test! {
         name:   print_int,
         input:  "print 7",
-        output: ["i32:7"],
+        output: ["7"],
     }
 
     test! {
         name:   print_ints,
         input:  "print 7\nprint 1 + 2",
-        output: ["i32:7", "i32:3"],
+        output: ["7", "3"],
     }
 
     test! {
         name: print_leq,
         input: "print 8 <= 8",
-        output: ["i32:1"],
+        output: ["True"],
     }
 
     test! {
         name: print_ne,
         input: "print 8 != 8",
-        output: ["i32:0"],
+        output: ["False"],
     }
 
     test! {
         name: print_div,
         input: "print 9 / 3",
-        output: ["i32:3"],
+        output: ["3"],
     }
 
     test! {
         name: print_mod,
         input: "print 13 % 7",
-        output: ["i32:6"],
+        output: ["6"],
     }
 
     test! {
         name: print_and,
         input: "print 1>=2 and 2<7",
-        output: ["i32:0"],
+        output: ["False"],
     }
 
     test! {
         name:   function_call,
         input:  "def f(a, b):\n  return a + b\nprint f(2, 3)",
-        output: ["i32:5"],
+        output: ["5"],
     }
 
     test! {
         name: if_else,
         input: "def f(a):\n  if a < 5:\n    return 0\n  else:\n    return 1\nprint f(1)",
-        output: ["i32:0"],
+        output: ["0"],
     }
 
     test! {
         name: elif,
         input: "def f(n):\n if n < 5:\n  return 0\n elif n < 10:\n  return 1\n elif n < 15:\
         \n  return 2  \n else:\n  return 3\nprint f(4)\nprint f(8)\nprint f(81)",
-        output: ["i32:0", "i32:1", "i32:3"],
+        output: ["0", "1", "3"],
     }
 
     test! {
         name: fib,
         input: "def fib(n):\n  if n < 2:\n    return n\n  else:\n    return fib(n - 2) + fib(n - 1)\nprint fib(4)",
-        output: ["i32:3"],
+        output: ["3"],
+    }
+
+    test! {
+        name: default_params,
+        input: "def f(a, b=10, c=100):\n  return a + b + c\nprint f(1)\nprint f(1, 2)\nprint f(1, 2, 3)",
+        output: ["111", "103", "6"],
+    }
+
+    test! {
+        name: keyword_arguments,
+        input: "def f(a, b=10, c=100):\n  return a + 2 * b + 3 * c\nprint f(c=1, a=30)\nprint f(10, c=3, b=2)",
+        output: ["53", "23"],
+    }
+
+    test! {
+        name: surplus_arguments,
+        input: "def f(a, *r, **k):\n  return r, k\nprint f(1, 2, x=3)\nprint f(1)\
+                \ndef g(a, b, **k):\n  return a, b, k\nprint g(1, z='s' + 't', b=[2])\
+                \nclass C:\n  def m(self, *r):\n    return len(r)\nprint C().m(1, 2, 3)",
+        output: ["((2,), {'x': 3})", "((), {})", "(1, [2], {'z': 'st'})", "3"],
+    }
+
+    test! {
+        name: surplus_arguments_kept,
+        heap_size: 4608,
+        input: "def g(a, b, *r, **k):\n  garbage = [[1], {2: 'x' + 'y'}, [3, 4, 5, 6, 7, 8]]\
+                \n  return len(k['z'][0]) + b[0] + len(r)\ndef make(n):\n  return [n]\
+                \ndef churn(n):\n  if n == 0:\n    return 0\
+                \n  m = g(1, z=['s' + 't', 'u' + 'v'], b=make(n))\n  return m + churn(n - 1)\
+                \nprint churn(100)",
+        output: ["5250"],
+    }
+
+    test! {
+        name: variables,
+        input: "x = 3\ndef f(a):\n  b = a * 2\n  if b > 4:\n    b = b - 4\n  return b + 1\
+        \nprint f(x)\nprint x + f(1)\nprint True",
+        output: ["3", "6", "True"],
+    }
+
+    test! {
+        name: globals,
+        input: "count = 10\ndef bump(n):\n  global count, total\n  count = count + n\n  total = count\
+        \n  return count\ndef peek():\n  return count\nprint bump(1)\nprint bump(5)\nprint peek()\
+        \nprint total",
+        output: ["11", "16", "16", "16"],
+    }
+
+    test! {
+        name: call_before_def,
+        input: "print 1\nprint f(2)\ndef f(a):\n  return a",
+        output: [
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: NameError: name 'f' is not defined",
+            "NameError: line 2",
+        ],
+    }
+
+    test! {
+        name: function_called_before_def,
+        input: "def g():\n  return f()\nprint g()\ndef f():\n  return 1",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 3, in <module>",
+            "stderr:   File \"<stdin>\", line 2, in g",
+            "stderr: NameError: global name 'f' is not defined",
+            "NameError: line 2",
+        ],
+    }
+
+    test! {
+        name: mutual_recursion,
+        input: "def even(n):\n  if n == 0:\n    return 1\n  return odd(n - 1)\
+        \ndef odd(n):\n  if n == 0:\n    return 0\n  return even(n - 1)\
+        \nprint even(10)\nprint odd(10)\nprint even(7)",
+        output: ["1", "0", "0"],
+    }
+
+    test! {
+        name: mutual_recursion_after_call,
+        input: "def even(n):\n  if n == 0:\n    return 1\n  return odd(n - 1)\
+        \nprint 0\ndef odd(n):\n  if n == 0:\n    return 0\n  return even(n - 1)\
+        \nprint even(4)\nprint even(0)",
+        output: ["0", "1", "1"],
+    }
+
+    test! {
+        name: floats,
+        input: "print 1.5 + 1\nprint 7.0 / 2\nprint 2 * .25",
+        output: ["2.5", "3.5", "0.5"],
+    }
+
+    test! {
+        name: none,
+        input: "def f():\n  print 1\nprint f()\nprint None == None",
+        output: ["1", "None", "True"],
+    }
+
+    test! {
+        name: floor_division,
+        input: "print (0 - 7) / 2\nprint (0 - 7) % 2\nprint 7 % (0 - 2)\nprint (0 - 7.5) % 2",
+        output: ["-4", "1", "-1", "0.5"],
+    }
+
+    test! {
+        name: and_or_operands,
+        input: "print 0 or 2\nprint 3 and 0.5\nprint None or False",
+        output: ["2", "0.5", "False"],
+    }
+
+    test! {
+        name: type_error,
+        input: "print 1\nprint 1 + None",
+        output: [
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: TypeError: unsupported operand type(s) for +: 'int' and 'NoneType'",
+            "TypeError: line 2",
+        ],
+    }
+
+    test! {
+        name: not_callable,
+        input: "def g(x):\n  return x\ndef f(g):\n  return g(1)\nlen = 3\ntry:\n  len([1])\
+                \nexcept TypeError:\n  print 'len'\nprint f(2)",
+        output: [
+            "len",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 10, in <module>",
+            "stderr:   File \"<stdin>\", line 4, in f",
+            "stderr: TypeError: 'int' object is not callable",
+            "TypeError: line 4",
+        ],
+    }
+
+    test! {
+        name: exception_messages,
+        input: "class A:\n  pass\nclass B(object):\n  pass\ndef f():\n  return later\
+                \ndef g():\n  n = m\n  m = 1\n  return n\ntry:\n  print 1 / 0\
+                \nexcept ZeroDivisionError, e:\n  print e\ntry:\n  print 1.5 % 0\
+                \nexcept ZeroDivisionError, e:\n  print e\ntry:\n  print 2 ** 70 / 0\
+                \nexcept ZeroDivisionError, e:\n  print e\ntry:\n  print f()\
+                \nexcept NameError, e:\n  print e\ntry:\n  print g()\
+                \nexcept UnboundLocalError, e:\n  print e\ntry:\n  print y\
+                \nexcept NameError, e:\n  print e\ny = 1\ntry:\n  del y\n  print y\
+                \nexcept NameError, e:\n  print e\nx = None\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = [1]\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = (1,)\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = B()\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = A()\ntry:\n  x()\
+                \nexcept AttributeError, e:\n  print e\nx = KeyError()\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = 2 ** 70\ntry:\n  x()\
+                \nexcept TypeError, e:\n  print e\nx = 3\ntry:\n  print [1][x]\
+                \nexcept IndexError, e:\n  print e\nx = 1\ntry:\n  x <<= -1\
+                \nexcept ValueError, e:\n  print e\ntry:\n  print 1 + \"a\"\
+                \nexcept TypeError, e:\n  print e\ntry:\n  print len(5)\
+                \nexcept TypeError, e:\n  print e\nxs = [1]\ntry:\n  xs.foo()\
+                \nexcept AttributeError, e:\n  print e\ntry:\n  print None.x\
+                \nexcept AttributeError, e:\n  print e\ntry:\n  print A().x\
+                \nexcept AttributeError, e:\n  print e\ntry:\n  print B.x\
+                \nexcept AttributeError, e:\n  print e\ntry:\n  a, b = [1, 2, 3]\
+                \nexcept ValueError, e:\n  print e\ntry:\n  a, b = [1]\
+                \nexcept ValueError, e:\n  print e\nlater = 1",
+        output: [
+            "integer division or modulo by zero",
+            "float modulo",
+            "long division or modulo by zero",
+            "global name 'later' is not defined",
+            "local variable 'm' referenced before assignment",
+            "name 'y' is not defined",
+            "name 'y' is not defined",
+            "'NoneType' object is not callable",
+            "'list' object is not callable",
+            "'tuple' object is not callable",
+            "'B' object is not callable",
+            "A instance has no __call__ method",
+            "'exceptions.KeyError' object is not callable",
+            "'long' object is not callable",
+            "list index out of range",
+            "negative shift count",
+            "unsupported operand type(s) for +: 'int' and 'str'",
+            "object of type 'int' has no len()",
+            "'list' object has no attribute 'foo'",
+            "'NoneType' object has no attribute 'x'",
+            "A instance has no attribute 'x'",
+            "type object 'B' has no attribute 'x'",
+            "too many values to unpack",
+            "need more than 1 value to unpack",
+        ],
+    }
+
+    error_test! {
+        name: function_value,
+        input: "def f():\n  return 1\ng = f",
+        error: Error::Unsupported {
+            message: "using the function 'f' as a value".to_owned(),
+            line: 3,
+        },
+    }
+
+    error_test! {
+        name: too_many_names,
+        input: &(0..64)
+            .map(|i| format!("def {}{}():\n  return 0\n", "f".repeat(250), i))
+            .collect::<String>(),
+        error: Error::TooManyNames(16182),
+    }
+
+    error_test! {
+        name: builtin_function_value,
+        input: "print len",
+        error: Error::Unsupported {
+            message: "using the builtin 'len' as a value".to_owned(),
+            line: 1,
+        },
+    }
+
+    test! {
+        name: local_read_before_assignment,
+        input: "x = 1\ndef f():\n  print x\n  x = 2\ndef g(a):\n  if a:\n    z = 1\n  return z\
+                \nprint g(1)\ntry:\n  g(0)\nexcept NameError:\n  print 'caught'\nf()",
+        output: [
+            "1",
+            "caught",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 14, in <module>",
+            "stderr:   File \"<stdin>\", line 3, in f",
+            "stderr: UnboundLocalError: local variable 'x' referenced before assignment",
+            "UnboundLocalError: line 3",
+        ],
+    }
+
+    test! {
+        name: global_read_before_assignment,
+        input: "def f():\n  return x\ntry:\n  print f()\nexcept NameError:\n  print 'caught'\
+                \nx = 1\nprint f()\nif 0:\n  y = 5\nprint y",
+        output: [
+            "caught",
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 11, in <module>",
+            "stderr: NameError: name 'y' is not defined",
+            "NameError: line 11",
+        ],
+    }
+
+    test! {
+        name: zero_division,
+        input: "x = 0\ndef f(n):\n  return 1 / n\nprint f(2)\nprint f(x)",
+        output: [
+            "0",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 5, in <module>",
+            "stderr:   File \"<stdin>\", line 3, in f",
+            "stderr: ZeroDivisionError: integer division or modulo by zero",
+            "ZeroDivisionError: line 3",
+        ],
+    }
+
+    test! {
+        name: mixed_parameter_types,
+        input: "def f(a):\n  return a * 2\nprint f(1)\nprint f(1.5)\nprint f(True)",
+        output: ["2", "3.0", "2"],
+    }
+
+    test! {
+        name: unboxed_mixed_arithmetic,
+        input: "a = 7\nb = 2.0\nc = True\nprint a / b\nprint a % 4 + c\nprint c == 1\nprint a < b",
+        output: ["3.5", "4", "True", "False"],
+    }
+
+    test! {
+        name: unboxed_zero_division,
+        input: "a = 7\nb = 0\nprint a / 1\nprint a % b",
+        output: [
+            "7",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 4, in <module>",
+            "stderr: ZeroDivisionError: integer division or modulo by zero",
+            "ZeroDivisionError: line 4",
+        ],
+    }
+
+    test! {
+        name: shadow_stack,
+        input: "def f(a):\n  return a\ndef g(a):\n  b = f(a)\n  return a + f(b)\
+                \nx = g(1.5)\nprint x + g(1)\nprint x",
+        output: ["5.0", "3.0"],
+    }
+
+    test! {
+        name: list_printing,
+        input: "xs = [1, 2.5, True, None]\nprint xs\nprint []\nprint [[1, 2], [0.1], -3]",
+        output: ["[1, 2.5, True, None]", "[]", "[[1, 2], [0.10000000000000001], -3]"],
+    }
+
+    test! {
+        name: list_indexing,
+        input: "xs = [10, 20, 30]\nprint xs[0] + xs[-1]\nxs[1] = 5\nxs[-3] = 7\nprint xs\
+                \nprint len(xs) * 2",
+        output: ["40", "[7, 5, 30]", "6"],
+    }
+
+    test! {
+        name: list_slicing,
+        input: "xs = [1, 2, 3, 4, 5]\nprint xs[1:3]\nprint xs[:-2]\nprint xs[3:]\
+                \nprint xs[-100:100]\nprint xs[4:2]\nprint xs[:]",
+        output: ["[2, 3]", "[1, 2, 3]", "[4, 5]", "[1, 2, 3, 4, 5]", "[]", "[1, 2, 3, 4, 5]"],
+    }
+
+    test! {
+        name: list_append,
+        input: "def fill(xs, n):\n  if n > 0:\n    xs.append(n)\n    fill(xs, n - 1)\
+                \nxs = []\nfill(xs, 5)\nprint xs + [0]\nprint len(xs)",
+        output: ["[5, 4, 3, 2, 1, 0]", "5"],
+    }
+
+    test! {
+        name: list_comparison,
+        input: "print [1, 2] == [1, 2]\nprint [1, 2] < [1, 3]\nprint [1] < [1, 0]\
+                \nif []:\n  print 1\nelse:\n  print 0",
+        output: ["True", "True", "True", "0"],
+    }
+
+    test! {
+        name: recursive_list,
+        input: "xs = [1]\nxs.append(xs)\nprint xs",
+        output: ["[1, [...]]"],
+    }
+
+    test! {
+        name: index_error,
+        input: "xs = [1]\nprint xs[-1]\nprint xs[1]",
+        output: [
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 3, in <module>",
+            "stderr: IndexError: list index out of range",
+            "IndexError: line 3",
+        ],
+    }
+
+    test! {
+        name: assignment_index_error,
+        input: "xs = [1]\nxs[-2] = 1",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: IndexError: list assignment index out of range",
+            "IndexError: line 2",
+        ],
+    }
+
+    test! {
+        name: attribute_error,
+        input: "x = 1\nx.append(2)",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: AttributeError: 'int' object has no attribute 'append'",
+            "AttributeError: line 2",
+        ],
+    }
+
+    test! {
+        name: lists_collected,
+        heap_size: 49152,
+        input: "def build(xs, n):\n  if n > 0:\n    xs.append([n, n + 1.5])\
+                \n    garbage = [xs, [n]] + [n]\n    build(xs, n - 1)\
+                \nxs = []\nbuild(xs, 200)\nprint len(xs)\nprint xs[0]\nprint xs[-1]\
+                \nprint xs[150:152]",
+        output: ["200", "[200, 201.5]", "[1, 2.5]", "[[50, 51.5], [49, 50.5]]"],
+    }
+
+    test! {
+        name: dict_display,
+        input: "d = {'b': 1, 'a': 2, 'c': 3}\nprint d\nprint d['a'] + d['c']\nd['a'] = 5\
+                \nd[True] = 'x'\nprint d[1]\nprint d",
+        output: ["{'a': 2, 'c': 3, 'b': 1}", "5", "x", "{'a': 5, True: 'x', 'c': 3, 'b': 1}"],
+    }
+
+    test! {
+        name: dict_methods,
+        input: "d = {'b': 1, 'a': 5, True: 'x', 'c': 3}\nprint len(d)\nprint 'a' in d\
+                \nprint 'z' in d\nprint d.keys()\nprint d.values()\nprint d.items()\
+                \nprint d.get('b')\nprint d.get('z')\nprint d.get('z', 0)",
+        output: [
+            "4",
+            "True",
+            "False",
+            "['a', True, 'c', 'b']",
+            "[5, 'x', 3, 1]",
+            "[('a', 5), (True, 'x'), ('c', 3), ('b', 1)]",
+            "1",
+            "None",
+            "0",
+        ],
+    }
+
+    test! {
+        name: dict_growth,
+        input: "def fill(d, n):\n  if n > 0:\n    d[n * 7 - 7] = n - 1\n    fill(d, n - 1)\
+                \ndef clear(d, n):\n  if n > 0:\n    del d[n * 14 - 14]\n    clear(d, n - 1)\
+                \nd = {}\nfill(d, 20)\nprint d\nclear(d, 10)\nprint d\nd[1.5] = 2\nd[-1] = 3\
+                \nd[{1: 2}.items()[0]] = 4\nd[''] = 5\nprint d\nprint len(d)",
+        output: [
+            "{0: 0, 98: 14, 35: 5, 133: 19, 70: 10, 7: 1, 105: 15, 42: 6, 77: 11, 14: 2, \
+             112: 16, 49: 7, 84: 12, 21: 3, 119: 17, 56: 8, 91: 13, 28: 4, 126: 18, 63: 9}",
+            "{35: 5, 133: 19, 7: 1, 105: 15, 77: 11, 49: 7, 21: 3, 119: 17, 91: 13, 63: 9}",
+            "{1.5: 2, (1, 2): 4, 35: 5, 133: 19, '': 5, 7: 1, 105: 15, 77: 11, 49: 7, 21: 3, \
+             119: 17, 91: 13, -1: 3, 63: 9}",
+            "14",
+        ],
+    }
+
+    test! {
+        name: key_error,
+        input: "d = {1: 2}\ndel d[1]\nprint d\ndel d[1]",
+        output: [
+            "{}",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 4, in <module>",
+            "stderr: KeyError: 1",
+            "KeyError: line 4",
+        ],
+    }
+
+    test! {
+        name: unhashable_key,
+        input: "d = {}\nd[[1]] = 2",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: TypeError: unhashable type: 'list'",
+            "TypeError: line 2",
+        ],
+    }
+
+    test! {
+        name: strings,
+        input: "print {'x': [1, {2: 3}.items()[0]], 'q': \"it's\", 'r': 'a\\\\b\\n\\x7f\\t\"'}\
+                \nprint 'ab' + 'cd'\nprint 'abc'[1]\nprint 'abc'[-1:]\nprint len('hello')\
+                \nprint 'ab' < 'a'",
+        output: [
+            r#"{'q': "it's", 'x': [1, (2, 3)], 'r': 'a\\b\n\x7f\t"'}"#,
+            "abcd",
+            "b",
+            "c",
+            "5",
+            "False",
+        ],
+    }
+
+    test! {
+        name: list_deletion,
+        input: "xs = [1, 2, 3, 4]\ndel xs[1]\ndel xs[-1]\nprint xs",
+        output: ["[1, 3]"],
+    }
+
+    test! {
+        name: dicts_collected,
+        heap_size: 98304,
+        input: "def build(d, n, s):\n  if n > 0:\n    d[n] = {s: [n]}\
+                \n    {n: s + 'x', 'y': [n, n]}.items()\n    build(d, n - 1, s + 'a')\
+                \nd = {}\nbuild(d, 150, '')\nprint len(d)\nprint len(d[1].keys()[0])\
+                \nprint d[150]\nprint d.get(75).values()",
+        output: ["150", "149", "{'': [150]}", "[[75]]"],
+    }
+
+    test! {
+        name: negation,
+        input: "x = 2.5\nprint -x\nprint -(1 + 2)\nprint --True\nprint -0.0\ny = None\ny = 3\nprint -y",
+        output: ["-2.5", "-3", "1", "-0.0", "-3"],
+    }
+
+    test! {
+        name: multiple_results,
+        input: "def divmod2(a, b):\n  return a / b, a % b\nq, r = divmod2(17, 5)\nprint (q, r)\
+                \nt = divmod2(7.5, 2)\nprint t\na = 1\nb = 'x'\na, b = b, a\nprint [a, b]",
+        output: ["(3, 2)", "(3.75, 1.5)", "['x', 1]"],
+    }
+
+    test! {
+        name: tuples,
+        input: "t = 1,\nprint [t, (), (1, 2) + (3,)]\nprint [(1, (2, 3))[1][0], (1, 2)[-1:]]\
+                \nprint [(1, 2) < (1, 3), (1, 2) == (1, 2), () < (0,), (2,) > [1]]\
+                \n(a, b), c = [1, [2, 3]], 4\nprint (a, b, c)\
+                \nxs = [0, 0]\nxs[0], xs[1] = 'p', 'q'\nprint xs\
+                \nd = {}\nd[1, 2] = 3\nprint [d, (1, 2) in d]",
+        output: [
+            "[(1,), (), (1, 2, 3)]",
+            "[2, (2,)]",
+            "[True, True, True, True]",
+            "(1, [2, 3], 4)",
+            "['p', 'q']",
+            "[{(1, 2): 3}, True]",
+        ],
+    }
+
+    test! {
+        name: unpack_arity,
+        input: "a, b = [1, 2]\nprint a + b\na, b = [1, 2, 3]",
+        output: [
+            "3",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 3, in <module>",
+            "stderr: ValueError: too many values to unpack",
+            "ValueError: line 3",
+        ],
+    }
+
+    test! {
+        name: unpack_non_sequence,
+        input: "a, b = 1",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 1, in <module>",
+            "stderr: TypeError: 'int' object is not iterable",
+            "TypeError: line 1",
+        ],
+    }
+
+    test! {
+        name: unpack_str_and_dict,
+        input: "a, b = 'xy'\nprint b, a\nc, d = {1: 2, 3: 4}\nprint c, d",
+        output: ["y x", "1 3"],
+    }
+
+    test! {
+        name: tuples_collected,
+        heap_size: 8192,
+        input: "def pair(n, s):\n  return [n, n], s + 'x'\ndef churn(n):\n  if n > 0:\
+                \n    t = pair(n, 'z')\n    xs, s = pair(-n, 'yy')\
+                \n    return (t[0][1] + len(t[1])) + (len(s) - xs[0]) + churn(n - 1)\n  return 0\
+                \ndef repeat(n):\n  if n > 0:\n    return churn(20) + repeat(n - 1)\n  return 0\
+                \nprint repeat(200)",
+        output: ["104000"],
+    }
+
+    test! {
+        name: membership,
+        input: "print ['ell' in 'hello', 'elo' in 'hello', '' in '', 'x' not in 'abc']\
+                \nprint [3 not in [1, 2], (1, 2) not in {(1, 2): 0}, 2 in (1, 2)]",
+        output: ["[True, False, True, True]", "[True, False, True]"],
+    }
+
+    test! {
+        name: membership_in_str_needs_str,
+        input: "print 1 in 'a'",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 1, in <module>",
+            "stderr: TypeError: 'in <string>' requires string as left operand, not int",
+            "TypeError: line 1",
+        ],
+    }
+
+    test! {
+        name: identity,
+        input: "x = None\nprint [x is None, x is not None]\nxs = [1]\nys = xs\
+                \nprint [xs is ys, xs is [1], [] is not []]\nprint [1 is 1, True is 1, 1 is not 1.0]\
+                \ndef f(a):\n  if a is None:\n    return 0\n  return a\nprint [f(None), f(4)]",
+        output: [
+            "[True, False]",
+            "[True, False, True]",
+            "[True, False, True]",
+            "[0, 4]",
+        ],
+    }
+
+    test! {
+        name: chained_comparisons,
+        input: "def f(n):\n  print n,\n  return n\nprint 1 < 2 < 3, 3 > 2 > 2, 1 < 3 > 2                \nprint 1 in [1] in [[1]], 2 not in [1] in [[1]], [] is not None is None                \nprint f(1) < f(2) < f(3)\nprint f(2) < f(1) < f(3)\nprint 1 <= 1.0 < f([1])",
+        output: [
+            "True False True",
+            "True True True",
+            "1 2 3 True",
+            "2 1 False",
+            "[1] True",
+        ],
+    }
+
+    test! {
+        name: bitwise_operators,
+        input: "print [6 & 3, 6 | 3, 6 ^ 3, ~5, ~-1, 1 << 4, -16 >> 2, -1 >> 40]\
+                \nprint [True & False, True | False, True ^ True, True << 2, ~True]\
+                \nprint [1 | 2 ^ 3 & 4 << 1, 1 + 2 << 1, 1 == 1 & 1, 10 - 2 - 3, 20 / 2 / 5]\
+                \ndef f(a, b):\n  return [a & b, a | b, a ^ b, a << b, a >> b, ~a]\
+                \nprint f(12, 2)\nprint f(True, True)",
+        output: [
+            "[2, 7, 5, -6, 0, 16, -4, -1]",
+            "[False, True, False, 4, -2]",
+            "[3, 6, True, 5, 2]",
+            "[0, 14, 14, 48, 3, -13]",
+            "[True, True, False, 2, 0, -2]",
+        ],
+    }
+
+    test! {
+        name: negative_shift_count,
+        input: "n = 3\nprint 5 << n\nprint 1 << -n",
+        output: [
+            "40",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 3, in <module>",
+            "stderr: ValueError: negative shift count",
+            "ValueError: line 3",
+        ],
+    }
+
+    test! {
+        name: bitwise_needs_ints,
+        input: "def f(a):\n  return a | 1\nprint f(1)\nprint f(1.5)",
+        output: [
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 4, in <module>",
+            "stderr:   File \"<stdin>\", line 2, in f",
+            "stderr: TypeError: unsupported operand type(s) for |: 'float' and 'int'",
+            "TypeError: line 2",
+        ],
+    }
+
+    test! {
+        name: power,
+        input: "print [2 ** 10, 2 ** 3 ** 2, -2 ** 2, (-2) ** 3, 2 ** -1, 10 ** 0, 0 ** 0, 3 ** True]\
+                \nprint 2 ** 0.5\nprint 2.5 ** 3.5\ndef f(a, b):\n  return a ** b\
+                \nprint [f(3, 4), f(2, -2), f(-8, 3), f(1.5, 2), f(-2.0, 3)]",
+        output: [
+            "[1024, 512, -4, -8, 0.5, 1, 1, 3]",
+            "1.41421356237",
+            "24.7052942201",
+            "[81, 0.25, -512, 2.25, -8.0]",
+        ],
+    }
+
+    test! {
+        name: zero_to_negative_power,
+        input: "n = -1\nprint 0 ** n",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: ZeroDivisionError: 0.0 cannot be raised to a negative power",
+            "ZeroDivisionError: line 2",
+        ],
+    }
+
+    test! {
+        name: negative_to_fractional_power,
+        input: "print (-8) ** (1.0 / 3)",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 1, in <module>",
+            "stderr: ValueError: negative number cannot be raised to a fractional power",
+            "ValueError: line 1",
+        ],
+    }
+
+    test! {
+        name: int_overflow_promotes_to_long,
+        input: "def fib(n, a, b):\n  if n == 0:\n    return a\n  return fib(n - 1, b, a + b)\
+                \nprint fib(50, 0, 1)\nprint [2147483647 + 1, -2147483648 - 1, 65536 * 65536, -2 ** 64]\
+                \nprint [1 << 40, 12345678901234567890123, 5L, 0L, 5L == 5]",
+        output: [
+            "12586269025",
+            "[2147483648L, -2147483649L, 4294967296L, -18446744073709551616L]",
+            "[1099511627776L, 12345678901234567890123L, 5L, 0L, True]",
+        ],
+    }
+
+    test! {
+        name: long_arithmetic,
+        input: "a = 2 ** 100\nprint [a / 3, a % 3, -a / 7, -a % 7, a / -7, a % -7, a / 2 ** 40]\
+                \nprint [a - a, a - a + 2147483647, a * 0.5, a > -a, [1, 2, 3][1L]]",
+        output: [
+            "[422550200076076467165567735125L, 1L, -181092942889747057356671886483L, 5L, \
+             -181092942889747057356671886483L, -5L, 1152921504606846976L]",
+            "[0L, 2147483647L, 6.338253001141147e+29, True, 2]",
+        ],
+    }
+
+    test! {
+        name: long_bitwise_operators,
+        input: "a = 2 ** 70 + 12345\nb = -3 ** 50\nprint [a & b, a | b, a ^ b, ~a, b >> 5, b >> 200, a << 10]",
+        output: [
+            "[1180591620717411307569L, -717897987691852588762049L, -719078579312570000069618L, \
+             -1180591620717411315770L, -22434312115370393399071L, -1L, 1208925819614629187347456L]",
+        ],
+    }
+
+    test! {
+        name: augmented_assignment,
+        input: "x = 1\nx += 2\nx *= 10\nx -= 4\nx /= 3\nx %= 5\nx **= 3\nx <<= 2\nx >>= 1\
+                \nx |= 64\nx &= 127\nx ^= 5\nf = 1.5\nf += 1\ns = 'ab'\ns += 'cd'\nn = 2147483647\
+                \nn += 1\nprint [x, f, s, n]",
+        output: ["[115, 2.5, 'abcd', 2147483648L]"],
+    }
+
+    test! {
+        name: augmented_item_evaluates_target_once,
+        input: "def key(n):\n  print n\n  return n\nzs = [[0, 0], [0, 0]]\nzs[key(1)][key(0)] += 5\
+                \nd = {'a': 1}\nd['a'] -= 3\nprint [zs, d]",
+        output: ["1", "0", "[[[0, 0], [5, 0]], {'a': -2}]"],
+    }
+
+    test! {
+        name: augmented_add_extends_list_in_place,
+        input: "xs = [1]\nys = xs\nxs += [2]\nxs += (3,)\nxs += xs\nt = (1,)\nu = t\nt += (2,)\
+                \nprint [xs, ys, t, u]",
+        output: ["[[1, 2, 3, 1, 2, 3], [1, 2, 3, 1, 2, 3], (1, 2), (1,)]"],
+    }
+
+    test! {
+        name: augmented_item_missing_key,
+        input: "d = {}\nd['a'] += 1",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: KeyError: 'a'",
+            "KeyError: line 2",
+        ],
+    }
+
+    test! {
+        name: conditional_expression,
+        input: "def key(n):\n  print n\n  return n\nprint key(1) if key(0) else key(2)\
+                \ndef sign(n):\n  return -1 if n < 0 else 1 if n > 0 else 0\
+                \nprint [sign(-5), sign(0), sign(7), 1.5 if True else 2, 'a' if 0 else None]\
+                \ndef f(a, b=1 if True else 2):\n  return a + b\nprint f(10 if 1 and 0 or 1 else 20)",
+        output: ["0", "2", "2", "[-1, 0, 1, 1.5, None]", "11"],
+    }
+
+    test! {
+        name: conditional_expression_skips_other_value,
+        input: "xs = []\nprint xs[0] if xs else 'empty'\ndef fact(n):\
+                \n  return 1 if n <= 1 else n * fact(n - 1)\nprint fact(20)",
+        output: ["empty", "2432902008176640000"],
+    }
+
+    test! {
+        name: pass_statement,
+        input: "def f():\n  pass\nif f():\n  pass\nelse:\n  print f()",
+        output: ["None"],
+    }
+
+    test! {
+        name: del_name,
+        input: "def f(n):\n  big = [n, n]\n  del big\n  return n\nx = 'x'\ndel x\nprint f(4)",
+        output: ["4"],
+    }
+
+    test! {
+        name: read_after_del,
+        input: "x = [1]\ndef f(a):\n  del a\n  try:\n    print a\n  except UnboundLocalError:\
+                \n    print 'local'\n  try:\n    del a\n  except NameError:\n    print 'again'\
+                \n  a = 2\n  return a\nprint f(1)\ndel x\nprint x",
+        output: [
+            "local",
+            "again",
+            "2",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 16, in <module>",
+            "stderr: NameError: name 'x' is not defined",
+            "NameError: line 16",
+        ],
+    }
+
+    test! {
+        name: assert_statement,
+        input: "def m():\n  print 'evaluated'\n  return 'm'\nassert 1 < 2, m()\nassert [0]\
+                \nassert False, m()\nprint 'unreachable'",
+        output: [
+            "evaluated",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 6, in <module>",
+            "stderr: AssertionError: m",
+            "AssertionError: line 6",
+        ],
+    }
+
+    test! {
+        name: assert_compiled_out,
+        optimize: true,
+        input: "def f(n):\n  assert n > 0, g()\n  return n\nassert False\nprint f(-1)",
+        output: ["-1"],
+    }
+
+    test! {
+        name: one_line_suites,
+        input: "def f(): return 1\ndef g(n):\n  if n < 0: return -1\n  elif n == 0: return 0\
+                \n  else: x = n; return x * 2;\nprint f(); print [g(-3), g(0), g(5)]\
+                \nif f(): print 'a'; print 'b'\nelse: print 'c'",
+        output: ["1", "[-1, 0, 10]", "a", "b"],
+    }
+
+    test! {
+        name: print_items,
+        input: "print 1, 2.5, 'x', [1, 'y'], None\nprint\nprint (1, 2), 12345678901234567890",
+        output: ["1 2.5 x [1, 'y'] None", "", "(1, 2) 12345678901234567890"],
+    }
+
+    test! {
+        name: print_trailing_comma,
+        input: "print 1,\nprint 2,\nprint\nprint 'a\\n', 3\nprint 'b\\t',\nprint 4\
+                \nprint '',\nprint 'c ',\nprint 5",
+        output: ["1 2", "a", "3", "b\t4", " c  5"],
+    }
+
+    test! {
+        name: print_to_stderr,
+        input: "import sys\nprint 1,\nprint >> sys.stderr, 2,\nprint >> sys.stderr, 3\
+                \nprint >> None, 4\nprint >> sys.stdout, 5",
+        output: ["1 4", "5", "stderr: 2 3"],
+    }
+
+    test! {
+        name: print_evaluates_items_in_turn,
+        input: "def f():\n  print 'f',\n  return 1\nprint 'a', f(), 'b'",
+        output: ["a f 1 b"],
+    }
+
+    test! {
+        name: softspace_survives_collection,
+        heap_size: 65536,
+        input: "def g(n):\n  if n:\n    [n, n, n, n, n, n, n, n, n, n, n, n, n, n, n, n]\
+                \n    [n, n, n, n, n, n, n, n, n, n, n, n, n, n, n, n]\n    g(n - 1)\
+                \nprint 1,\ng(1000)\nprint 2",
+        output: ["1 2"],
+    }
+
+    test! {
+        name: open_line_ends_on_exit,
+        input: "def f(x):\n  print x,\nf(1)\nf(2)",
+        output: ["1 2"],
+    }
+
+    test! {
+        name: open_line_ends_on_exception,
+        input: "print 1,\nprint 1 / 0",
+        output: [
+            "1",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 2, in <module>",
+            "stderr: ZeroDivisionError: integer division or modulo by zero",
+            "ZeroDivisionError: line 2",
+        ],
+    }
+
+    test! {
+        name: long_dict_keys,
+        input: "d = {2 ** 80: 'big', 5: 'five'}\nprint [d[2 ** 80], d[5L], d[5.0]]",
+        output: ["['big', 'five', 'five']"],
+    }
+
+    test! {
+        name: try_except_else_finally,
+        input: "def check(n):\n  if n < 0:\n    raise ValueError('negative', n)\n  return n * 2\
+                \ndef run(n):\n  try:\n    print check(n)\n  except ValueError, e:\
+                \n    print 'caught', e\n  else:\n    print 'fine'\n  finally:\n    print 'done', n\
+                \nrun(1)\nrun(-2)",
+        output: ["2", "fine", "done 1", "caught ('negative', -2)", "done -2"],
+    }
+
+    test! {
+        name: runtime_errors_caught,
+        input: "d = {}\ntry:\n  d['k']\nexcept LookupError, e:\n  print 'lookup', [e], e\
+                \ntry:\n  print 1 / 0\nexcept (TypeError, ZeroDivisionError):\n  print 'zero'",
+        output: ["lookup [KeyError('k',)] 'k'", "zero"],
+    }
+
+    test! {
+        name: return_through_finally,
+        input: "def f(xs):\n  try:\n    return xs[3]\n  finally:\n    print 'finally'\
+                \ntry:\n  f([1])\nexcept IndexError:\n  print 'index'\nprint f([1, 2, 3, 4])",
+        output: ["finally", "index", "finally", "4"],
+    }
+
+    test! {
+        name: bare_raise_and_uncaught,
+        input: "def g():\n  try:\n    raise KeyError('x')\n  except KeyError:\n    print 'inner'\
+                \n    raise\ntry:\n  g()\nexcept Exception, e:\n  print 'outer', e\
+                \nraise TypeError('bad')",
+        output: [
+            "inner",
+            "outer 'x'",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 11, in <module>",
+            "stderr: TypeError: bad",
+            "TypeError: line 11",
+        ],
+    }
+
+    test! {
+        name: handler_survives_collection,
+        heap_size: 40000,
+        input: "def build(n):\n  if n == 0:\n    return []\n  xs = build(n - 1)\
+                \n  xs.append([n, [n]])\n  return xs\ntry:\n  raise ValueError\
+                \nexcept ValueError, e:\n  ys = build(300)\n  print len(ys), [e]\
+                \ndef h(n):\n  try:\n    if n:\n      raise ValueError('v')\n    return 'ok'\
+                \n  except ValueError, e:\n    return e\nprint h(0), h(1)",
+        output: ["300 [ValueError()]", "ok v"],
+    }
+
+    test! {
+        name: assertion_caught,
+        input: "try:\n  assert 1 > 2, 'no'\nexcept AssertionError, e:\n  print 'assert', e\
+                \ntry:\n  raise 5\nexcept TypeError:\n  print 'type'",
+        output: ["assert no", "type"],
+    }
+
+    test! {
+        name: traceback_frames,
+        input: "def f(n):\n  if n == 0:\n    return 1 / n\n  return f(n - 1)\nprint f(2)",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 5, in <module>",
+            "stderr:   File \"<stdin>\", line 4, in f",
+            "stderr:   File \"<stdin>\", line 4, in f",
+            "stderr:   File \"<stdin>\", line 3, in f",
+            "stderr: ZeroDivisionError: integer division or modulo by zero",
+            "ZeroDivisionError: line 3",
+        ],
+    }
+
+    test! {
+        name: traceback_after_handler,
+        options: Options {
+            filename: "x.py".to_owned(),
+            ..Options::default()
+        },
+        input: "def f(n):\n  try:\n    return 10 / n\n  except ZeroDivisionError:\n    return g(n)\
+                \ndef g(n):\n  raise ValueError('bad', n)\nprint f(2)\nprint f(0)",
+        output: [
+            "5",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"x.py\", line 9, in <module>",
+            "stderr:   File \"x.py\", line 5, in f",
+            "stderr:   File \"x.py\", line 7, in g",
+            "stderr: ValueError: ('bad', 0)",
+            "ValueError: line 7",
+        ],
+    }
+
+    test! {
+        name: bare_raise_keeps_traceback,
+        input: "def f(d):\n  return d['k']\ndef g(d):\n  try:\n    f(d)\n  except KeyError:\
+                \n    print 'again'\n    raise\ng({})",
+        output: [
+            "again",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 9, in <module>",
+            "stderr:   File \"<stdin>\", line 5, in g",
+            "stderr:   File \"<stdin>\", line 2, in f",
+            "stderr: KeyError: 'k'",
+            "KeyError: line 2",
+        ],
+    }
+
+    // the same, with wasm's own exception handling.
+    test! {
+        name: try_except_else_finally_wasm,
+        exceptions: Handling::Wasm,
+        input: "def check(n):\n  if n < 0:\n    raise ValueError('negative', n)\n  return n * 2\
+                \ndef run(n):\n  try:\n    print check(n)\n  except ValueError, e:\
+                \n    print 'caught', e\n  else:\n    print 'fine'\n  finally:\n    print 'done', n\
+                \nrun(1)\nrun(-2)",
+        output: ["2", "fine", "done 1", "caught ('negative', -2)", "done -2"],
+    }
+
+    test! {
+        name: return_through_finally_wasm,
+        exceptions: Handling::Wasm,
+        input: "def f(xs):\n  try:\n    return xs[3]\n  finally:\n    print 'finally'\
+                \ntry:\n  f([1])\nexcept IndexError:\n  print 'index'\nprint f([1, 2, 3, 4])",
+        output: ["finally", "index", "finally", "4"],
+    }
+
+    test! {
+        name: bare_raise_and_uncaught_wasm,
+        exceptions: Handling::Wasm,
+        input: "def g():\n  try:\n    raise KeyError('x')\n  except KeyError:\n    print 'inner'\
+                \n    raise\ntry:\n  g()\nexcept Exception, e:\n  print 'outer', e\
+                \nraise TypeError('bad')",
+        output: [
+            "inner",
+            "outer 'x'",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 11, in <module>",
+            "stderr: TypeError: bad",
+            "TypeError: line 11",
+        ],
+    }
+
+    test! {
+        name: bare_raise_keeps_traceback_wasm,
+        exceptions: Handling::Wasm,
+        input: "def f(d):\n  return d['k']\ndef g(d):\n  try:\n    f(d)\n  except KeyError:\
+                \n    print 'again'\n    raise\ng({})",
+        output: [
+            "again",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 9, in <module>",
+            "stderr:   File \"<stdin>\", line 5, in g",
+            "stderr:   File \"<stdin>\", line 2, in f",
+            "stderr: KeyError: 'k'",
+            "KeyError: line 2",
+        ],
+    }
+
+    test! {
+        name: classes,
+        input: "class Animal(object):\n  kind = 'animal'\n  def __init__(self, name):\
+                \n    self.name = name\n    self.count = 0\n  def speak(self):\
+                \n    return self.name + ' makes a sound'\n  def bump(self, n=1):\
+                \n    self.count += n\n    return self.count\nclass Dog(Animal):\
+                \n  def speak(self):\n    return self.name + ' barks'\
+                \na = Animal('cat')\nd = Dog('rex')\nprint a.speak(), d.speak()\
+                \nprint d.kind, d.name\nprint d.bump(), d.bump(5), d.bump(n=2)\
+                \nAnimal.__init__(d, 'max')\nprint d.name, d.count\nprint [d.kind, a == d]",
+        output: [
+            "cat makes a sound rex barks",
+            "animal rex",
+            "1 6 8",
+            "max 0",
+            "['animal', False]",
+        ],
+    }
+
+    test! {
+        name: class_attributes,
+        input: "class Counter:\n  total = 0\n  def add(self):\n    Counter.total += 1\
+                \nc = Counter()\nc.add()\nc.add()\nprint Counter.total, c.total\
+                \nc.total = 10\nprint Counter.total, c.total\ndel c.total\nprint c.total\
+                \ntry:\n  del c.total\nexcept AttributeError:\n  print 'not its own'\
+                \nprint Counter, Counter is Counter",
+        output: ["2 2", "2 10", "2", "not its own", "__main__.Counter True"],
+    }
+
+    test! {
+        name: class_body_names,
+        input: "w = 5\nx = 10\nclass A:\n  z = x\n  x = 1\n  y = x + w\nclass B:\n  v = 2\
+                \n  u = v * v\nprint A.y, A.z, x, B.u",
+        output: ["6 10 10 4"],
+    }
+
+    test! {
+        name: isinstance,
+        input: "class A: pass\nclass B(A): pass\nclass C(object): pass\na = A()\nb = B()\
+                \nprint isinstance(b, A), isinstance(a, B), isinstance(a, (C, B, A))\
+                \nprint isinstance(3, A), isinstance(a, object), isinstance(KeyError(), LookupError)\
+                \nprint C",
+        output: [
+            "True False True",
+            "False True True",
+            "<class '__main__.C'>",
+        ],
+    }
+
+    test! {
+        name: method_dispatch,
+        input: "class A:\n  def f(self, x):\n    return x + 1\nclass B:\n  def f(self):\
+                \n    return 'b'\ndef call(o):\n  return o.f(1)\nprint call(A())\
+                \nxs = [1]\nxs.append(2)\nprint xs\ntry:\n  call(B())\nexcept TypeError, e:\
+                \n  print e\ntry:\n  B().f(x=1)\nexcept TypeError, e:\n  print e\ntry:\n  call(xs)                \nexcept AttributeError:\n  print 'no f'",
+        output: [
+            "2",
+            "[1, 2]",
+            "f() takes exactly 1 argument (2 given)",
+            "f() got an unexpected keyword argument 'x'",
+            "no f",
+        ],
+    }
+
+    test! {
+        name: missing_attribute,
+        input: "class P:\n  def f(self, x):\n    return x.missing\np = P()\nprint p.f(p)",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 5, in <module>",
+            "stderr:   File \"<stdin>\", line 3, in f",
+            "stderr: AttributeError: P instance has no attribute 'missing'",
+            "AttributeError: line 3",
+        ],
+    }
+
+    test! {
+        name: init_returns_none,
+        input: "class A:\n  def __init__(self):\n    return 1\nA()",
+        output: [
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 4, in <module>",
+            "stderr: TypeError: __init__() should return None, not 'int'",
+            "TypeError: line 4",
+        ],
+    }
+
+    test! {
+        name: operator_methods,
+        input: "class Money(object):\n  def __init__(self, cents):\n    self.cents = cents\
+                \n  def __add__(self, other):\n    if isinstance(other, Money):\
+                \n      return Money(self.cents + other.cents)\n    return NotImplemented\
+                \n  def __radd__(self, other):\n    return Money(self.cents + other)\
+                \na = Money(5) + Money(7)\nb = 3 + a\
+                \nprint a.cents, b.cents, (b + Money(1)).cents\nb += Money(10)\nprint b.cents\ntry:\n  a + 'x'\nexcept TypeError:\
+                \n  print 'unsupported'\
+                \nprint NotImplemented, [NotImplemented, None], NotImplemented is None",
+        output: [
+            "12 15 16",
+            "25",
+            "unsupported",
+            "NotImplemented [NotImplemented, None] False",
+        ],
+    }
+
+    test! {
+        name: comparison_methods,
+        input: "class Version:\n  def __init__(self, major, minor):\n    self.major = major\
+                \n    self.minor = minor\n  def __eq__(self, other):\
+                \n    if isinstance(other, Version):\
+                \n      return [self.major, self.minor] == [other.major, other.minor]\
+                \n    return NotImplemented\
+                \n  def __lt__(self, other):\
+                \n    return [self.major, self.minor] < [other.major, other.minor]\
+                \n  def __gt__(self, other):\
+                \n    return [self.major, self.minor] > [other.major, other.minor]\
+                \nv = Version(1, 2)\
+                \nprint v == Version(1, 2), v == Version(1, 3), v == 1, 1 == v, v != Version(1, 2)\
+                \nprint v < Version(2, 0), Version(2, 0) < v, v < v\
+                \nvs = [Version(1, 0), v]\nprint vs[0] < vs[1], 3 < 4",
+        output: ["True False False False True", "True False False", "True True"],
+    }
+
+    test! {
+        name: container_methods,
+        input: "class Grid:\n  def __init__(self):\n    self.cells = {}\
+                \n  def __getitem__(self, at):\n    if at in self.cells:\
+                \n      return self.cells[at]\n    return '.'\
+                \n  def __setitem__(self, at, value):\n    self.cells[at] = value\
+                \n  def __len__(self):\n    return len(self.cells)\ng = Grid()\
+                \ng[0, 1] = 'x'\ng[2, 2] = 'y'\ng[0, 1] += '!'\
+                \nprint g[0, 1], g[1, 1], len(g), len('ab'), [3][0]\
+                \nclass Bad(object):\n  def __len__(self):\n    return -1\ntry:\
+                \n  len(Bad())\nexcept ValueError:\n  print 'negative'\ntry:\
+                \n  Bad()[0]\nexcept TypeError:\n  print 'unsubscriptable'",
+        output: ["x! . 2 2 3", "negative", "unsubscriptable"],
+    }
+
+    test! {
+        name: str_methods,
+        input: "class Point(object):\n  def __repr__(self):\n    return 'Point'\
+                \nclass Named(Point):\n  def __str__(self):\n    return 'Named point'\
+                \np = Point()\nn = Named()\nprint p\nprint n, p, 5\nprint 'a', n,\nprint\
+                \nclass Broken:\n  def __str__(self):\n    return 7\nprint Broken()",
+        output: [
+            "Point",
+            "Named point Point 5",
+            "a Named point",
+            "stderr: Traceback (most recent call last):",
+            "stderr:   File \"<stdin>\", line 16, in <module>",
+            "stderr: TypeError: __str__ returned non-string (type int)",
+            "TypeError: line 16",
+        ],
+    }
+
+    test! {
+        name: repr_in_containers,
+        input: "class R(object):\n  def __repr__(self):\n    return 'R'\nclass N(R):\
+                \n  def __str__(self):\n    return 'N'\nprint [R()], (R(),), {1: R()}\
+                \nprint N(), [N(), [N()]]",
+        output: ["[R] (R,) {1: R}", "N [R, [R]]"],
+    }
+
+    test! {
+        name: operands_kept_for_reflected_method,
+        heap_size: 4096,
+        input: "class V(object):\n  def __init__(self, n):\n    self.items = [n, 'v' + 'w']\
+                \n  def __add__(self, other):\n    other = None\
+                \n    garbage = [[1], {2: 'x' + 'y'}, [3, 4, 5, 6, 7, 8]]\
+                \n    return NotImplemented\nclass W(V):\n  def __radd__(self, other):\
+                \n    garbage = [V(0), V(0), V(0)]\
+                \n    return self.items[0] + other.items[0]\ndef make(n):\n  return W(n)\
+                \ndef churn(n):\n  if n == 0:\n    return 0\n  m = V(1) + make(n)\
+                \n  return m + churn(n - 1)\nprint churn(100)",
+        output: ["5150"],
+    }
+
+    test! {
+        name: instances_collected,
+        heap_size: 16384,
+        input: "class Node(object):\n  def __init__(self, value, next):\n    self.value = value\
+                \n    self.next = next\n    self.tag = 'n' + 'x'\n  def total(self):\
+                \n    if self.next is None:\n      return self.value\
+                \n    return self.value + self.next.total()\ndef chain(n):\n  if n == 0:\
+                \n    return None\n  return Node([n, n + 0.5][0], chain(n - 1))\ndef once(n):\
+                \n  c = chain(20)\n  garbage = [Node(n, None), {n: Node(n, c)}]\
+                \n  return c.total()\ndef churn(n):\n  if n > 0:\
+                \n    return once(n) + churn(n - 1)\n  return 0\nprint churn(100)",
+        output: ["21000"],
+    }
+
+    #[test]
+    fn dump_types_without_asserts() {
+        let text = "def f(a):\n  return a\nassert f(1.5)";
+        let options = Options {
+            optimize: true,
+            ..Options::default()
+        };
+        let types = infer_types(text, &Options::default()).unwrap();
+        assert_eq!(types.to_string(), "f -> float\n  a: float\n");
+        let types = infer_types(text, &options).unwrap();
+        assert_eq!(types.to_string(), "f -> object\n  a: object\n");
     }
 }
@@ -1,11 +1,84 @@
+use runtime;
+use std::fmt;
 use token::Token;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq)]
 pub enum Error {
     UnexpectedStartOfToken(char),
     UnexpectedCharacter(Option<char>),
     UnpairedBackslash(Option<char>),
     UnmatchedIndentationLevel(u64),
+    UnterminatedString,
 
     UnexpectedToken(Token),
+    InvalidEscape(usize),
+    DuplicateArgument(String),
+    NonDefaultArgumentFollowsDefault,
+    NonKeywordArgumentFollowsKeyword,
+    RepeatedKeywordArgument(String),
+    LocalAndGlobal(String),
+    TooManyNames(usize),
+
+    TypeError { message: String, line: usize },
+    NameError { message: String, line: usize },
+    ImportError { message: String, line: usize },
+    Unsupported { message: String, line: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnexpectedStartOfToken(c) => write!(f, "unexpected character {:?}", c),
+            Error::UnexpectedCharacter(Some(c)) => write!(f, "unexpected character {:?}", c),
+            Error::UnexpectedCharacter(None) => write!(f, "unexpected end of input"),
+            Error::UnpairedBackslash(_) => {
+                write!(f, "unexpected character after line continuation character")
+            }
+            Error::UnmatchedIndentationLevel(_) => {
+                write!(f, "unindent does not match any outer indentation level")
+            }
+            Error::UnterminatedString => write!(f, "EOL while scanning single-quoted string"),
+            Error::InvalidEscape(line) => {
+                write!(f, "line {}: ValueError: invalid \\x escape", line)
+            }
+            Error::UnexpectedToken(token) => {
+                write!(
+                    f,
+                    "line {}: invalid syntax at {:?}",
+                    token.line, token.lexeme
+                )
+            }
+            Error::DuplicateArgument(name) => {
+                write!(f, "duplicate argument '{}' in function definition", name)
+            }
+            Error::NonDefaultArgumentFollowsDefault => {
+                write!(f, "non-default argument follows default argument")
+            }
+            Error::NonKeywordArgumentFollowsKeyword => {
+                write!(f, "non-keyword arg after keyword arg")
+            }
+            Error::RepeatedKeywordArgument(_) => write!(f, "keyword argument repeated"),
+            Error::LocalAndGlobal(name) => write!(f, "name '{}' is local and global", name),
+            Error::TooManyNames(size) => write!(
+                f,
+                "the names of the functions and classes take {} bytes, more than the {} \
+                 there is room for",
+                size,
+                runtime::HEAP - runtime::NAMES
+            ),
+            Error::NameError { message, line } => {
+                write!(f, "line {}: NameError: {}", line, message)
+            }
+            Error::TypeError { message, line } => {
+                write!(f, "line {}: TypeError: {}", line, message)
+            }
+            Error::ImportError { message, line } => {
+                write!(f, "line {}: ImportError: {}", line, message)
+            }
+            Error::Unsupported { message, line } => {
+                write!(f, "line {}: {} is not supported", line, message)
+            }
+        }
+    }
 }
@@ -0,0 +1,117 @@
+// the builtin exception classes. the discriminant is the class's code, which
+// is what the runtime keeps in an exception and passes to the host's `raise`
+// import, along with the line number, for one that isn't caught.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Exception {
+    NameError = 1,
+    TypeError = 2,
+    ZeroDivisionError = 3,
+    MemoryError = 4,
+    IndexError = 5,
+    AttributeError = 6,
+    KeyError = 7,
+    ValueError = 8,
+    AssertionError = 9,
+    Exception = 10,
+    StandardError = 11,
+    ArithmeticError = 12,
+    LookupError = 13,
+    BaseException = 14,
+    UnboundLocalError = 15,
+}
+
+// how a raised exception gets to the handler that catches it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Handling {
+    // every call that can raise is followed by a check of the runtime's
+    // $exception global, which branches to the handler or returns.
+    Status,
+    // the exception handling proposal's throw and try_table.
+    Wasm,
+}
+
+impl Exception {
+    pub const ALL: [Exception; 15] = [
+        Exception::NameError,
+        Exception::TypeError,
+        Exception::ZeroDivisionError,
+        Exception::MemoryError,
+        Exception::IndexError,
+        Exception::AttributeError,
+        Exception::KeyError,
+        Exception::ValueError,
+        Exception::AssertionError,
+        Exception::Exception,
+        Exception::StandardError,
+        Exception::ArithmeticError,
+        Exception::LookupError,
+        Exception::BaseException,
+        Exception::UnboundLocalError,
+    ];
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    #[cfg(test)]
+    pub fn from_code(code: u32) -> Option<Exception> {
+        Exception::ALL.iter().cloned().find(|e| e.code() == code)
+    }
+
+    pub fn from_name(name: &str) -> Option<Exception> {
+        Exception::ALL.iter().cloned().find(|e| e.name() == name)
+    }
+
+    pub fn name(self) -> String {
+        format!("{:?}", self)
+    }
+
+    // the class this one derives from, as python 2.5 has it.
+    pub fn parent(self) -> Option<Exception> {
+        match self {
+            Exception::BaseException => None,
+            Exception::Exception => Some(Exception::BaseException),
+            Exception::StandardError => Some(Exception::Exception),
+            Exception::ZeroDivisionError => Some(Exception::ArithmeticError),
+            Exception::IndexError | Exception::KeyError => Some(Exception::LookupError),
+            Exception::UnboundLocalError => Some(Exception::NameError),
+            _ => Some(Exception::StandardError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        for exception in Exception::ALL.iter() {
+            assert_eq!(Exception::from_code(exception.code()), Some(*exception));
+            assert_eq!(Exception::from_name(&exception.name()), Some(*exception));
+        }
+    }
+
+    #[test]
+    fn hierarchy() {
+        let ancestors = |mut exception: Exception| {
+            let mut ancestors = vec![];
+            while let Some(parent) = exception.parent() {
+                ancestors.push(parent);
+                exception = parent;
+            }
+            ancestors
+        };
+        assert_eq!(
+            ancestors(Exception::KeyError),
+            vec![
+                Exception::LookupError,
+                Exception::StandardError,
+                Exception::Exception,
+                Exception::BaseException,
+            ]
+        );
+        assert_eq!(ancestors(Exception::BaseException), vec![]);
+    }
+}
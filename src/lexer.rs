@@ -4,6 +4,7 @@ use token::{Token, TokenKind};
 pub struct Lexer {
     chars: Vec<char>,
     current: Option<char>,
+    line: usize,
     column: u64,
     indent_stack: Vec<u64>,
     // false until we've encountered a nonblank character on a line.
@@ -46,11 +47,12 @@ impl Lexer {
             // as flowing from the outputs to the inputs
             chars,
             current,
+            line: 1,
             column: 0,
             // indent_stack
             // - push current level of indentation onto stack whenever I add indent token.
             // - ex: [2, 4, 7, 9] means that indent, respectively, were: [2, 2, 3, 2].
-            indent_stack: indent_stack,
+            indent_stack,
             seen_nonblank: false,
             cur_token,
         }
@@ -60,6 +62,7 @@ impl Lexer {
         let token = Token {
             kind,
             lexeme: self.cur_token.clone(),
+            line: self.line,
         };
         self.cur_token.clear();
         token
@@ -75,7 +78,11 @@ impl Lexer {
                 }
             }
         }
-        Token { kind, lexeme }
+        Token {
+            kind,
+            lexeme,
+            line: self.line,
+        }
     }
 
     fn next(&mut self) -> Option<char> {
@@ -123,8 +130,12 @@ impl Lexer {
             }
 
             match c {
-                'a'...'z' => tokens.push(self.lex_identifier_or_keyword()),
-                '0'...'9' => tokens.push(self.lex_integer()),
+                'a'..='z' | 'A'..='Z' | '_' => tokens.push(self.lex_identifier_or_keyword()),
+                '0'..='9' => tokens.push(self.lex_number()?),
+                '\'' | '"' => tokens.push(self.lex_string()?),
+                '.' if matches!(self.chars.first(), Some('0'..='9')) => {
+                    tokens.push(self.lex_number()?)
+                }
                 '#' => self.lex_comment(),
                 '\n' => tokens.push(self.lex_newline()),
                 '\\' => self.lex_backslash()?,
@@ -146,14 +157,39 @@ impl Lexer {
                     let t = self.make_token(TokenKind::ParenR);
                     tokens.push(t);
                 }
+                '[' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::BracketL);
+                    tokens.push(t);
+                }
+                ']' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::BracketR);
+                    tokens.push(t);
+                }
+                '{' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::BraceL);
+                    tokens.push(t);
+                }
+                '}' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::BraceR);
+                    tokens.push(t);
+                }
+                '.' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::Dot);
+                    tokens.push(t);
+                }
                 '+' => {
                     self.next();
-                    let t = self.make_token(TokenKind::Plus);
+                    let t = self.lex_augmented(TokenKind::Plus, TokenKind::PlusEquals);
                     tokens.push(t);
                 }
                 '-' => {
                     self.next();
-                    let t = self.make_token(TokenKind::Minus);
+                    let t = self.lex_augmented(TokenKind::Minus, TokenKind::MinusEquals);
                     tokens.push(t);
                 }
                 ':' => {
@@ -166,19 +202,40 @@ impl Lexer {
                     let t = self.make_token(TokenKind::Comma);
                     tokens.push(t);
                 }
-                '*' => {
+                ';' => {
                     self.next();
-                    let t = self.make_token(TokenKind::Mult);
+                    let t = self.make_token(TokenKind::Semicolon);
                     tokens.push(t);
                 }
+                '*' => tokens.push(self.lex_star()?),
                 '/' => {
                     self.next();
-                    let t = self.make_token(TokenKind::Div);
+                    let t = self.lex_augmented(TokenKind::Div, TokenKind::DivEquals);
                     tokens.push(t);
                 }
                 '%' => {
                     self.next();
-                    let t = self.make_token(TokenKind::Mod);
+                    let t = self.lex_augmented(TokenKind::Mod, TokenKind::ModEquals);
+                    tokens.push(t);
+                }
+                '&' => {
+                    self.next();
+                    let t = self.lex_augmented(TokenKind::BitAnd, TokenKind::BitAndEquals);
+                    tokens.push(t);
+                }
+                '|' => {
+                    self.next();
+                    let t = self.lex_augmented(TokenKind::BitOr, TokenKind::BitOrEquals);
+                    tokens.push(t);
+                }
+                '^' => {
+                    self.next();
+                    let t = self.lex_augmented(TokenKind::BitXor, TokenKind::BitXorEquals);
+                    tokens.push(t);
+                }
+                '~' => {
+                    self.next();
+                    let t = self.make_token(TokenKind::Invert);
                     tokens.push(t);
                 }
                 '<' => tokens.push(self.lex_lt()?),
@@ -204,7 +261,7 @@ impl Lexer {
         let mut text = String::new();
         while let Some(c) = self.current {
             match c {
-                'a'...'z' | '0'...'9' => text.push(c),
+                'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => text.push(c),
                 _ => break,
             }
             self.next();
@@ -216,25 +273,83 @@ impl Lexer {
             "else" => self.make_token(TokenKind::Else),
             "print" => self.make_token(TokenKind::Print),
             "def" => self.make_token(TokenKind::Def),
+            "class" => self.make_token(TokenKind::Class),
             "return" => self.make_token(TokenKind::Return),
+            "global" => self.make_token(TokenKind::Global),
+            "import" => self.make_token(TokenKind::Import),
+            "del" => self.make_token(TokenKind::Del),
+            "pass" => self.make_token(TokenKind::Pass),
+            "assert" => self.make_token(TokenKind::Assert),
+            "try" => self.make_token(TokenKind::Try),
+            "except" => self.make_token(TokenKind::Except),
+            "finally" => self.make_token(TokenKind::Finally),
+            "raise" => self.make_token(TokenKind::Raise),
+            "in" => self.make_token(TokenKind::In),
+            "not" => self.make_token(TokenKind::Not),
+            "is" => self.make_token(TokenKind::Is),
             "or" => self.make_token(TokenKind::Or),
             "and" => self.make_token(TokenKind::And),
             _ => self.make_token(TokenKind::Identifier),
         }
     }
 
-    fn lex_integer(&mut self) -> Token {
-        let mut integer = 0;
-        while let Some(c) = self.current {
-            match c {
-                '0'...'9' => {
-                    integer = integer * 10 + (c as u32 - '0' as u32);
+    fn lex_number(&mut self) -> Result<Token, Error> {
+        let mut kind = TokenKind::Integer;
+        self.lex_digits();
+        // an `L` makes an int literal a long.
+        if self.current == Some('L') || self.current == Some('l') {
+            self.next();
+            return Ok(self.make_token(kind));
+        }
+        if self.current == Some('.') {
+            kind = TokenKind::Float;
+            self.next();
+            self.lex_digits();
+        }
+        if self.current == Some('e') || self.current == Some('E') {
+            kind = TokenKind::Float;
+            self.next();
+            if self.current == Some('+') || self.current == Some('-') {
+                self.next();
+            }
+            match self.current {
+                Some('0'..='9') => self.lex_digits(),
+                c => return Err(Error::UnexpectedCharacter(c)),
+            }
+        }
+        Ok(self.make_token(kind))
+    }
+
+    // the lexeme keeps its quotes and escapes; the parser works out the bytes.
+    fn lex_string(&mut self) -> Result<Token, Error> {
+        let quote = self.current;
+        let line = self.line;
+        self.next();
+        loop {
+            match self.current {
+                None | Some('\n') => return Err(Error::UnterminatedString),
+                Some('\\') => {
+                    if self.next() == Some('\n') {
+                        self.line += 1;
+                    }
+                    self.next();
+                }
+                c if c == quote => break,
+                _ => {
+                    self.next();
                 }
-                _ => break,
             }
+        }
+        self.next();
+        let mut token = self.make_token(TokenKind::Str);
+        token.line = line;
+        Ok(token)
+    }
+
+    fn lex_digits(&mut self) {
+        while let Some('0'..='9') = self.current {
             self.next();
         }
-        self.make_token(TokenKind::Integer)
     }
 
     fn lex_comment(&mut self) {
@@ -251,11 +366,14 @@ impl Lexer {
         self.next();
         self.column = 0;
         self.seen_nonblank = false;
-        self.make_token(TokenKind::Newline)
+        let token = self.make_token(TokenKind::Newline);
+        self.line += 1;
+        token
     }
 
     fn lex_backslash(&mut self) -> Result<(), Error> {
         if self.next() == Some('\n') {
+            self.line += 1;
             self.next();
             self.cur_token.clear();
             Ok(())
@@ -280,25 +398,56 @@ impl Lexer {
             self.next();
             Ok(self.make_token(TokenKind::EqEq))
         } else {
-            Err(Error::UnexpectedCharacter(self.current))
+            Ok(self.make_token(TokenKind::Equals))
         }
     }
 
     fn lex_lt(&mut self) -> Result<Token, Error> {
-        if self.next() == Some('=') {
-            self.next();
-            Ok(self.make_token(TokenKind::Leq))
-        } else {
-            Ok(self.make_token(TokenKind::Lt))
+        match self.next() {
+            Some('=') => {
+                self.next();
+                Ok(self.make_token(TokenKind::Leq))
+            }
+            Some('<') => {
+                self.next();
+                Ok(self.lex_augmented(TokenKind::LShift, TokenKind::LShiftEquals))
+            }
+            _ => Ok(self.make_token(TokenKind::Lt)),
         }
     }
 
     fn lex_gt(&mut self) -> Result<Token, Error> {
-        if self.next() == Some('=') {
+        match self.next() {
+            Some('=') => {
+                self.next();
+                Ok(self.make_token(TokenKind::Geq))
+            }
+            Some('>') => {
+                self.next();
+                Ok(self.lex_augmented(TokenKind::RShift, TokenKind::RShiftEquals))
+            }
+            _ => Ok(self.make_token(TokenKind::Gt)),
+        }
+    }
+
+    fn lex_star(&mut self) -> Result<Token, Error> {
+        match self.next() {
+            Some('*') => {
+                self.next();
+                Ok(self.lex_augmented(TokenKind::Power, TokenKind::PowerEquals))
+            }
+            _ => Ok(self.lex_augmented(TokenKind::Mult, TokenKind::MultEquals)),
+        }
+    }
+
+    // an operator that has been read, or its augmented assignment if an `=`
+    // follows it.
+    fn lex_augmented(&mut self, kind: TokenKind, augmented: TokenKind) -> Token {
+        if self.current == Some('=') {
             self.next();
-            Ok(self.make_token(TokenKind::Geq))
+            self.make_token(augmented)
         } else {
-            Ok(self.make_token(TokenKind::Gt))
+            self.make_token(kind)
         }
     }
 
@@ -328,6 +477,7 @@ mod test {
                 expected.push(Token {
                     kind: TokenKind::Eof,
                     lexeme: "".to_owned(),
+                    line: text.matches('\n').count() + 1,
                 });
                 assert_eq!(tokens, expected);
             }
@@ -365,6 +515,17 @@ mod test {
         token: [Token{
             kind: TokenKind::Identifier,
             lexeme: "hi".to_owned(),
+            line: 1,
+        }],
+    }
+
+    token_test! {
+        name: mixed_case_identifier,
+        text: "_Max_2",
+        token: [Token{
+            kind: TokenKind::Identifier,
+            lexeme: "_Max_2".to_owned(),
+            line: 1,
         }],
     }
 
@@ -374,19 +535,167 @@ mod test {
         token: [Token{
             kind: TokenKind::If,
             lexeme: "if".to_owned(),
+            line: 1,
+        }],
+    }
+
+    token_test! {
+        name: global_keyword,
+        text: "global",
+        token: [Token{
+            kind: TokenKind::Global,
+            lexeme: "global".to_owned(),
+            line: 1,
+        }],
+    }
+
+    token_test! {
+        name: import_keyword,
+        text: "import",
+        token: [Token{
+            kind: TokenKind::Import,
+            lexeme: "import".to_owned(),
+            line: 1,
         }],
     }
 
+    token_test! {
+        name: pass_and_assert_keywords,
+        text: "pass assert",
+        token: [
+            Token {
+                kind: TokenKind::Pass,
+                lexeme: "pass".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Assert,
+                lexeme: "assert".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: exception_keywords,
+        text: "try except finally raise",
+        token: [
+            Token {
+                kind: TokenKind::Try,
+                lexeme: "try".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Except,
+                lexeme: "except".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Finally,
+                lexeme: "finally".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Raise,
+                lexeme: "raise".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: class_keyword,
+        text: "class classes",
+        token: [
+            Token {
+                kind: TokenKind::Class,
+                lexeme: "class".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "classes".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: semicolon,
+        text: ";",
+        token: [Token {
+            kind: TokenKind::Semicolon,
+            lexeme: ";".to_owned(),
+            line: 1,
+        }],
+    }
+
+    token_test! {
+        name: float,
+        text: "1.5 2. .25 1e3 2.5E-2",
+        token: [
+            Token {
+                kind: TokenKind::Float,
+                lexeme: "1.5".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Float,
+                lexeme: "2.".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Float,
+                lexeme: ".25".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Float,
+                lexeme: "1e3".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Float,
+                lexeme: "2.5E-2".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    error_test! {
+        name: float_missing_exponent,
+        text: "1e",
+        error: Error::UnexpectedCharacter(None),
+    }
+
     token_test! {
         name: decimal_integer,
         text: "1234",
         token: [Token{
             kind: TokenKind::Integer,
             lexeme: "1234".to_owned(),
+            line: 1,
         }
             ],
     }
 
+    token_test! {
+        name: long_integer,
+        text: "10L 7l",
+        token: [
+            Token {
+                kind: TokenKind::Integer,
+                lexeme: "10L".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Integer,
+                lexeme: "7l".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
     token_test! {
         name: comment,
         text: "# this is a comment",
@@ -399,6 +708,7 @@ mod test {
         token: [Token{
             kind: TokenKind::Newline,
             lexeme: "\n".to_owned(),
+            line: 1,
         }],
     }
 
@@ -427,12 +737,15 @@ mod test {
             Token {
                 kind: TokenKind::Indent,
                 lexeme: "   ".to_owned(),
+                line: 1,
             }, Token {
                 kind: TokenKind::Integer,
                 lexeme: "39".to_owned(),
+                line: 1,
             }, Token {
                 kind: TokenKind::Dedent,
                 lexeme: "".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -444,12 +757,15 @@ mod test {
             Token {
                 kind: TokenKind::Indent,
                 lexeme: "    ".to_owned(),
+                line: 1,
             }, Token {
                 kind: TokenKind::Integer,
                 lexeme: "39".to_owned(),
+                line: 1,
             }, Token {
                 kind: TokenKind::Dedent,
                 lexeme: "".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -466,22 +782,27 @@ mod test {
         token: [Token {
             kind: TokenKind::Indent,
             lexeme: "  ".to_owned(),
+            line: 1,
         },
         Token {
             kind: TokenKind::Integer,
             lexeme: "39".to_owned(),
+            line: 1,
         },
         Token {
             kind: TokenKind::Newline,
             lexeme: "\n".to_owned(),
+            line: 1,
         },
         Token {
             kind: TokenKind::Dedent,
             lexeme: "".to_owned(),
+            line: 2,
         },
         Token {
             kind: TokenKind::Identifier,
             lexeme: "hmm".to_owned(),
+            line: 2,
         }],
     }
 
@@ -492,38 +813,74 @@ mod test {
             Token {
                 kind: TokenKind::Indent,
                 lexeme: "  ".to_owned(),
+                line: 1,
             },
             Token {
                 kind: TokenKind::Integer,
                 lexeme: "39".to_owned(),
+                line: 1,
             },
             Token {
                 kind: TokenKind::Newline,
                 lexeme: "\n".to_owned(),
+                line: 1,
             },
             Token {
                 kind: TokenKind::Indent,
                 lexeme: "   ".to_owned(),
+                line: 2,
             },
             Token {
                 kind: TokenKind::Identifier,
                 lexeme: "hmm".to_owned(),
+                line: 2,
             },
             Token {
                 kind: TokenKind::Newline,
                 lexeme: "\n".to_owned(),
+                line: 2,
             },
             Token {
                 kind: TokenKind::Dedent,
                 lexeme: "  ".to_owned(),
+                line: 3,
             },
             Token {
                 kind: TokenKind::Integer,
                 lexeme: "1".to_owned(),
+                line: 3,
             },
             Token {
                 kind: TokenKind::Dedent,
                 lexeme: "".to_owned(),
+                line: 3,
+            }
+        ],
+    }
+
+    token_test! {
+        name: line_numbers,
+        text: "a\\\nb\nc",
+        token: [
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "a".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "b".to_owned(),
+                line: 2,
+            },
+            Token {
+                kind: TokenKind::Newline,
+                lexeme: "\n".to_owned(),
+                line: 2,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "c".to_owned(),
+                line: 3,
             }
         ],
     }
@@ -541,6 +898,7 @@ mod test {
             Token {
                 kind: TokenKind::Print,
                 lexeme: "print".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -552,6 +910,7 @@ mod test {
             Token {
                 kind: TokenKind::Def,
                 lexeme: "def".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -563,6 +922,7 @@ mod test {
             Token {
                 kind: TokenKind::Elif,
                 lexeme: "elif".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -574,6 +934,7 @@ mod test {
             Token {
                 kind: TokenKind::Else,
                 lexeme: "else".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -585,6 +946,7 @@ mod test {
             Token {
                 kind: TokenKind::ParenL,
                 lexeme: "(".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -596,10 +958,103 @@ mod test {
             Token {
                 kind: TokenKind::ParenR,
                 lexeme: ")".to_owned(),
+                line: 1,
             }
         ],
     }
 
+    token_test! {
+        name: brackets_and_dot,
+        text: "xs[0].y",
+        token: [
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "xs".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BracketL,
+                lexeme: "[".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Integer,
+                lexeme: "0".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BracketR,
+                lexeme: "]".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Dot,
+                lexeme: ".".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "y".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: strings,
+        text: "'a\\'b' \"c\"",
+        token: [
+            Token {
+                kind: TokenKind::Str,
+                lexeme: "'a\\'b'".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Str,
+                lexeme: "\"c\"".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: dict_display,
+        text: "{k: v}",
+        token: [
+            Token {
+                kind: TokenKind::BraceL,
+                lexeme: "{".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "k".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Colon,
+                lexeme: ":".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "v".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BraceR,
+                lexeme: "}".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    error_test! {
+        name: unterminated_string,
+        text: "'abc\n'",
+        error: UnterminatedString,
+    }
+
     token_test! {
         name: return_token,
         text: "return",
@@ -607,6 +1062,7 @@ mod test {
             Token {
                 kind: TokenKind::Return,
                 lexeme: "return".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -618,6 +1074,7 @@ mod test {
             Token {
                 kind: TokenKind::Colon,
                 lexeme: ":".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -629,6 +1086,7 @@ mod test {
             Token {
                 kind: TokenKind::Comma,
                 lexeme: ",".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -640,6 +1098,7 @@ mod test {
             Token {
                 kind: TokenKind::Minus,
                 lexeme: "-".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -651,6 +1110,7 @@ mod test {
             Token {
                 kind: TokenKind::Plus,
                 lexeme: "+".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -662,6 +1122,7 @@ mod test {
             Token {
                 kind: TokenKind::EqEq,
                 lexeme: "==".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -673,6 +1134,7 @@ mod test {
             Token {
                 kind: TokenKind::Lt,
                 lexeme: "<".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -684,6 +1146,7 @@ mod test {
             Token {
                 kind: TokenKind::Gt,
                 lexeme: ">".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -695,10 +1158,90 @@ mod test {
             Token {
                 kind: TokenKind::Mult,
                 lexeme: "*".to_owned(),
+                line: 1,
             }
         ],
     }
 
+    token_test! {
+        name: power,
+        text: "** *",
+        token: [
+            Token {
+                kind: TokenKind::Power,
+                lexeme: "**".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Mult,
+                lexeme: "*".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: augmented_assignment,
+        text: "+= -= *= **= /= %= &= |= ^= <<= >>=",
+        token: [
+            Token {
+                kind: TokenKind::PlusEquals,
+                lexeme: "+=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::MinusEquals,
+                lexeme: "-=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::MultEquals,
+                lexeme: "*=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::PowerEquals,
+                lexeme: "**=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::DivEquals,
+                lexeme: "/=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::ModEquals,
+                lexeme: "%=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BitAndEquals,
+                lexeme: "&=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BitOrEquals,
+                lexeme: "|=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BitXorEquals,
+                lexeme: "^=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::LShiftEquals,
+                lexeme: "<<=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::RShiftEquals,
+                lexeme: ">>=".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
     token_test! {
         name: div,
         text: "/",
@@ -706,6 +1249,7 @@ mod test {
             Token {
                 kind: TokenKind::Div,
                 lexeme: "/".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -717,6 +1261,7 @@ mod test {
             Token {
                 kind: TokenKind::Mod,
                 lexeme: "%".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -728,10 +1273,80 @@ mod test {
             Token {
                 kind: TokenKind::And,
                 lexeme: "and".to_owned(),
+                line: 1,
             }
         ],
     }
 
+    token_test! {
+        name: membership_and_identity,
+        text: "not in is",
+        token: [
+            Token {
+                kind: TokenKind::Not,
+                lexeme: "not".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::In,
+                lexeme: "in".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Is,
+                lexeme: "is".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
+    token_test! {
+        name: bitwise_operators,
+        text: "&|^~<<>><>",
+        token: [
+            Token {
+                kind: TokenKind::BitAnd,
+                lexeme: "&".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BitOr,
+                lexeme: "|".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::BitXor,
+                lexeme: "^".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Invert,
+                lexeme: "~".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::LShift,
+                lexeme: "<<".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::RShift,
+                lexeme: ">>".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Lt,
+                lexeme: "<".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Gt,
+                lexeme: ">".to_owned(),
+                line: 1,
+            },
+        ],
+    }
+
     token_test! {
         name: or,
         text: "or",
@@ -739,6 +1354,7 @@ mod test {
             Token {
                 kind: TokenKind::Or,
                 lexeme: "or".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -750,6 +1366,7 @@ mod test {
             Token {
                 kind: TokenKind::Leq,
                 lexeme: "<=".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -761,6 +1378,7 @@ mod test {
             Token {
                 kind: TokenKind::Geq,
                 lexeme: ">=".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -772,6 +1390,7 @@ mod test {
             Token {
                 kind: TokenKind::Ne,
                 lexeme: "!=".to_owned(),
+                line: 1,
             }
         ],
     }
@@ -782,9 +1401,37 @@ mod test {
         error: UnexpectedCharacter(None),
     }
 
-    error_test! {
-        name:  eqeq_error,
-        text:  "=",
-        error: UnexpectedCharacter(None),
+    token_test! {
+        name: equals,
+        text: "=",
+        token: [
+            Token {
+                kind: TokenKind::Equals,
+                lexeme: "=".to_owned(),
+                line: 1,
+            }
+        ],
+    }
+
+    token_test! {
+        name: keyword_argument,
+        text: "b=2",
+        token: [
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: "b".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Equals,
+                lexeme: "=".to_owned(),
+                line: 1,
+            },
+            Token {
+                kind: TokenKind::Integer,
+                lexeme: "2".to_owned(),
+                line: 1,
+            }
+        ],
     }
 }
@@ -3,28 +3,84 @@ extern crate regex;
 #[cfg(test)]
 extern crate tempfile;
 #[cfg(test)]
+extern crate wasmtime;
+#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
 
+mod binding;
+mod checker;
+mod classes;
 mod codegen;
 mod common;
 mod compile;
 mod error;
+mod exception;
 mod lexer;
 mod parser;
 mod program;
+mod propagate;
+mod runtime;
+mod scope;
 #[cfg(test)]
 mod testing;
 mod token;
+mod types;
+mod unbound;
 mod wexp;
 
+use exception::Handling;
+use std::env;
 use std::io::{stdin, Read};
+use std::process;
+
+const USAGE: &str = "usage: compyler [--dump-types] [--heap-size BYTES] [-O] \
+                     [--exceptions status|wasm] [--filename NAME] < program.py";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
 
 fn main() {
+    let mut dump_types = false;
+    let mut options = compile::Options::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump-types" => dump_types = true,
+            "-O" => options.optimize = true,
+            "--heap-size" => match args.next().and_then(|size| size.parse().ok()) {
+                Some(size) => options.heap_size = size,
+                None => usage(),
+            },
+            "--exceptions" => match args.next().as_deref() {
+                Some("status") => options.exceptions = Handling::Status,
+                Some("wasm") => options.exceptions = Handling::Wasm,
+                _ => usage(),
+            },
+            "--filename" => match args.next() {
+                Some(filename) => options.filename = filename,
+                None => usage(),
+            },
+            _ => usage(),
+        }
+    }
     let mut text = String::new();
     stdin()
         .read_to_string(&mut text)
         .expect("Failed to read input.");
-    let wexp = compile::compile(&text).expect("Compilation failed.");
-    println!("{}", wexp);
+    // --dump-types prints what inference found instead of the module.
+    let output = if dump_types {
+        compile::infer_types(&text, &options).map(|types| types.to_string())
+    } else {
+        compile::compile(&text, &options).map(|wexp| format!("{}\n", wexp))
+    };
+    match output {
+        Ok(output) => print!("{}", output),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
 }
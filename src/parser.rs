@@ -1,16 +1,32 @@
 use error::Error;
 use program::*;
-use token::{Token, TokenKind::{self, *}};
+use token::{
+    Token,
+    TokenKind::{self, *},
+};
+
+// builds the expression for a binary operator from its operands.
+type Operator = fn(Box<Expression>, Box<Expression>) -> Expression;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: Token,
+    // how many suites the statement being parsed is inside of, since a
+    // class can only be defined at the top level
+    depth: usize,
+    // whether the statement being parsed is in the body of a class
+    class_body: bool,
 }
 
 impl Parser {
     pub fn new(mut tokens: Vec<Token>) -> Parser {
         let current = tokens.remove(0);
-        Parser { tokens, current }
+        Parser {
+            tokens,
+            current,
+            depth: 0,
+            class_body: false,
+        }
     }
 
     fn next(&mut self) {
@@ -35,7 +51,10 @@ impl Parser {
     }
 
     fn parse_body(&mut self) -> Result<Body, Error> {
-        let mut statements = Vec::new();
+        let mut body = Body {
+            statements: Vec::new(),
+            lines: Vec::new(),
+        };
         loop {
             match self.current.kind {
                 Eof => break,
@@ -44,54 +63,310 @@ impl Parser {
                     break;
                 }
                 Newline => self.next(),
-                _ => statements.push(self.parse_statement()?),
+                _ => self.parse_line(&mut body)?,
+            }
+        }
+        Ok(body)
+    }
+
+    // a compound statement, or simple statements separated by `;`, with an
+    // optional `;` at the end.
+    fn parse_line(&mut self, body: &mut Body) -> Result<(), Error> {
+        loop {
+            let compound = Self::compound(&self.current.kind);
+            body.lines.push(self.current.line);
+            body.statements.push(self.parse_statement()?);
+            if compound {
+                return Ok(());
             }
+            match self.current.kind {
+                Semicolon => self.next(),
+                Newline | Eof | Dedent => return Ok(()),
+                _ => return Err(Error::UnexpectedToken(self.current.clone())),
+            }
+            if matches!(self.current.kind, Newline | Eof) {
+                return Ok(());
+            }
+            if Self::compound(&self.current.kind) {
+                return Err(Error::UnexpectedToken(self.current.clone()));
+            }
+        }
+    }
+
+    fn compound(kind: &TokenKind) -> bool {
+        matches!(kind, Def | Class | If | Try)
+    }
+
+    fn ends_statement(kind: &TokenKind) -> bool {
+        matches!(kind, Newline | Semicolon | Eof | Dedent)
+    }
+
+    // what follows the colon of a compound statement: an indented block, or
+    // simple statements on the rest of the line.
+    fn parse_suite(&mut self) -> Result<Body, Error> {
+        self.depth += 1;
+        let body = self.parse_block();
+        self.depth -= 1;
+        body
+    }
+
+    fn parse_block(&mut self) -> Result<Body, Error> {
+        self.expect(TokenKind::Colon)?;
+        if self.current.kind == Newline {
+            self.next();
+            self.expect(TokenKind::Indent)?;
+            return self.parse_body();
         }
-        Ok(Body { statements })
+        if Self::compound(&self.current.kind) {
+            return Err(Error::UnexpectedToken(self.current.clone()));
+        }
+        let mut body = Body {
+            statements: Vec::new(),
+            lines: Vec::new(),
+        };
+        self.parse_line(&mut body)?;
+        match self.current.kind {
+            Newline => self.next(),
+            Eof => (),
+            _ => return Err(Error::UnexpectedToken(self.current.clone())),
+        }
+        Ok(body)
     }
 
     fn parse_statement(&mut self) -> Result<Statement, Error> {
+        // a class's body can only define methods and set attributes.
+        if self.class_body {
+            let allowed = match self.current.kind {
+                Def | Pass | Str => true,
+                Identifier => *self.peek() == Equals,
+                _ => false,
+            };
+            if !allowed {
+                return Err(Error::UnexpectedToken(self.current.clone()));
+            }
+        }
         match self.current.kind {
             Print => {
                 self.next();
-                Ok(Statement::Print(self.parse_expression()?))
+                self.parse_print()
             }
             Return => {
                 self.next();
-                Ok(Statement::Return(self.parse_expression()?))
+                Ok(Statement::Return(self.parse_expression_list()?))
             }
             Def => {
                 self.next();
                 self.parse_def()
             }
+            Class if self.depth == 0 => {
+                self.next();
+                self.parse_class()
+            }
             If => {
                 self.next();
                 self.parse_if()
             }
-            _ => Err(Error::UnexpectedToken(self.current.clone())),
+            Try => {
+                self.next();
+                self.parse_try()
+            }
+            Raise => {
+                self.next();
+                self.parse_raise()
+            }
+            Del => {
+                let del = self.current.clone();
+                self.next();
+                match Self::single_target(self.parse_expression()?) {
+                    Some(target) => Ok(Statement::Del(target)),
+                    None => Err(Error::UnexpectedToken(del)),
+                }
+            }
+            Pass => {
+                self.next();
+                Ok(Statement::Pass)
+            }
+            Assert => {
+                self.next();
+                let condition = self.parse_expression()?;
+                let message = match self.current.kind {
+                    Comma => {
+                        self.next();
+                        Some(self.parse_expression()?)
+                    }
+                    _ => None,
+                };
+                Ok(Statement::Assert(condition, message))
+            }
+            Global => {
+                self.next();
+                let mut names = vec![self.expect(TokenKind::Identifier)?.lexeme];
+                while self.current.kind == Comma {
+                    self.next();
+                    names.push(self.expect(TokenKind::Identifier)?.lexeme);
+                }
+                Ok(Statement::Global(names))
+            }
+            Import => {
+                self.next();
+                let module = self.expect(TokenKind::Identifier)?;
+                // sys is the only module, for print >> sys.stderr.
+                if module.lexeme != "sys" {
+                    return Err(Error::ImportError {
+                        message: format!("No module named {}", module.lexeme),
+                        line: module.line,
+                    });
+                }
+                Ok(Statement::Import(module.lexeme))
+            }
+            Identifier if *self.peek() == Equals => {
+                let name = self.expect(TokenKind::Identifier)?.lexeme;
+                self.next();
+                Ok(Statement::Assign(name, self.parse_expression_list()?))
+            }
+            _ => {
+                let target = self.parse_expression_list()?;
+                if let Some(operator) = Self::augmented(&self.current.kind) {
+                    let token = self.current.clone();
+                    self.next();
+                    let value = self.parse_expression_list()?;
+                    return match Self::single_target(target) {
+                        Some(target) => Ok(Statement::AugAssign(operator(
+                            Box::new(target),
+                            Box::new(value),
+                        ))),
+                        None => Err(Error::UnexpectedToken(token)),
+                    };
+                }
+                if self.current.kind != Equals {
+                    return Ok(Statement::Expression(target));
+                }
+                let equals = self.expect(TokenKind::Equals)?;
+                let value = self.parse_expression_list()?;
+                match Self::target(target) {
+                    Some(Target::Name(name)) => Ok(Statement::Assign(name, value)),
+                    Some(Target::Item(object, index)) => Ok(Statement::SetItem {
+                        object,
+                        index,
+                        value,
+                    }),
+                    Some(Target::Attribute(object, name)) => Ok(Statement::SetAttr {
+                        object,
+                        name,
+                        value,
+                    }),
+                    Some(Target::Tuple(targets)) => Ok(Statement::Unpack(targets, value)),
+                    None => Err(Error::UnexpectedToken(equals)),
+                }
+            }
+        }
+    }
+
+    // what the expression on the left of `=` assigns to, if it can be
+    // assigned to at all.
+    fn target(expr: Expression) -> Option<Target> {
+        match expr {
+            Expression::Simple(Value::Variable(name, _)) => Some(Target::Name(name)),
+            Expression::Simple(Value::Complex(e)) => Self::target(*e),
+            Expression::Subscript(object, index) => Some(Target::Item(*object, *index)),
+            Expression::Attribute(object, name) => Some(Target::Attribute(*object, name)),
+            Expression::Tuple(items) | Expression::List(items) => items
+                .into_iter()
+                .map(Self::target)
+                .collect::<Option<Vec<Target>>>()
+                .map(Target::Tuple),
+            _ => None,
         }
     }
 
+    // the operator an augmented assignment applies.
+    fn augmented(kind: &TokenKind) -> Option<Operator> {
+        match kind {
+            PlusEquals => Some(Expression::Add),
+            MinusEquals => Some(Expression::Sub),
+            MultEquals => Some(Expression::Mult),
+            PowerEquals => Some(Expression::Power),
+            DivEquals => Some(Expression::Div),
+            ModEquals => Some(Expression::Mod),
+            BitAndEquals => Some(Expression::BitAnd),
+            BitOrEquals => Some(Expression::BitOr),
+            BitXorEquals => Some(Expression::BitXor),
+            LShiftEquals => Some(Expression::LShift),
+            RShiftEquals => Some(Expression::RShift),
+            _ => None,
+        }
+    }
+
+    // a name, an item or an attribute, which is all that an augmented
+    // assignment can update and del can delete.
+    fn single_target(expr: Expression) -> Option<Expression> {
+        match expr {
+            Expression::Simple(Value::Complex(e)) => Self::single_target(*e),
+            Expression::Simple(Value::Variable(..))
+            | Expression::Subscript(..)
+            | Expression::Attribute(..) => Some(expr),
+            _ => None,
+        }
+    }
+
+    // a token of the wrong kind isn't consumed, since it could be the end
+    // of the input.
     fn expect(&mut self, kind: TokenKind) -> Result<Token, Error> {
         let res = self.current.clone();
-        self.next();
-        if res.kind == kind {
-            Ok(res)
-        } else {
-            Err(Error::UnexpectedToken(res))
+        if res.kind != kind {
+            return Err(Error::UnexpectedToken(res));
         }
+        self.next();
+        Ok(res)
     }
 
-    fn parse_def_params(&mut self) -> Vec<String> {
-        let mut params = Vec::new();
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[0].kind
+    }
+
+    fn parse_def_params(&mut self) -> Result<Vec<Parameter>, Error> {
+        let mut params: Vec<Parameter> = Vec::new();
         loop {
-            match self.current.kind {
+            let token = self.current.clone();
+            let param = match self.current.kind {
                 TokenKind::Identifier => {
-                    params.push(self.current.lexeme.clone());
+                    let name = self.expect(TokenKind::Identifier)?.lexeme;
+                    if self.current.kind == Equals {
+                        self.next();
+                        Parameter::Default(name, self.parse_expression()?)
+                    } else if params
+                        .iter()
+                        .any(|param| matches!(param, Parameter::Default(..)))
+                    {
+                        return Err(Error::NonDefaultArgumentFollowsDefault);
+                    } else {
+                        Parameter::Positional(name)
+                    }
+                }
+                TokenKind::Power => {
+                    self.next();
+                    Parameter::DoubleStar(self.expect(TokenKind::Identifier)?.lexeme)
+                }
+                TokenKind::Mult => {
                     self.next();
+                    Parameter::Star(self.expect(TokenKind::Identifier)?.lexeme)
                 }
                 _ => break,
+            };
+            if params.iter().any(|p| p.name() == param.name()) {
+                return Err(Error::DuplicateArgument(param.name().to_owned()));
+            }
+            // nothing may follow `**kwargs`, and only `**kwargs` may follow `*args`.
+            let misplaced = match (params.last(), &param) {
+                (Some(Parameter::DoubleStar(_)), _) => true,
+                (Some(Parameter::Star(_)), Parameter::DoubleStar(_)) => false,
+                (Some(Parameter::Star(_)), _) => true,
+                _ => false,
+            };
+            if misplaced {
+                return Err(Error::UnexpectedToken(token));
             }
+            params.push(param);
             match self.current.kind {
                 TokenKind::Comma => {
                     self.next();
@@ -99,32 +374,57 @@ impl Parser {
                 _ => break,
             }
         }
-        params
+        Ok(params)
     }
 
-    fn parse_def(&mut self) -> Result<Statement, Error> {
+    fn parse_function(&mut self) -> Result<Statement, Error> {
         let name_token = self.expect(TokenKind::Identifier)?;
         let name_string = name_token.lexeme;
         self.expect(TokenKind::ParenL)?;
-        let params = self.parse_def_params();
+        let params = self.parse_def_params()?;
         self.expect(TokenKind::ParenR)?;
-        self.expect(TokenKind::Colon)?;
-        self.expect(TokenKind::Newline)?;
-        self.expect(TokenKind::Indent)?;
-        let body = self.parse_body()?;
+        let body = self.parse_suite()?;
         Ok(Statement::Def {
             name: name_string.to_owned(),
-            params: params,
+            params,
             body,
         })
     }
 
+    // `class Name:`, `class Name():` or `class Name(Base):`. the methods in
+    // the body are parsed like any other def.
+    fn parse_class(&mut self) -> Result<Statement, Error> {
+        let name = self.expect(TokenKind::Identifier)?.lexeme;
+        let mut base = None;
+        if self.current.kind == ParenL {
+            self.next();
+            if self.current.kind == Identifier {
+                base = Some(Expression::Simple(self.parse_value()?));
+            }
+            self.expect(TokenKind::ParenR)?;
+        }
+        self.class_body = true;
+        let body = self.parse_suite();
+        self.class_body = false;
+        Ok(Statement::Class {
+            name,
+            base,
+            body: body?,
+        })
+    }
+
+    fn parse_def(&mut self) -> Result<Statement, Error> {
+        // the body of a method is an ordinary function body.
+        let class_body = self.class_body;
+        self.class_body = false;
+        let def = self.parse_function();
+        self.class_body = class_body;
+        def
+    }
+
     fn parse_if(&mut self) -> Result<Statement, Error> {
-        let condition = self.parse_comparison()?;
-        self.expect(TokenKind::Colon)?;
-        self.expect(TokenKind::Newline)?;
-        self.expect(TokenKind::Indent)?;
-        let body = self.parse_body()?;
+        let condition = self.parse_expression()?;
+        let body = self.parse_suite()?;
         let elif = self.parse_elif()?;
         let else_body = self.parse_else()?;
         Ok(Statement::If {
@@ -140,11 +440,8 @@ impl Parser {
         loop {
             if self.current.kind == TokenKind::Elif {
                 self.next();
-                let condition = self.parse_comparison()?;
-                self.expect(TokenKind::Colon)?;
-                self.expect(TokenKind::Newline)?;
-                self.expect(TokenKind::Indent)?;
-                let body = self.parse_body()?;
+                let condition = self.parse_expression()?;
+                let body = self.parse_suite()?;
                 elif.push((condition, body));
             } else {
                 break;
@@ -156,166 +453,638 @@ impl Parser {
     fn parse_else(&mut self) -> Result<Option<Body>, Error> {
         if self.current.kind == TokenKind::Else {
             self.next();
-            self.expect(TokenKind::Colon)?;
-            self.expect(TokenKind::Newline)?;
-            self.expect(TokenKind::Indent)?;
-            let body = self.parse_body()?;
+            let body = self.parse_suite()?;
             Ok(Some(body))
         } else {
             Ok(None)
         }
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, Error> {
-        let c = self.parse_comparison()?;
-        match self.current.kind {
-            And => {
+    // a try statement needs a handler or a finally clause, and can only have
+    // an else clause after its handlers. a bare except has to be the last.
+    fn parse_try(&mut self) -> Result<Statement, Error> {
+        let body = self.parse_suite()?;
+        let mut handlers: Vec<Handler> = Vec::new();
+        while self.current.kind == Except {
+            if handlers
+                .last()
+                .is_some_and(|handler| handler.exception.is_none())
+            {
+                return Err(Error::UnexpectedToken(self.current.clone()));
+            }
+            self.next();
+            let (exception, name) = match self.current.kind {
+                Colon => (None, None),
+                _ => {
+                    let exception = self.parse_expression()?;
+                    let name = match self.current.kind {
+                        Comma => {
+                            self.next();
+                            Some(self.expect(TokenKind::Identifier)?.lexeme)
+                        }
+                        _ => None,
+                    };
+                    (Some(exception), name)
+                }
+            };
+            let body = self.parse_suite()?;
+            handlers.push(Handler {
+                exception,
+                name,
+                body,
+            });
+        }
+        let else_body = if handlers.is_empty() {
+            None
+        } else {
+            self.parse_else()?
+        };
+        let finally = match self.current.kind {
+            Finally => {
                 self.next();
-                let e = self.parse_expression()?;
-                Ok(Expression::And(Box::new(c), Box::new(e)))
+                Some(self.parse_suite()?)
             }
+            _ if handlers.is_empty() => return Err(Error::UnexpectedToken(self.current.clone())),
+            _ => None,
+        };
+        Ok(Statement::Try {
+            body,
+            handlers,
+            else_body,
+            finally,
+        })
+    }
+
+    // `raise E, v` makes the exception as the call E(v), or with the items
+    // of v as the args if it is a tuple display.
+    fn parse_raise(&mut self) -> Result<Statement, Error> {
+        if Self::ends_statement(&self.current.kind) {
+            return Ok(Statement::Raise(None));
+        }
+        let exception = self.parse_expression()?;
+        if self.current.kind != Comma {
+            return Ok(Statement::Raise(Some(exception)));
+        }
+        let comma = self.expect(TokenKind::Comma)?;
+        let (name, line) = match exception {
+            Expression::Simple(Value::Variable(name, line)) => (name, line),
+            _ => return Err(Error::UnexpectedToken(comma)),
+        };
+        let params = match self.parse_expression()? {
+            Expression::Tuple(items) => items.into_iter().map(Argument::Positional).collect(),
+            value => vec![Argument::Positional(value)],
+        };
+        Ok(Statement::Raise(Some(Expression::Call {
+            name,
+            params,
+            line,
+        })))
+    }
+
+    // a conditional expression binds more loosely than anything else.
+    fn parse_expression(&mut self) -> Result<Expression, Error> {
+        let value = self.parse_or()?;
+        if self.current.kind != If {
+            return Ok(value);
+        }
+        self.next();
+        let condition = self.parse_or()?;
+        self.expect(TokenKind::Else)?;
+        let otherwise = self.parse_expression()?;
+        Ok(Expression::Conditional(
+            Box::new(condition),
+            Box::new(value),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, Error> {
+        let c = self.parse_and()?;
+        match self.current.kind {
             Or => {
                 self.next();
-                let e = self.parse_expression()?;
+                let e = self.parse_or()?;
                 Ok(Expression::Or(Box::new(c), Box::new(e)))
             }
             _ => Ok(c),
         }
     }
 
-    fn parse_comparison(&mut self) -> Result<Expression, Error> {
-        let t = self.parse_term()?;
+    fn parse_and(&mut self) -> Result<Expression, Error> {
+        let c = self.parse_comparison()?;
         match self.current.kind {
-            EqEq => {
+            And => {
                 self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::EqEq(Box::new(t), Box::new(e)))
+                let e = self.parse_and()?;
+                Ok(Expression::And(Box::new(c), Box::new(e)))
             }
-            Ne => {
+            _ => Ok(c),
+        }
+    }
+
+    // print [>> stream,] item, ... with a trailing comma leaving the line
+    // open.
+    fn parse_print(&mut self) -> Result<Statement, Error> {
+        let mut stream = Stream::Stdout;
+        let mut items = Vec::new();
+        if self.current.kind == RShift {
+            self.next();
+            stream = self.parse_stream()?;
+            if self.current.kind == Comma {
                 self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::Ne(Box::new(t), Box::new(e)))
+                items.push(self.parse_expression()?);
             }
-            Lt => {
-                self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::Lt(Box::new(t), Box::new(e)))
+        } else if !Self::ends_statement(&self.current.kind) {
+            items.push(self.parse_expression()?);
+        }
+        let mut newline = true;
+        while !items.is_empty() && self.current.kind == Comma {
+            self.next();
+            if Self::ends_statement(&self.current.kind) {
+                newline = false;
+                break;
             }
-            Gt => {
-                self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::Gt(Box::new(t), Box::new(e)))
+            items.push(self.parse_expression()?);
+        }
+        Ok(Statement::Print {
+            stream,
+            items,
+            newline,
+        })
+    }
+
+    // sys.stdout or sys.stderr, or None for stdout.
+    fn parse_stream(&mut self) -> Result<Stream, Error> {
+        let name = self.expect(TokenKind::Identifier)?;
+        match name.lexeme.as_str() {
+            "None" => return Ok(Stream::Stdout),
+            "sys" => (),
+            _ => return Err(Error::UnexpectedToken(name)),
+        }
+        self.expect(TokenKind::Dot)?;
+        let stream = self.expect(TokenKind::Identifier)?;
+        match stream.lexeme.as_str() {
+            "stdout" => Ok(Stream::Stdout),
+            "stderr" => Ok(Stream::Stderr),
+            _ => Err(Error::UnexpectedToken(stream)),
+        }
+    }
+
+    // an expression, or a tuple of them if there are bare commas, as in
+    // `a, b` or `a,`.
+    fn parse_expression_list(&mut self) -> Result<Expression, Error> {
+        let first = self.parse_expression()?;
+        if self.current.kind != Comma {
+            return Ok(first);
+        }
+        let mut items = vec![first];
+        while self.current.kind == Comma {
+            self.next();
+            match self.current.kind {
+                Newline | Eof | Equals | BracketR | Semicolon => break,
+                _ => items.push(self.parse_expression()?),
             }
-            Leq => {
+        }
+        Ok(Expression::Tuple(items))
+    }
+
+    // comparisons chain, so that `a < b < c` is `a < b and b < c`, except
+    // that b is only evaluated once.
+    fn parse_comparison(&mut self) -> Result<Expression, Error> {
+        let mut left = self.parse_bitwise_or()?;
+        let mut links = vec![];
+        while let Some(operator) = self.parse_comparison_operator()? {
+            let right = self.parse_bitwise_or()?;
+            links.push(operator(Box::new(left), Box::new(right.clone())));
+            left = right;
+        }
+        Ok(match links.len() {
+            0 => left,
+            1 => links.pop().unwrap(),
+            _ => Expression::Chain(links),
+        })
+    }
+
+    // the comparison operator next in the tokens, if there is one.
+    fn parse_comparison_operator(&mut self) -> Result<Option<Operator>, Error> {
+        let operator: Operator = match self.current.kind {
+            EqEq => Expression::EqEq,
+            Ne => Expression::Ne,
+            Lt => Expression::Lt,
+            Gt => Expression::Gt,
+            Leq => Expression::Leq,
+            Geq => Expression::Geq,
+            In => Expression::In,
+            Not => {
                 self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::Leq(Box::new(t), Box::new(e)))
+                self.expect(TokenKind::In)?;
+                return Ok(Some(Expression::NotIn));
             }
-            Geq => {
+            Is => {
                 self.next();
-                let e = self.parse_comparison()?;
-                Ok(Expression::Geq(Box::new(t), Box::new(e)))
+                if self.current.kind == Not {
+                    self.next();
+                    return Ok(Some(Expression::IsNot));
+                }
+                return Ok(Some(Expression::Is));
             }
-            _ => Ok(t),
+            _ => return Ok(None),
+        };
+        self.next();
+        Ok(Some(operator))
+    }
+
+    // the binary operators below comparison group to the left, from the
+    // loosest to the tightest: |, ^, &, the shifts, + and -, then * / and %.
+    fn parse_bitwise_or(&mut self) -> Result<Expression, Error> {
+        let mut e = self.parse_bitwise_xor()?;
+        while self.current.kind == BitOr {
+            self.next();
+            let right = self.parse_bitwise_xor()?;
+            e = Expression::BitOr(Box::new(e), Box::new(right));
         }
+        Ok(e)
     }
 
-    fn parse_term(&mut self) -> Result<Expression, Error> {
-        let c = self.parse_product()?;
-        match self.current.kind {
-            Plus => {
-                self.next();
-                let e = self.parse_term()?;
-                Ok(Expression::Add(Box::new(c), Box::new(e)))
+    fn parse_bitwise_xor(&mut self) -> Result<Expression, Error> {
+        let mut e = self.parse_bitwise_and()?;
+        while self.current.kind == BitXor {
+            self.next();
+            let right = self.parse_bitwise_and()?;
+            e = Expression::BitXor(Box::new(e), Box::new(right));
+        }
+        Ok(e)
+    }
+
+    fn parse_bitwise_and(&mut self) -> Result<Expression, Error> {
+        let mut e = self.parse_shift()?;
+        while self.current.kind == BitAnd {
+            self.next();
+            let right = self.parse_shift()?;
+            e = Expression::BitAnd(Box::new(e), Box::new(right));
+        }
+        Ok(e)
+    }
+
+    fn parse_shift(&mut self) -> Result<Expression, Error> {
+        let mut e = self.parse_term()?;
+        loop {
+            e = match self.current.kind {
+                LShift => {
+                    self.next();
+                    Expression::LShift(Box::new(e), Box::new(self.parse_term()?))
+                }
+                RShift => {
+                    self.next();
+                    Expression::RShift(Box::new(e), Box::new(self.parse_term()?))
+                }
+                _ => return Ok(e),
             }
-            Minus => {
-                self.next();
-                let e = self.parse_term()?;
-                Ok(Expression::Sub(Box::new(c), Box::new(e)))
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, Error> {
+        let mut e = self.parse_product()?;
+        loop {
+            e = match self.current.kind {
+                Plus => {
+                    self.next();
+                    Expression::Add(Box::new(e), Box::new(self.parse_product()?))
+                }
+                Minus => {
+                    self.next();
+                    Expression::Sub(Box::new(e), Box::new(self.parse_product()?))
+                }
+                _ => return Ok(e),
             }
-            _ => Ok(c),
         }
     }
 
     fn parse_product(&mut self) -> Result<Expression, Error> {
-        let c = self.parse_primary()?;
+        let mut e = self.parse_factor()?;
+        loop {
+            e = match self.current.kind {
+                Mult => {
+                    self.next();
+                    Expression::Mult(Box::new(e), Box::new(self.parse_factor()?))
+                }
+                Div => {
+                    self.next();
+                    Expression::Div(Box::new(e), Box::new(self.parse_factor()?))
+                }
+                Mod => {
+                    self.next();
+                    Expression::Mod(Box::new(e), Box::new(self.parse_factor()?))
+                }
+                _ => return Ok(e),
+            }
+        }
+    }
+
+    fn parse_factor(&mut self) -> Result<Expression, Error> {
         match self.current.kind {
-            Mult => {
+            Minus => {
                 self.next();
-                let e = self.parse_product()?;
-                Ok(Expression::Mult(Box::new(c), Box::new(e)))
+                let e = self.parse_factor()?;
+                Ok(Expression::Neg(Box::new(e)))
             }
-            Div => {
+            Invert => {
                 self.next();
-                let e = self.parse_product()?;
-                Ok(Expression::Div(Box::new(c), Box::new(e)))
+                let e = self.parse_factor()?;
+                Ok(Expression::Invert(Box::new(e)))
             }
-            Mod => {
+            _ => self.parse_power(),
+        }
+    }
+
+    // `**` binds tighter than a unary operator on its left, but not on its
+    // right, and is right-associative.
+    fn parse_power(&mut self) -> Result<Expression, Error> {
+        let e = self.parse_primary()?;
+        match self.current.kind {
+            Power => {
                 self.next();
-                let e = self.parse_product()?;
-                Ok(Expression::Mod(Box::new(c), Box::new(e)))
+                Ok(Expression::Power(
+                    Box::new(e),
+                    Box::new(self.parse_factor()?),
+                ))
             }
-            _ => Ok(c),
+            _ => Ok(e),
         }
     }
 
     fn parse_primary(&mut self) -> Result<Expression, Error> {
-        let v = self.parse_value()?;
-        match self.current.kind {
-            ParenL => match v {
-                Value::Variable(s) => self.parse_call(s),
-                _ => Err(Error::UnexpectedToken(self.current.clone())),
-            },
-            _ => Ok(Expression::Simple(v)),
+        let line = self.current.line;
+        let mut e = match self.current.kind {
+            BracketL => self.parse_list()?,
+            BraceL => self.parse_dict()?,
+            ParenL => self.parse_parenthesised()?,
+            _ => {
+                let v = self.parse_value()?;
+                match self.current.kind {
+                    ParenL => match v {
+                        Value::Variable(s, _) => self.parse_call(s, line)?,
+                        _ => return Err(Error::UnexpectedToken(self.current.clone())),
+                    },
+                    _ => Expression::Simple(v),
+                }
+            }
+        };
+        loop {
+            match self.current.kind {
+                BracketL => e = self.parse_subscript(e)?,
+                Dot => {
+                    self.next();
+                    let name = self.expect(TokenKind::Identifier)?.lexeme;
+                    if self.current.kind != ParenL {
+                        e = Expression::Attribute(Box::new(e), name);
+                        continue;
+                    }
+                    let params = self.parse_arguments()?;
+                    e = Expression::MethodCall {
+                        object: Box::new(e),
+                        name,
+                        params,
+                    };
+                }
+                _ => return Ok(e),
+            }
         }
     }
 
-    fn parse_call(&mut self, name: String) -> Result<Expression, Error> {
+    fn parse_list(&mut self) -> Result<Expression, Error> {
         self.next();
-        let mut params = Vec::new();
-        while self.current.kind != ParenR {
-            let e = self.parse_comparison()?;
-            params.push(e);
+        let mut items = Vec::new();
+        while self.current.kind != BracketR {
+            items.push(self.parse_expression()?);
             match self.current.kind {
                 Comma => self.next(),
                 _ => break,
             }
         }
-        self.expect(TokenKind::ParenR)?;
-        Ok(Expression::Call { name, params })
+        self.expect(TokenKind::BracketR)?;
+        Ok(Expression::List(items))
     }
 
-    fn parse_value(&mut self) -> Result<Value, Error> {
-        match self.current.clone() {
-            Token {
-                kind: TokenKind::Integer,
-                lexeme: i,
-            } => {
-                self.next();
-                let int_i = i.chars()
-                    .fold(0, |acc, c| acc * 10 + (c as u32 - '0' as u32));
-                Ok(Value::Integer(int_i))
-            }
-            Token {
-                kind: TokenKind::Identifier,
-                lexeme: s,
-            } => {
-                self.next();
-                Ok(Value::Variable(s))
-            }
-            Token {
-                kind: TokenKind::ParenL,
-                ..
-            } => {
-                self.next();
-                let expr = self.parse_expression()?;
-                self.expect(TokenKind::ParenR);
-                let complex_atom = Value::Complex(Box::new(expr));
-                Ok(complex_atom)
+    // `(e)` only groups, but `()`, `(e,)` and `(e, f)` are tuples.
+    fn parse_parenthesised(&mut self) -> Result<Expression, Error> {
+        self.next();
+        let mut items = Vec::new();
+        let mut comma = false;
+        while self.current.kind != ParenR {
+            items.push(self.parse_expression()?);
+            match self.current.kind {
+                Comma => {
+                    self.next();
+                    comma = true;
+                }
+                _ => break,
             }
-            _ => Err(Error::UnexpectedToken(self.current.clone())),
         }
-    }
+        self.expect(TokenKind::ParenR)?;
+        if items.len() == 1 && !comma {
+            let e = items.remove(0);
+            return Ok(Expression::Simple(Value::Complex(Box::new(e))));
+        }
+        Ok(Expression::Tuple(items))
+    }
+
+    fn parse_dict(&mut self) -> Result<Expression, Error> {
+        self.next();
+        let mut entries = Vec::new();
+        while self.current.kind != BraceR {
+            let key = self.parse_expression()?;
+            self.expect(TokenKind::Colon)?;
+            entries.push((key, self.parse_expression()?));
+            match self.current.kind {
+                Comma => self.next(),
+                _ => break,
+            }
+        }
+        self.expect(TokenKind::BraceR)?;
+        Ok(Expression::Dict(entries))
+    }
+
+    // `xs[i]`, or a slice `xs[a:b]` where either bound can be left out.
+    fn parse_subscript(&mut self, object: Expression) -> Result<Expression, Error> {
+        self.next();
+        let lower = match self.current.kind {
+            Colon => None,
+            _ => Some(Box::new(self.parse_expression_list()?)),
+        };
+        if self.current.kind != Colon {
+            self.expect(TokenKind::BracketR)?;
+            return match lower {
+                Some(index) => Ok(Expression::Subscript(Box::new(object), index)),
+                None => Err(Error::UnexpectedToken(self.current.clone())),
+            };
+        }
+        self.next();
+        let upper = match self.current.kind {
+            BracketR => None,
+            _ => Some(Box::new(self.parse_expression()?)),
+        };
+        self.expect(TokenKind::BracketR)?;
+        Ok(Expression::Slice(Box::new(object), lower, upper))
+    }
+
+    fn parse_call(&mut self, name: String, line: usize) -> Result<Expression, Error> {
+        let params = self.parse_arguments()?;
+        Ok(Expression::Call { name, params, line })
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<Argument>, Error> {
+        self.next();
+        let mut params = Vec::new();
+        while self.current.kind != ParenR {
+            let arg = if self.current.kind == Identifier && *self.peek() == Equals {
+                let keyword = self.expect(TokenKind::Identifier)?.lexeme;
+                self.next();
+                let repeated = params.iter().any(|arg| match arg {
+                    Argument::Keyword(k, _) => *k == keyword,
+                    _ => false,
+                });
+                if repeated {
+                    return Err(Error::RepeatedKeywordArgument(keyword));
+                }
+                Argument::Keyword(keyword, self.parse_expression()?)
+            } else {
+                let e = self.parse_expression()?;
+                let keywords = params
+                    .iter()
+                    .any(|arg| matches!(arg, Argument::Keyword(..)));
+                if keywords {
+                    return Err(Error::NonKeywordArgumentFollowsKeyword);
+                }
+                Argument::Positional(e)
+            };
+            params.push(arg);
+            match self.current.kind {
+                Comma => self.next(),
+                _ => break,
+            }
+        }
+        self.expect(TokenKind::ParenR)?;
+        Ok(params)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, Error> {
+        match self.current.clone() {
+            Token {
+                kind: TokenKind::Integer,
+                lexeme: i,
+                ..
+            } => {
+                self.next();
+                Ok(integer(&i))
+            }
+            Token {
+                kind: TokenKind::Float,
+                lexeme: f,
+                ..
+            } => {
+                self.next();
+                Ok(Value::Float(f.parse().unwrap()))
+            }
+            Token {
+                kind: TokenKind::Str,
+                ..
+            } => {
+                // adjacent literals make one string.
+                let mut bytes = Vec::new();
+                while self.current.kind == TokenKind::Str {
+                    bytes.extend(unescape(&self.current)?);
+                    self.next();
+                }
+                Ok(Value::String(bytes))
+            }
+            Token {
+                kind: TokenKind::Identifier,
+                lexeme: s,
+                line,
+            } => {
+                self.next();
+                Ok(Value::Variable(s, line))
+            }
+            _ => Err(Error::UnexpectedToken(self.current.clone())),
+        }
+    }
+}
+
+// an int literal, or a long if it has an `L` or doesn't fit in an int.
+fn integer(lexeme: &str) -> Value {
+    let digits = lexeme.trim_end_matches(['L', 'l']);
+    let mut limbs: Vec<u32> = vec![];
+    for c in digits.bytes() {
+        let mut carry = u64::from(c - b'0');
+        for limb in limbs.iter_mut() {
+            let n = u64::from(*limb) * 10 + carry;
+            *limb = n as u32;
+            carry = n >> 32;
+        }
+        if carry != 0 {
+            limbs.push(carry as u32);
+        }
+    }
+    match limbs[..] {
+        [] if digits.len() == lexeme.len() => Value::Integer(0),
+        [n] if digits.len() == lexeme.len() && n <= i32::MAX as u32 => Value::Integer(n),
+        _ => Value::Long(limbs),
+    }
+}
+
+// the bytes a string literal stands for, without its quotes and with its escapes
+// replaced. an unknown escape keeps its backslash.
+fn unescape(token: &Token) -> Result<Vec<u8>, Error> {
+    let text = &token.lexeme[1..token.lexeme.len() - 1];
+    let mut bytes = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buffer).bytes());
+            continue;
+        }
+        let escape = chars.next().unwrap();
+        match escape {
+            '\n' => {}
+            '\\' | '\'' | '"' => bytes.push(escape as u8),
+            'a' => bytes.push(7),
+            'b' => bytes.push(8),
+            'f' => bytes.push(12),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            't' => bytes.push(b'\t'),
+            'v' => bytes.push(11),
+            '0'..='7' => {
+                let mut code = escape.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(code as u8);
+            }
+            'x' => {
+                let high = chars.next().and_then(|c| c.to_digit(16));
+                let low = chars.next().and_then(|c| c.to_digit(16));
+                match (high, low) {
+                    (Some(high), Some(low)) => bytes.push((high * 16 + low) as u8),
+                    _ => return Err(Error::InvalidEscape(token.line)),
+                }
+            }
+            _ => {
+                bytes.push(b'\\');
+                let mut buffer = [0; 4];
+                bytes.extend(escape.encode_utf8(&mut buffer).bytes());
+            }
+        }
+    }
+    Ok(bytes)
 }
 
 #[cfg(test)]
@@ -324,6 +1093,15 @@ mod test {
     use common::*;
     use testing::*;
 
+    // print item, to stdout with a newline.
+    fn print(item: Expression) -> Statement {
+        Statement::Print {
+            stream: Stream::Stdout,
+            items: vec![item],
+            newline: true,
+        }
+    }
+
     macro_rules! parse_test {
         (name: $name:ident,text: $text:expr,program: $expected:expr,) => {
             #[test]
@@ -357,20 +1135,26 @@ mod test {
     parse_test! {
         name: print_integer,
         text: "print 7",
-        program: [Statement::Print(
+        program: [print(
             Expression::Simple(
                 Value::Integer(7))
         )],
     }
 
+    parse_test! {
+        name: print_float,
+        text: "print 2.5",
+        program: [print(Expression::Simple(Value::Float(2.5)))],
+    }
+
     parse_test! {
         name:    print_variable,
         text:    "print name",
         program: [
-            Statement::Print(
+            print(
                 Expression::Simple(
                     Value::Variable(
-                    "name".to_owned()
+                    "name".to_owned(), 1
                     )
                 )
             )
@@ -381,7 +1165,7 @@ mod test {
         name:    print_add,
         text:    "print 1 + 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::Add(
                     Box::new(Expression::Simple(
                         Value::Integer(1))),
@@ -398,7 +1182,7 @@ mod test {
         name:    print_sub,
         text:    "print 2- 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::Sub(
                     Box::new(Expression::Simple(
                         Value::Integer(2))),
@@ -415,7 +1199,7 @@ mod test {
         name:    print_mult,
         text:    "print 2*2 - 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::Sub(
                     Box::new(Expression::Mult(
                         Box::new(Expression::Simple(
@@ -438,7 +1222,7 @@ mod test {
         name:    print_mod_sub,
         text:    "print 2%2 - 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::Sub(
                     Box::new(Expression::Mod(
                         Box::new(Expression::Simple(
@@ -461,18 +1245,18 @@ mod test {
         name:    print_mod_mult,
         text:    "print 5 % 2*3",
         program: [
-            Statement::Print(
-                Expression::Mod(
-                    Box::new(Expression::Simple(
-                        Value::Integer(5)
-                    )),
-                    Box::new(Expression::Mult(
+            print(
+                Expression::Mult(
+                    Box::new(Expression::Mod(
                         Box::new(Expression::Simple(
-                            Value::Integer(2)
+                            Value::Integer(5)
                         )),
                         Box::new(Expression::Simple(
-                            Value::Integer(3)
+                            Value::Integer(2)
                         ))
+                    )),
+                    Box::new(Expression::Simple(
+                        Value::Integer(3)
                     ))
                 )
             )
@@ -483,7 +1267,7 @@ mod test {
         name:    print_paren_expression,
         text:    "print (2 + 1) * 7",
         program: [
-            Statement::Print(
+            print(
                 Expression::Mult(
                     Box::new(Expression::Simple(
                         Value::Complex(
@@ -510,7 +1294,7 @@ mod test {
         name:    print_eqeq,
         text:    "print 0 == 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::EqEq(
                     Box::new(Expression::Simple(
                         Value::Integer(0))
@@ -527,7 +1311,7 @@ mod test {
         name:    print_complex_eqeq,
         text:    "print 0 + 1 == 1",
         program: [
-            Statement::Print(
+            print(
                 Expression::EqEq(
                     Box::new(Expression::Add(
                         Box::new(Expression::Simple(
@@ -585,7 +1369,7 @@ mod test {
     parse_test! {
         name: print_lt,
         text: "print 1 < 2",
-        program: [Statement::Print(
+        program: [print(
             Expression::Lt(
                 Box::new(Expression::Simple(
                     Value::Integer(1)
@@ -600,7 +1384,7 @@ mod test {
     parse_test! {
         name: print_geq,
         text: "print 1>=2",
-        program: [Statement::Print(
+        program: [print(
             Expression::Geq(
                 Box::new(Expression::Simple(
                     Value::Integer(1)
@@ -615,7 +1399,7 @@ mod test {
     parse_test! {
         name: print_ne,
         text: "print 1 !=2",
-        program: [Statement::Print(
+        program: [print(
             Expression::Ne(
                 Box::new(Expression::Simple(
                     Value::Integer(1)
@@ -630,7 +1414,7 @@ mod test {
     parse_test! {
         name: print_and,
         text: "print 1>=2 and 2<7",
-        program: [Statement::Print(
+        program: [print(
             Expression::And(
                 Box::new(Expression::Geq(
                     Box::new(Expression::Simple(
@@ -655,7 +1439,7 @@ mod test {
     parse_test! {
         name: print_complex_gt,
         text: "print 1 + 3 > 2 - 1",
-        program: [Statement::Print(
+        program: [print(
             Expression::Gt(
                 Box::new(Expression::Add(
                     Box::new(Expression::Simple(
@@ -675,6 +1459,24 @@ mod test {
         )],
     }
 
+    parse_test! {
+        name: assign,
+        text: "x = 1 + 2",
+        program: [Statement::Assign(
+            "x".to_owned(),
+            Expression::Add(
+                Box::new(Expression::Simple(Value::Integer(1))),
+                Box::new(Expression::Simple(Value::Integer(2))),
+            ),
+        )],
+    }
+
+    parse_test! {
+        name: global,
+        text: "global x, y",
+        program: [Statement::Global(vec!["x".to_owned(), "y".to_owned()])],
+    }
+
     parse_test! {
         name: def_simple_func,
         text: "def fib():\n   print 0",
@@ -683,11 +1485,12 @@ mod test {
                 name: "fib".to_owned(),
                 params: vec![],
                 body: Body {
-                    statements: vec![Statement::Print(
+                    statements: vec![print(
                         Expression::Simple(
                             Value::Integer(0)
                         )
-                    )]
+                    )],
+                    lines: vec![2],
                 }
             }],
     }
@@ -701,16 +1504,17 @@ mod test {
                 params: vec![],
                 body: Body {
                     statements: vec![
-                    Statement::Print(
+                    print(
                         Expression::Simple(
                             Value::Integer(0)
                         )
                     ),
-                    Statement::Print(
+                    print(
                         Expression::Simple(
                             Value::Integer(1)
                         )
-                    )]
+                    )],
+                    lines: vec![2, 3],
                 }
             }],
     }
@@ -721,13 +1525,14 @@ mod test {
         program:
             [Statement::Def{
                 name: "fib".to_owned(),
-                params: vec!["a".to_owned()],
+                params: vec![Parameter::Positional("a".to_owned())],
                 body: Body {
-                    statements: vec![Statement::Print(
+                    statements: vec![print(
                         Expression::Simple(
                             Value::Integer(0)
                         )
-                    )]
+                    )],
+                    lines: vec![2],
                 }
             }],
     }
@@ -738,63 +1543,178 @@ mod test {
         program:
             [Statement::Def{
                 name: "fib".to_owned(),
-                params: vec!["a".to_owned(), "bb".to_owned(), "ccc".to_owned()],
+                params: vec![
+                    Parameter::Positional("a".to_owned()),
+                    Parameter::Positional("bb".to_owned()),
+                    Parameter::Positional("ccc".to_owned()),
+                ],
+                body: Body {
+                    statements: vec![print(
+                        Expression::Simple(
+                            Value::Integer(0)
+                        )
+                    )],
+                    lines: vec![2],
+                }
+            }],
+    }
+
+    parse_test! {
+        name: def_default_params,
+        text: "def f(a, b=2, c=1+1):\n   print 0",
+        program:
+            [Statement::Def{
+                name: "f".to_owned(),
+                params: vec![
+                    Parameter::Positional("a".to_owned()),
+                    Parameter::Default(
+                        "b".to_owned(),
+                        Expression::Simple(Value::Integer(2))
+                    ),
+                    Parameter::Default(
+                        "c".to_owned(),
+                        Expression::Add(
+                            Box::new(Expression::Simple(Value::Integer(1))),
+                            Box::new(Expression::Simple(Value::Integer(1)))
+                        )
+                    ),
+                ],
+                body: Body {
+                    statements: vec![print(
+                        Expression::Simple(
+                            Value::Integer(0)
+                        )
+                    )],
+                    lines: vec![2],
+                }
+            }],
+    }
+
+    parse_test! {
+        name: def_variadic_params,
+        text: "def f(a, *args, **kwargs):\n   print 0",
+        program:
+            [Statement::Def{
+                name: "f".to_owned(),
+                params: vec![
+                    Parameter::Positional("a".to_owned()),
+                    Parameter::Star("args".to_owned()),
+                    Parameter::DoubleStar("kwargs".to_owned()),
+                ],
                 body: Body {
-                    statements: vec![Statement::Print(
+                    statements: vec![print(
                         Expression::Simple(
                             Value::Integer(0)
                         )
-                    )]
+                    )],
+                    lines: vec![2],
                 }
             }],
     }
 
+    error_test! {
+        name: def_non_default_after_default,
+        text: "def f(a=1, b):\n   print 0",
+        error: Error::NonDefaultArgumentFollowsDefault,
+    }
+
+    error_test! {
+        name: def_duplicate_param,
+        text: "def f(a, a):\n   print 0",
+        error: Error::DuplicateArgument("a".to_owned()),
+    }
+
+    error_test! {
+        name: def_param_after_kwargs,
+        text: "def f(**kwargs, a):\n   print 0",
+        error: Error::UnexpectedToken(Token {
+            kind: Identifier,
+            lexeme: "a".to_owned(),
+            line: 1,
+        }),
+    }
+
     error_test! {
         name: def_missing_paren,
         text: "def fib(a, bb, ccc:\n   print 0",
         error: Error::UnexpectedToken(Token {
             kind: Colon,
             lexeme: ":".to_owned(),
+            line: 1,
         }),
     }
 
     parse_test! {
         name: function_call,
         text: "print foo(n, 7+ 9)",
-        program: [Statement::Print(
+        program: [print(
             Expression::Call {
                 name: "foo".to_owned(),
                 params: vec![
-                    Expression::Simple(
-                        Value::Variable("n".to_owned())
-                    ),
-                    Expression::Add(
+                    Argument::Positional(Expression::Simple(
+                        Value::Variable("n".to_owned(), 1)
+                    )),
+                    Argument::Positional(Expression::Add(
                         Box::new(Expression::Simple(
                             Value::Integer(7))),
                         Box::new(Expression::Simple(
                             Value::Integer(9)
                         ))
-                    )
+                    ))
                 ],
+                line: 1,
             }
         )],
     }
 
+    parse_test! {
+        name: keyword_call,
+        text: "print foo(1, b=2)",
+        program: [print(
+            Expression::Call {
+                name: "foo".to_owned(),
+                params: vec![
+                    Argument::Positional(Expression::Simple(
+                        Value::Integer(1)
+                    )),
+                    Argument::Keyword(
+                        "b".to_owned(),
+                        Expression::Simple(Value::Integer(2))
+                    ),
+                ],
+                line: 1,
+            }
+        )],
+    }
+
+    error_test! {
+        name: keyword_call_repeated,
+        text: "print foo(b=1, b=2)",
+        error: Error::RepeatedKeywordArgument("b".to_owned()),
+    }
+
+    error_test! {
+        name: keyword_call_positional_after_keyword,
+        text: "print foo(b=1, 2)",
+        error: Error::NonKeywordArgumentFollowsKeyword,
+    }
+
     parse_test! {
         name: parse_if,
         text: "if a:\n  print 7",
         program:
             [Statement::If{
                 condition: Expression::Simple(
-                    Value::Variable("a".to_owned())),
+                    Value::Variable("a".to_owned(), 1)),
                 body: Body {
                     statements: vec![
-                        Statement::Print(
+                        print(
                             Expression::Simple(
                                 Value::Integer(7)
                             )
                         ),
-                    ]
+                    ],
+                    lines: vec![2],
                 },
                 elif: vec![],
                 else_body: None,
@@ -807,6 +1727,7 @@ mod test {
         error: Error::UnexpectedToken(Token {
             kind: Colon,
             lexeme: ":".to_owned(),
+            line: 1,
         }),
     }
 
@@ -816,26 +1737,28 @@ mod test {
         program:
             [Statement::If{
                 condition: Expression::Simple(
-                    Value::Variable("a".to_owned())),
+                    Value::Variable("a".to_owned(), 1)),
                 body: Body {
                     statements: vec![
-                        Statement::Print(
+                        print(
                             Expression::Simple(
                                 Value::Integer(7)
                             )
                         ),
-                    ]
+                    ],
+                    lines: vec![2],
                 },
                 elif: vec![(Expression::Simple(
-                    Value::Variable("b".to_owned())),
+                    Value::Variable("b".to_owned(), 3)),
                     Body {
                         statements: vec![
-                            Statement::Print(
+                            print(
                                 Expression::Simple(
                                     Value::Integer(8)
                                 )
                             ),
-                        ]
+                        ],
+                        lines: vec![4],
                     })],
                 else_body: None,
             }],
@@ -847,26 +1770,28 @@ mod test {
         program:
             [Statement::If{
                 condition: Expression::Simple(
-                    Value::Variable("a".to_owned())),
+                    Value::Variable("a".to_owned(), 1)),
                 body: Body {
                     statements: vec![
-                        Statement::Print(
+                        print(
                             Expression::Simple(
                                 Value::Integer(7)
                             )
                         ),
-                    ]
+                    ],
+                    lines: vec![2],
                 },
                 elif: vec![],
                 else_body: Some(
                     Body {
                         statements: vec![
-                            Statement::Print(
+                            print(
                                 Expression::Simple(
                                     Value::Integer(8)
                                 )
                             ),
-                        ]
+                        ],
+                        lines: vec![4],
                     }),
             }],
     }
@@ -877,36 +1802,39 @@ mod test {
         program:
             [Statement::If{
                 condition: Expression::Simple(
-                    Value::Variable("a".to_owned())),
+                    Value::Variable("a".to_owned(), 1)),
                 body: Body {
                     statements: vec![
-                        Statement::Print(
+                        print(
                             Expression::Simple(
                                 Value::Integer(7)
                             )
                         ),
-                    ]
+                    ],
+                    lines: vec![2],
                 },
                 elif: vec![(Expression::Simple(
-                    Value::Variable("b".to_owned())),
+                    Value::Variable("b".to_owned(), 3)),
                     Body {
                         statements: vec![
-                            Statement::Print(
+                            print(
                                 Expression::Simple(
                                     Value::Integer(8)
                                 )
                             ),
-                        ]
+                        ],
+                        lines: vec![4],
                     })],
                 else_body: Some(
                     Body {
                         statements: vec![
-                            Statement::Print(
+                            print(
                                 Expression::Simple(
                                     Value::Integer(9)
                                 )
                             ),
-                        ]
+                        ],
+                        lines: vec![6],
                     }
                 ),
             }],
@@ -918,13 +1846,13 @@ mod test {
         program:
             [Statement::Def{
                 name: "fib".to_owned(),
-                params: vec!["n".to_owned()],
+                params: vec![Parameter::Positional("n".to_owned())],
                 body: Body {
                     statements: vec![
                         Statement::If {
                             condition: Expression::Lt(
                                 Box::new(Expression::Simple(
-                                    Value::Variable("n".to_owned())
+                                    Value::Variable("n".to_owned(), 2)
                                 )),
                                 Box::new(Expression::Simple(
                                     Value::Integer(2)
@@ -933,9 +1861,10 @@ mod test {
                             body: Body {
                                 statements: vec![Statement::Return(
                                     Expression::Simple(
-                                        Value::Variable("n".to_owned())
+                                        Value::Variable("n".to_owned(), 3)
                                     )
-                                )]
+                                )],
+                                lines: vec![3],
                             },
                             elif: vec![],
                             else_body: Some(
@@ -945,38 +1874,732 @@ mod test {
                                             Box::new(Expression::Call{
                                                 name: "fib".to_owned(),
                                                 params: vec![
-                                                    Expression::Sub(
+                                                    Argument::Positional(Expression::Sub(
                                                         Box::new(Expression::Simple(
-                                                            Value::Variable("n".to_owned())
+                                                            Value::Variable("n".to_owned(), 5)
                                                         )),
                                                         Box::new(Expression::Simple(
                                                             Value::Integer(2)
                                                         ))
-                                                    )
+                                                    ))
                                                 ],
+                                                line: 5,
                                             }),
                                             Box::new(Expression::Call{
                                                 name:"fib".to_owned(),
                                                 params: vec![
-                                                    Expression::Sub(
+                                                    Argument::Positional(Expression::Sub(
                                                         Box::new(Expression::Simple(
-                                                            Value::Variable("n".to_owned())
+                                                            Value::Variable("n".to_owned(), 5)
                                                         )),
                                                         Box::new(Expression::Simple(
                                                             Value::Integer(1)
                                                         ))
-                                                    )
+                                                    ))
                                                 ],
+                                                line: 5,
                                             })
                                         )
                                         )
-                                    ]
+                                    ],
+                                    lines: vec![5],
                                 }
                             )
                         }
-                    ]
+                    ],
+                    lines: vec![2],
                 },
             }],
     }
 
+    parse_test! {
+        name: list_literal,
+        text: "print [1, [2.5], x,]",
+        program: [print(Expression::List(vec![
+            Expression::Simple(Value::Integer(1)),
+            Expression::List(vec![Expression::Simple(Value::Float(2.5))]),
+            Expression::Simple(Value::Variable("x".to_owned(), 1)),
+        ]))],
+    }
+
+    parse_test! {
+        name: subscripts,
+        text: "print xs[0][i:]\nprint xs[:2]",
+        program: [
+            print(Expression::Slice(
+                Box::new(Expression::Subscript(
+                    Box::new(Expression::Simple(Value::Variable("xs".to_owned(), 1))),
+                    Box::new(Expression::Simple(Value::Integer(0))),
+                )),
+                Some(Box::new(Expression::Simple(Value::Variable("i".to_owned(), 1)))),
+                None,
+            )),
+            print(Expression::Slice(
+                Box::new(Expression::Simple(Value::Variable("xs".to_owned(), 2))),
+                None,
+                Some(Box::new(Expression::Simple(Value::Integer(2)))),
+            )),
+        ],
+    }
+
+    parse_test! {
+        name: set_item,
+        text: "xs[0] = 1",
+        program: [Statement::SetItem {
+            object: Expression::Simple(Value::Variable("xs".to_owned(), 1)),
+            index: Expression::Simple(Value::Integer(0)),
+            value: Expression::Simple(Value::Integer(1)),
+        }],
+    }
+
+    parse_test! {
+        name: method_call_statement,
+        text: "xs.append(1)",
+        program: [Statement::Expression(Expression::MethodCall {
+            object: Box::new(Expression::Simple(Value::Variable("xs".to_owned(), 1))),
+            name: "append".to_owned(),
+            params: vec![Argument::Positional(Expression::Simple(Value::Integer(1)))],
+        })],
+    }
+
+    error_test! {
+        name: assign_to_call,
+        text: "f() = 1",
+        error: Error::UnexpectedToken(Token {
+            kind: Equals,
+            lexeme: "=".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: negation,
+        text: "print -x * 2",
+        program: [print(Expression::Mult(
+            Box::new(Expression::Neg(Box::new(Expression::Simple(Value::Variable(
+                "x".to_owned(),
+                1
+            ))))),
+            Box::new(Expression::Simple(Value::Integer(2))),
+        ))],
+    }
+
+    parse_test! {
+        name: string_escapes,
+        text: "print 'a\\n\\x41\\101\\q' \"b\"",
+        program: [print(Expression::Simple(Value::String(
+            b"a\nAA\\qb".to_vec()
+        )))],
+    }
+
+    error_test! {
+        name: invalid_hex_escape,
+        text: "print '\\x4'",
+        error: Error::InvalidEscape(1),
+    }
+
+    parse_test! {
+        name: dict_display,
+        text: "d = {1: x, 2: 3,}",
+        program: [Statement::Assign(
+            "d".to_owned(),
+            Expression::Dict(vec![
+                (
+                    Expression::Simple(Value::Integer(1)),
+                    Expression::Simple(Value::Variable("x".to_owned(), 1)),
+                ),
+                (
+                    Expression::Simple(Value::Integer(2)),
+                    Expression::Simple(Value::Integer(3)),
+                ),
+            ]),
+        )],
+    }
+
+    parse_test! {
+        name: membership_and_del,
+        text: "if k in d:\n    del d[k]",
+        program: [Statement::If {
+            condition: Expression::In(
+                Box::new(Expression::Simple(Value::Variable("k".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("d".to_owned(), 1))),
+            ),
+            body: Body {
+                statements: vec![Statement::Del(Expression::Subscript(
+                    Box::new(Expression::Simple(Value::Variable("d".to_owned(), 2))),
+                    Box::new(Expression::Simple(Value::Variable("k".to_owned(), 2))),
+                ))],
+                lines: vec![2],
+            },
+            elif: vec![],
+            else_body: None,
+        }],
+    }
+
+    parse_test! {
+        name: tuple_displays,
+        text: "t = (), (1,), (x)\nreturn 1, 2,",
+        program: [
+            Statement::Assign(
+                "t".to_owned(),
+                Expression::Tuple(vec![
+                    Expression::Tuple(vec![]),
+                    Expression::Tuple(vec![Expression::Simple(Value::Integer(1))]),
+                    Expression::Simple(Value::Complex(Box::new(Expression::Simple(
+                        Value::Variable("x".to_owned(), 1)
+                    )))),
+                ]),
+            ),
+            Statement::Return(Expression::Tuple(vec![
+                Expression::Simple(Value::Integer(1)),
+                Expression::Simple(Value::Integer(2)),
+            ])),
+        ],
+    }
+
+    parse_test! {
+        name: unpacking,
+        text: "a, (b, [c]), xs[0] = t",
+        program: [Statement::Unpack(
+            vec![
+                Target::Name("a".to_owned()),
+                Target::Tuple(vec![
+                    Target::Name("b".to_owned()),
+                    Target::Tuple(vec![Target::Name("c".to_owned())]),
+                ]),
+                Target::Item(
+                    Expression::Simple(Value::Variable("xs".to_owned(), 1)),
+                    Expression::Simple(Value::Integer(0)),
+                ),
+            ],
+            Expression::Simple(Value::Variable("t".to_owned(), 1)),
+        )],
+    }
+
+    error_test! {
+        name: unpack_into_call,
+        text: "a, f() = t",
+        error: Error::UnexpectedToken(Token {
+            kind: Equals,
+            lexeme: "=".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: negated_operators,
+        text: "print a not in b is not c",
+        program: [print(Expression::Chain(vec![
+            Expression::NotIn(
+                Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+            ),
+            Expression::IsNot(
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+            ),
+        ]))],
+    }
+
+    error_test! {
+        name: not_without_in,
+        text: "print a not b",
+        error: Error::UnexpectedToken(Token {
+            kind: Identifier,
+            lexeme: "b".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: left_associative_arithmetic,
+        text: "print a - b - c",
+        program: [print(Expression::Sub(
+            Box::new(Expression::Sub(
+                Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+            )),
+            Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+        ))],
+    }
+
+    parse_test! {
+        name: bitwise_precedence,
+        text: "print a | b ^ c & d << ~e",
+        program: [print(Expression::BitOr(
+            Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+            Box::new(Expression::BitXor(
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+                Box::new(Expression::BitAnd(
+                    Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+                    Box::new(Expression::LShift(
+                        Box::new(Expression::Simple(Value::Variable("d".to_owned(), 1))),
+                        Box::new(Expression::Invert(Box::new(Expression::Simple(
+                            Value::Variable("e".to_owned(), 1),
+                        )))),
+                    )),
+                )),
+            )),
+        ))],
+    }
+
+    parse_test! {
+        name: power_associativity,
+        text: "print -a ** -b ** c",
+        program: [print(Expression::Neg(Box::new(Expression::Power(
+            Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+            Box::new(Expression::Neg(Box::new(Expression::Power(
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+            )))),
+        ))))],
+    }
+
+    parse_test! {
+        name: long_literals,
+        text: "print [2147483647, 2147483648, 7L, 0L, 18446744073709551616]",
+        program: [print(Expression::List(vec![
+            Expression::Simple(Value::Integer(2147483647)),
+            Expression::Simple(Value::Long(vec![0x8000_0000])),
+            Expression::Simple(Value::Long(vec![7])),
+            Expression::Simple(Value::Long(vec![])),
+            Expression::Simple(Value::Long(vec![0, 0, 1])),
+        ]))],
+    }
+
+    parse_test! {
+        name: augmented_assignment,
+        text: "x -= 1\nxs[i] <<= 2",
+        program: [
+            Statement::AugAssign(Expression::Sub(
+                Box::new(Expression::Simple(Value::Variable("x".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Integer(1))),
+            )),
+            Statement::AugAssign(Expression::LShift(
+                Box::new(Expression::Subscript(
+                    Box::new(Expression::Simple(Value::Variable("xs".to_owned(), 2))),
+                    Box::new(Expression::Simple(Value::Variable("i".to_owned(), 2))),
+                )),
+                Box::new(Expression::Simple(Value::Integer(2))),
+            )),
+        ],
+    }
+
+    error_test! {
+        name: augmented_assignment_to_tuple,
+        text: "a, b += 1",
+        error: Error::UnexpectedToken(Token {
+            kind: PlusEquals,
+            lexeme: "+=".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: conditional_expression,
+        text: "print a if b or c else d if e else f",
+        program: [print(Expression::Conditional(
+            Box::new(Expression::Or(
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+            )),
+            Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+            Box::new(Expression::Conditional(
+                Box::new(Expression::Simple(Value::Variable("e".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("d".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("f".to_owned(), 1))),
+            )),
+        ))],
+    }
+
+    parse_test! {
+        name: and_binds_tighter_than_or,
+        text: "print a and b or c",
+        program: [print(Expression::Or(
+            Box::new(Expression::And(
+                Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+                Box::new(Expression::Simple(Value::Variable("b".to_owned(), 1))),
+            )),
+            Box::new(Expression::Simple(Value::Variable("c".to_owned(), 1))),
+        ))],
+    }
+
+    error_test! {
+        name: conditional_without_else,
+        text: "print a if b",
+        error: Error::UnexpectedToken(Token {
+            kind: Eof,
+            lexeme: "".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: pass_del_and_assert,
+        text: "def f():\n  pass\ndel (x)\nassert x, 'm'\nassert y",
+        program: [
+            Statement::Def {
+                name: "f".to_owned(),
+                params: vec![],
+                body: Body {
+                    statements: vec![Statement::Pass],
+                    lines: vec![2],
+                },
+            },
+            Statement::Del(Expression::Simple(Value::Variable("x".to_owned(), 3))),
+            Statement::Assert(
+                Expression::Simple(Value::Variable("x".to_owned(), 4)),
+                Some(Expression::Simple(Value::String(b"m".to_vec()))),
+            ),
+            Statement::Assert(Expression::Simple(Value::Variable("y".to_owned(), 5)), None),
+        ],
+    }
+
+    error_test! {
+        name: del_call,
+        text: "del f()",
+        error: Error::UnexpectedToken(Token {
+            kind: Del,
+            lexeme: "del".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: semicolons,
+        text: "print 1; print 2;\nprint 3",
+        program: [
+            print(Expression::Simple(Value::Integer(1))),
+            print(Expression::Simple(Value::Integer(2))),
+            print(Expression::Simple(Value::Integer(3))),
+        ],
+    }
+
+    parse_test! {
+        name: one_line_suites,
+        text: "def f(): return 1\nif x: pass; pass\nelse: print 2",
+        program: [
+            Statement::Def {
+                name: "f".to_owned(),
+                params: vec![],
+                body: Body {
+                    statements: vec![Statement::Return(Expression::Simple(Value::Integer(1)))],
+                    lines: vec![1],
+                },
+            },
+            Statement::If {
+                condition: Expression::Simple(Value::Variable("x".to_owned(), 2)),
+                body: Body {
+                    statements: vec![Statement::Pass, Statement::Pass],
+                    lines: vec![2, 2],
+                },
+                elif: vec![],
+                else_body: Some(Body {
+                    statements: vec![print(Expression::Simple(Value::Integer(2)))],
+                    lines: vec![3],
+                }),
+            },
+        ],
+    }
+
+    error_test! {
+        name: compound_statement_after_semicolon,
+        text: "print 1; if x: print 2",
+        error: Error::UnexpectedToken(Token {
+            kind: If,
+            lexeme: "if".to_owned(),
+            line: 1,
+        }),
+    }
+
+    error_test! {
+        name: compound_statement_in_one_line_suite,
+        text: "if x: if y: print 2",
+        error: Error::UnexpectedToken(Token {
+            kind: If,
+            lexeme: "if".to_owned(),
+            line: 1,
+        }),
+    }
+
+    error_test! {
+        name: statements_without_separator,
+        text: "print 1 print 2",
+        error: Error::UnexpectedToken(Token {
+            kind: Print,
+            lexeme: "print".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: print_forms,
+        text: "print\nprint 1, 2\nprint 1,\nprint >> None, 3",
+        program: [
+            Statement::Print {
+                stream: Stream::Stdout,
+                items: vec![],
+                newline: true,
+            },
+            Statement::Print {
+                stream: Stream::Stdout,
+                items: vec![
+                    Expression::Simple(Value::Integer(1)),
+                    Expression::Simple(Value::Integer(2)),
+                ],
+                newline: true,
+            },
+            Statement::Print {
+                stream: Stream::Stdout,
+                items: vec![Expression::Simple(Value::Integer(1))],
+                newline: false,
+            },
+            print(Expression::Simple(Value::Integer(3))),
+        ],
+    }
+
+    parse_test! {
+        name: print_to_stderr,
+        text: "import sys\nprint >> sys.stderr\nprint >> sys.stderr, 1, 2,; pass",
+        program: [
+            Statement::Import("sys".to_owned()),
+            Statement::Print {
+                stream: Stream::Stderr,
+                items: vec![],
+                newline: true,
+            },
+            Statement::Print {
+                stream: Stream::Stderr,
+                items: vec![
+                    Expression::Simple(Value::Integer(1)),
+                    Expression::Simple(Value::Integer(2)),
+                ],
+                newline: false,
+            },
+            Statement::Pass,
+        ],
+    }
+
+    error_test! {
+        name: print_only_comma,
+        text: "print ,",
+        error: Error::UnexpectedToken(Token {
+            kind: Comma,
+            lexeme: ",".to_owned(),
+            line: 1,
+        }),
+    }
+
+    error_test! {
+        name: print_stream_without_items,
+        text: "print >> sys.stderr,",
+        error: Error::UnexpectedToken(Token {
+            kind: Eof,
+            lexeme: "".to_owned(),
+            line: 1,
+        }),
+    }
+
+    error_test! {
+        name: print_to_unknown_stream,
+        text: "print >> sys.stdin, 1",
+        error: Error::UnexpectedToken(Token {
+            kind: Identifier,
+            lexeme: "stdin".to_owned(),
+            line: 1,
+        }),
+    }
+
+    error_test! {
+        name: import_unknown_module,
+        text: "print 1\nimport os",
+        error: Error::ImportError {
+            message: "No module named os".to_owned(),
+            line: 2,
+        },
+    }
+
+    parse_test! {
+        name: try_statement,
+        text: "try:\n  pass\nexcept (KeyError, IndexError), e:\n  pass\nexcept: pass\n\
+               else: pass\nfinally: pass",
+        program: [Statement::Try {
+            body: Body {
+                statements: vec![Statement::Pass],
+                lines: vec![2],
+            },
+            handlers: vec![
+                Handler {
+                    exception: Some(Expression::Tuple(vec![
+                        Expression::Simple(Value::Variable("KeyError".to_owned(), 3)),
+                        Expression::Simple(Value::Variable("IndexError".to_owned(), 3)),
+                    ])),
+                    name: Some("e".to_owned()),
+                    body: Body {
+                        statements: vec![Statement::Pass],
+                        lines: vec![4],
+                    },
+                },
+                Handler {
+                    exception: None,
+                    name: None,
+                    body: Body {
+                        statements: vec![Statement::Pass],
+                        lines: vec![5],
+                    },
+                },
+            ],
+            else_body: Some(Body {
+                statements: vec![Statement::Pass],
+                lines: vec![6],
+            }),
+            finally: Some(Body {
+                statements: vec![Statement::Pass],
+                lines: vec![7],
+            }),
+        }],
+    }
+
+    error_test! {
+        name: try_without_handlers,
+        text: "try: pass\nprint 1",
+        error: Error::UnexpectedToken(Token {
+            kind: Print,
+            lexeme: "print".to_owned(),
+            line: 2,
+        }),
+    }
+
+    error_test! {
+        name: else_without_handlers,
+        text: "try: pass\nelse: pass",
+        error: Error::UnexpectedToken(Token {
+            kind: Else,
+            lexeme: "else".to_owned(),
+            line: 2,
+        }),
+    }
+
+    error_test! {
+        name: bare_except_not_last,
+        text: "try: pass\nexcept: pass\nexcept KeyError: pass",
+        error: Error::UnexpectedToken(Token {
+            kind: Except,
+            lexeme: "except".to_owned(),
+            line: 3,
+        }),
+    }
+
+    parse_test! {
+        name: raise_forms,
+        text: "raise\nraise e\nraise ValueError, 'x'\nraise KeyError, (1, 2)",
+        program: [
+            Statement::Raise(None),
+            Statement::Raise(Some(Expression::Simple(Value::Variable("e".to_owned(), 2)))),
+            Statement::Raise(Some(Expression::Call {
+                name: "ValueError".to_owned(),
+                params: vec![Argument::Positional(Expression::Simple(Value::String(
+                    b"x".to_vec(),
+                )))],
+                line: 3,
+            })),
+            Statement::Raise(Some(Expression::Call {
+                name: "KeyError".to_owned(),
+                params: vec![
+                    Argument::Positional(Expression::Simple(Value::Integer(1))),
+                    Argument::Positional(Expression::Simple(Value::Integer(2))),
+                ],
+                line: 4,
+            })),
+        ],
+    }
+
+    error_test! {
+        name: raise_with_traceback,
+        text: "raise ValueError, 'x', t",
+        error: Error::UnexpectedToken(Token {
+            kind: Comma,
+            lexeme: ",".to_owned(),
+            line: 1,
+        }),
+    }
+
+    parse_test! {
+        name: class_definition,
+        text: "class B(A):\n  'doc'\n  n = 1\n  def f(self):\n    self.n += 1",
+        program: [Statement::Class {
+            name: "B".to_owned(),
+            base: Some(Expression::Simple(Value::Variable("A".to_owned(), 1))),
+            body: Body {
+                statements: vec![
+                    Statement::Expression(Expression::Simple(Value::String(b"doc".to_vec()))),
+                    Statement::Assign("n".to_owned(), Expression::Simple(Value::Integer(1))),
+                    Statement::Def {
+                        name: "f".to_owned(),
+                        params: vec![Parameter::Positional("self".to_owned())],
+                        body: Body {
+                            statements: vec![Statement::AugAssign(Expression::Add(
+                                Box::new(Expression::Attribute(
+                                    Box::new(Expression::Simple(Value::Variable(
+                                        "self".to_owned(),
+                                        5,
+                                    ))),
+                                    "n".to_owned(),
+                                )),
+                                Box::new(Expression::Simple(Value::Integer(1))),
+                            ))],
+                            lines: vec![5],
+                        },
+                    },
+                ],
+                lines: vec![2, 3, 4],
+            },
+        }],
+    }
+
+    parse_test! {
+        name: attributes,
+        text: "a.b.c = a.d\nclass C(): pass",
+        program: [
+            Statement::SetAttr {
+                object: Expression::Attribute(
+                    Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+                    "b".to_owned(),
+                ),
+                name: "c".to_owned(),
+                value: Expression::Attribute(
+                    Box::new(Expression::Simple(Value::Variable("a".to_owned(), 1))),
+                    "d".to_owned(),
+                ),
+            },
+            Statement::Class {
+                name: "C".to_owned(),
+                base: None,
+                body: Body {
+                    statements: vec![Statement::Pass],
+                    lines: vec![2],
+                },
+            },
+        ],
+    }
+
+    error_test! {
+        name: nested_class,
+        text: "def f():\n  class A: pass",
+        error: Error::UnexpectedToken(Token {
+            kind: Class,
+            lexeme: "class".to_owned(),
+            line: 2,
+        }),
+    }
+
+    error_test! {
+        name: statement_in_class_body,
+        text: "class A:\n  print 1",
+        error: Error::UnexpectedToken(Token {
+            kind: Print,
+            lexeme: "print".to_owned(),
+            line: 2,
+        }),
+    }
 }
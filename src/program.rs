@@ -8,13 +8,44 @@ pub struct Program {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Body {
     pub statements: Vec<Statement>,
+    pub lines: Vec<usize>, // the line each statement starts on
 }
 
 // enum variants and their fields public by default
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
-    Print(Expression),
+    Print {
+        stream: Stream,
+        items: Vec<Expression>,
+        newline: bool, // false after a trailing comma
+    },
     Return(Expression),
+    Assign(String, Expression),
+    SetItem {
+        object: Expression,
+        index: Expression,
+        value: Expression,
+    }, // xs[i] = v
+    SetAttr {
+        object: Expression,
+        name: String,
+        value: Expression,
+    }, // x.a = v
+    Unpack(Vec<Target>, Expression), // a, b = b, a
+    AugAssign(Expression),           // x += 1 holds x + 1, whose left operand is the target
+    Expression(Expression),
+    Del(Expression), // del x or del xs[i]
+    Pass,
+    Assert(Expression, Option<Expression>), // assert condition, message
+    Global(Vec<String>),
+    Import(String),
+    Raise(Option<Expression>), // a bare raise raises the exception being handled again
+    Try {
+        body: Body,
+        handlers: Vec<Handler>,
+        else_body: Option<Body>,
+        finally: Option<Body>,
+    },
     If {
         condition: Expression,
         body: Body,
@@ -23,11 +54,50 @@ pub enum Statement {
     },
     Def {
         name: String,
-        params: Vec<String>,
+        params: Vec<Parameter>,
+        body: Body,
+    },
+    // the body only has defs, assignments to the class's attributes, pass
+    // and expressions.
+    Class {
+        name: String,
+        base: Option<Expression>, // a name
         body: Body,
     },
 }
 
+// where print >> sys.stdout or sys.stderr writes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stream {
+    Stdout = 1,
+    Stderr = 2,
+}
+
+// an except clause. a bare except, with no exception, catches anything.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Handler {
+    pub exception: Option<Expression>, // a class, or a tuple of them
+    pub name: Option<String>,          // except ValueError, e
+    pub body: Body,
+}
+
+// where one of the values of an unpacking assignment goes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Target {
+    Name(String),
+    Item(Expression, Expression),  // xs[i]
+    Attribute(Expression, String), // x.a
+    Tuple(Vec<Target>),            // (a, b) or [a, b]
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Parameter {
+    Positional(String),
+    Default(String, Expression), // b=2
+    Star(String),                // *args
+    DoubleStar(String),          // **kwargs
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     EqEq(Box<Expression>, Box<Expression>),
@@ -41,18 +111,346 @@ pub enum Expression {
     Mult(Box<Expression>, Box<Expression>),
     Div(Box<Expression>, Box<Expression>),
     Mod(Box<Expression>, Box<Expression>),
+    Power(Box<Expression>, Box<Expression>),
+    BitAnd(Box<Expression>, Box<Expression>),
+    BitOr(Box<Expression>, Box<Expression>),
+    BitXor(Box<Expression>, Box<Expression>),
+    LShift(Box<Expression>, Box<Expression>),
+    RShift(Box<Expression>, Box<Expression>),
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Box<Expression>),
+    NotIn(Box<Expression>, Box<Expression>),
+    Is(Box<Expression>, Box<Expression>),
+    IsNot(Box<Expression>, Box<Expression>),
+    // a < b in c holds the comparisons a < b and b in c, which share b.
+    Chain(Vec<Expression>),
+    Neg(Box<Expression>),
+    Invert(Box<Expression>),                                        // ~x
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>), // a if c else b holds c, a and b
     Call {
         name: String,
-        params: Vec<Expression>,
+        params: Vec<Argument>,
+        line: usize,
+    },
+    MethodCall {
+        object: Box<Expression>,
+        name: String,
+        params: Vec<Argument>,
     },
+    List(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    Dict(Vec<(Expression, Expression)>),         // {k: v}
+    Subscript(Box<Expression>, Box<Expression>), // xs[i]
+    Attribute(Box<Expression>, String),          // x.a
+    Slice(
+        Box<Expression>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+    ), // xs[a:b]
     Simple(Value),
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Argument {
+    Positional(Expression),
+    Keyword(String, Expression), // f(b=3)
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     Integer(u32),
-    Variable(String),
+    Long(Vec<u32>), // limbs, least significant first
+    Float(f64),
+    String(Vec<u8>),
+    Variable(String, usize),  // name, line
     Complex(Box<Expression>), // () precedence
 }
+
+// a function of the module: a def at its top level, or a method defined in
+// the body of one of its classes, which is named after both of them, as
+// `Class.method`.
+pub struct Function<'a> {
+    pub name: String,
+    pub params: &'a [Parameter],
+    pub body: &'a Body,
+    pub method: bool,
+}
+
+impl Parameter {
+    pub fn name(&self) -> &str {
+        match self {
+            Parameter::Positional(name)
+            | Parameter::Default(name, _)
+            | Parameter::Star(name)
+            | Parameter::DoubleStar(name) => name,
+        }
+    }
+}
+
+impl Argument {
+    pub fn expression(&self) -> &Expression {
+        match self {
+            Argument::Positional(e) | Argument::Keyword(_, e) => e,
+        }
+    }
+}
+
+impl Body {
+    // the functions defined at the top level of this body, and in the
+    // bodies of the classes defined there, in the order they appear.
+    pub fn functions(&self) -> Vec<Function<'_>> {
+        let mut functions = vec![];
+        for stmt in self.statements.iter() {
+            match stmt {
+                Statement::Def { name, params, body } => functions.push(Function {
+                    name: name.clone(),
+                    params,
+                    body,
+                    method: false,
+                }),
+                Statement::Class {
+                    name: class, body, ..
+                } => {
+                    for stmt in body.statements.iter() {
+                        if let Statement::Def { name, params, body } = stmt {
+                            functions.push(Function {
+                                name: format!("{}.{}", class, name),
+                                params,
+                                body,
+                                method: true,
+                            });
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        functions
+    }
+
+    // takes out the assert statements, as -O does, leaving the line of each
+    // statement that is kept.
+    pub fn remove_asserts(&mut self) {
+        let mut lines = self.lines.iter();
+        let mut kept = vec![];
+        for mut stmt in self.statements.drain(..) {
+            let line = *lines.next().unwrap();
+            if let Statement::Assert(..) = stmt {
+                continue;
+            }
+            match stmt {
+                Statement::If {
+                    ref mut body,
+                    ref mut elif,
+                    ref mut else_body,
+                    ..
+                } => {
+                    body.remove_asserts();
+                    for (_, body) in elif.iter_mut() {
+                        body.remove_asserts();
+                    }
+                    if let Some(body) = else_body {
+                        body.remove_asserts();
+                    }
+                }
+                Statement::Try {
+                    ref mut body,
+                    ref mut handlers,
+                    ref mut else_body,
+                    ref mut finally,
+                } => {
+                    body.remove_asserts();
+                    for handler in handlers.iter_mut() {
+                        handler.body.remove_asserts();
+                    }
+                    if let Some(body) = else_body {
+                        body.remove_asserts();
+                    }
+                    if let Some(body) = finally {
+                        body.remove_asserts();
+                    }
+                }
+                Statement::Def { ref mut body, .. } | Statement::Class { ref mut body, .. } => {
+                    body.remove_asserts()
+                }
+                _ => (),
+            }
+            kept.push((stmt, line));
+        }
+        let (statements, lines) = kept.into_iter().unzip();
+        self.statements = statements;
+        self.lines = lines;
+    }
+}
+
+impl Statement {
+    // the expressions this statement evaluates itself, in order.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Statement::Print { items, .. } => items.iter().collect(),
+            Statement::Return(e)
+            | Statement::Assign(_, e)
+            | Statement::Expression(e)
+            | Statement::AugAssign(e)
+            | Statement::Del(e) => vec![e],
+            Statement::SetItem {
+                object,
+                index,
+                value,
+            } => vec![value, object, index],
+            Statement::SetAttr { object, value, .. } => vec![value, object],
+            Statement::Unpack(targets, value) => {
+                let mut expressions = vec![value];
+                for target in targets.iter() {
+                    expressions.extend(target.expressions());
+                }
+                expressions
+            }
+            Statement::If {
+                condition, elif, ..
+            } => {
+                let mut expressions = vec![condition];
+                expressions.extend(elif.iter().map(|(condition, _)| condition));
+                expressions
+            }
+            Statement::Def { params, .. } => params
+                .iter()
+                .filter_map(|param| match param {
+                    Parameter::Default(_, e) => Some(e),
+                    _ => None,
+                })
+                .collect(),
+            // the message is only evaluated if the assertion fails.
+            Statement::Assert(condition, message) => {
+                let mut expressions = vec![condition];
+                expressions.extend(message.iter());
+                expressions
+            }
+            Statement::Raise(e) => e.iter().collect(),
+            // the statements of a class's body run with it.
+            Statement::Class { base, .. } => base.iter().collect(),
+            // the classes of each handler are only evaluated if the ones
+            // before it don't match.
+            Statement::Try { handlers, .. } => handlers
+                .iter()
+                .filter_map(|handler| handler.exception.as_ref())
+                .collect(),
+            Statement::Global(_) | Statement::Import(_) | Statement::Pass => vec![],
+        }
+    }
+
+    // the name an augmented assignment updates, unless it updates an item.
+    pub fn augmented_name(&self) -> Option<&str> {
+        match self {
+            Statement::AugAssign(e) => match e.operands()[0] {
+                Expression::Simple(Value::Variable(name, _)) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // the bodies that run as part of this statement; a def's body runs
+    // later, but a class's runs as the class is made.
+    pub fn bodies(&self) -> Vec<&Body> {
+        match self {
+            Statement::If {
+                body,
+                elif,
+                else_body,
+                ..
+            } => {
+                let mut bodies = vec![body];
+                bodies.extend(elif.iter().map(|(_, body)| body));
+                bodies.extend(else_body.iter());
+                bodies
+            }
+            Statement::Try {
+                body,
+                handlers,
+                else_body,
+                finally,
+            } => {
+                let mut bodies = vec![body];
+                bodies.extend(handlers.iter().map(|handler| &handler.body));
+                bodies.extend(else_body.iter());
+                bodies.extend(finally.iter());
+                bodies
+            }
+            Statement::Class { body, .. } => vec![body],
+            _ => vec![],
+        }
+    }
+}
+
+impl Target {
+    // the expressions evaluated to store into this target, in order.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Target::Name(_) => vec![],
+            Target::Item(object, index) => vec![object, index],
+            Target::Attribute(object, _) => vec![object],
+            Target::Tuple(targets) => targets.iter().flat_map(Target::expressions).collect(),
+        }
+    }
+}
+
+impl Expression {
+    // the expressions this one is made of, in the order they are evaluated.
+    pub fn operands(&self) -> Vec<&Expression> {
+        match self {
+            Expression::EqEq(l, r)
+            | Expression::Ne(l, r)
+            | Expression::Lt(l, r)
+            | Expression::Gt(l, r)
+            | Expression::Leq(l, r)
+            | Expression::Geq(l, r)
+            | Expression::Add(l, r)
+            | Expression::Sub(l, r)
+            | Expression::Mult(l, r)
+            | Expression::Div(l, r)
+            | Expression::Mod(l, r)
+            | Expression::Power(l, r)
+            | Expression::BitAnd(l, r)
+            | Expression::BitOr(l, r)
+            | Expression::BitXor(l, r)
+            | Expression::LShift(l, r)
+            | Expression::RShift(l, r)
+            | Expression::And(l, r)
+            | Expression::Or(l, r)
+            | Expression::In(l, r)
+            | Expression::NotIn(l, r)
+            | Expression::Is(l, r)
+            | Expression::IsNot(l, r)
+            | Expression::Subscript(l, r) => vec![l, r],
+            // only one of the values is evaluated, after the condition.
+            Expression::Conditional(c, a, b) => vec![c, a, b],
+            // each operand once, though two comparisons can share it.
+            Expression::Chain(links) => {
+                let mut operands = links[0].operands();
+                operands.extend(links[1..].iter().map(|link| link.operands()[1]));
+                operands
+            }
+            Expression::Call { params, .. } => params.iter().map(Argument::expression).collect(),
+            Expression::MethodCall { object, params, .. } => {
+                let mut operands = vec![&**object];
+                operands.extend(params.iter().map(Argument::expression));
+                operands
+            }
+            Expression::List(items) | Expression::Tuple(items) => items.iter().collect(),
+            // a value is evaluated before its key.
+            Expression::Dict(entries) => entries.iter().flat_map(|(k, v)| vec![v, k]).collect(),
+            Expression::Slice(object, lower, upper) => {
+                let mut operands = vec![&**object];
+                operands.extend(lower.iter().chain(upper.iter()).map(|bound| &**bound));
+                operands
+            }
+            Expression::Neg(e)
+            | Expression::Invert(e)
+            | Expression::Attribute(e, _)
+            | Expression::Simple(Value::Complex(e)) => vec![e],
+            Expression::Simple(_) => vec![],
+        }
+    }
+}
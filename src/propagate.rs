@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::mem;
+use wexp::Wexp::{self, *};
+
+// the status way of handling exceptions, for hosts without the exception
+// handling proposal. $rt.throw only leaves the exception in the runtime's
+// $exception global and returns, so every call to a function that can end
+// up in it is followed by a check of $exception. the check branches to the
+// innermost block labelled $catch.N around the call, where a try statement
+// handles it, or returns from the function if there isn't one. the values
+// returned then are never looked at. main reports the exception instead.
+pub fn propagate(fields: &mut [Wexp]) {
+    let raising = raising(fields);
+    for field in fields.iter_mut() {
+        if let List(items) = field {
+            if is_func(items) {
+                *items = check_calls(mem::take(items), &raising);
+            }
+        }
+    }
+}
+
+fn is_func(items: &[Wexp]) -> bool {
+    matches!(items.first(), Some(Atom(head)) if head == "func")
+}
+
+fn atom(wexp: Option<&Wexp>) -> Option<&str> {
+    match wexp {
+        Some(Atom(atom)) => Some(atom),
+        _ => None,
+    }
+}
+
+// the functions called right after `call` anywhere in a function's items.
+fn callees(items: &[Wexp]) -> impl Iterator<Item = &str> {
+    items
+        .windows(2)
+        .filter(|pair| atom(pair.first()) == Some("call"))
+        .filter_map(|pair| atom(pair.get(1)))
+}
+
+// the functions that can raise: $rt.throw, and everything that calls a
+// function that can.
+fn raising(fields: &[Wexp]) -> HashSet<String> {
+    let mut raising = HashSet::new();
+    raising.insert("$rt.throw".to_owned());
+    loop {
+        let before = raising.len();
+        for field in fields.iter() {
+            if let List(items) = field {
+                if !is_func(items) {
+                    continue;
+                }
+                if let Some(name) = atom(items.get(1)) {
+                    if callees(items).any(|callee| raising.contains(callee)) {
+                        raising.insert(name.to_owned());
+                    }
+                }
+            }
+        }
+        if raising.len() == before {
+            return raising;
+        }
+    }
+}
+
+// the items of a function, with a check after each call that can raise.
+fn check_calls(mut items: Vec<Wexp>, raising: &HashSet<String>) -> Vec<Wexp> {
+    // the results come before the first instruction, after which a result
+    // belongs to a block.
+    let body = items
+        .iter()
+        .skip(1)
+        .position(|item| matches!(item, Atom(atom) if !atom.starts_with('$')))
+        .map_or(items.len(), |i| i + 1);
+    let mut results: Vec<String> = vec![];
+    let mut main = false;
+    for item in items[..body].iter() {
+        if let List(list) = item {
+            match atom(list.first()) {
+                Some("result") => results.extend(
+                    list[1..]
+                        .iter()
+                        .filter_map(|t| atom(Some(t)))
+                        .map(str::to_owned),
+                ),
+                Some("export") => main = atom(list.get(1)) == Some("\"main\""),
+                _ => (),
+            }
+        }
+    }
+    let instructions = items.split_off(body);
+    let mut checked = items;
+    // the label of each block the instruction is in, if it has one.
+    let mut labels: Vec<Option<String>> = vec![];
+    let mut items = instructions.into_iter().peekable();
+    while let Some(item) = items.next() {
+        let instruction = match item {
+            Atom(ref instruction) => instruction.clone(),
+            List(_) => {
+                checked.push(item);
+                continue;
+            }
+        };
+        checked.push(item);
+        match instruction.as_str() {
+            "block" | "loop" | "if" | "try_table" => {
+                let label = match items.peek() {
+                    Some(Atom(label)) if label.starts_with('$') => Some(label.clone()),
+                    _ => None,
+                };
+                labels.push(label);
+            }
+            "end" => {
+                labels.pop();
+            }
+            "call" => {
+                let callee = match items.next() {
+                    Some(callee) => callee,
+                    None => continue,
+                };
+                let raises = raising.contains(atom(Some(&callee)).unwrap_or(""));
+                checked.push(callee);
+                if !raises {
+                    continue;
+                }
+                checked.extend(atoms(&[
+                    "get_global",
+                    "$exception",
+                    "i64.const",
+                    "0",
+                    "i64.ne",
+                ]));
+                let handler = labels
+                    .iter()
+                    .rev()
+                    .flatten()
+                    .find(|label| label.starts_with("$catch."));
+                match handler {
+                    Some(handler) => checked.extend(atoms(&["br_if", handler])),
+                    None if main => checked.extend(atoms(&["if", "call", "$rt.uncaught", "end"])),
+                    None => {
+                        checked.push(Atom("if".to_owned()));
+                        for t in results.iter() {
+                            checked.extend(atoms(&[&format!("{}.const", t), "0"]));
+                        }
+                        checked.extend(atoms(&["return", "end"]));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    checked
+}
+
+fn atoms(atoms: &[&str]) -> Vec<Wexp> {
+    atoms.iter().map(|atom| Atom((*atom).to_owned())).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn propagated(text: &str) -> Vec<String> {
+        let mut fields = Wexp::parse(text);
+        propagate(&mut fields);
+        fields.iter().map(Wexp::to_string).collect()
+    }
+
+    #[test]
+    fn checks_raising_calls() {
+        let fields = propagated(
+            "(func $rt.throw (param $value i64) get_local $value set_global $exception) \
+             (func $f (result i64 i32) i64.const 1 call $rt.throw i64.const 2 i32.const 3) \
+             (func $g call $h block $catch.1 call $f drop drop end) \
+             (func $h) \
+             (func (export \"main\") call $f drop drop)",
+        );
+        assert_eq!(
+            fields,
+            [
+                "(func $rt.throw (param $value i64) get_local $value set_global $exception)",
+                "(func $f (result i64 i32) i64.const 1 call $rt.throw \
+                 get_global $exception i64.const 0 i64.ne \
+                 if i64.const 0 i32.const 0 return end i64.const 2 i32.const 3)",
+                "(func $g call $h block $catch.1 call $f \
+                 get_global $exception i64.const 0 i64.ne br_if $catch.1 drop drop end)",
+                "(func $h)",
+                "(func (export \"main\") call $f \
+                 get_global $exception i64.const 0 i64.ne if call $rt.uncaught end drop drop)",
+            ]
+        );
+    }
+}
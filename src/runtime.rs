@@ -0,0 +1,389 @@
+use exception::{Exception, Handling};
+use propagate;
+use wexp::Wexp;
+
+// the imports, globals and helper functions in runtime.wat, which generated
+// code calls into for anything that depends on the kind of a value.
+const RUNTIME: &str = include_str!("runtime.wat");
+
+pub const NONE: u64 = 0xfffb;
+
+// where the shadow stack starts in linear memory.
+pub const SHADOW_BASE: usize = 512;
+
+// where the call stack starts, and where the names of the program's file
+// and functions are kept for tracebacks.
+pub const CALLS: usize = 32768;
+const FILENAME: usize = 49280;
+pub const NAMES: usize = 49536;
+
+// where the heap starts, which is as far as the names can go.
+pub const HEAP: usize = 65536;
+
+// where the table of exception classes starts, and the names it points to.
+const CLASSES: usize = 208;
+const CLASS_NAMES: usize = CLASSES + 4 * (Exception::ALL.len() + 1);
+
+// how many bytes the heap can grow to when --heap-size isn't given.
+pub const DEFAULT_HEAP_SIZE: u32 = 64 << 20;
+
+// the boxed representation of a value with the given tag and payload.
+pub fn boxed(tag: u64, payload: u64) -> u64 {
+    tag << 48 | payload
+}
+
+// the wat for an i64 constant, written with the tag split off so that it can
+// be read at a glance.
+pub fn constant(value: u64) -> String {
+    format!("0x{:04x}_{:012x}", value >> 48, value & 0xffff_ffff_ffff)
+}
+
+// adds the runtime to a module generated by CodeGenerator, with a heap that
+// can grow to `heap_size` bytes, exceptions raised the way `handling` says,
+// and tracebacks naming `filename`. the imports have to come before the
+// module's own definitions.
+pub fn link(module: Wexp, heap_size: u32, handling: Handling, filename: &str) -> Wexp {
+    match module {
+        Wexp::List(mut fields) => {
+            let mut runtime = Wexp::parse(RUNTIME);
+            runtime.extend(Wexp::parse(&format!(
+                "(global $heap.limit i32 (i32.const {}))",
+                heap_size
+            )));
+            runtime.extend(Wexp::parse(&classes()));
+            runtime.extend(Wexp::parse(&format!(
+                "(data (i32.const {}) {})",
+                FILENAME,
+                string(&name(filename))
+            )));
+            runtime.extend(Wexp::parse(match handling {
+                Handling::Status => {
+                    "(func $rt.throw (param $value i64) get_local $value set_global $exception)"
+                }
+                Handling::Wasm => {
+                    "(tag $python (param i64)) \
+                     (func $rt.throw (param $value i64) get_local $value throw $python)"
+                }
+            }));
+            // a module whose classes have no __repr__ has no $special.repr
+            // for the runtime to call, and one without attributes has no
+            // table of their names.
+            if !defines(&fields, "func", "$special.repr") {
+                runtime.extend(Wexp::parse(
+                    "(func $special.repr (param $value i64) (result i64) get_local $value)",
+                ));
+            }
+            if !defines(&fields, "global", "$attributes") {
+                runtime.extend(Wexp::parse("(global $attributes i32 (i32.const 0))"));
+            }
+            fields.splice(1..1, runtime);
+            if handling == Handling::Status {
+                propagate::propagate(&mut fields[1..]);
+            }
+            Wexp::List(fields)
+        }
+        atom => atom,
+    }
+}
+
+// whether one of `fields` is the `kind` of field called `name`.
+fn defines(fields: &[Wexp], kind: &str, name: &str) -> bool {
+    fields.iter().any(|field| match field {
+        Wexp::List(items) => {
+            matches!(items.first(), Some(Wexp::Atom(head)) if head == kind)
+                && matches!(items.get(1), Some(Wexp::Atom(func)) if func == name)
+        }
+        _ => false,
+    })
+}
+
+// the data for the table of exception classes that the runtime looks up
+// codes in: the code of each class's parent, and the length and address of
+// its name.
+fn classes() -> String {
+    let mut table = vec![0; CLASS_NAMES - CLASSES];
+    let mut names = vec![];
+    for exception in Exception::ALL.iter() {
+        let entry = 4 * exception.code() as usize;
+        let address = CLASS_NAMES + names.len();
+        let name = exception.name();
+        table[entry] = exception.parent().map_or(0, Exception::code) as u8;
+        table[entry + 1] = name.len() as u8;
+        table[entry + 2] = address as u8;
+        table[entry + 3] = (address >> 8) as u8;
+        names.extend(name.bytes());
+    }
+    assert!(CLASS_NAMES + names.len() <= SHADOW_BASE);
+    table.extend(names);
+    format!("(data (i32.const {}) {})", CLASSES, string(&table))
+}
+
+// a name the way the runtime keeps it, after a byte of its length. longer
+// names are cut short.
+pub fn name(name: &str) -> Vec<u8> {
+    let bytes = &name.as_bytes()[..name.len().min(255)];
+    let mut name = vec![bytes.len() as u8];
+    name.extend(bytes);
+    name
+}
+
+// a wat string of any bytes.
+pub fn string(bytes: &[u8]) -> String {
+    let escaped: String = bytes.iter().map(|b| format!("\\{:02x}", b)).collect();
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing::{codegen_handling, run_wat};
+
+    // runs `main` as the body of a module's main function.
+    fn run(main: &str, heap_size: u32) -> Vec<String> {
+        let text = format!(
+            "(module (func (export \"main\") (local $a i32) (local $b i32) (local $i i32) {}))",
+            main
+        );
+        let module = Wexp::parse(&text).pop().unwrap();
+        run_wat(&link(module, heap_size, Handling::Status, "test.py").to_string())
+    }
+
+    #[test]
+    fn payload_constant() {
+        assert_eq!(constant(boxed(NONE, 24)), "0xfffb_000000000018");
+    }
+
+    #[test]
+    fn none_constant() {
+        assert_eq!(constant(boxed(NONE, 0)), "0xfffb_000000000000");
+    }
+
+    #[test]
+    fn link_after_module() {
+        let module = Wexp::parse("(module (func $f))").pop().unwrap();
+        let linked = link(module, DEFAULT_HEAP_SIZE, Handling::Status, "test.py").to_string();
+        assert!(linked.starts_with("(module (func $putchar (import \"host\" \"putchar\")"));
+        assert!(linked.ends_with(" (func $f))"));
+    }
+
+    // wasm-interp can't run a module that handles exceptions the wasm way,
+    // so this only checks that the linked text reads back as one module
+    // that defines every function it calls and the tag it catches.
+    #[test]
+    fn link_wasm_handling() {
+        let text = codegen_handling(
+            "try:\n  print 1 / 0\nexcept ZeroDivisionError, e:\n  print e\nraise e",
+            Handling::Wasm,
+        );
+        let module = Wexp::parse(&text).pop().unwrap();
+        let linked = link(module, DEFAULT_HEAP_SIZE, Handling::Wasm, "test.py").to_string();
+        let modules = Wexp::parse(&linked);
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].to_string(), linked);
+        let fields = match &modules[0] {
+            Wexp::List(fields) => fields,
+            Wexp::Atom(atom) => panic!("{} is no module", atom),
+        };
+        let mut defined = vec![];
+        let mut used = vec![];
+        for field in fields.iter() {
+            if let Wexp::List(items) = field {
+                let head = items.first().map(Wexp::to_string);
+                if head.as_deref() == Some("func") || head.as_deref() == Some("tag") {
+                    defined.push(items[1].to_string());
+                }
+                collect_calls(items, &mut used);
+            }
+        }
+        assert!(used.contains(&"$python".to_owned()));
+        for name in used.iter() {
+            assert!(defined.contains(name), "{} is not defined", name);
+        }
+    }
+
+    // what `items` calls or catches, anywhere in them.
+    fn collect_calls(items: &[Wexp], used: &mut Vec<String>) {
+        for pair in items.windows(2) {
+            let op = pair[0].to_string();
+            if op == "call" || op == "catch" || op == "throw" {
+                used.push(pair[1].to_string());
+            }
+        }
+        for item in items.iter() {
+            if let Wexp::List(items) = item {
+                collect_calls(items, used);
+            }
+        }
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let output = run(
+            "i32.const 4 call $rt.alloc call $rt.int call $rt.print \
+             i32.const 9 call $rt.alloc call $rt.int call $rt.print \
+             i32.const 0 call $rt.alloc call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["65544", "65560", "65584"]);
+    }
+
+    #[test]
+    fn free_list_reuse() {
+        let output = run(
+            "i32.const 4 call $rt.alloc tee_local $a \
+             i32.const 7 i32.store \
+             i32.const 32 call $rt.alloc drop \
+             get_local $a call $rt.free \
+             i32.const 8 call $rt.alloc tee_local $a call $rt.int call $rt.print \
+             get_local $a i32.load call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["65544", "0"]);
+    }
+
+    #[test]
+    fn large_block_split() {
+        let output = run(
+            "i32.const 1000 call $rt.alloc call $rt.free \
+             i32.const 300 call $rt.alloc call $rt.int call $rt.print \
+             i32.const 300 call $rt.alloc call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["65544", "65856"]);
+    }
+
+    #[test]
+    fn memory_grows() {
+        let output = run(
+            "i32.const 200000 call $rt.alloc \
+             i32.const 199996 i32.add i32.const 1 i32.store \
+             current_memory call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["5"]);
+    }
+
+    #[test]
+    fn heap_limit() {
+        let output = run(
+            "loop $forever \
+             i32.const 100 call $rt.alloc tee_local $a call $rt.int call $rt.print \
+             get_local $a i64.extend_i32_u i64.const 0xfffc_000000000000 i64.or \
+             call $rt.root drop \
+             br $forever \
+             end",
+            256,
+        );
+        assert_eq!(output, ["65544", "65656", "MemoryError: line 0"]);
+    }
+
+    #[test]
+    fn oversized_alloc() {
+        let output = run(
+            "i32.const -1 call $rt.alloc call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["MemoryError: line 0"]);
+    }
+
+    #[test]
+    fn garbage_collected() {
+        let output = run(
+            "loop $next \
+             i32.const 100 call $rt.alloc drop \
+             get_local $i i32.const 1 i32.add tee_local $i \
+             i32.const 10000 i32.lt_u br_if $next \
+             end \
+             get_global $heap.top call $rt.int call $rt.print",
+            1024,
+        );
+        assert_eq!(output, ["65648"]);
+    }
+
+    #[test]
+    fn reachable_objects_kept() {
+        // $b is on the shadow stack, and points to $a.
+        let output = run(
+            "i32.const 16 call $rt.alloc tee_local $a \
+             i64.const 0xfff9_000000000007 i64.store \
+             i32.const 16 call $rt.alloc tee_local $b \
+             get_local $a i64.extend_i32_u i64.const 0xfffc_000000000000 i64.or i64.store \
+             get_local $b i64.extend_i32_u i64.const 0xfffc_000000000000 i64.or \
+             call $rt.root drop \
+             loop $next \
+             i32.const 100 call $rt.alloc drop \
+             get_local $i i32.const 1 i32.add tee_local $i \
+             i32.const 1000 i32.lt_u br_if $next \
+             end \
+             get_local $a i32.load call $rt.int call $rt.print \
+             get_local $b i32.load get_local $a i32.eq call $rt.int call $rt.print",
+            1024,
+        );
+        assert_eq!(output, ["7", "1"]);
+    }
+
+    #[test]
+    fn exception_classes() {
+        let output = run(
+            "block $catch.0 i32.const 7 call $rt.raise end \
+             get_global $exception i32.const 13 call $rt.matches set_local $a \
+             get_global $exception i32.const 3 call $rt.matches set_local $b \
+             get_global $exception i64.const 0 set_global $exception \
+             get_local $a call $rt.int call $rt.print \
+             get_local $b call $rt.int call $rt.print \
+             i32.const 1 call $rt.write_exception \
+             i32.const 7 call $rt.raise",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(
+            output,
+            [
+                "1",
+                "0",
+                "KeyError()",
+                "stderr: Traceback (most recent call last):",
+                "stderr:   File \"test.py\", line 0, in <module>",
+                "stderr: KeyError",
+                "KeyError: line 0",
+            ]
+        );
+    }
+
+    // the file's name stands in for a function's.
+    #[test]
+    fn traceback_frames() {
+        let output = run(
+            "i32.const 3 set_global $line \
+             i32.const 49280 call $rt.call \
+             i32.const 7 set_global $line \
+             call $rt.return \
+             get_global $line call $rt.int call $rt.print \
+             i32.const 49280 call $rt.call \
+             i32.const 5 set_global $line \
+             i32.const 8 call $rt.raise",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(
+            output,
+            [
+                "3",
+                "stderr: Traceback (most recent call last):",
+                "stderr:   File \"test.py\", line 3, in <module>",
+                "stderr:   File \"test.py\", line 5, in test.py",
+                "stderr: ValueError",
+                "ValueError: line 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn shadow_stack_frames() {
+        let output = run(
+            "i32.const 2 call $rt.enter call $rt.int call $rt.print \
+             i32.const 3 call $rt.enter call $rt.int call $rt.print \
+             get_global $shadow.top call $rt.int call $rt.print",
+            DEFAULT_HEAP_SIZE,
+        );
+        assert_eq!(output, ["512", "528", "552"]);
+    }
+}
@@ -0,0 +1,7389 @@
+;; the runtime every compiled program is linked with.
+;;
+;; values are nan-boxed into i64s. a float is kept as its own bits, and every
+;; other kind of value lives in the negative quiet nan space, with a tag in
+;; the top 16 bits and a payload in the rest:
+;;
+;;   0xfff9  int, a signed 32-bit payload
+;;   0xfffa  bool, 0 or 1
+;;   0xfffb  None, or NotImplemented with a payload of 1
+;;
+;; tags from 0xfffc up, and from 0x7ffc up, point to objects on the heap:
+;;
+;;   0xfffc  dict, a hash table of keys and values
+;;   0xfffd  list, with its length and then its array of items
+;;   0xfffe  str, with its length and then its bytes
+;;   0xffff  tuple, laid out like a list
+;;   0x7ffc  array, whose capacity follows from the size of its block
+;;   0x7ffd  long, with its length and sign and then its limbs
+;;   0x7ffe  exception, with its args, then its class and line as ints, then
+;;           its traceback
+;;   0x7fff  object, a class of the program's or an instance of one
+;;
+;; arrays are only ever seen by the runtime. the tags of the containers
+;; and strs are in the order python 2 sorts them by type name, and each of
+;; them keeps its length as the first word of the object. every nan is made
+;; the same one before it is boxed, so no float has any of these tags.
+;;
+;; an instance is its class and a dict of its attributes. a class is its
+;; number as an int, a dict of its attributes, the class it derives from or
+;; None, the address of its name as an int, and whether it is new-style as a
+;; bool. so only a class has an int first. the compiler numbers attribute
+;; names, and the numbers are the keys of the dicts. the address of the
+;; name of each number is kept in 2 bytes of a table at $attributes.
+;;
+;; a long is as many 32-bit limbs as its magnitude needs, least significant
+;; first, after a word that is 1 if it is negative.
+;;
+;; a dict holds the number of keys in it and the number of its slots that
+;; are or have been in use, then an array of keys and values, an array of
+;; the hashes of the keys, and the mask that takes a hash to a slot. slots
+;; are probed the way python 2 probes them, with the same hashes, so that
+;; keys come out in the same order. empty and deleted slots have keys that
+;; are nans no float can be.
+;;
+;; the exception codes passed to $rt.raise are the ones in exception.rs.
+;; every builtin exception class has 4 bytes at 208 plus 4 times its code:
+;; the code of the class it derives from, or 0, then the length and the
+;; address of its name. the linker writes them from exception.rs.
+;;
+;; a raised exception is thrown by $rt.throw, which the linker writes for
+;; the way the module handles exceptions. it either throws the exception
+;; with the exception handling proposal's throw, or leaves it in
+;; $exception and returns, in which case every call that can raise is
+;; followed by a check of $exception that branches to the handler, or
+;; returns to the caller.
+;;
+;; every call to a function of the program pushes an entry on the call
+;; stack, which the function pops as it returns: the line the call was made
+;; from as 2 bytes, then 2 bytes of the address of the function's name. a
+;; name is a byte of its length and then its bytes. an exception keeps a
+;; copy of the call stack from when it was raised as a str, its traceback.
+;;
+;; objects live in linear memory, which is exported for the host. the first
+;; page holds the heads of the free lists, then at 132 plus each stream a
+;; byte that is 1 while print owes the stream a space, then the text the
+;; runtime writes out from address 136, then the exception classes from
+;; 208, then the shadow stack from address 512, the call stack from 32768,
+;; the message being put together from 47104, the text of the runtime's
+;; messages from 47616, of tracebacks from 49152, then of classes and
+;; instances from 49220. the name of the program's file follows at 49280,
+;; and the names of its functions, classes, methods and attributes at
+;; 49536, up to the heap, which starts at the second page. every block on the heap starts with an 8 byte
+;; header: the size of the whole block, then a word that links free blocks
+;; together, and objects waiting to be scanned by the collector. the size
+;; is a multiple of 8, and its low bits are flags: 1 if the block holds raw
+;; bytes rather than values, 2 while it is marked, and 4 while it is being
+;; written out.
+;;
+;; freed blocks of up to 256 bytes go on a list of blocks of exactly their
+;; size, whose head is kept at address size / 2. a small request whose own
+;; list is empty splits the smallest block on the lists of bigger sizes.
+;; bigger blocks go on a single list at address 0, which is searched first
+;; fit. when no free block will do, the heap grows upwards from $heap.top, a
+;; page of memory at a time, until it reaches $heap.limit bytes. then the
+;; heap is collected.
+
+;; the host writes bytes to a stream, 1 for stdout or 2 for stderr, and
+;; floats the way python's str() does if the last argument is 0, or its
+;; repr() if it is 1.
+(func $putchar (import "host" "putchar") (param i32 i32))
+(func $put_float (import "host" "put_float") (param i32 f64 i32))
+(func $raise (import "host" "raise") (param i32 i32))
+
+(memory (export "memory") 2)
+
+(data (i32.const 136) "TrueFalseNone[...], {...}: 0123456789abcdefNotImplemented")
+(data (i32.const 47616) "unsupported operand type(s) for ' and '+-*/%&|^<<>>~** or pow()cannot concatenate '' objectscan only concatenate  (not \"\") to negative shift countbad operand type for unary : 'negative number cannot be raised to a fractional powerobject of type '' has no len()string index out of range assignment indices must be integers, not ' object has no attribute '__getitem____setitem____delitem____len____neg____invert____str____repr__ does not support  doesn't support indexingitem assignmentitem deletionslice indices must be integers or None or have an __index__ methodargument of type ' is not iterable'in <string>' requires string as left operand, not unhashable type: 'isinstance() arg 2 must be a class, type, or tuple of classes and types returned non-string (type an integer is required() should return >= 0__init__() should return None, not 'exceptions must be old-style classes or derived from BaseException, not too many values to unpackneed more than iteration over non-sequenceclass type object 'appendkeysvaluesitemsget")
+(data (i32.const 48896) "integer division or modulo by zerofloat division by zerofloat modulo0.0 cannot be raised to a negative powerlong division or modulo by zero'exceptions.' object is not callable instance has no __call__ methodboolstrlisttupledictNoneTypetypeclassobj")
+(data (i32.const 49152) "Traceback (most recent call last):\0a  File \"\", line , in \08<module>")
+(data (i32.const 49220) "<class '__main__.'> instance at 0x object at 0x")
+
+;; the keys of empty and deleted dict slots.
+(global $dict.empty i64 (i64.const 0xfff8_000000000000))
+(global $dict.dummy i64 (i64.const 0xfff8_000000000001))
+
+;; the line of the statement being run, for error messages.
+(global $line (mut i32) (i32.const 0))
+
+;; the stream that print is writing to.
+(global $stream (mut i32) (i32.const 1))
+
+;; the exception being raised, or 0 while there isn't one.
+(global $exception (mut i64) (i64.const 0))
+
+;; the end of the heap, of the shadow stack, of the call stack, and of the
+;; message being put together.
+(global $heap.top (mut i32) (i32.const 65536))
+(global $shadow.top (mut i32) (i32.const 512))
+(global $calls.top (mut i32) (i32.const 32768))
+(global $message.top (mut i32) (i32.const 47104))
+
+;; objects waiting to have their contents marked, linked through their
+;; headers.
+(global $gray (mut i32) (i32.const 0))
+
+;; raises a new exception of the builtin class `code`, with no args.
+(func $rt.raise (param $code i32)
+  i32.const 0
+  call $rt.new_tuple
+  get_local $code
+  call $rt.new_exception
+  call $rt.throw)
+
+;; raises a new exception of the builtin class `code`, whose only arg is
+;; `arg`.
+(func $rt.raise_arg (param $arg i64) (param $code i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $arg
+  call $rt.root
+  drop
+  i32.const 1
+  call $rt.new_tuple
+  i32.const 0
+  get_local $arg
+  call $rt.init_item
+  get_local $top
+  set_global $shadow.top
+  get_local $code
+  call $rt.new_exception
+  call $rt.throw)
+
+;; raises a new exception of the builtin class `code`, with the `length`
+;; bytes at `address` as its message.
+(func $rt.raise_message (param $code i32) (param $address i32) (param $length i32)
+  get_local $address
+  get_local $length
+  call $rt.say
+  get_local $code
+  call $rt.raise_said)
+
+;; raises a new exception of the builtin class `code`, whose message is the
+;; one that has been put together.
+(func $rt.raise_said (param $code i32)
+  call $rt.said
+  get_local $code
+  call $rt.raise_arg)
+
+;; adds the `length` bytes at `address` to the message being put together,
+;; or as many of them as it has room for.
+(func $rt.say (param $address i32) (param $length i32)
+  (local $room i32)
+  i32.const 47616
+  get_global $message.top
+  i32.sub
+  tee_local $room
+  get_local $length
+  get_local $length
+  get_local $room
+  i32.gt_u
+  select
+  set_local $length
+  get_local $address
+  get_global $message.top
+  get_local $length
+  call $rt.copy_bytes
+  get_global $message.top
+  get_local $length
+  i32.add
+  set_global $message.top)
+
+;; the message that has been put together as a new str, which starts the
+;; next one.
+(func $rt.said (result i64)
+  (local $s i64)
+  get_global $message.top
+  i32.const 47104
+  i32.sub
+  call $rt.new_str
+  set_local $s
+  i32.const 47104
+  get_local $s
+  call $rt.bytes
+  get_global $message.top
+  i32.const 47104
+  i32.sub
+  call $rt.copy_bytes
+  i32.const 47104
+  set_global $message.top
+  get_local $s)
+
+;; says `n`, taken as unsigned, in decimal.
+(func $rt.say_digits (param $n i32)
+  get_local $n
+  i32.const 10
+  i32.ge_u
+  if
+    get_local $n
+    i32.const 10
+    i32.div_u
+    call $rt.say_digits
+  end
+  get_local $n
+  i32.const 10
+  i32.rem_u
+  i32.const 163
+  i32.add
+  i32.const 1
+  call $rt.say)
+
+;; says a name the way the compiler lays them out, after a byte of its
+;; length.
+(func $rt.say_name (param $name i32)
+  get_local $name
+  i32.const 1
+  i32.add
+  get_local $name
+  i32.load8_u
+  call $rt.say)
+
+;; says the name python gives the type of `value`, in quotes if `quoted`
+;; isn't 0. an instance of an old-style class is an instance, and one of a
+;; new-style class is of its class.
+(func $rt.say_type (param $value i64) (param $quoted i32)
+  (local $class i32)
+  get_local $quoted
+  if
+    i32.const 47648
+    i32.const 1
+    call $rt.say
+  end
+  block $said
+    get_local $value
+    call $rt.is_float
+    if
+      i32.const 48930
+      i32.const 5
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_str
+    if
+      i32.const 49107
+      i32.const 3
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_list
+    if
+      i32.const 49110
+      i32.const 4
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_tuple
+    if
+      i32.const 49114
+      i32.const 5
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_dict
+    if
+      i32.const 49119
+      i32.const 4
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_long
+    if
+      i32.const 49004
+      i32.const 4
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_exception
+    if
+      i32.const 49036
+      i32.const 11
+      call $rt.say
+      get_local $value
+      call $rt.exception_class
+      i32.const 2
+      i32.shl
+      tee_local $class
+      i32.load16_u offset=210
+      get_local $class
+      i32.load8_u offset=209
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_class
+    if
+      i32.const 49131
+      i32.const 49135
+      get_local $value
+      i32.wrap_i64
+      i32.load offset=32
+      select
+      i32.const 4
+      i32.const 8
+      get_local $value
+      i32.wrap_i64
+      i32.load offset=32
+      select
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_old_style
+    if
+      i32.const 49072
+      i32.const 8
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.is_instance
+    if
+      get_local $value
+      i32.wrap_i64
+      i32.load
+      i32.load offset=24
+      call $rt.say_name
+      br $said
+    end
+    get_local $value
+    call $rt.tag
+    i32.const 0xfffa
+    i32.eq
+    if
+      i32.const 49103
+      i32.const 4
+      call $rt.say
+      br $said
+    end
+    get_local $value
+    call $rt.tag
+    i32.const 0xfffb
+    i32.eq
+    if
+      i32.const 49123
+      i32.const 8
+      call $rt.say
+      br $said
+    end
+    i32.const 48896
+    i32.const 3
+    call $rt.say
+  end
+  get_local $quoted
+  if
+    i32.const 47648
+    i32.const 1
+    call $rt.say
+  end)
+
+;; says the type of `value` the way python does when it talks about the
+;; value as an object.
+(func $rt.say_object (param $value i64)
+  i32.const 47648
+  i32.const 1
+  call $rt.say
+  get_local $value
+  i32.const 0
+  call $rt.say_type
+  i32.const 47943
+  i32.const 8
+  call $rt.say)
+
+;; raises a KeyError for a key that isn't in a dict.
+(func $rt.key_error (param $key i64)
+  get_local $key
+  i32.const 7
+  call $rt.raise_arg)
+
+;; a MemoryError can't be caught, since there may be no memory left to
+;; make it, so it is reported to the host straight away.
+(func $rt.out_of_memory
+  call $rt.exit
+  i32.const 4
+  get_global $line
+  call $raise
+  unreachable)
+
+;; returns the address of `size` bytes of zeroed memory, 8 byte aligned, in
+;; a block that the collector scans for values.
+(func $rt.alloc (param $size i32) (result i32)
+  (local $total i32)
+  (local $block i32)
+  (local $link i32)
+  (local $top i32)
+  (local $collected i32)
+  get_local $size
+  get_global $heap.limit
+  i32.gt_u
+  if
+    call $rt.out_of_memory
+  end
+  ;; the size of the block, header included, rounded up to a multiple of 8.
+  get_local $size
+  i32.const 15
+  i32.add
+  i32.const -8
+  i32.and
+  tee_local $total
+  i32.const 16
+  i32.lt_u
+  if
+    i32.const 16
+    set_local $total
+  end
+  block $ready
+    loop $retry
+      get_local $total
+      i32.const 256
+      i32.le_u
+      if
+        get_local $total
+        i32.const 1
+        i32.shr_u
+        tee_local $link
+        i32.load
+        tee_local $block
+        if
+          get_local $link
+          get_local $block
+          i32.load offset=4
+          i32.store
+          br $ready
+        end
+        ;; failing that, a bigger small block is split in two.
+        get_local $total
+        i32.const 16
+        i32.add
+        set_local $top
+        block $none
+          loop $bigger
+            get_local $top
+            i32.const 256
+            i32.gt_u
+            br_if $none
+            get_local $top
+            i32.const 1
+            i32.shr_u
+            tee_local $link
+            i32.load
+            tee_local $block
+            if
+              get_local $link
+              get_local $block
+              i32.load offset=4
+              i32.store
+              get_local $block
+              get_local $total
+              i32.add
+              get_local $top
+              get_local $total
+              i32.sub
+              call $rt.release
+              br $ready
+            end
+            get_local $top
+            i32.const 8
+            i32.add
+            set_local $top
+            br $bigger
+          end
+        end
+      end
+      i32.const 0
+      set_local $link
+      block $done
+        loop $next
+          get_local $link
+          i32.load
+          tee_local $block
+          i32.eqz
+          br_if $done
+          get_local $block
+          i32.load
+          get_local $total
+          i32.ge_u
+          if
+            get_local $link
+            get_local $block
+            i32.load offset=4
+            i32.store
+            ;; whatever is left over goes back on a free list.
+            get_local $block
+            i32.load
+            get_local $total
+            i32.sub
+            tee_local $top
+            i32.const 16
+            i32.ge_u
+            if
+              get_local $block
+              get_local $total
+              i32.add
+              get_local $top
+              call $rt.release
+            else
+              get_local $block
+              i32.load
+              set_local $total
+            end
+            br $ready
+          end
+          get_local $block
+          i32.const 4
+          i32.add
+          set_local $link
+          br $next
+        end
+      end
+      get_global $heap.top
+      tee_local $block
+      get_local $total
+      i32.add
+      tee_local $top
+      i32.const 65536
+      i32.sub
+      get_global $heap.limit
+      i32.gt_u
+      if
+        ;; a full heap is collected once before giving up.
+        get_local $collected
+        if
+          call $rt.out_of_memory
+        end
+        i32.const 1
+        set_local $collected
+        call $rt.collect
+        br $retry
+      end
+    end
+    get_local $top
+    current_memory
+    i32.const 16
+    i32.shl
+    i32.gt_u
+    if
+      get_local $top
+      current_memory
+      i32.const 16
+      i32.shl
+      i32.sub
+      i32.const 65535
+      i32.add
+      i32.const 16
+      i32.shr_u
+      grow_memory
+      i32.const -1
+      i32.eq
+      if
+        call $rt.out_of_memory
+      end
+    end
+    get_local $top
+    set_global $heap.top
+  end
+  get_local $block
+  get_local $total
+  i32.store
+  get_local $block
+  i32.const 0
+  i32.store offset=4
+  ;; blocks from a free list still hold whatever was in them.
+  get_local $block
+  get_local $total
+  i32.add
+  set_local $top
+  get_local $block
+  i32.const 8
+  i32.add
+  set_local $link
+  block $done
+    loop $next
+      get_local $link
+      get_local $top
+      i32.ge_u
+      br_if $done
+      get_local $link
+      i64.const 0
+      i64.store
+      get_local $link
+      i32.const 8
+      i32.add
+      set_local $link
+      br $next
+    end
+  end
+  get_local $block
+  i32.const 8
+  i32.add)
+
+;; like $rt.alloc, but for a block that the collector doesn't scan.
+(func $rt.alloc_bytes (param $size i32) (result i32)
+  (local $address i32)
+  get_local $size
+  call $rt.alloc
+  tee_local $address
+  i32.const 8
+  i32.sub
+  get_local $address
+  i32.const 8
+  i32.sub
+  i32.load
+  i32.const 1
+  i32.or
+  i32.store
+  get_local $address)
+
+;; puts the block holding `address`, which came from $rt.alloc, back on a
+;; free list.
+(func $rt.free (param $address i32)
+  get_local $address
+  i32.const 8
+  i32.sub
+  tee_local $address
+  get_local $address
+  i32.load
+  i32.const -8
+  i32.and
+  call $rt.release)
+
+;; makes the `size` bytes at `block` into a free block.
+(func $rt.release (param $block i32) (param $size i32)
+  (local $link i32)
+  get_local $size
+  i32.const 256
+  i32.le_u
+  if (result i32)
+    get_local $size
+    i32.const 1
+    i32.shr_u
+  else
+    i32.const 0
+  end
+  set_local $link
+  get_local $block
+  get_local $size
+  i32.store
+  get_local $block
+  get_local $link
+  i32.load
+  i32.store offset=4
+  get_local $link
+  get_local $block
+  i32.store)
+
+;; the shadow stack keeps the values the collector has to treat as live,
+;; since it can't see into wasm locals or the operand stack. functions
+;; reserve a frame of `slots` zeroed values on entry, and put back the
+;; returned top of the stack on the way out.
+(func $rt.enter (param $slots i32) (result i32)
+  (local $frame i32)
+  (local $top i32)
+  get_global $shadow.top
+  tee_local $frame
+  get_local $slots
+  i32.const 3
+  i32.shl
+  i32.add
+  tee_local $top
+  i32.const 32768
+  i32.gt_u
+  if
+    call $rt.out_of_memory
+  end
+  get_local $top
+  set_global $shadow.top
+  block $done
+    loop $next
+      get_local $top
+      get_local $frame
+      i32.le_u
+      br_if $done
+      get_local $top
+      i32.const 8
+      i32.sub
+      tee_local $top
+      i64.const 0
+      i64.store
+      br $next
+    end
+  end
+  get_local $frame)
+
+;; keeps `value` alive until the end of the statement being run.
+(func $rt.root (param $value i64) (result i64)
+  get_global $shadow.top
+  i32.const 32768
+  i32.ge_u
+  if
+    call $rt.out_of_memory
+  end
+  get_global $shadow.top
+  get_local $value
+  i64.store
+  get_global $shadow.top
+  i32.const 8
+  i32.add
+  set_global $shadow.top
+  get_local $value)
+
+;; marks the object `value` points to, if it is one.
+(func $rt.mark (param $value i64)
+  (local $block i32)
+  (local $size i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0x7fff
+  i32.and
+  i32.const 0x7ffc
+  i32.lt_u
+  if
+    return
+  end
+  get_local $value
+  i32.wrap_i64
+  i32.const 8
+  i32.sub
+  tee_local $block
+  i32.load
+  tee_local $size
+  i32.const 2
+  i32.and
+  if
+    return
+  end
+  get_local $block
+  get_local $size
+  i32.const 2
+  i32.or
+  i32.store
+  get_local $size
+  i32.const 1
+  i32.and
+  if
+    return
+  end
+  get_local $block
+  get_global $gray
+  i32.store offset=4
+  get_local $block
+  set_global $gray)
+
+;; a mark and sweep collection. everything reachable from the shadow stack
+;; and the exception being raised is marked, and every run of unmarked blocks becomes one free block. a run
+;; at the end of the heap is given back to the bump allocator.
+(func $rt.collect
+  (local $p i32)
+  (local $end i32)
+  (local $run i32)
+  get_global $exception
+  call $rt.mark
+  i32.const 512
+  set_local $p
+  block $done
+    loop $next
+      get_local $p
+      get_global $shadow.top
+      i32.ge_u
+      br_if $done
+      get_local $p
+      i64.load
+      call $rt.mark
+      get_local $p
+      i32.const 8
+      i32.add
+      set_local $p
+      br $next
+    end
+  end
+  block $done
+    loop $next
+      get_global $gray
+      tee_local $p
+      i32.eqz
+      br_if $done
+      get_local $p
+      i32.load offset=4
+      set_global $gray
+      get_local $p
+      get_local $p
+      i32.load
+      i32.const -8
+      i32.and
+      i32.add
+      set_local $end
+      block $scanned
+        loop $scan
+          get_local $p
+          i32.const 8
+          i32.add
+          tee_local $p
+          get_local $end
+          i32.ge_u
+          br_if $scanned
+          get_local $p
+          i64.load
+          call $rt.mark
+          br $scan
+        end
+      end
+      br $next
+    end
+  end
+  ;; the free lists are rebuilt from scratch, leaving alone the bytes print
+  ;; keeps for each stream after them.
+  i32.const 0
+  set_local $p
+  block $done
+    loop $next
+      get_local $p
+      i32.const 0
+      i32.store
+      get_local $p
+      i32.const 4
+      i32.add
+      tee_local $p
+      i32.const 132
+      i32.lt_u
+      br_if $next
+    end
+  end
+  i32.const 65536
+  set_local $p
+  block $done
+    loop $next
+      get_local $p
+      get_global $heap.top
+      i32.ge_u
+      br_if $done
+      get_local $p
+      i32.load
+      tee_local $end
+      i32.const 2
+      i32.and
+      if
+        get_local $p
+        get_local $end
+        i32.const -3
+        i32.and
+        i32.store
+        get_local $run
+        if
+          get_local $run
+          get_local $p
+          get_local $run
+          i32.sub
+          call $rt.release
+          i32.const 0
+          set_local $run
+        end
+      else
+        get_local $run
+        i32.eqz
+        if
+          get_local $p
+          set_local $run
+        end
+      end
+      get_local $p
+      get_local $end
+      i32.const -8
+      i32.and
+      i32.add
+      set_local $p
+      br $next
+    end
+  end
+  get_local $run
+  if
+    get_local $run
+    set_global $heap.top
+  end)
+
+(func $rt.tag (param $value i64) (result i32)
+  get_local $value
+  i64.const 48
+  i64.shr_u
+  i32.wrap_i64)
+
+(func $rt.int (param $i i32) (result i64)
+  get_local $i
+  i64.extend_i32_u
+  i64.const 0xfff9_000000000000
+  i64.or)
+
+(func $rt.bool (param $b i32) (result i64)
+  get_local $b
+  i64.extend_i32_u
+  i64.const 0xfffa_000000000000
+  i64.or)
+
+(func $rt.float (param $f f64) (result i64)
+  ;; every nan becomes the canonical one, so that none looks like a tag.
+  get_local $f
+  get_local $f
+  f64.ne
+  if (result i64)
+    i64.const 0x7ff8_000000000000
+  else
+    get_local $f
+    i64.reinterpret_f64
+  end)
+
+(func $rt.is_float (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0x7fff
+  i32.and
+  i32.const 0x7ff9
+  i32.lt_u)
+
+;; bools count as integers in arithmetic.
+(func $rt.is_int (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfff9
+  i32.eq
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffa
+  i32.eq
+  i32.or)
+
+(func $rt.to_float (param $value i64) (result f64)
+  get_local $value
+  call $rt.is_float
+  if (result f64)
+    get_local $value
+    f64.reinterpret_i64
+  else
+    get_local $value
+    call $rt.is_long
+    if (result f64)
+      get_local $value
+      call $rt.long_to_float
+    else
+      get_local $value
+      i32.wrap_i64
+      f64.convert_i32_s
+    end
+  end)
+
+;; 0 if both operands are ints, 1 if either is a float, and otherwise 2 if
+;; either is a long. anything else is a TypeError for the operator whose
+;; symbol is the `length` bytes at `symbol`.
+(func $rt.numeric (param $a i64) (param $b i64) (param $symbol i32) (param $length i32) (result i32)
+  get_local $a
+  call $rt.is_number
+  get_local $b
+  call $rt.is_number
+  i32.and
+  i32.eqz
+  if
+    get_local $a
+    get_local $b
+    get_local $symbol
+    get_local $length
+    call $rt.unsupported
+  end
+  get_local $a
+  call $rt.is_float
+  get_local $b
+  call $rt.is_float
+  i32.or
+  if (result i32)
+    i32.const 1
+  else
+    get_local $a
+    call $rt.is_long
+    get_local $b
+    call $rt.is_long
+    i32.or
+    i32.const 1
+    i32.shl
+  end)
+
+;; raises the TypeError for the operator whose symbol is the `length`
+;; bytes at `symbol`, which doesn't take operands of the types of `a` and
+;; `b`.
+(func $rt.unsupported (param $a i64) (param $b i64) (param $symbol i32) (param $length i32)
+  i32.const 47616
+  i32.const 32
+  call $rt.say
+  get_local $symbol
+  get_local $length
+  call $rt.say
+  i32.const 47789
+  i32.const 3
+  call $rt.say
+  get_local $a
+  i32.const 0
+  call $rt.say_type
+  i32.const 47648
+  i32.const 7
+  call $rt.say
+  get_local $b
+  i32.const 0
+  call $rt.say_type
+  i32.const 2
+  i32.const 47648
+  i32.const 1
+  call $rt.raise_message)
+
+;; raises the TypeError for the unary operator whose symbol is the byte at
+;; `symbol`, which doesn't take an operand of the type of `a`. an instance
+;; of an old-style class lacks the special method `name` instead.
+(func $rt.bad_operand (param $a i64) (param $symbol i32) (param $name i32) (param $length i32)
+  get_local $a
+  get_local $name
+  get_local $length
+  call $rt.lacks
+  i32.const 47762
+  i32.const 27
+  call $rt.say
+  get_local $symbol
+  i32.const 1
+  call $rt.say
+  i32.const 47789
+  i32.const 2
+  call $rt.say
+  get_local $a
+  i32.const 1
+  call $rt.say_type
+  i32.const 2
+  call $rt.raise_said)
+
+;; a str or a sequence can only be added to another of its own kind.
+(func $rt.add (param $a i64) (param $b i64) (result i64)
+  get_local $a
+  call $rt.is_sequence
+  get_local $a
+  call $rt.tag
+  get_local $b
+  call $rt.tag
+  i32.eq
+  i32.and
+  if
+    get_local $a
+    get_local $b
+    call $rt.concat
+    return
+  end
+  get_local $a
+  call $rt.is_str
+  get_local $b
+  call $rt.is_str
+  i32.and
+  if
+    get_local $a
+    get_local $b
+    call $rt.concat_str
+    return
+  end
+  get_local $a
+  call $rt.is_str
+  if
+    i32.const 47679
+    i32.const 20
+    call $rt.say
+    i32.const 49107
+    i32.const 3
+    call $rt.say
+    i32.const 47648
+    i32.const 7
+    call $rt.say
+    get_local $b
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    i32.const 47699
+    i32.const 9
+    call $rt.raise_message
+  end
+  get_local $a
+  call $rt.is_sequence
+  if
+    i32.const 47708
+    i32.const 21
+    call $rt.say
+    get_local $a
+    i32.const 0
+    call $rt.say_type
+    i32.const 47729
+    i32.const 7
+    call $rt.say
+    get_local $b
+    i32.const 0
+    call $rt.say_type
+    i32.const 47736
+    i32.const 6
+    call $rt.say
+    get_local $a
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    call $rt.raise_said
+  end
+  block $long
+    block $float
+      block $int
+        get_local $a
+        get_local $b
+        i32.const 47655
+        i32.const 1
+        call $rt.numeric
+        br_table $int $float $long
+      end
+      get_local $a
+      i32.wrap_i64
+      i64.extend_i32_s
+      get_local $b
+      i32.wrap_i64
+      i64.extend_i32_s
+      i64.add
+      call $rt.int64
+      return
+    end
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    f64.add
+    call $rt.float
+    return
+  end
+  get_local $a
+  get_local $b
+  i32.const 0
+  call $rt.add_long)
+
+(func $rt.sub (param $a i64) (param $b i64) (result i64)
+  block $long
+    block $float
+      block $int
+        get_local $a
+        get_local $b
+        i32.const 47656
+        i32.const 1
+        call $rt.numeric
+        br_table $int $float $long
+      end
+      get_local $a
+      i32.wrap_i64
+      i64.extend_i32_s
+      get_local $b
+      i32.wrap_i64
+      i64.extend_i32_s
+      i64.sub
+      call $rt.int64
+      return
+    end
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    f64.sub
+    call $rt.float
+    return
+  end
+  get_local $a
+  get_local $b
+  i32.const 1
+  call $rt.add_long)
+
+(func $rt.mul (param $a i64) (param $b i64) (result i64)
+  block $long
+    block $float
+      block $int
+        get_local $a
+        get_local $b
+        i32.const 47657
+        i32.const 1
+        call $rt.numeric
+        br_table $int $float $long
+      end
+      get_local $a
+      i32.wrap_i64
+      i64.extend_i32_s
+      get_local $b
+      i32.wrap_i64
+      i64.extend_i32_s
+      i64.mul
+      call $rt.int64
+      return
+    end
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    f64.mul
+    call $rt.float
+    return
+  end
+  get_local $a
+  get_local $b
+  call $rt.mul_long)
+
+(func $rt.neg (param $a i64) (result i64)
+  get_local $a
+  call $rt.is_float
+  if
+    get_local $a
+    f64.reinterpret_i64
+    f64.neg
+    call $rt.float
+    return
+  end
+  get_local $a
+  call $rt.is_long
+  if
+    i64.const 0xfff9_000000000000
+    get_local $a
+    i32.const 1
+    call $rt.add_long
+    return
+  end
+  get_local $a
+  call $rt.is_int
+  i32.eqz
+  if
+    get_local $a
+    i32.const 47656
+    i32.const 48010
+    i32.const 7
+    call $rt.bad_operand
+  end
+  i64.const 0
+  get_local $a
+  i32.wrap_i64
+  i64.extend_i32_s
+  i64.sub
+  call $rt.int64)
+
+;; ints, bools and longs.
+(func $rt.is_integral (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_int
+  get_local $value
+  call $rt.is_long
+  i32.or)
+
+;; `&` if `op` is 0, `|` if it is 1, or `^` if it is 2. these take ints,
+;; bools and longs, and give a bool when both operands are bools, and a
+;; long when either is a long.
+(func $rt.bitwise (param $a i64) (param $b i64) (param $op i32) (result i64)
+  (local $x i32)
+  get_local $a
+  call $rt.is_integral
+  get_local $b
+  call $rt.is_integral
+  i32.and
+  i32.eqz
+  if
+    get_local $a
+    get_local $b
+    get_local $op
+    i32.const 47660
+    i32.add
+    i32.const 1
+    call $rt.unsupported
+  end
+  get_local $a
+  call $rt.is_long
+  get_local $b
+  call $rt.is_long
+  i32.or
+  if
+    get_local $a
+    get_local $b
+    get_local $op
+    call $rt.bitwise_long
+    return
+  end
+  get_local $a
+  i32.wrap_i64
+  get_local $b
+  i32.wrap_i64
+  get_local $op
+  call $rt.bitwise_int
+  set_local $x
+  get_local $a
+  call $rt.tag
+  get_local $b
+  call $rt.tag
+  i32.and
+  i32.const 0xfffa
+  i32.eq
+  if (result i64)
+    get_local $x
+    call $rt.bool
+  else
+    get_local $x
+    call $rt.int
+  end)
+
+(func $rt.bitwise_int (param $x i32) (param $y i32) (param $op i32) (result i32)
+  block $xor
+    block $or
+      block $and
+        get_local $op
+        br_table $and $or $xor
+      end
+      get_local $x
+      get_local $y
+      i32.and
+      return
+    end
+    get_local $x
+    get_local $y
+    i32.or
+    return
+  end
+  get_local $x
+  get_local $y
+  i32.xor)
+
+(func $rt.and (param $a i64) (param $b i64) (result i64)
+  get_local $a
+  get_local $b
+  i32.const 0
+  call $rt.bitwise)
+
+(func $rt.or (param $a i64) (param $b i64) (result i64)
+  get_local $a
+  get_local $b
+  i32.const 1
+  call $rt.bitwise)
+
+(func $rt.xor (param $a i64) (param $b i64) (result i64)
+  get_local $a
+  get_local $b
+  i32.const 2
+  call $rt.bitwise)
+
+;; a shift by a negative count is a ValueError. wasm only looks at the low
+;; 5 bits of the count, so longer shifts are worked out here.
+(func $rt.shr_int (param $x i32) (param $n i32) (result i32)
+  get_local $n
+  i32.const 0
+  i32.lt_s
+  if
+    i32.const 8
+    i32.const 47742
+    i32.const 20
+    call $rt.raise_message
+  end
+  get_local $x
+  get_local $n
+  i32.const 31
+  get_local $n
+  i32.const 31
+  i32.lt_s
+  select
+  i32.shr_s)
+
+;; the count of a shift has to be an int or a long that isn't negative. the
+;; symbol of the shift is the 2 bytes at `symbol`.
+(func $rt.check_shift (param $a i64) (param $b i64) (param $symbol i32)
+  get_local $a
+  call $rt.is_integral
+  get_local $b
+  call $rt.is_integral
+  i32.and
+  i32.eqz
+  if
+    get_local $a
+    get_local $b
+    get_local $symbol
+    i32.const 2
+    call $rt.unsupported
+  end
+  get_local $b
+  call $rt.negative
+  if
+    i32.const 8
+    i32.const 47742
+    i32.const 20
+    call $rt.raise_message
+  end)
+
+;; an int shifted left by less than 32 fits in 64 bits. anything else is
+;; shifted as a long, and a long count would never fit in memory.
+(func $rt.shl (param $a i64) (param $b i64) (result i64)
+  get_local $a
+  get_local $b
+  i32.const 47663
+  call $rt.check_shift
+  get_local $b
+  call $rt.is_long
+  if
+    call $rt.out_of_memory
+  end
+  get_local $a
+  call $rt.is_int
+  get_local $b
+  i32.wrap_i64
+  i32.const 32
+  i32.lt_u
+  i32.and
+  if (result i64)
+    get_local $a
+    i32.wrap_i64
+    i64.extend_i32_s
+    get_local $b
+    i32.wrap_i64
+    i64.extend_i32_u
+    i64.shl
+    call $rt.int64
+  else
+    get_local $a
+    get_local $b
+    i32.wrap_i64
+    call $rt.shl_long
+  end)
+
+;; a long count shifts everything out.
+(func $rt.shr (param $a i64) (param $b i64) (result i64)
+  (local $n i32)
+  get_local $a
+  get_local $b
+  i32.const 47665
+  call $rt.check_shift
+  i32.const 0x7fffffff
+  get_local $b
+  i32.wrap_i64
+  get_local $b
+  call $rt.is_long
+  select
+  set_local $n
+  get_local $a
+  call $rt.is_long
+  if (result i64)
+    get_local $a
+    get_local $n
+    call $rt.shr_long
+  else
+    get_local $a
+    i32.wrap_i64
+    get_local $n
+    call $rt.shr_int
+    call $rt.int
+  end)
+
+(func $rt.invert (param $a i64) (result i64)
+  get_local $a
+  call $rt.is_long
+  if
+    ;; ~a is -1 - a.
+    i64.const 0xfff9_0000ffffffff
+    get_local $a
+    i32.const 1
+    call $rt.add_long
+    return
+  end
+  get_local $a
+  call $rt.is_int
+  i32.eqz
+  if
+    get_local $a
+    i32.const 47667
+    i32.const 48017
+    i32.const 10
+    call $rt.bad_operand
+  end
+  get_local $a
+  i32.wrap_i64
+  i32.const -1
+  i32.xor
+  call $rt.int)
+
+;; python 2 rounds integer division towards negative infinity, and the
+;; remainder takes the sign of the divisor. these take unboxed operands, so
+;; that code working on known ints and floats can call them directly.
+(func $rt.div_int (param $x i32) (param $y i32) (result i32)
+  (local $q i32)
+  (local $r i32)
+  get_local $y
+  i32.eqz
+  if
+    i32.const 3
+    i32.const 48896
+    i32.const 34
+    call $rt.raise_message
+  end
+  get_local $x
+  get_local $y
+  i32.div_s
+  set_local $q
+  ;; truncation rounded up if there is a remainder of the wrong sign.
+  get_local $x
+  get_local $y
+  i32.rem_s
+  tee_local $r
+  i32.const 0
+  i32.ne
+  get_local $r
+  get_local $y
+  i32.xor
+  i32.const 0
+  i32.lt_s
+  i32.and
+  if
+    get_local $q
+    i32.const 1
+    i32.sub
+    set_local $q
+  end
+  get_local $q)
+
+(func $rt.mod_int (param $x i32) (param $y i32) (result i32)
+  (local $r i32)
+  get_local $y
+  i32.eqz
+  if
+    i32.const 3
+    i32.const 48896
+    i32.const 34
+    call $rt.raise_message
+  end
+  get_local $x
+  get_local $y
+  i32.rem_s
+  tee_local $r
+  i32.const 0
+  i32.ne
+  get_local $r
+  get_local $y
+  i32.xor
+  i32.const 0
+  i32.lt_s
+  i32.and
+  if (result i32)
+    get_local $r
+    get_local $y
+    i32.add
+  else
+    get_local $r
+  end)
+
+(func $rt.div_float (param $x f64) (param $y f64) (result f64)
+  get_local $y
+  f64.const 0
+  f64.eq
+  if
+    i32.const 3
+    i32.const 48930
+    i32.const 22
+    call $rt.raise_message
+  end
+  get_local $x
+  get_local $y
+  f64.div)
+
+(func $rt.mod_float (param $x f64) (param $y f64) (result f64)
+  get_local $y
+  f64.const 0
+  f64.eq
+  if
+    i32.const 3
+    i32.const 48952
+    i32.const 12
+    call $rt.raise_message
+  end
+  get_local $x
+  get_local $y
+  get_local $x
+  get_local $y
+  f64.div
+  f64.floor
+  f64.mul
+  f64.sub)
+
+
+;; -x / -1 is the one division of ints that can overflow.
+(func $rt.div (param $a i64) (param $b i64) (result i64)
+  block $long
+    block $float
+      block $int
+        get_local $a
+        get_local $b
+        i32.const 47658
+        i32.const 1
+        call $rt.numeric
+        br_table $int $float $long
+      end
+      get_local $b
+      i32.wrap_i64
+      i32.const -1
+      i32.eq
+      if
+        i64.const 0
+        get_local $a
+        i32.wrap_i64
+        i64.extend_i32_s
+        i64.sub
+        call $rt.int64
+        return
+      end
+      get_local $a
+      i32.wrap_i64
+      get_local $b
+      i32.wrap_i64
+      call $rt.div_int
+      call $rt.int
+      return
+    end
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    call $rt.div_float
+    call $rt.float
+    return
+  end
+  get_local $a
+  get_local $b
+  i32.const 0
+  call $rt.divmod_long)
+
+(func $rt.mod (param $a i64) (param $b i64) (result i64)
+  block $long
+    block $float
+      block $int
+        get_local $a
+        get_local $b
+        i32.const 47659
+        i32.const 1
+        call $rt.numeric
+        br_table $int $float $long
+      end
+      get_local $a
+      i32.wrap_i64
+      get_local $b
+      i32.wrap_i64
+      call $rt.mod_int
+      call $rt.int
+      return
+    end
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    call $rt.mod_float
+    call $rt.float
+    return
+  end
+  get_local $a
+  get_local $b
+  i32.const 1
+  call $rt.divmod_long)
+
+;; integral exponents are done by squaring, and anything else as
+;; exp(y * ln(x)), which a negative base can't be raised to. that is only
+;; good to within a few bits of the last digit.
+(func $rt.pow_float (param $x f64) (param $y f64) (result f64)
+  (local $n i64)
+  (local $result f64)
+  get_local $y
+  f64.const 0
+  f64.eq
+  if
+    f64.const 1
+    return
+  end
+  get_local $x
+  f64.const 0
+  f64.eq
+  if
+    get_local $y
+    f64.const 0
+    f64.lt
+    if
+      i32.const 3
+      i32.const 48964
+      i32.const 40
+      call $rt.raise_message
+    end
+    f64.const 0
+    return
+  end
+  get_local $y
+  f64.trunc
+  get_local $y
+  f64.ne
+  if
+    get_local $x
+    f64.const 0
+    f64.lt
+    if
+      i32.const 8
+      i32.const 47792
+      i32.const 54
+      call $rt.raise_message
+    end
+  end
+  get_local $y
+  f64.trunc
+  get_local $y
+  f64.eq
+  get_local $y
+  f64.abs
+  f64.const 0x1p53
+  f64.lt
+  i32.and
+  i32.eqz
+  if
+    get_local $y
+    get_local $x
+    f64.abs
+    call $rt.ln
+    f64.mul
+    call $rt.exp
+    return
+  end
+  get_local $y
+  f64.abs
+  i64.trunc_f64_s
+  set_local $n
+  f64.const 1
+  set_local $result
+  block
+    loop
+      get_local $n
+      i64.eqz
+      br_if 1
+      get_local $n
+      i64.const 1
+      i64.and
+      i32.wrap_i64
+      if
+        get_local $result
+        get_local $x
+        f64.mul
+        set_local $result
+      end
+      get_local $x
+      get_local $x
+      f64.mul
+      set_local $x
+      get_local $n
+      i64.const 1
+      i64.shr_u
+      set_local $n
+      br 0
+    end
+  end
+  get_local $y
+  f64.const 0
+  f64.lt
+  if (result f64)
+    f64.const 1
+    get_local $result
+    f64.div
+  else
+    get_local $result
+  end)
+
+;; the natural log of a positive number. x is split into m * 2^k with m
+;; near 1, and ln(m) is 2 * atanh((m - 1) / (m + 1)) by its series.
+(func $rt.ln (param $x f64) (result f64)
+  (local $bits i64)
+  (local $k i32)
+  (local $m f64)
+  (local $s f64)
+  (local $s2 f64)
+  (local $term f64)
+  (local $sum f64)
+  (local $j i32)
+  ;; subnormals are scaled up first.
+  get_local $x
+  f64.const 0x1p-1022
+  f64.lt
+  if
+    get_local $x
+    f64.const 0x1p54
+    f64.mul
+    set_local $x
+    i32.const -54
+    set_local $k
+  end
+  get_local $x
+  i64.reinterpret_f64
+  set_local $bits
+  get_local $k
+  get_local $bits
+  i64.const 52
+  i64.shr_u
+  i32.wrap_i64
+  i32.const 0x7ff
+  i32.and
+  i32.const 1023
+  i32.sub
+  i32.add
+  set_local $k
+  get_local $bits
+  i64.const 0x000f_ffff_ffff_ffff
+  i64.and
+  i64.const 0x3ff0_0000_0000_0000
+  i64.or
+  f64.reinterpret_i64
+  set_local $m
+  get_local $m
+  f64.const 1.4142135623730951
+  f64.gt
+  if
+    get_local $m
+    f64.const 0.5
+    f64.mul
+    set_local $m
+    get_local $k
+    i32.const 1
+    i32.add
+    set_local $k
+  end
+  get_local $m
+  f64.const 1
+  f64.sub
+  get_local $m
+  f64.const 1
+  f64.add
+  f64.div
+  tee_local $s
+  set_local $term
+  get_local $s
+  get_local $s
+  f64.mul
+  set_local $s2
+  i32.const 1
+  set_local $j
+  block
+    loop
+      get_local $j
+      i32.const 41
+      i32.gt_s
+      br_if 1
+      get_local $sum
+      get_local $term
+      get_local $j
+      f64.convert_i32_s
+      f64.div
+      f64.add
+      set_local $sum
+      get_local $term
+      get_local $s2
+      f64.mul
+      set_local $term
+      get_local $j
+      i32.const 2
+      i32.add
+      set_local $j
+      br 0
+    end
+  end
+  get_local $k
+  f64.convert_i32_s
+  f64.const 6.93147180369123816490e-01
+  f64.mul
+  get_local $sum
+  f64.const 2
+  f64.mul
+  get_local $k
+  f64.convert_i32_s
+  f64.const 1.90821492927058770002e-10
+  f64.mul
+  f64.add
+  f64.add)
+
+;; e^t, as 2^k * e^r with |r| at most ln(2) / 2, and e^r by its series.
+(func $rt.exp (param $t f64) (result f64)
+  (local $k i32)
+  (local $r f64)
+  (local $sum f64)
+  (local $j i32)
+  get_local $t
+  f64.const 709.8
+  f64.gt
+  if
+    f64.const inf
+    return
+  end
+  get_local $t
+  f64.const -745.2
+  f64.lt
+  if
+    f64.const 0
+    return
+  end
+  get_local $t
+  f64.const 1.4426950408889634
+  f64.mul
+  f64.nearest
+  i32.trunc_f64_s
+  set_local $k
+  ;; ln(2) is split in two so that k * ln(2) is exact enough.
+  get_local $t
+  get_local $k
+  f64.convert_i32_s
+  f64.const 6.93147180369123816490e-01
+  f64.mul
+  f64.sub
+  get_local $k
+  f64.convert_i32_s
+  f64.const 1.90821492927058770002e-10
+  f64.mul
+  f64.sub
+  set_local $r
+  ;; the series is summed from its smallest term.
+  f64.const 1
+  set_local $sum
+  i32.const 20
+  set_local $j
+  block
+    loop
+      get_local $j
+      i32.eqz
+      br_if 1
+      get_local $sum
+      get_local $r
+      f64.mul
+      get_local $j
+      f64.convert_i32_s
+      f64.div
+      f64.const 1
+      f64.add
+      set_local $sum
+      get_local $j
+      i32.const 1
+      i32.sub
+      set_local $j
+      br 0
+    end
+  end
+  ;; 2^k is applied in two halves, since it may not be a normal float.
+  get_local $sum
+  get_local $k
+  i32.const 2
+  i32.div_s
+  call $rt.pow2
+  f64.mul
+  get_local $k
+  get_local $k
+  i32.const 2
+  i32.div_s
+  i32.sub
+  call $rt.pow2
+  f64.mul)
+
+(func $rt.pow2 (param $k i32) (result f64)
+  get_local $k
+  i32.const 1023
+  i32.add
+  i64.extend_i32_u
+  i64.const 52
+  i64.shl
+  f64.reinterpret_i64)
+
+
+;; an int to a negative power is a float. otherwise the power is worked
+;; out by squaring, and becomes a long if it overflows, or if the base is
+;; one. a long exponent would never fit in memory.
+(func $rt.pow (param $a i64) (param $b i64) (result i64)
+  (local $n i32)
+  (local $base i64)
+  (local $result i64)
+  (local $top i32)
+  get_local $a
+  get_local $b
+  i32.const 47668
+  i32.const 11
+  call $rt.numeric
+  i32.const 1
+  i32.eq
+  if (result i32)
+    i32.const 1
+  else
+    get_local $b
+    call $rt.negative
+  end
+  if
+    get_local $a
+    call $rt.to_float
+    get_local $b
+    call $rt.to_float
+    call $rt.pow_float
+    call $rt.float
+    return
+  end
+  get_local $b
+  call $rt.is_long
+  if
+    call $rt.out_of_memory
+  end
+  get_local $b
+  i32.wrap_i64
+  set_local $n
+  i64.const 0xfff9_000000000001
+  set_local $result
+  get_local $a
+  call $rt.is_long
+  if
+    i64.const 1
+    call $rt.long
+    set_local $result
+  end
+  ;; the base and the result so far are kept in the two slots at $top.
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  tee_local $base
+  call $rt.root
+  drop
+  get_local $result
+  call $rt.root
+  drop
+  block $done
+    loop $next
+      get_local $n
+      i32.eqz
+      br_if $done
+      get_local $n
+      i32.const 1
+      i32.and
+      if
+        get_local $top
+        get_local $result
+        get_local $base
+        call $rt.mul
+        tee_local $result
+        i64.store offset=8
+      end
+      get_local $n
+      i32.const 1
+      i32.shr_u
+      tee_local $n
+      if
+        get_local $top
+        get_local $base
+        get_local $base
+        call $rt.mul
+        tee_local $base
+        i64.store
+      end
+      br $next
+    end
+  end
+  get_local $top
+  set_global $shadow.top
+  get_local $result)
+
+;; longs are kept as their sign and the 32-bit limbs of their magnitude, and
+;; once a long is made it stays one, like it does in python 2. the helpers
+;; that only read a magnitude take ints as well, so that mixing the two
+;; doesn't need a long to be made from the int.
+(func $rt.is_long (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0x7ffd
+  i32.eq)
+
+;; ints, bools, floats and longs.
+(func $rt.is_number (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_integral
+  get_local $value
+  call $rt.is_float
+  i32.or)
+
+;; a long of `size` limbs, which are 0 until they are filled in.
+(func $rt.new_long (param $size i32) (result i64)
+  (local $address i32)
+  get_local $size
+  i32.const 0x1ffffff0
+  i32.ge_u
+  if
+    call $rt.out_of_memory
+  end
+  get_local $size
+  i32.const 2
+  i32.shl
+  i32.const 8
+  i32.add
+  call $rt.alloc_bytes
+  tee_local $address
+  get_local $size
+  i32.store
+  get_local $address
+  i64.extend_i32_u
+  i64.const 0x7ffd_000000000000
+  i64.or)
+
+;; the long equal to `x`.
+(func $rt.long (param $x i64) (result i64)
+  (local $long i64)
+  i32.const 2
+  call $rt.new_long
+  tee_local $long
+  i32.wrap_i64
+  ;; the magnitude of the most negative i64 still fits when unsigned.
+  i64.const 0
+  get_local $x
+  i64.sub
+  get_local $x
+  get_local $x
+  i64.const 0
+  i64.lt_s
+  select
+  i64.store offset=8
+  get_local $long
+  i32.wrap_i64
+  get_local $x
+  i64.const 0
+  i64.lt_s
+  i32.store offset=4
+  get_local $long
+  call $rt.normalize)
+
+;; an int if `x` fits in one, and a long otherwise.
+(func $rt.int64 (param $x i64) (result i64)
+  get_local $x
+  get_local $x
+  i32.wrap_i64
+  i64.extend_i32_s
+  i64.eq
+  if (result i64)
+    get_local $x
+    i32.wrap_i64
+    call $rt.int
+  else
+    get_local $x
+    call $rt.long
+  end)
+
+;; drops the zero limbs from the top of `long`, which is made positive if
+;; that leaves none.
+(func $rt.normalize (param $long i64) (result i64)
+  (local $address i32)
+  (local $size i32)
+  get_local $long
+  i32.wrap_i64
+  tee_local $address
+  i32.load
+  set_local $size
+  block $done
+    loop $next
+      get_local $size
+      i32.eqz
+      br_if $done
+      get_local $address
+      get_local $size
+      i32.const 2
+      i32.shl
+      i32.add
+      i32.load offset=4
+      br_if $done
+      get_local $size
+      i32.const 1
+      i32.sub
+      set_local $size
+      br $next
+    end
+  end
+  get_local $address
+  get_local $size
+  i32.store
+  get_local $size
+  i32.eqz
+  if
+    get_local $address
+    i32.const 0
+    i32.store offset=4
+  end
+  get_local $long)
+
+;; the address of the first limb of a long.
+(func $rt.limbs (param $long i64) (result i32)
+  get_local $long
+  i32.wrap_i64
+  i32.const 8
+  i32.add)
+
+;; how many limbs the magnitude of an int or long has.
+(func $rt.size (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_long
+  if (result i32)
+    get_local $value
+    i32.wrap_i64
+    i32.load
+  else
+    get_local $value
+    i32.wrap_i64
+    i32.const 0
+    i32.ne
+  end)
+
+(func $rt.negative (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_long
+  if (result i32)
+    get_local $value
+    i32.wrap_i64
+    i32.load offset=4
+  else
+    get_local $value
+    i32.wrap_i64
+    i32.const 0
+    i32.lt_s
+  end)
+
+;; limb `i` of the magnitude of an int or long, or 0 past its top.
+(func $rt.limb (param $value i64) (param $i i32) (result i32)
+  (local $x i32)
+  get_local $i
+  get_local $value
+  call $rt.size
+  i32.ge_u
+  if
+    i32.const 0
+    return
+  end
+  get_local $value
+  call $rt.is_long
+  if (result i32)
+    get_local $value
+    call $rt.limbs
+    get_local $i
+    i32.const 2
+    i32.shl
+    i32.add
+    i32.load
+  else
+    get_local $value
+    i32.wrap_i64
+    tee_local $x
+    get_local $x
+    i32.const 31
+    i32.shr_s
+    tee_local $x
+    i32.xor
+    get_local $x
+    i32.sub
+  end)
+
+(func $rt.max_size (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  call $rt.size
+  get_local $b
+  call $rt.size
+  get_local $a
+  call $rt.size
+  get_local $b
+  call $rt.size
+  i32.gt_u
+  select)
+
+;; -1, 0 or 1 as the magnitude of a is less than, equal to or greater than
+;; that of b.
+(func $rt.compare_magnitudes (param $a i64) (param $b i64) (result i32)
+  (local $i i32)
+  (local $x i32)
+  (local $y i32)
+  get_local $a
+  get_local $b
+  call $rt.max_size
+  set_local $i
+  block $done
+    loop $next
+      get_local $i
+      i32.eqz
+      br_if $done
+      get_local $a
+      get_local $i
+      i32.const 1
+      i32.sub
+      tee_local $i
+      call $rt.limb
+      tee_local $x
+      get_local $b
+      get_local $i
+      call $rt.limb
+      tee_local $y
+      i32.ne
+      if
+        get_local $x
+        get_local $y
+        i32.gt_u
+        get_local $x
+        get_local $y
+        i32.lt_u
+        i32.sub
+        return
+      end
+      br $next
+    end
+  end
+  i32.const 0)
+
+;; stores the magnitude of a + b in the limbs of `into`, which may be a.
+(func $rt.add_magnitudes (param $a i64) (param $b i64) (param $into i64)
+  (local $i i32)
+  (local $sum i64)
+  block $done
+    loop $next
+      get_local $i
+      get_local $into
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $into
+      call $rt.limbs
+      get_local $i
+      i32.const 2
+      i32.shl
+      i32.add
+      get_local $a
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      get_local $b
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      i64.add
+      get_local $sum
+      i64.const 32
+      i64.shr_u
+      i64.add
+      tee_local $sum
+      i64.store32
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end)
+
+;; stores the magnitude of a less that of b, which is no bigger, in the
+;; limbs of `into`, which may be either of them.
+(func $rt.sub_magnitudes (param $a i64) (param $b i64) (param $into i64)
+  (local $i i32)
+  (local $difference i64)
+  block $done
+    loop $next
+      get_local $i
+      get_local $into
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $into
+      call $rt.limbs
+      get_local $i
+      i32.const 2
+      i32.shl
+      i32.add
+      get_local $a
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      get_local $b
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      i64.sub
+      get_local $difference
+      i64.const 63
+      i64.shr_u
+      i64.sub
+      tee_local $difference
+      i64.store32
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end)
+
+;; a + b, or a - b if `negate` is 1, for ints and longs.
+(func $rt.add_long (param $a i64) (param $b i64) (param $negate i32) (result i64)
+  (local $result i64)
+  (local $sign i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  get_local $b
+  call $rt.root
+  call $rt.max_size
+  i32.const 1
+  i32.add
+  call $rt.new_long
+  set_local $result
+  get_local $a
+  call $rt.negative
+  get_local $b
+  call $rt.negative
+  get_local $negate
+  i32.xor
+  i32.eq
+  if
+    get_local $a
+    get_local $b
+    get_local $result
+    call $rt.add_magnitudes
+    get_local $a
+    call $rt.negative
+    set_local $sign
+  else
+    get_local $a
+    get_local $b
+    call $rt.compare_magnitudes
+    i32.const 0
+    i32.ge_s
+    if
+      get_local $a
+      get_local $b
+      get_local $result
+      call $rt.sub_magnitudes
+      get_local $a
+      call $rt.negative
+      set_local $sign
+    else
+      get_local $b
+      get_local $a
+      get_local $result
+      call $rt.sub_magnitudes
+      get_local $b
+      call $rt.negative
+      get_local $negate
+      i32.xor
+      set_local $sign
+    end
+  end
+  get_local $result
+  i32.wrap_i64
+  get_local $sign
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top
+  get_local $result
+  call $rt.normalize)
+
+(func $rt.mul_long (param $a i64) (param $b i64) (result i64)
+  (local $result i64)
+  (local $i i32)
+  (local $j i32)
+  (local $x i64)
+  (local $carry i64)
+  (local $address i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  call $rt.size
+  get_local $b
+  call $rt.root
+  call $rt.size
+  i32.add
+  call $rt.new_long
+  set_local $result
+  block $done
+    loop $next
+      get_local $i
+      get_local $a
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $a
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      set_local $x
+      i64.const 0
+      set_local $carry
+      i32.const 0
+      set_local $j
+      block $row
+        loop $column
+          get_local $j
+          get_local $b
+          call $rt.size
+          i32.ge_u
+          br_if $row
+          get_local $result
+          call $rt.limbs
+          get_local $i
+          get_local $j
+          i32.add
+          i32.const 2
+          i32.shl
+          i32.add
+          tee_local $address
+          get_local $address
+          i64.load32_u
+          get_local $x
+          get_local $b
+          get_local $j
+          call $rt.limb
+          i64.extend_i32_u
+          i64.mul
+          i64.add
+          get_local $carry
+          i64.add
+          tee_local $carry
+          i64.store32
+          get_local $carry
+          i64.const 32
+          i64.shr_u
+          set_local $carry
+          get_local $j
+          i32.const 1
+          i32.add
+          set_local $j
+          br $column
+        end
+      end
+      get_local $result
+      call $rt.limbs
+      get_local $i
+      get_local $j
+      i32.add
+      i32.const 2
+      i32.shl
+      i32.add
+      get_local $carry
+      i64.store32
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $result
+  i32.wrap_i64
+  get_local $a
+  call $rt.negative
+  get_local $b
+  call $rt.negative
+  i32.xor
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top
+  get_local $result
+  call $rt.normalize)
+
+;; doubles the magnitude of `long` and adds `bit` to it, in place.
+(func $rt.shift_in (param $long i64) (param $bit i32)
+  (local $address i32)
+  (local $end i32)
+  (local $limb i32)
+  get_local $long
+  call $rt.limbs
+  tee_local $address
+  get_local $long
+  call $rt.size
+  i32.const 2
+  i32.shl
+  i32.add
+  set_local $end
+  block $done
+    loop $next
+      get_local $address
+      get_local $end
+      i32.ge_u
+      br_if $done
+      get_local $address
+      get_local $address
+      i32.load
+      tee_local $limb
+      i32.const 1
+      i32.shl
+      get_local $bit
+      i32.or
+      i32.store
+      get_local $limb
+      i32.const 31
+      i32.shr_u
+      set_local $bit
+      get_local $address
+      i32.const 4
+      i32.add
+      set_local $address
+      br $next
+    end
+  end)
+
+;; the floor of a / b if `part` is 0, or what is left over if it is 1, for
+;; ints and longs. the magnitudes are divided a bit at a time.
+(func $rt.divmod_long (param $a i64) (param $b i64) (param $part i32) (result i64)
+  (local $q i64)
+  (local $r i64)
+  (local $i i32)
+  (local $address i32)
+  (local $top i32)
+  get_local $b
+  call $rt.size
+  i32.eqz
+  if
+    i32.const 3
+    i32.const 49004
+    i32.const 31
+    call $rt.raise_message
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  call $rt.size
+  call $rt.new_long
+  call $rt.root
+  set_local $q
+  get_local $b
+  call $rt.root
+  call $rt.size
+  i32.const 1
+  i32.add
+  call $rt.new_long
+  set_local $r
+  get_local $a
+  call $rt.size
+  i32.const 5
+  i32.shl
+  set_local $i
+  block $done
+    loop $next
+      get_local $i
+      i32.eqz
+      br_if $done
+      get_local $r
+      get_local $a
+      get_local $i
+      i32.const 1
+      i32.sub
+      tee_local $i
+      i32.const 5
+      i32.shr_u
+      call $rt.limb
+      get_local $i
+      i32.shr_u
+      i32.const 1
+      i32.and
+      call $rt.shift_in
+      get_local $r
+      get_local $b
+      call $rt.compare_magnitudes
+      i32.const 0
+      i32.ge_s
+      if
+        get_local $r
+        get_local $b
+        get_local $r
+        call $rt.sub_magnitudes
+        get_local $q
+        call $rt.limbs
+        get_local $i
+        i32.const 5
+        i32.shr_u
+        i32.const 2
+        i32.shl
+        i32.add
+        tee_local $address
+        get_local $address
+        i32.load
+        i32.const 1
+        get_local $i
+        i32.shl
+        i32.or
+        i32.store
+      end
+      br $next
+    end
+  end
+  ;; python rounds the quotient down, so that the remainder takes the sign
+  ;; of b.
+  get_local $a
+  call $rt.negative
+  get_local $b
+  call $rt.negative
+  i32.ne
+  get_local $r
+  call $rt.normalize
+  call $rt.size
+  i32.const 0
+  i32.ne
+  i32.and
+  if
+    get_local $q
+    i64.const 0xfff9_000000000001
+    get_local $q
+    call $rt.add_magnitudes
+    get_local $r
+    i32.wrap_i64
+    get_local $b
+    call $rt.size
+    i32.store
+    get_local $b
+    get_local $r
+    get_local $r
+    call $rt.sub_magnitudes
+  end
+  get_local $q
+  i32.wrap_i64
+  get_local $a
+  call $rt.negative
+  get_local $b
+  call $rt.negative
+  i32.xor
+  i32.store offset=4
+  get_local $r
+  i32.wrap_i64
+  get_local $b
+  call $rt.negative
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top
+  get_local $r
+  get_local $q
+  get_local $part
+  select
+  call $rt.normalize)
+
+;; a long shifted left by `n` bits, which is a whole number of limbs and
+;; then what is left over.
+(func $rt.shl_long (param $a i64) (param $n i32) (result i64)
+  (local $result i64)
+  (local $i i32)
+  (local $shifted i64)
+  (local $address i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  call $rt.size
+  get_local $n
+  i32.const 5
+  i32.shr_u
+  i32.add
+  i32.const 1
+  i32.add
+  call $rt.new_long
+  set_local $result
+  block $done
+    loop $next
+      get_local $i
+      get_local $a
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $a
+      get_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      get_local $n
+      i32.const 31
+      i32.and
+      i64.extend_i32_u
+      i64.shl
+      set_local $shifted
+      get_local $result
+      call $rt.limbs
+      get_local $i
+      get_local $n
+      i32.const 5
+      i32.shr_u
+      i32.add
+      i32.const 2
+      i32.shl
+      i32.add
+      tee_local $address
+      get_local $address
+      i32.load
+      get_local $shifted
+      i32.wrap_i64
+      i32.or
+      i32.store
+      get_local $address
+      get_local $shifted
+      i64.const 32
+      i64.shr_u
+      i64.store32 offset=4
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $result
+  i32.wrap_i64
+  get_local $a
+  call $rt.negative
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top
+  get_local $result
+  call $rt.normalize)
+
+;; a long shifted right by `n` bits. python shifts negative numbers down,
+;; so a negative long that loses any bits that are set is one further from
+;; zero, which can take one more limb.
+(func $rt.shr_long (param $a i64) (param $n i32) (result i64)
+  (local $result i64)
+  (local $i i32)
+  (local $q i32)
+  (local $lost i32)
+  (local $top i32)
+  get_local $n
+  i32.const 5
+  i32.shr_u
+  set_local $q
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  call $rt.size
+  get_local $q
+  i32.sub
+  i32.const 1
+  i32.add
+  i32.const 1
+  get_local $a
+  call $rt.size
+  get_local $q
+  i32.gt_u
+  select
+  call $rt.new_long
+  set_local $result
+  block $done
+    loop $next
+      get_local $i
+      get_local $result
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $result
+      call $rt.limbs
+      get_local $i
+      i32.const 2
+      i32.shl
+      i32.add
+      get_local $a
+      get_local $i
+      get_local $q
+      i32.add
+      i32.const 1
+      i32.add
+      call $rt.limb
+      i64.extend_i32_u
+      i64.const 32
+      i64.shl
+      get_local $a
+      get_local $i
+      get_local $q
+      i32.add
+      call $rt.limb
+      i64.extend_i32_u
+      i64.or
+      get_local $n
+      i32.const 31
+      i32.and
+      i64.extend_i32_u
+      i64.shr_u
+      i64.store32
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $a
+  call $rt.negative
+  if
+    get_local $a
+    get_local $q
+    call $rt.limb
+    i32.const 1
+    get_local $n
+    i32.shl
+    i32.const 1
+    i32.sub
+    i32.const 0
+    get_local $n
+    i32.const 31
+    i32.and
+    select
+    i32.and
+    set_local $lost
+    i32.const 0
+    set_local $i
+    block $done
+      loop $next
+        get_local $i
+        get_local $q
+        i32.ge_u
+        br_if $done
+        get_local $lost
+        get_local $a
+        get_local $i
+        call $rt.limb
+        i32.or
+        set_local $lost
+        get_local $i
+        i32.const 1
+        i32.add
+        set_local $i
+        br $next
+      end
+    end
+    get_local $lost
+    if
+      get_local $result
+      i64.const 0xfff9_000000000001
+      get_local $result
+      call $rt.add_magnitudes
+    end
+    get_local $result
+    i32.wrap_i64
+    i32.const 1
+    i32.store offset=4
+  end
+  get_local $top
+  set_global $shadow.top
+  get_local $result
+  call $rt.normalize)
+
+;; `&`, `|` or `^` of ints and longs, as if they were in two's complement
+;; with their sign going on forever. the limbs of a negative magnitude m
+;; are those of ~(m - 1), and a negative result is made back into a
+;; magnitude the same way.
+(func $rt.bitwise_long (param $a i64) (param $b i64) (param $op i32) (result i64)
+  (local $result i64)
+  (local $i i32)
+  (local $x i32)
+  (local $y i32)
+  (local $z i32)
+  (local $sign i32)
+  (local $borrow_a i32)
+  (local $borrow_b i32)
+  (local $carry i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  get_local $b
+  call $rt.root
+  call $rt.max_size
+  i32.const 1
+  i32.add
+  call $rt.new_long
+  set_local $result
+  i32.const 0
+  get_local $a
+  call $rt.negative
+  i32.sub
+  i32.const 0
+  get_local $b
+  call $rt.negative
+  i32.sub
+  get_local $op
+  call $rt.bitwise_int
+  i32.const 1
+  i32.and
+  set_local $sign
+  i32.const 1
+  set_local $borrow_a
+  i32.const 1
+  set_local $borrow_b
+  i32.const 1
+  set_local $carry
+  block $done
+    loop $next
+      get_local $i
+      get_local $result
+      call $rt.size
+      i32.ge_u
+      br_if $done
+      get_local $a
+      get_local $i
+      call $rt.limb
+      set_local $x
+      get_local $a
+      call $rt.negative
+      if
+        get_local $x
+        get_local $borrow_a
+        i32.sub
+        i32.const -1
+        i32.xor
+        get_local $borrow_a
+        get_local $x
+        i32.eqz
+        i32.and
+        set_local $borrow_a
+        set_local $x
+      end
+      get_local $b
+      get_local $i
+      call $rt.limb
+      set_local $y
+      get_local $b
+      call $rt.negative
+      if
+        get_local $y
+        get_local $borrow_b
+        i32.sub
+        i32.const -1
+        i32.xor
+        get_local $borrow_b
+        get_local $y
+        i32.eqz
+        i32.and
+        set_local $borrow_b
+        set_local $y
+      end
+      get_local $x
+      get_local $y
+      get_local $op
+      call $rt.bitwise_int
+      set_local $z
+      get_local $sign
+      if
+        get_local $z
+        i32.const -1
+        i32.xor
+        get_local $carry
+        i32.add
+        tee_local $z
+        i32.eqz
+        get_local $carry
+        i32.and
+        set_local $carry
+      end
+      get_local $result
+      call $rt.limbs
+      get_local $i
+      i32.const 2
+      i32.shl
+      i32.add
+      get_local $z
+      i32.store
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $result
+  i32.wrap_i64
+  get_local $sign
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top
+  get_local $result
+  call $rt.normalize)
+
+;; compares ints and longs by their signs, and then by their magnitudes.
+(func $rt.compare_longs (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  call $rt.negative
+  get_local $b
+  call $rt.negative
+  i32.ne
+  if
+    get_local $b
+    call $rt.negative
+    get_local $a
+    call $rt.negative
+    i32.sub
+    return
+  end
+  i32.const 0
+  get_local $a
+  get_local $b
+  call $rt.compare_magnitudes
+  i32.sub
+  get_local $a
+  get_local $b
+  call $rt.compare_magnitudes
+  get_local $a
+  call $rt.negative
+  select)
+
+;; a long as a float, which is inf if it is too big for one.
+(func $rt.long_to_float (param $long i64) (result f64)
+  (local $i i32)
+  (local $f f64)
+  get_local $long
+  call $rt.size
+  set_local $i
+  block $done
+    loop $next
+      get_local $i
+      i32.eqz
+      br_if $done
+      get_local $f
+      f64.const 0x1p32
+      f64.mul
+      get_local $long
+      get_local $i
+      i32.const 1
+      i32.sub
+      tee_local $i
+      call $rt.limb
+      f64.convert_i32_u
+      f64.add
+      set_local $f
+      br $next
+    end
+  end
+  get_local $f
+  f64.neg
+  get_local $f
+  get_local $long
+  call $rt.negative
+  select)
+
+;; an index that is a long is clamped to a range that no sequence reaches,
+;; if it doesn't fit in an int.
+(func $rt.index (param $value i64) (result i32)
+  (local $limb i32)
+  get_local $value
+  call $rt.is_long
+  i32.eqz
+  if
+    get_local $value
+    i32.wrap_i64
+    return
+  end
+  get_local $value
+  i32.const 0
+  call $rt.limb
+  tee_local $limb
+  i32.const 0x7fffffff
+  get_local $limb
+  i32.const 0
+  i32.ge_s
+  get_local $value
+  call $rt.size
+  i32.const 2
+  i32.lt_u
+  i32.and
+  select
+  set_local $limb
+  i32.const 0
+  get_local $limb
+  i32.sub
+  get_local $limb
+  get_local $value
+  call $rt.negative
+  select)
+
+;; where a value sorts among values of other kinds: None first, then
+;; numbers, then everything else by tag.
+(func $rt.rank (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffb
+  i32.eq
+  if (result i32)
+    i32.const 0
+  else
+    get_local $value
+    call $rt.is_number
+    if (result i32)
+      i32.const 1
+    else
+      get_local $value
+      call $rt.tag
+    end
+  end)
+
+;; -1, 0 or 1 as a is less than, equal to or greater than b, or 2 if they
+;; are unordered, which only happens with nans.
+(func $rt.compare (param $a i64) (param $b i64) (result i32)
+  (local $x f64)
+  (local $y f64)
+  (local $kind i32)
+  get_local $a
+  call $rt.rank
+  get_local $b
+  call $rt.rank
+  i32.ne
+  if
+    get_local $a
+    call $rt.rank
+    get_local $b
+    call $rt.rank
+    i32.gt_u
+    get_local $a
+    call $rt.rank
+    get_local $b
+    call $rt.rank
+    i32.lt_u
+    i32.sub
+    return
+  end
+  get_local $a
+  call $rt.rank
+  i32.const 1
+  i32.ne
+  if
+    get_local $a
+    call $rt.is_sequence
+    if
+      get_local $a
+      get_local $b
+      call $rt.compare_sequences
+      return
+    end
+    get_local $a
+    call $rt.is_str
+    if
+      get_local $a
+      get_local $b
+      call $rt.compare_strs
+      return
+    end
+    get_local $a
+    call $rt.is_dict
+    if
+      get_local $a
+      get_local $b
+      call $rt.compare_dicts
+      return
+    end
+    ;; values of the same kind, other than numbers and containers, are
+    ;; equal when they are the same value.
+    get_local $a
+    get_local $b
+    i64.gt_u
+    get_local $a
+    get_local $b
+    i64.lt_u
+    i32.sub
+    return
+  end
+  ;; only numbers get this far, so there is no operator to name.
+  get_local $a
+  get_local $b
+  i32.const 0
+  i32.const 0
+  call $rt.numeric
+  tee_local $kind
+  i32.const 2
+  i32.eq
+  if
+    get_local $a
+    get_local $b
+    call $rt.compare_longs
+    return
+  end
+  get_local $kind
+  if (result i32)
+    get_local $a
+    call $rt.to_float
+    set_local $x
+    get_local $b
+    call $rt.to_float
+    set_local $y
+    get_local $x
+    get_local $y
+    f64.eq
+    get_local $x
+    get_local $y
+    f64.lt
+    get_local $x
+    get_local $y
+    f64.gt
+    i32.or
+    i32.or
+    if (result i32)
+      get_local $x
+      get_local $y
+      f64.gt
+      get_local $x
+      get_local $y
+      f64.lt
+      i32.sub
+    else
+      i32.const 2
+    end
+  else
+    get_local $a
+    i32.wrap_i64
+    get_local $b
+    i32.wrap_i64
+    i32.gt_s
+    get_local $a
+    i32.wrap_i64
+    get_local $b
+    i32.wrap_i64
+    i32.lt_s
+    i32.sub
+  end)
+
+;; the comparisons give unboxed bools.
+(func $rt.eq (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.eqz)
+
+(func $rt.ne (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const 0
+  i32.ne)
+
+(func $rt.lt (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const -1
+  i32.eq)
+
+(func $rt.le (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const 1
+  i32.add
+  i32.const 1
+  i32.le_u)
+
+(func $rt.gt (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const 1
+  i32.eq)
+
+(func $rt.ge (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const -1
+  i32.gt_s
+  get_local $a
+  get_local $b
+  call $rt.compare
+  i32.const 2
+  i32.lt_s
+  i32.and)
+
+(func $rt.truthy (param $value i64) (result i32)
+  ;; empty containers and strs are false.
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffc
+  i32.ge_u
+  if
+    get_local $value
+    call $rt.len
+    i32.const 0
+    i32.ne
+    return
+  end
+  get_local $value
+  call $rt.is_long
+  if
+    get_local $value
+    call $rt.size
+    i32.const 0
+    i32.ne
+    return
+  end
+  get_local $value
+  call $rt.is_float
+  if (result i32)
+    get_local $value
+    f64.reinterpret_i64
+    f64.const 0
+    f64.ne
+  else
+    ;; None is false, and so are the ints and bools with a zero payload.
+    get_local $value
+    call $rt.tag
+    i32.const 0xfffb
+    i32.ne
+    get_local $value
+    i32.wrap_i64
+    i32.const 0
+    i32.ne
+    i32.and
+  end)
+
+(func $rt.is_dict (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffc
+  i32.eq)
+
+(func $rt.is_list (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffd
+  i32.eq)
+
+(func $rt.is_str (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffe
+  i32.eq)
+
+(func $rt.is_tuple (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xffff
+  i32.eq)
+
+;; lists and tuples.
+(func $rt.is_sequence (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_list
+  get_local $value
+  call $rt.is_tuple
+  i32.or)
+
+;; an array with room for at least `capacity` values, all 0.
+(func $rt.new_array (param $capacity i32) (result i64)
+  get_local $capacity
+  i32.const 0x10000000
+  i32.ge_u
+  if
+    call $rt.out_of_memory
+  end
+  get_local $capacity
+  i32.const 3
+  i32.shl
+  call $rt.alloc
+  i64.extend_i32_u
+  i64.const 0x7ffc_000000000000
+  i64.or)
+
+(func $rt.capacity (param $array i64) (result i32)
+  get_local $array
+  i32.wrap_i64
+  i32.const 8
+  i32.sub
+  i32.load
+  i32.const -8
+  i32.and
+  i32.const 8
+  i32.sub
+  i32.const 3
+  i32.shr_u)
+
+;; a list of `length` items, which are 0 until $rt.init_item fills them in.
+(func $rt.new_list (param $length i32) (result i64)
+  (local $list i64)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  i32.const 16
+  call $rt.alloc
+  i64.extend_i32_u
+  i64.const 0xfffd_000000000000
+  i64.or
+  call $rt.root
+  tee_local $list
+  i32.wrap_i64
+  get_local $length
+  i32.store
+  get_local $list
+  i32.wrap_i64
+  get_local $length
+  call $rt.new_array
+  i64.store offset=8
+  get_local $top
+  set_global $shadow.top
+  get_local $list)
+
+;; a list or tuple of `length` items, of the same kind as `sequence`.
+(func $rt.new_sequence (param $sequence i64) (param $length i32) (result i64)
+  get_local $length
+  call $rt.new_list
+  get_local $sequence
+  call $rt.is_tuple
+  i64.extend_i32_u
+  i64.const 49
+  i64.shl
+  i64.or)
+
+;; a tuple is made as a list, and then given its own tag.
+(func $rt.new_tuple (param $length i32) (result i64)
+  get_local $length
+  call $rt.new_list
+  i64.const 0x0002_000000000000
+  i64.or)
+
+(func $rt.init_item (param $list i64) (param $i i32) (param $value i64) (result i64)
+  get_local $list
+  call $rt.elements
+  get_local $i
+  i32.const 3
+  i32.shl
+  i32.add
+  get_local $value
+  i64.store
+  get_local $list)
+
+;; the address of the items of a sequence that is being unpacked into `n`
+;; targets, which is a ValueError unless it has exactly that many. a str
+;; is unpacked into its bytes, and a dict into its keys.
+(func $rt.unpack (param $sequence i64) (param $n i32) (result i32)
+  (local $length i32)
+  get_local $sequence
+  call $rt.is_str
+  if
+    get_local $sequence
+    call $rt.chars
+    set_local $sequence
+  end
+  get_local $sequence
+  call $rt.is_dict
+  if
+    get_local $sequence
+    call $rt.keys
+    set_local $sequence
+  end
+  get_local $sequence
+  call $rt.is_sequence
+  i32.eqz
+  if
+    get_local $sequence
+    call $rt.is_old_style
+    if
+      i32.const 48571
+      i32.const 27
+      call $rt.say
+    else
+      i32.const 47648
+      i32.const 1
+      call $rt.say
+      get_local $sequence
+      i32.const 0
+      call $rt.say_type
+      i32.const 47943
+      i32.const 8
+      call $rt.say
+      i32.const 48197
+      i32.const 16
+      call $rt.say
+    end
+    i32.const 2
+    call $rt.raise_said
+  end
+  get_local $sequence
+  call $rt.len
+  tee_local $length
+  get_local $n
+  i32.gt_u
+  if
+    i32.const 8
+    i32.const 48531
+    i32.const 25
+    call $rt.raise_message
+  end
+  get_local $length
+  get_local $n
+  i32.lt_u
+  if
+    i32.const 48556
+    i32.const 15
+    call $rt.say
+    get_local $length
+    call $rt.say_digits
+    i32.const 48539
+    i32.const 6
+    call $rt.say
+    i32.const 48545
+    get_local $length
+    i32.const 1
+    i32.ne
+    call $rt.say
+    i32.const 8
+    i32.const 48546
+    i32.const 10
+    call $rt.raise_message
+  end
+  get_local $sequence
+  call $rt.elements)
+
+;; a new tuple of strs of each byte of the str `s`.
+(func $rt.chars (param $s i64) (result i64)
+  (local $tuple i64)
+  (local $i i32)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $s
+  call $rt.root
+  call $rt.len
+  call $rt.new_tuple
+  call $rt.root
+  set_local $tuple
+  block $done
+    loop $next
+      get_local $i
+      get_local $s
+      call $rt.len
+      i32.ge_u
+      br_if $done
+      get_local $tuple
+      get_local $i
+      get_local $s
+      get_local $i
+      call $rt.int
+      call $rt.getitem
+      call $rt.init_item
+      drop
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $top
+  set_global $shadow.top
+  get_local $tuple)
+
+;; the address of the first item of a list or tuple.
+(func $rt.elements (param $list i64) (result i32)
+  get_local $list
+  i32.wrap_i64
+  i64.load offset=8
+  i32.wrap_i64)
+
+(func $rt.copy (param $from i32) (param $to i32) (param $count i32)
+  block $done
+    loop $next
+      get_local $count
+      i32.eqz
+      br_if $done
+      get_local $to
+      get_local $from
+      i64.load
+      i64.store
+      get_local $from
+      i32.const 8
+      i32.add
+      set_local $from
+      get_local $to
+      i32.const 8
+      i32.add
+      set_local $to
+      get_local $count
+      i32.const 1
+      i32.sub
+      set_local $count
+      br $next
+    end
+  end)
+
+(func $rt.len (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0xfffc
+  i32.lt_u
+  if
+    get_local $value
+    i32.const 48003
+    i32.const 7
+    call $rt.lacks
+    i32.const 47846
+    i32.const 16
+    call $rt.say
+    get_local $value
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    i32.const 47862
+    i32.const 14
+    call $rt.raise_message
+  end
+  get_local $value
+  i32.wrap_i64
+  i32.load)
+
+;; the position in `sequence` that `index` refers to, counting back from
+;; the end if it is negative. python words it differently when it is out
+;; of range of a list that an item is being assigned or deleted at, which
+;; `assigning` is 1 for.
+(func $rt.position (param $index i64) (param $sequence i64) (param $assigning i32) (result i32)
+  (local $i i32)
+  (local $length i32)
+  get_local $sequence
+  call $rt.len
+  set_local $length
+  get_local $index
+  call $rt.is_integral
+  i32.eqz
+  if
+    get_local $sequence
+    call $rt.say_sequence
+    i32.const 47912
+    i32.const 31
+    call $rt.say
+    get_local $index
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    call $rt.raise_said
+  end
+  get_local $index
+  call $rt.index
+  tee_local $i
+  i32.const 0
+  i32.lt_s
+  if
+    get_local $i
+    get_local $length
+    i32.add
+    set_local $i
+  end
+  ;; what is still negative is out of range too, as an unsigned number.
+  get_local $i
+  get_local $length
+  i32.ge_u
+  if
+    get_local $sequence
+    call $rt.say_sequence
+    get_local $assigning
+    if
+      i32.const 47901
+      i32.const 11
+      call $rt.say
+    end
+    i32.const 5
+    i32.const 47882
+    i32.const 19
+    call $rt.raise_message
+  end
+  get_local $i)
+
+;; says what python calls the type of `sequence` when it talks about its
+;; indices, which is a string for a str.
+(func $rt.say_sequence (param $sequence i64)
+  get_local $sequence
+  call $rt.is_str
+  if
+    i32.const 47876
+    i32.const 6
+    call $rt.say
+  else
+    get_local $sequence
+    i32.const 0
+    call $rt.say_type
+  end)
+
+;; the address of the item of a list or tuple at `index`, which is being
+;; assigned or deleted if `assigning` is 1.
+(func $rt.item (param $list i64) (param $index i64) (param $assigning i32) (result i32)
+  get_local $list
+  call $rt.elements
+  get_local $index
+  get_local $list
+  get_local $assigning
+  call $rt.position
+  i32.const 3
+  i32.shl
+  i32.add)
+
+;; the item of an exception is the item of its args.
+(func $rt.getitem (param $object i64) (param $index i64) (result i64)
+  (local $entry i32)
+  (local $byte i32)
+  (local $s i64)
+  get_local $object
+  call $rt.is_dict
+  if
+    get_local $object
+    get_local $index
+    call $rt.find
+    tee_local $entry
+    i32.eqz
+    if
+      get_local $index
+      call $rt.key_error
+    end
+    get_local $entry
+    i64.load offset=8
+    return
+  end
+  ;; an item of a str is a str of one byte.
+  get_local $object
+  call $rt.is_str
+  if
+    get_local $object
+    call $rt.bytes
+    get_local $index
+    get_local $object
+    i32.const 0
+    call $rt.position
+    i32.add
+    i32.load8_u
+    set_local $byte
+    i32.const 1
+    call $rt.new_str
+    tee_local $s
+    call $rt.bytes
+    get_local $byte
+    i32.store8
+    get_local $s
+    return
+  end
+  get_local $object
+  call $rt.is_exception
+  if
+    get_local $object
+    i32.wrap_i64
+    i64.load
+    set_local $object
+  end
+  get_local $object
+  call $rt.is_sequence
+  i32.eqz
+  if
+    get_local $object
+    i32.const 47970
+    i32.const 11
+    call $rt.lacks
+    get_local $object
+    call $rt.say_object
+    get_local $object
+    call $rt.is_instance
+    if
+      i32.const 48042
+      i32.const 18
+      call $rt.say
+      i32.const 48077
+      i32.const 8
+      call $rt.say
+    else
+      i32.const 47951
+      i32.const 19
+      call $rt.say
+      i32.const 47970
+      i32.const 11
+      call $rt.say
+      i32.const 47648
+      i32.const 1
+      call $rt.say
+    end
+    i32.const 2
+    call $rt.raise_said
+  end
+  get_local $object
+  get_local $index
+  i32.const 0
+  call $rt.item
+  i64.load)
+
+;; tuples and strs can't be changed.
+(func $rt.setitem (param $value i64) (param $object i64) (param $index i64)
+  get_local $object
+  call $rt.is_dict
+  if
+    get_local $object
+    get_local $index
+    get_local $value
+    call $rt.dict_set
+    return
+  end
+  get_local $object
+  call $rt.is_list
+  i32.eqz
+  if
+    get_local $object
+    i32.const 47981
+    i32.const 11
+    call $rt.lacks
+    get_local $object
+    call $rt.say_object
+    i32.const 48042
+    i32.const 18
+    call $rt.say
+    i32.const 2
+    i32.const 48085
+    i32.const 15
+    call $rt.raise_message
+  end
+  get_local $object
+  get_local $index
+  i32.const 1
+  call $rt.item
+  get_local $value
+  i64.store)
+
+(func $rt.delitem (param $object i64) (param $index i64)
+  (local $entry i32)
+  (local $length i32)
+  get_local $object
+  call $rt.is_dict
+  if
+    get_local $object
+    get_local $index
+    call $rt.find
+    tee_local $entry
+    i32.eqz
+    if
+      get_local $index
+      call $rt.key_error
+    end
+    get_local $entry
+    get_global $dict.dummy
+    i64.store
+    get_local $entry
+    i64.const 0
+    i64.store offset=8
+    get_local $object
+    i32.wrap_i64
+    get_local $object
+    call $rt.len
+    i32.const 1
+    i32.sub
+    i32.store
+    return
+  end
+  get_local $object
+  call $rt.is_list
+  i32.eqz
+  if
+    get_local $object
+    i32.const 47992
+    i32.const 11
+    call $rt.lacks
+    get_local $object
+    call $rt.say_object
+    ;; python words it differently for some types.
+    i32.const 48060
+    i32.const 48042
+    get_local $object
+    call $rt.is_tuple
+    get_local $object
+    call $rt.is_str
+    i32.or
+    get_local $object
+    call $rt.is_instance
+    i32.or
+    tee_local $length
+    select
+    i32.const 18
+    get_local $length
+    i32.sub
+    call $rt.say
+    i32.const 2
+    i32.const 48100
+    i32.const 13
+    call $rt.raise_message
+  end
+  ;; the items after it move down one place.
+  get_local $object
+  get_local $index
+  i32.const 1
+  call $rt.item
+  tee_local $entry
+  i32.const 8
+  i32.add
+  get_local $entry
+  get_local $object
+  call $rt.elements
+  get_local $object
+  call $rt.len
+  tee_local $length
+  i32.const 3
+  i32.shl
+  i32.add
+  get_local $entry
+  i32.sub
+  i32.const 3
+  i32.shr_u
+  i32.const 1
+  i32.sub
+  call $rt.copy
+  get_local $object
+  i32.wrap_i64
+  get_local $length
+  i32.const 1
+  i32.sub
+  tee_local $length
+  i32.store
+  get_local $object
+  call $rt.elements
+  get_local $length
+  i32.const 3
+  i32.shl
+  i32.add
+  i64.const 0
+  i64.store)
+
+;; whether `item` is a key of a dict, or an item of a list or tuple.
+(func $rt.contains (param $item i64) (param $container i64) (result i32)
+  (local $i i32)
+  get_local $container
+  call $rt.is_dict
+  if
+    get_local $container
+    get_local $item
+    call $rt.find
+    i32.const 0
+    i32.ne
+    return
+  end
+  get_local $container
+  call $rt.is_str
+  if
+    get_local $item
+    get_local $container
+    call $rt.contains_str
+    return
+  end
+  get_local $container
+  call $rt.is_sequence
+  i32.eqz
+  if
+    i32.const 48179
+    i32.const 17
+    call $rt.say
+    get_local $container
+    i32.const 1
+    call $rt.say_type
+    i32.const 2
+    i32.const 48197
+    i32.const 16
+    call $rt.raise_message
+  end
+  block $done
+    loop $next
+      get_local $i
+      get_local $container
+      call $rt.len
+      i32.ge_u
+      br_if $done
+      get_local $item
+      get_local $container
+      call $rt.elements
+      get_local $i
+      i32.const 3
+      i32.shl
+      i32.add
+      i64.load
+      call $rt.compare
+      i32.eqz
+      if
+        i32.const 1
+        return
+      end
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  i32.const 0)
+
+;; whether `part` appears anywhere in the str `s`. only a str can be looked
+;; for in one.
+(func $rt.contains_str (param $part i64) (param $s i64) (result i32)
+  (local $i i32)
+  (local $j i32)
+  get_local $part
+  call $rt.is_str
+  i32.eqz
+  if
+    i32.const 48213
+    i32.const 51
+    call $rt.say
+    get_local $part
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    call $rt.raise_said
+  end
+  block $done
+    loop $next
+      get_local $i
+      get_local $part
+      call $rt.len
+      i32.add
+      get_local $s
+      call $rt.len
+      i32.gt_u
+      br_if $done
+      i32.const 0
+      set_local $j
+      block $mismatch
+        loop $compare
+          get_local $j
+          get_local $part
+          call $rt.len
+          i32.eq
+          if
+            i32.const 1
+            return
+          end
+          get_local $s
+          call $rt.bytes
+          get_local $i
+          i32.add
+          get_local $j
+          i32.add
+          i32.load8_u
+          get_local $part
+          call $rt.bytes
+          get_local $j
+          i32.add
+          i32.load8_u
+          i32.ne
+          br_if $mismatch
+          get_local $j
+          i32.const 1
+          i32.add
+          set_local $j
+          br $compare
+        end
+      end
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  i32.const 0)
+
+;; where a slice of a sequence of `length` items starts or stops. None
+;; gives `default`, and anything past either end is clamped to it.
+(func $rt.bound (param $value i64) (param $default i32) (param $length i32) (result i32)
+  (local $i i32)
+  get_local $value
+  i64.const 0xfffb_000000000000
+  i64.eq
+  if
+    get_local $default
+    return
+  end
+  get_local $value
+  call $rt.is_integral
+  i32.eqz
+  if
+    i32.const 2
+    i32.const 48113
+    i32.const 66
+    call $rt.raise_message
+  end
+  get_local $value
+  call $rt.index
+  tee_local $i
+  i32.const 0
+  i32.lt_s
+  if
+    get_local $i
+    get_local $length
+    i32.add
+    tee_local $i
+    i32.const 0
+    i32.lt_s
+    if
+      i32.const 0
+      set_local $i
+    end
+  end
+  get_local $i
+  get_local $length
+  i32.gt_s
+  if (result i32)
+    get_local $length
+  else
+    get_local $i
+  end)
+
+;; a new list, tuple or str of the items of `object` from `lower` up to
+;; `upper`.
+(func $rt.getslice (param $object i64) (param $lower i64) (param $upper i64) (result i64)
+  (local $start i32)
+  (local $stop i32)
+  (local $slice i64)
+  (local $top i32)
+  get_local $object
+  call $rt.is_sequence
+  get_local $object
+  call $rt.is_str
+  i32.or
+  i32.eqz
+  if
+    get_local $object
+    i32.const 47970
+    i32.const 11
+    call $rt.lacks
+    get_local $object
+    call $rt.say_object
+    i32.const 47951
+    i32.const 19
+    call $rt.say
+    i32.const 47970
+    i32.const 11
+    call $rt.say
+    i32.const 2
+    i32.const 47648
+    i32.const 1
+    call $rt.raise_message
+  end
+  get_local $lower
+  i32.const 0
+  get_local $object
+  call $rt.len
+  call $rt.bound
+  set_local $start
+  get_local $upper
+  get_local $object
+  call $rt.len
+  get_local $object
+  call $rt.len
+  call $rt.bound
+  set_local $stop
+  get_local $stop
+  get_local $start
+  i32.lt_s
+  if
+    get_local $start
+    set_local $stop
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $object
+  call $rt.root
+  drop
+  get_local $object
+  call $rt.is_str
+  if
+    get_local $stop
+    get_local $start
+    i32.sub
+    call $rt.new_str
+    set_local $slice
+    get_local $object
+    call $rt.bytes
+    get_local $start
+    i32.add
+    get_local $slice
+    call $rt.bytes
+    get_local $stop
+    get_local $start
+    i32.sub
+    call $rt.copy_bytes
+    get_local $top
+    set_global $shadow.top
+    get_local $slice
+    return
+  end
+  get_local $object
+  get_local $stop
+  get_local $start
+  i32.sub
+  call $rt.new_sequence
+  set_local $slice
+  get_local $object
+  call $rt.elements
+  get_local $start
+  i32.const 3
+  i32.shl
+  i32.add
+  get_local $slice
+  call $rt.elements
+  get_local $stop
+  get_local $start
+  i32.sub
+  call $rt.copy
+  get_local $top
+  set_global $shadow.top
+  get_local $slice)
+
+;; a new list or tuple of the items of `a` followed by those of `b`.
+(func $rt.concat (param $a i64) (param $b i64) (result i64)
+  (local $list i64)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  drop
+  get_local $b
+  call $rt.root
+  drop
+  get_local $a
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.add
+  call $rt.new_sequence
+  set_local $list
+  get_local $a
+  call $rt.elements
+  get_local $list
+  call $rt.elements
+  get_local $a
+  call $rt.len
+  call $rt.copy
+  get_local $b
+  call $rt.elements
+  get_local $list
+  call $rt.elements
+  get_local $a
+  call $rt.len
+  i32.const 3
+  i32.shl
+  i32.add
+  get_local $b
+  call $rt.len
+  call $rt.copy
+  get_local $top
+  set_global $shadow.top
+  get_local $list)
+
+;; adds `value` to the end of `list`, making its array twice as big when
+;; it is full.
+(func $rt.append (param $list i64) (param $value i64) (result i64)
+  (local $length i32)
+  (local $array i64)
+  (local $top i32)
+  get_local $list
+  call $rt.is_list
+  i32.eqz
+  if
+    get_local $list
+    i32.const 48617
+    i32.const 6
+    call $rt.attribute_error
+  end
+  get_local $list
+  call $rt.len
+  tee_local $length
+  get_local $list
+  i32.wrap_i64
+  i64.load offset=8
+  call $rt.capacity
+  i32.eq
+  if
+    get_global $shadow.top
+    set_local $top
+    get_local $list
+    call $rt.root
+    drop
+    get_local $value
+    call $rt.root
+    drop
+    get_local $length
+    i32.const 1
+    i32.shl
+    call $rt.new_array
+    set_local $array
+    get_local $list
+    call $rt.elements
+    get_local $array
+    i32.wrap_i64
+    get_local $length
+    call $rt.copy
+    get_local $list
+    i32.wrap_i64
+    get_local $array
+    i64.store offset=8
+    get_local $top
+    set_global $shadow.top
+  end
+  get_local $list
+  call $rt.elements
+  get_local $length
+  i32.const 3
+  i32.shl
+  i32.add
+  get_local $value
+  i64.store
+  get_local $list
+  i32.wrap_i64
+  get_local $length
+  i32.const 1
+  i32.add
+  i32.store
+  i64.const 0xfffb_000000000000)
+
+;; `a += b` extends a list in place with the items of a list or tuple, and
+;; is the same as `a + b` for anything else.
+(func $rt.iadd (param $a i64) (param $b i64) (result i64)
+  (local $i i32)
+  (local $n i32)
+  (local $top i32)
+  get_local $a
+  call $rt.is_list
+  get_local $b
+  call $rt.is_sequence
+  i32.and
+  i32.eqz
+  if
+    get_local $a
+    get_local $b
+    call $rt.add
+    return
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $b
+  call $rt.root
+  drop
+  ;; the length is taken first, in case b is a.
+  get_local $b
+  call $rt.len
+  set_local $n
+  block $done
+    loop $next
+      get_local $i
+      get_local $n
+      i32.eq
+      br_if $done
+      get_local $a
+      get_local $b
+      call $rt.elements
+      get_local $i
+      i32.const 3
+      i32.shl
+      i32.add
+      i64.load
+      call $rt.append
+      drop
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $top
+  set_global $shadow.top
+  get_local $a)
+
+;; a str of `length` bytes, which are 0 until $rt.init_bytes fills them in.
+(func $rt.new_str (param $length i32) (result i64)
+  (local $address i32)
+  get_local $length
+  i32.const 0x7ffffff0
+  i32.ge_u
+  if
+    call $rt.out_of_memory
+  end
+  get_local $length
+  i32.const 8
+  i32.add
+  call $rt.alloc_bytes
+  tee_local $address
+  get_local $length
+  i32.store
+  get_local $address
+  i64.extend_i32_u
+  i64.const 0xfffe_000000000000
+  i64.or)
+
+;; stores the 8 bytes of `bytes` in `s` from `offset`, a multiple of 8. the
+;; limbs of a long are filled in the same way.
+(func $rt.init_bytes (param $s i64) (param $offset i32) (param $bytes i64) (result i64)
+  get_local $s
+  i32.wrap_i64
+  get_local $offset
+  i32.add
+  get_local $bytes
+  i64.store offset=8
+  get_local $s)
+
+;; the address of the first byte of a str.
+(func $rt.bytes (param $s i64) (result i32)
+  get_local $s
+  i32.wrap_i64
+  i32.const 8
+  i32.add)
+
+(func $rt.copy_bytes (param $from i32) (param $to i32) (param $count i32)
+  block $done
+    loop $next
+      get_local $count
+      i32.eqz
+      br_if $done
+      get_local $to
+      get_local $from
+      i32.load8_u
+      i32.store8
+      get_local $from
+      i32.const 1
+      i32.add
+      set_local $from
+      get_local $to
+      i32.const 1
+      i32.add
+      set_local $to
+      get_local $count
+      i32.const 1
+      i32.sub
+      set_local $count
+      br $next
+    end
+  end)
+
+(func $rt.concat_str (param $a i64) (param $b i64) (result i64)
+  (local $s i64)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $a
+  call $rt.root
+  drop
+  get_local $b
+  call $rt.root
+  drop
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.add
+  call $rt.new_str
+  set_local $s
+  get_local $a
+  call $rt.bytes
+  get_local $s
+  call $rt.bytes
+  get_local $a
+  call $rt.len
+  call $rt.copy_bytes
+  get_local $b
+  call $rt.bytes
+  get_local $s
+  call $rt.bytes
+  get_local $a
+  call $rt.len
+  i32.add
+  get_local $b
+  call $rt.len
+  call $rt.copy_bytes
+  get_local $top
+  set_global $shadow.top
+  get_local $s)
+
+;; strs compare byte by byte, and then by length.
+(func $rt.compare_strs (param $a i64) (param $b i64) (result i32)
+  (local $i i32)
+  (local $x i32)
+  (local $y i32)
+  block $done
+    loop $next
+      get_local $i
+      get_local $a
+      call $rt.len
+      i32.ge_u
+      get_local $i
+      get_local $b
+      call $rt.len
+      i32.ge_u
+      i32.or
+      br_if $done
+      get_local $a
+      call $rt.bytes
+      get_local $i
+      i32.add
+      i32.load8_u
+      tee_local $x
+      get_local $b
+      call $rt.bytes
+      get_local $i
+      i32.add
+      i32.load8_u
+      tee_local $y
+      i32.ne
+      if
+        get_local $x
+        get_local $y
+        i32.gt_u
+        get_local $x
+        get_local $y
+        i32.lt_u
+        i32.sub
+        return
+      end
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.gt_u
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.lt_u
+  i32.sub)
+
+;; python 2's hash of a key, so that keys that are equal hash the same.
+;; lists and dicts can't be keys.
+(func $rt.hash (param $value i64) (result i64)
+  (local $h i64)
+  block $hashed
+    get_local $value
+    call $rt.is_int
+    if
+      get_local $value
+      i32.wrap_i64
+      i64.extend_i32_s
+      set_local $h
+      br $hashed
+    end
+    get_local $value
+    call $rt.is_long
+    if
+      get_local $value
+      call $rt.hash_long
+      set_local $h
+      br $hashed
+    end
+    get_local $value
+    call $rt.is_float
+    if
+      get_local $value
+      f64.reinterpret_i64
+      call $rt.hash_float
+      set_local $h
+      br $hashed
+    end
+    get_local $value
+    call $rt.is_str
+    if
+      get_local $value
+      call $rt.hash_str
+      set_local $h
+      br $hashed
+    end
+    get_local $value
+    call $rt.is_tuple
+    if
+      get_local $value
+      call $rt.hash_tuple
+      set_local $h
+      br $hashed
+    end
+    ;; None hashes by its bits, which are all there is to it, and an
+    ;; exception or an object by its address.
+    get_local $value
+    call $rt.tag
+    i32.const 0xfffb
+    i32.ne
+    get_local $value
+    call $rt.is_exception
+    i32.eqz
+    i32.and
+    get_local $value
+    call $rt.is_object
+    i32.eqz
+    i32.and
+    if
+      i32.const 48264
+      i32.const 17
+      call $rt.say
+      get_local $value
+      i32.const 1
+      call $rt.say_type
+      i32.const 2
+      call $rt.raise_said
+    end
+    get_local $value
+    set_local $h
+  end
+  ;; -1 is never a hash.
+  get_local $h
+  i64.const -2
+  get_local $h
+  i64.const -1
+  i64.ne
+  select)
+
+;; a whole number hashes like the int or long it equals. otherwise,
+;; python 2 takes the float apart into a mantissa in [0.5, 1) and an
+;; exponent, and mixes the top 62 bits of the mantissa with the exponent.
+(func $rt.hash_float (param $f f64) (result i64)
+  (local $bits i64)
+  (local $exponent i64)
+  (local $hi i64)
+  (local $m f64)
+  get_local $f
+  f64.trunc
+  get_local $f
+  f64.eq
+  get_local $f
+  f64.abs
+  f64.const 0x1p63
+  f64.lt
+  i32.and
+  if
+    get_local $f
+    i64.trunc_f64_s
+    return
+  end
+  get_local $f
+  f64.trunc
+  get_local $f
+  f64.eq
+  get_local $f
+  f64.abs
+  f64.const inf
+  f64.lt
+  i32.and
+  if
+    ;; the mantissa as a whole number, rotated by the exponent, is the
+    ;; value mod 2^64 - 1, like the hash of a long.
+    i64.const 0
+    get_local $f
+    i64.reinterpret_f64
+    tee_local $bits
+    i64.const 0x000fffffffffffff
+    i64.and
+    i64.const 0x0010000000000000
+    i64.or
+    get_local $bits
+    i64.const 52
+    i64.shr_u
+    i64.const 0x7ff
+    i64.and
+    i64.const 1075
+    i64.sub
+    i64.rotl
+    tee_local $hi
+    i64.sub
+    get_local $hi
+    get_local $f
+    f64.const 0
+    f64.lt
+    select
+    return
+  end
+  get_local $f
+  i64.reinterpret_f64
+  tee_local $bits
+  i64.const 52
+  i64.shr_u
+  i64.const 0x7ff
+  i64.and
+  tee_local $exponent
+  i64.eqz
+  if
+    ;; subnormals are scaled up into the normal range first.
+    get_local $f
+    f64.const 0x1p54
+    f64.mul
+    i64.reinterpret_f64
+    tee_local $bits
+    i64.const 52
+    i64.shr_u
+    i64.const 0x7ff
+    i64.and
+    i64.const 54
+    i64.sub
+    set_local $exponent
+  end
+  get_local $bits
+  i64.const 0x800fffffffffffff
+  i64.and
+  i64.const 0x3fe0000000000000
+  i64.or
+  f64.reinterpret_i64
+  f64.const 0x1p31
+  f64.mul
+  tee_local $m
+  i64.trunc_f64_s
+  set_local $hi
+  get_local $hi
+  get_local $m
+  get_local $hi
+  f64.convert_i64_s
+  f64.sub
+  f64.const 0x1p31
+  f64.mul
+  i64.trunc_f64_s
+  i64.add
+  get_local $exponent
+  i64.const 1022
+  i64.sub
+  i64.const 15
+  i64.shl
+  i64.add)
+
+;; python 2 hashes a long by adding its digits from the top, rotating the
+;; sum by a digit's width each time and adding its carry back in. that is
+;; its magnitude mod 2^64 - 1, whatever the width of a digit.
+(func $rt.hash_long (param $long i64) (result i64)
+  (local $i i32)
+  (local $x i64)
+  (local $limb i64)
+  get_local $long
+  call $rt.size
+  set_local $i
+  block $done
+    loop $next
+      get_local $i
+      i32.eqz
+      br_if $done
+      get_local $x
+      i64.const 32
+      i64.rotl
+      get_local $long
+      get_local $i
+      i32.const 1
+      i32.sub
+      tee_local $i
+      call $rt.limb
+      i64.extend_i32_u
+      tee_local $limb
+      i64.add
+      tee_local $x
+      get_local $limb
+      i64.lt_u
+      i64.extend_i32_u
+      get_local $x
+      i64.add
+      set_local $x
+      br $next
+    end
+  end
+  i64.const 0
+  get_local $x
+  i64.sub
+  get_local $x
+  get_local $long
+  call $rt.negative
+  select)
+
+(func $rt.hash_str (param $s i64) (result i64)
+  (local $address i32)
+  (local $end i32)
+  (local $x i64)
+  get_local $s
+  call $rt.bytes
+  tee_local $address
+  get_local $s
+  call $rt.len
+  i32.add
+  set_local $end
+  ;; an empty str starts from 0.
+  get_local $address
+  i64.load8_u
+  i64.const 7
+  i64.shl
+  i64.const 0
+  get_local $s
+  call $rt.len
+  select
+  set_local $x
+  block $done
+    loop $next
+      get_local $address
+      get_local $end
+      i32.ge_u
+      br_if $done
+      get_local $x
+      i64.const 1000003
+      i64.mul
+      get_local $address
+      i64.load8_u
+      i64.xor
+      set_local $x
+      get_local $address
+      i32.const 1
+      i32.add
+      set_local $address
+      br $next
+    end
+  end
+  get_local $x
+  get_local $s
+  call $rt.len
+  i64.extend_i32_u
+  i64.xor)
+
+(func $rt.hash_tuple (param $tuple i64) (result i64)
+  (local $x i64)
+  (local $mult i64)
+  (local $n i32)
+  (local $address i32)
+  i64.const 0x345678
+  set_local $x
+  i64.const 1000003
+  set_local $mult
+  get_local $tuple
+  call $rt.len
+  set_local $n
+  get_local $tuple
+  call $rt.elements
+  set_local $address
+  block $done
+    loop $next
+      get_local $n
+      i32.eqz
+      br_if $done
+      get_local $n
+      i32.const 1
+      i32.sub
+      set_local $n
+      get_local $x
+      get_local $address
+      i64.load
+      call $rt.hash
+      i64.xor
+      get_local $mult
+      i64.mul
+      set_local $x
+      get_local $mult
+      get_local $n
+      i32.const 1
+      i32.shl
+      i64.extend_i32_u
+      i64.const 82520
+      i64.add
+      i64.add
+      set_local $mult
+      get_local $address
+      i32.const 8
+      i32.add
+      set_local $address
+      br $next
+    end
+  end
+  get_local $x
+  i64.const 97531
+  i64.add)
+
+(func $rt.new_dict (result i64)
+  (local $dict i64)
+  (local $top i32)
+  get_global $shadow.top
+  set_local $top
+  i32.const 32
+  call $rt.alloc
+  i64.extend_i32_u
+  i64.const 0xfffc_000000000000
+  i64.or
+  call $rt.root
+  tee_local $dict
+  i32.const 8
+  call $rt.empty_table
+  get_local $top
+  set_global $shadow.top
+  get_local $dict)
+
+;; puts a key and value from a dict display into the new dict.
+(func $rt.init_entry (param $dict i64) (param $value i64) (param $key i64) (result i64)
+  get_local $dict
+  get_local $key
+  get_local $value
+  call $rt.dict_set
+  get_local $dict)
+
+;; gives `dict`, which has to be rooted, a table of `size` empty slots.
+(func $rt.empty_table (param $dict i64) (param $size i32)
+  (local $slot i32)
+  get_local $dict
+  i32.wrap_i64
+  get_local $size
+  i32.const 1
+  i32.shl
+  call $rt.new_array
+  i64.store offset=8
+  get_local $dict
+  i32.wrap_i64
+  get_local $size
+  i32.const 3
+  i32.shl
+  call $rt.alloc_bytes
+  i64.extend_i32_u
+  i64.const 0x7ffc_000000000000
+  i64.or
+  i64.store offset=16
+  get_local $dict
+  i32.wrap_i64
+  get_local $size
+  i32.const 1
+  i32.sub
+  i64.extend_i32_u
+  i64.store offset=24
+  get_local $dict
+  i32.wrap_i64
+  i64.const 0
+  i64.store
+  block $done
+    loop $next
+      get_local $slot
+      get_local $size
+      i32.ge_u
+      br_if $done
+      get_local $dict
+      get_local $slot
+      call $rt.entry
+      get_global $dict.empty
+      i64.store
+      get_local $slot
+      i32.const 1
+      i32.add
+      set_local $slot
+      br $next
+    end
+  end)
+
+;; the address of the key in `slot` of `dict`, which its value follows.
+(func $rt.entry (param $dict i64) (param $slot i32) (result i32)
+  get_local $dict
+  i32.wrap_i64
+  i64.load offset=8
+  i32.wrap_i64
+  get_local $slot
+  i32.const 4
+  i32.shl
+  i32.add)
+
+;; the address of the hash of the key in `slot` of `dict`.
+(func $rt.slot_hash (param $dict i64) (param $slot i32) (result i32)
+  get_local $dict
+  i32.wrap_i64
+  i64.load offset=16
+  i32.wrap_i64
+  get_local $slot
+  i32.const 3
+  i32.shl
+  i32.add)
+
+(func $rt.mask (param $dict i64) (result i64)
+  get_local $dict
+  i32.wrap_i64
+  i64.load offset=24)
+
+;; whether a slot whose key is `key` holds an entry, rather than being
+;; empty or deleted.
+(func $rt.active (param $key i64) (result i32)
+  get_local $key
+  get_global $dict.empty
+  i64.xor
+  i64.const 1
+  i64.gt_u)
+
+;; the slot of `dict` that holds `key`, or failing that the one it should
+;; go in: the first deleted slot on its way to an empty one.
+(func $rt.lookup (param $dict i64) (param $key i64) (param $hash i64) (result i32)
+  (local $i i64)
+  (local $perturb i64)
+  (local $slot i32)
+  (local $free i32)
+  (local $k i64)
+  get_local $hash
+  tee_local $perturb
+  get_local $dict
+  call $rt.mask
+  i64.and
+  tee_local $i
+  i32.wrap_i64
+  set_local $slot
+  i32.const -1
+  set_local $free
+  loop $probe
+    get_local $dict
+    get_local $slot
+    call $rt.entry
+    i64.load
+    tee_local $k
+    get_global $dict.empty
+    i64.eq
+    if
+      get_local $free
+      get_local $slot
+      get_local $free
+      i32.const -1
+      i32.ne
+      select
+      return
+    end
+    get_local $k
+    get_local $key
+    i64.eq
+    if
+      get_local $slot
+      return
+    end
+    get_local $k
+    get_global $dict.dummy
+    i64.eq
+    if
+      get_local $free
+      i32.const -1
+      i32.eq
+      if
+        get_local $slot
+        set_local $free
+      end
+    else
+      get_local $dict
+      get_local $slot
+      call $rt.slot_hash
+      i64.load
+      get_local $hash
+      i64.eq
+      if
+        get_local $k
+        get_local $key
+        call $rt.compare
+        i32.eqz
+        if
+          get_local $slot
+          return
+        end
+      end
+    end
+    ;; the next slot mixes in 5 more of the hash's high bits each time.
+    get_local $i
+    i64.const 5
+    i64.mul
+    get_local $perturb
+    i64.add
+    i64.const 1
+    i64.add
+    tee_local $i
+    get_local $dict
+    call $rt.mask
+    i64.and
+    i32.wrap_i64
+    set_local $slot
+    get_local $perturb
+    i64.const 5
+    i64.shr_u
+    set_local $perturb
+    br $probe
+  end
+  unreachable)
+
+;; the address of the entry for `key` in `dict`, or 0 if it has none.
+(func $rt.find (param $dict i64) (param $key i64) (result i32)
+  (local $entry i32)
+  get_local $dict
+  get_local $dict
+  get_local $key
+  get_local $key
+  call $rt.hash
+  call $rt.lookup
+  call $rt.entry
+  tee_local $entry
+  i32.const 0
+  get_local $entry
+  i64.load
+  call $rt.active
+  select)
+
+;; puts `value` under `key` in `dict`. the table gets bigger once two
+;; thirds of its slots have been used.
+(func $rt.dict_set (param $dict i64) (param $key i64) (param $value i64)
+  (local $hash i64)
+  (local $slot i32)
+  (local $entry i32)
+  (local $used i32)
+  get_local $key
+  call $rt.hash
+  set_local $hash
+  get_local $dict
+  get_local $dict
+  get_local $key
+  get_local $hash
+  call $rt.lookup
+  tee_local $slot
+  call $rt.entry
+  tee_local $entry
+  i64.load
+  call $rt.active
+  if
+    get_local $entry
+    get_local $value
+    i64.store offset=8
+    return
+  end
+  get_local $entry
+  i64.load
+  get_global $dict.empty
+  i64.eq
+  if
+    get_local $dict
+    i32.wrap_i64
+    get_local $dict
+    i32.wrap_i64
+    i32.load offset=4
+    i32.const 1
+    i32.add
+    i32.store offset=4
+  end
+  get_local $entry
+  get_local $key
+  i64.store
+  get_local $entry
+  get_local $value
+  i64.store offset=8
+  get_local $dict
+  get_local $slot
+  call $rt.slot_hash
+  get_local $hash
+  i64.store
+  get_local $dict
+  i32.wrap_i64
+  get_local $dict
+  call $rt.len
+  i32.const 1
+  i32.add
+  tee_local $used
+  i32.store
+  get_local $dict
+  i32.wrap_i64
+  i32.load offset=4
+  i32.const 3
+  i32.mul
+  get_local $dict
+  call $rt.mask
+  i32.wrap_i64
+  i32.const 1
+  i32.add
+  i32.const 1
+  i32.shl
+  i32.ge_u
+  if
+    get_local $dict
+    get_local $used
+    i32.const 2
+    i32.shl
+    get_local $used
+    i32.const 1
+    i32.shl
+    get_local $used
+    i32.const 50000
+    i32.le_u
+    select
+    call $rt.resize
+  end)
+
+;; moves the entries of `dict` to a new table with more than `used` slots,
+;; leaving the deleted ones behind.
+(func $rt.resize (param $dict i64) (param $used i32)
+  (local $size i32)
+  (local $entries i32)
+  (local $hashes i32)
+  (local $old i32)
+  (local $slot i32)
+  (local $entry i32)
+  (local $hash i64)
+  (local $new i32)
+  (local $count i32)
+  (local $top i32)
+  i32.const 8
+  set_local $size
+  block $done
+    loop $next
+      get_local $size
+      get_local $used
+      i32.gt_u
+      br_if $done
+      get_local $size
+      i32.const 1
+      i32.shl
+      set_local $size
+      br $next
+    end
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $dict
+  call $rt.root
+  i32.wrap_i64
+  i64.load offset=8
+  call $rt.root
+  i32.wrap_i64
+  set_local $entries
+  get_local $dict
+  i32.wrap_i64
+  i64.load offset=16
+  call $rt.root
+  i32.wrap_i64
+  set_local $hashes
+  get_local $dict
+  call $rt.mask
+  i32.wrap_i64
+  i32.const 1
+  i32.add
+  set_local $old
+  get_local $dict
+  get_local $size
+  call $rt.empty_table
+  block $done
+    loop $next
+      get_local $slot
+      get_local $old
+      i32.ge_u
+      br_if $done
+      get_local $entries
+      get_local $slot
+      i32.const 4
+      i32.shl
+      i32.add
+      tee_local $entry
+      i64.load
+      call $rt.active
+      if
+        get_local $dict
+        get_local $dict
+        get_local $entry
+        i64.load
+        get_local $hashes
+        get_local $slot
+        i32.const 3
+        i32.shl
+        i32.add
+        i64.load
+        tee_local $hash
+        call $rt.lookup
+        tee_local $new
+        call $rt.entry
+        get_local $entry
+        i64.load
+        i64.store
+        get_local $dict
+        get_local $new
+        call $rt.entry
+        get_local $entry
+        i64.load offset=8
+        i64.store offset=8
+        get_local $dict
+        get_local $new
+        call $rt.slot_hash
+        get_local $hash
+        i64.store
+        get_local $count
+        i32.const 1
+        i32.add
+        set_local $count
+      end
+      get_local $slot
+      i32.const 1
+      i32.add
+      set_local $slot
+      br $next
+    end
+  end
+  get_local $dict
+  i32.wrap_i64
+  get_local $count
+  i32.store
+  get_local $dict
+  i32.wrap_i64
+  get_local $count
+  i32.store offset=4
+  get_local $top
+  set_global $shadow.top)
+
+;; a new list of the keys of `dict` if `part` is 0, its values if it is
+;; 1, or (key, value) tuples if it is 2, in the order of its slots.
+(func $rt.dict_list (param $dict i64) (param $part i32) (result i64)
+  (local $list i64)
+  (local $slot i32)
+  (local $i i32)
+  (local $entry i32)
+  (local $item i64)
+  (local $top i32)
+  get_local $dict
+  call $rt.is_dict
+  i32.eqz
+  if
+    ;; the method is keys, values or items.
+    get_local $dict
+    i32.const 48623
+    i32.const 48633
+    i32.const 48627
+    get_local $part
+    i32.const 2
+    i32.eq
+    select
+    get_local $part
+    i32.eqz
+    select
+    i32.const 4
+    i32.const 5
+    i32.const 6
+    get_local $part
+    i32.const 2
+    i32.eq
+    select
+    get_local $part
+    i32.eqz
+    select
+    call $rt.attribute_error
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $dict
+  call $rt.root
+  call $rt.len
+  call $rt.new_list
+  call $rt.root
+  set_local $list
+  block $done
+    loop $next
+      get_local $slot
+      i64.extend_i32_u
+      get_local $dict
+      call $rt.mask
+      i64.gt_u
+      br_if $done
+      get_local $dict
+      get_local $slot
+      call $rt.entry
+      tee_local $entry
+      i64.load
+      call $rt.active
+      if
+        get_local $part
+        i32.const 2
+        i32.eq
+        if (result i64)
+          i32.const 2
+          call $rt.new_tuple
+          i32.const 0
+          get_local $entry
+          i64.load
+          call $rt.init_item
+          i32.const 1
+          get_local $entry
+          i64.load offset=8
+          call $rt.init_item
+        else
+          get_local $entry
+          get_local $part
+          i32.const 3
+          i32.shl
+          i32.add
+          i64.load
+        end
+        set_local $item
+        get_local $list
+        get_local $i
+        get_local $item
+        call $rt.init_item
+        drop
+        get_local $i
+        i32.const 1
+        i32.add
+        set_local $i
+      end
+      get_local $slot
+      i32.const 1
+      i32.add
+      set_local $slot
+      br $next
+    end
+  end
+  get_local $top
+  set_global $shadow.top
+  get_local $list)
+
+(func $rt.keys (param $dict i64) (result i64)
+  get_local $dict
+  i32.const 0
+  call $rt.dict_list)
+
+(func $rt.values (param $dict i64) (result i64)
+  get_local $dict
+  i32.const 1
+  call $rt.dict_list)
+
+(func $rt.items (param $dict i64) (result i64)
+  get_local $dict
+  i32.const 2
+  call $rt.dict_list)
+
+(func $rt.get (param $dict i64) (param $key i64) (param $default i64) (result i64)
+  (local $entry i32)
+  get_local $dict
+  call $rt.is_dict
+  i32.eqz
+  if
+    get_local $dict
+    i32.const 48638
+    i32.const 3
+    call $rt.attribute_error
+  end
+  get_local $dict
+  get_local $key
+  call $rt.find
+  tee_local $entry
+  if (result i64)
+    get_local $entry
+    i64.load offset=8
+  else
+    get_local $default
+  end)
+
+;; lists and tuples compare item by item, and then by length.
+(func $rt.compare_sequences (param $a i64) (param $b i64) (result i32)
+  (local $i i32)
+  (local $c i32)
+  block $done
+    loop $next
+      get_local $i
+      get_local $a
+      call $rt.len
+      i32.ge_u
+      get_local $i
+      get_local $b
+      call $rt.len
+      i32.ge_u
+      i32.or
+      br_if $done
+      get_local $a
+      call $rt.elements
+      get_local $i
+      i32.const 3
+      i32.shl
+      i32.add
+      i64.load
+      get_local $b
+      call $rt.elements
+      get_local $i
+      i32.const 3
+      i32.shl
+      i32.add
+      i64.load
+      call $rt.compare
+      tee_local $c
+      if
+        get_local $c
+        return
+      end
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.gt_u
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.lt_u
+  i32.sub)
+
+;; dicts compare by length first. dicts of the same length are equal if
+;; they hold equal values under the same keys, and are otherwise ordered
+;; by where they are in memory.
+(func $rt.compare_dicts (param $a i64) (param $b i64) (result i32)
+  (local $slot i32)
+  (local $entry i32)
+  (local $other i32)
+  get_local $a
+  call $rt.len
+  get_local $b
+  call $rt.len
+  i32.ne
+  if
+    get_local $a
+    call $rt.len
+    get_local $b
+    call $rt.len
+    i32.gt_u
+    get_local $a
+    call $rt.len
+    get_local $b
+    call $rt.len
+    i32.lt_u
+    i32.sub
+    return
+  end
+  block $done
+    loop $next
+      get_local $slot
+      i64.extend_i32_u
+      get_local $a
+      call $rt.mask
+      i64.gt_u
+      br_if $done
+      get_local $a
+      get_local $slot
+      call $rt.entry
+      tee_local $entry
+      i64.load
+      call $rt.active
+      if
+        get_local $b
+        get_local $entry
+        i64.load
+        call $rt.find
+        tee_local $other
+        if (result i32)
+          get_local $entry
+          i64.load offset=8
+          get_local $other
+          i64.load offset=8
+          call $rt.compare
+        else
+          i32.const 1
+        end
+        if
+          get_local $a
+          get_local $b
+          i64.gt_u
+          get_local $a
+          get_local $b
+          i64.lt_u
+          i32.sub
+          return
+        end
+      end
+      get_local $slot
+      i32.const 1
+      i32.add
+      set_local $slot
+      br $next
+    end
+  end
+  i32.const 0)
+
+;; whether `value` is a class of the program's, or an instance of one.
+(func $rt.is_object (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0x7fff
+  i32.eq)
+
+(func $rt.is_class (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_object
+  if (result i32)
+    get_local $value
+    i32.wrap_i64
+    i64.load
+    call $rt.is_int
+  else
+    i32.const 0
+  end)
+
+(func $rt.is_instance (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_object
+  get_local $value
+  call $rt.is_class
+  i32.eqz
+  i32.and)
+
+(func $rt.is_old_style (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_instance
+  if (result i32)
+    get_local $value
+    i32.wrap_i64
+    i32.load
+    i32.load offset=32
+    i32.eqz
+  else
+    i32.const 0
+  end)
+
+;; makes the class numbered `id`, whose name is at `name`, deriving from
+;; `base` or from nothing if it is None.
+(func $rt.new_class (param $base i64) (param $id i32) (param $name i32) (param $new_style i32) (result i64)
+  (local $top i32)
+  (local $attrs i64)
+  (local $address i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $base
+  call $rt.root
+  drop
+  call $rt.new_dict
+  call $rt.root
+  set_local $attrs
+  i32.const 40
+  call $rt.alloc
+  tee_local $address
+  get_local $id
+  call $rt.int
+  i64.store
+  get_local $address
+  get_local $attrs
+  i64.store offset=8
+  get_local $address
+  get_local $base
+  i64.store offset=16
+  get_local $address
+  get_local $name
+  call $rt.int
+  i64.store offset=24
+  get_local $address
+  get_local $new_style
+  call $rt.bool
+  i64.store offset=32
+  get_local $top
+  set_global $shadow.top
+  get_local $address
+  i64.extend_i32_u
+  i64.const 0x7fff_000000000000
+  i64.or)
+
+;; makes an instance of `class`, without any attributes of its own. only a
+;; class can be called to make one.
+(func $rt.new_instance (param $class i64) (result i64)
+  (local $top i32)
+  (local $attrs i64)
+  (local $address i32)
+  get_local $class
+  call $rt.is_class
+  i32.eqz
+  if
+    get_local $class
+    call $rt.not_callable
+    unreachable
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $class
+  call $rt.root
+  drop
+  call $rt.new_dict
+  call $rt.root
+  set_local $attrs
+  i32.const 16
+  call $rt.alloc
+  tee_local $address
+  get_local $class
+  i64.store
+  get_local $address
+  get_local $attrs
+  i64.store offset=8
+  get_local $top
+  set_global $shadow.top
+  get_local $address
+  i64.extend_i32_u
+  i64.const 0x7fff_000000000000
+  i64.or)
+
+;; the number of the class `value` is an instance of, or -1 if it isn't an
+;; instance.
+(func $rt.class_id (param $value i64) (result i32)
+  get_local $value
+  call $rt.is_instance
+  if (result i32)
+    get_local $value
+    i32.wrap_i64
+    i64.load
+    i32.wrap_i64
+    i32.load
+  else
+    i32.const -1
+  end)
+
+;; whether `a` and `b` are instances of the same new-style class, for which
+;; python doesn't try the reflected method of an operator.
+(func $rt.same_class (param $a i64) (param $b i64) (result i32)
+  get_local $a
+  call $rt.is_instance
+  get_local $b
+  call $rt.is_instance
+  i32.and
+  if (result i32)
+    get_local $a
+    i32.wrap_i64
+    i32.load
+    get_local $b
+    i32.wrap_i64
+    i32.load
+    i32.eq
+    if (result i32)
+      get_local $a
+      i32.wrap_i64
+      i32.load
+      i32.load offset=32
+    else
+      i32.const 0
+    end
+  else
+    i32.const 0
+  end)
+
+;; raises the AttributeError for the attribute of `object` named by the
+;; `length` bytes at `name`, which it doesn't have.
+(func $rt.attribute_error (param $object i64) (param $name i32) (param $length i32)
+  block $said
+    get_local $object
+    call $rt.is_old_style
+    if
+      get_local $object
+      i32.wrap_i64
+      i32.load
+      i32.load offset=24
+      call $rt.say_name
+      i32.const 49071
+      i32.const 9
+      call $rt.say
+      br $said
+    end
+    get_local $object
+    call $rt.is_class
+    if
+      get_local $object
+      i32.wrap_i64
+      i32.load offset=32
+      if
+        i32.const 48604
+        i32.const 13
+        call $rt.say
+        get_local $object
+        i32.wrap_i64
+        i32.load offset=24
+        call $rt.say_name
+        i32.const 47648
+        i32.const 1
+        call $rt.say
+      else
+        i32.const 48598
+        i32.const 6
+        call $rt.say
+        get_local $object
+        i32.wrap_i64
+        i32.load offset=24
+        call $rt.say_name
+      end
+      br $said
+    end
+    get_local $object
+    call $rt.say_object
+  end
+  i32.const 47951
+  i32.const 19
+  call $rt.say
+  get_local $name
+  get_local $length
+  call $rt.say
+  i32.const 6
+  i32.const 47648
+  i32.const 1
+  call $rt.raise_message)
+
+;; raises the AttributeError for the attribute numbered `name` of `object`.
+(func $rt.no_attribute (param $object i64) (param $name i32)
+  get_local $object
+  get_local $name
+  call $rt.attribute_name
+  tee_local $name
+  i32.const 1
+  i32.add
+  get_local $name
+  i32.load8_u
+  call $rt.attribute_error)
+
+;; the address of the name of the attribute numbered `name`.
+(func $rt.attribute_name (param $name i32) (result i32)
+  get_global $attributes
+  get_local $name
+  i32.const 1
+  i32.shl
+  i32.add
+  i32.load16_u)
+
+;; an instance of an old-style class that doesn't have a special method,
+;; named by the `length` bytes at `name`, raises an AttributeError for it.
+;; python looks the method up on the instance itself.
+(func $rt.lacks (param $value i64) (param $name i32) (param $length i32)
+  get_local $value
+  call $rt.is_old_style
+  if
+    get_local $value
+    get_local $name
+    get_local $length
+    call $rt.attribute_error
+  end)
+
+;; the attribute numbered `name` of `object`: its own, or else its class's
+;; or the nearest one's its class derives from.
+(func $rt.getattr (param $object i64) (param $name i32) (result i64)
+  (local $key i64)
+  (local $entry i32)
+  (local $class i64)
+  get_local $object
+  call $rt.is_object
+  i32.eqz
+  if
+    get_local $object
+    get_local $name
+    call $rt.no_attribute
+    unreachable
+  end
+  get_local $name
+  call $rt.int
+  set_local $key
+  get_local $object
+  set_local $class
+  get_local $object
+  call $rt.is_instance
+  if
+    get_local $object
+    i32.wrap_i64
+    i64.load offset=8
+    get_local $key
+    call $rt.find
+    tee_local $entry
+    if
+      get_local $entry
+      i64.load offset=8
+      return
+    end
+    get_local $object
+    i32.wrap_i64
+    i64.load
+    set_local $class
+  end
+  loop $next
+    get_local $class
+    i32.wrap_i64
+    i64.load offset=8
+    get_local $key
+    call $rt.find
+    tee_local $entry
+    if
+      get_local $entry
+      i64.load offset=8
+      return
+    end
+    get_local $class
+    i32.wrap_i64
+    i64.load offset=16
+    tee_local $class
+    call $rt.is_class
+    br_if $next
+  end
+  get_local $object
+  get_local $name
+  call $rt.no_attribute
+  unreachable)
+
+;; sets the attribute numbered `name` of `object` to `value`.
+(func $rt.setattr (param $value i64) (param $object i64) (param $name i32)
+  get_local $object
+  call $rt.is_object
+  i32.eqz
+  if
+    get_local $object
+    get_local $name
+    call $rt.no_attribute
+    return
+  end
+  get_local $object
+  i32.wrap_i64
+  i64.load offset=8
+  get_local $name
+  call $rt.int
+  get_local $value
+  call $rt.dict_set)
+
+;; deletes the attribute numbered `name` of `object`, which has to be its
+;; own.
+(func $rt.delattr (param $object i64) (param $name i32)
+  get_local $object
+  call $rt.is_object
+  if
+    get_local $object
+    i32.wrap_i64
+    i64.load offset=8
+    get_local $name
+    call $rt.int
+    call $rt.find
+    if
+      get_local $object
+      i32.wrap_i64
+      i64.load offset=8
+      get_local $name
+      call $rt.int
+      call $rt.delitem
+      return
+    end
+  end
+  ;; python only names the attribute for a new-style class.
+  get_local $object
+  call $rt.is_class
+  if
+    get_local $object
+    i32.wrap_i64
+    i32.load offset=32
+    if
+      get_local $name
+      call $rt.attribute_name
+      call $rt.say_name
+      i32.const 6
+      call $rt.raise_said
+      return
+    end
+  end
+  get_local $object
+  get_local $name
+  call $rt.no_attribute)
+
+;; whether `value` is an instance of `class`, or of a class derived from
+;; it, or of any class in a tuple of them.
+(func $rt.isinstance (param $value i64) (param $class i64) (result i32)
+  (local $i i32)
+  (local $c i64)
+  get_local $class
+  call $rt.is_tuple
+  if
+    block $done
+      loop $next
+        get_local $i
+        get_local $class
+        call $rt.len
+        i32.ge_u
+        br_if $done
+        get_local $value
+        get_local $class
+        call $rt.elements
+        get_local $i
+        i32.const 3
+        i32.shl
+        i32.add
+        i64.load
+        call $rt.isinstance
+        if
+          i32.const 1
+          return
+        end
+        get_local $i
+        i32.const 1
+        i32.add
+        set_local $i
+        br $next
+      end
+    end
+    i32.const 0
+    return
+  end
+  get_local $class
+  call $rt.is_class
+  i32.eqz
+  if
+    i32.const 2
+    i32.const 48282
+    i32.const 71
+    call $rt.raise_message
+    unreachable
+  end
+  get_local $value
+  call $rt.is_instance
+  i32.eqz
+  if
+    i32.const 0
+    return
+  end
+  get_local $value
+  i32.wrap_i64
+  i64.load
+  set_local $c
+  loop $next
+    get_local $c
+    get_local $class
+    i64.eq
+    if
+      i32.const 1
+      return
+    end
+    get_local $c
+    i32.wrap_i64
+    i64.load offset=16
+    tee_local $c
+    call $rt.is_class
+    br_if $next
+  end
+  i32.const 0)
+
+;; what __init__ gives back has to be None.
+(func $rt.check_init (param $result i64)
+  get_local $result
+  i64.const 0xfffb_000000000000
+  i64.ne
+  if
+    i32.const 48423
+    i32.const 36
+    call $rt.say
+    get_local $result
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    i32.const 47648
+    i32.const 1
+    call $rt.raise_message
+  end)
+
+;; what __len__ gives back has to be an int or a bool, and not negative.
+(func $rt.check_len (param $result i64) (result i32)
+  get_local $result
+  call $rt.is_int
+  i32.eqz
+  if
+    i32.const 2
+    i32.const 48380
+    i32.const 22
+    call $rt.raise_message
+  end
+  get_local $result
+  i32.wrap_i64
+  i32.const 0
+  i32.lt_s
+  if
+    i32.const 48003
+    i32.const 7
+    call $rt.say
+    i32.const 8
+    i32.const 48402
+    i32.const 21
+    call $rt.raise_message
+  end
+  get_local $result
+  i32.wrap_i64)
+
+;; what __str__, or __repr__ if `repr` is 1, gives back has to be a str.
+(func $rt.check_str (param $result i64) (param $repr i32) (result i64)
+  get_local $result
+  call $rt.is_str
+  i32.eqz
+  if
+    i32.const 48034
+    i32.const 48027
+    get_local $repr
+    select
+    i32.const 7
+    get_local $repr
+    i32.add
+    call $rt.say
+    i32.const 48353
+    i32.const 27
+    call $rt.say
+    get_local $result
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    i32.const 47642
+    i32.const 1
+    call $rt.raise_message
+  end
+  get_local $result)
+
+;; calling something that isn't a function, which is a TypeError naming its
+;; type. python says an instance of an old-style class has no __call__
+;; method instead.
+(func $rt.not_callable (param $value i64)
+  get_local $value
+  call $rt.is_old_style
+  if
+    get_local $value
+    i32.wrap_i64
+    i32.load
+    i32.load offset=24
+    call $rt.say_name
+    i32.const 6
+    i32.const 49071
+    i32.const 32
+    call $rt.raise_message
+    return
+  end
+  get_local $value
+  call $rt.say_object
+  i32.const 2
+  i32.const 49055
+  i32.const 16
+  call $rt.raise_message)
+
+(func $rt.putchar (param $c i32)
+  get_global $stream
+  get_local $c
+  call $putchar)
+
+(func $rt.write_bytes (param $address i32) (param $length i32)
+  block $done
+    loop $next
+      get_local $length
+      i32.eqz
+      br_if $done
+      get_local $address
+      i32.load8_u
+      call $rt.putchar
+      get_local $address
+      i32.const 1
+      i32.add
+      set_local $address
+      get_local $length
+      i32.const 1
+      i32.sub
+      set_local $length
+      br $next
+    end
+  end)
+
+;; writes `n`, taken as unsigned, in decimal.
+(func $rt.write_digits (param $n i32)
+  get_local $n
+  i32.const 10
+  i32.ge_u
+  if
+    get_local $n
+    i32.const 10
+    i32.div_u
+    call $rt.write_digits
+  end
+  get_local $n
+  i32.const 10
+  i32.rem_u
+  i32.const 48
+  i32.add
+  call $rt.putchar)
+
+;; writes `n` in hex, without leading zeros.
+(func $rt.write_hex (param $n i32)
+  get_local $n
+  i32.const 16
+  i32.ge_u
+  if
+    get_local $n
+    i32.const 4
+    i32.shr_u
+    call $rt.write_hex
+  end
+  get_local $n
+  i32.const 15
+  i32.and
+  i32.const 163
+  i32.add
+  i32.load8_u
+  call $rt.putchar)
+
+(func $rt.write_int (param $i i32)
+  get_local $i
+  i32.const 0
+  i32.lt_s
+  if
+    i32.const 45
+    call $rt.putchar
+    i32.const 0
+    get_local $i
+    i32.sub
+    set_local $i
+  end
+  get_local $i
+  call $rt.write_digits)
+
+;; a long is written nine digits at a time. its magnitude is copied, and
+;; the copy divided by 10^9 until nothing is left of it, which leaves the
+;; groups of digits from the bottom after it.
+(func $rt.write_long (param $long i64) (param $repr i32)
+  (local $size i32)
+  (local $copy i32)
+  (local $groups i32)
+  (local $count i32)
+  (local $i i32)
+  (local $rest i64)
+  (local $address i32)
+  (local $group i32)
+  get_local $long
+  call $rt.negative
+  if
+    i32.const 45
+    call $rt.putchar
+  end
+  ;; a limb is less than ten digits, so there are at most two groups to a
+  ;; limb, and one more for zero.
+  get_local $long
+  call $rt.size
+  tee_local $size
+  i32.const 12
+  i32.mul
+  i32.const 4
+  i32.add
+  call $rt.alloc_bytes
+  tee_local $copy
+  get_local $size
+  i32.const 2
+  i32.shl
+  i32.add
+  set_local $groups
+  get_local $long
+  call $rt.limbs
+  get_local $copy
+  get_local $size
+  i32.const 2
+  i32.shl
+  call $rt.copy_bytes
+  loop $next
+    i64.const 0
+    set_local $rest
+    get_local $size
+    set_local $i
+    block $divided
+      loop $limb
+        get_local $i
+        i32.eqz
+        br_if $divided
+        get_local $copy
+        get_local $i
+        i32.const 1
+        i32.sub
+        tee_local $i
+        i32.const 2
+        i32.shl
+        i32.add
+        tee_local $address
+        get_local $rest
+        i64.const 32
+        i64.shl
+        get_local $address
+        i64.load32_u
+        i64.or
+        tee_local $rest
+        i64.const 1000000000
+        i64.div_u
+        i64.store32
+        get_local $rest
+        i64.const 1000000000
+        i64.rem_u
+        set_local $rest
+        br $limb
+      end
+    end
+    get_local $groups
+    get_local $count
+    i32.const 2
+    i32.shl
+    i32.add
+    get_local $rest
+    i64.store32
+    get_local $count
+    i32.const 1
+    i32.add
+    set_local $count
+    block $trimmed
+      loop $trim
+        get_local $size
+        i32.eqz
+        br_if $trimmed
+        get_local $copy
+        get_local $size
+        i32.const 2
+        i32.shl
+        i32.add
+        i32.const 4
+        i32.sub
+        i32.load
+        br_if $trimmed
+        get_local $size
+        i32.const 1
+        i32.sub
+        set_local $size
+        br $trim
+      end
+    end
+    get_local $size
+    br_if $next
+  end
+  get_local $groups
+  get_local $count
+  i32.const 1
+  i32.sub
+  tee_local $count
+  i32.const 2
+  i32.shl
+  i32.add
+  i32.load
+  call $rt.write_digits
+  block $done
+    loop $next
+      get_local $count
+      i32.eqz
+      br_if $done
+      ;; the groups after the first are padded to nine digits.
+      i32.const 100000000
+      set_local $i
+      get_local $groups
+      get_local $count
+      i32.const 1
+      i32.sub
+      tee_local $count
+      i32.const 2
+      i32.shl
+      i32.add
+      i32.load
+      set_local $group
+      block $written
+        loop $digit
+          get_local $group
+          get_local $i
+          i32.div_u
+          i32.const 10
+          i32.rem_u
+          i32.const 48
+          i32.add
+          call $rt.putchar
+          get_local $i
+          i32.const 10
+          i32.div_u
+          tee_local $i
+          br_if $digit
+        end
+      end
+      br $next
+    end
+  end
+  get_local $repr
+  if
+    i32.const 76
+    call $rt.putchar
+  end
+  get_local $copy
+  call $rt.free)
+
+;; a list that contains itself is written as [...] where it recurs, and
+;; a tuple of one item has a comma after it.
+(func $rt.write_sequence (param $sequence i64)
+  (local $block i32)
+  (local $i i32)
+  (local $tuple i32)
+  get_local $sequence
+  call $rt.is_tuple
+  set_local $tuple
+  get_local $sequence
+  i32.wrap_i64
+  i32.const 8
+  i32.sub
+  tee_local $block
+  i32.load
+  i32.const 4
+  i32.and
+  if
+    i32.const 149
+    i32.const 5
+    call $rt.write_bytes
+    return
+  end
+  get_local $block
+  get_local $block
+  i32.load
+  i32.const 4
+  i32.or
+  i32.store
+  i32.const 40
+  i32.const 91
+  get_local $tuple
+  select
+  call $rt.putchar
+  block $done
+    loop $next
+      get_local $i
+      get_local $sequence
+      call $rt.len
+      i32.ge_u
+      br_if $done
+      get_local $i
+      if
+        i32.const 154
+        i32.const 2
+        call $rt.write_bytes
+      end
+      get_local $sequence
+      call $rt.elements
+      get_local $i
+      i32.const 3
+      i32.shl
+      i32.add
+      i64.load
+      i32.const 1
+      call $rt.write
+      get_local $i
+      i32.const 1
+      i32.add
+      set_local $i
+      br $next
+    end
+  end
+  get_local $tuple
+  get_local $i
+  i32.const 1
+  i32.eq
+  i32.and
+  if
+    i32.const 44
+    call $rt.putchar
+  end
+  i32.const 41
+  i32.const 93
+  get_local $tuple
+  select
+  call $rt.putchar
+  get_local $block
+  get_local $block
+  i32.load
+  i32.const -5
+  i32.and
+  i32.store)
+
+;; a dict that contains itself is written as {...} where it recurs.
+(func $rt.write_dict (param $dict i64)
+  (local $block i32)
+  (local $slot i32)
+  (local $entry i32)
+  (local $first i32)
+  get_local $dict
+  i32.wrap_i64
+  i32.const 8
+  i32.sub
+  tee_local $block
+  i32.load
+  i32.const 4
+  i32.and
+  if
+    i32.const 156
+    i32.const 5
+    call $rt.write_bytes
+    return
+  end
+  get_local $block
+  get_local $block
+  i32.load
+  i32.const 4
+  i32.or
+  i32.store
+  i32.const 123
+  call $rt.putchar
+  i32.const 1
+  set_local $first
+  block $done
+    loop $next
+      get_local $slot
+      i64.extend_i32_u
+      get_local $dict
+      call $rt.mask
+      i64.gt_u
+      br_if $done
+      get_local $dict
+      get_local $slot
+      call $rt.entry
+      tee_local $entry
+      i64.load
+      call $rt.active
+      if
+        get_local $first
+        i32.eqz
+        if
+          i32.const 154
+          i32.const 2
+          call $rt.write_bytes
+        end
+        i32.const 0
+        set_local $first
+        get_local $entry
+        i64.load
+        i32.const 1
+        call $rt.write
+        i32.const 161
+        i32.const 2
+        call $rt.write_bytes
+        get_local $entry
+        i64.load offset=8
+        i32.const 1
+        call $rt.write
+      end
+      get_local $slot
+      i32.const 1
+      i32.add
+      set_local $slot
+      br $next
+    end
+  end
+  i32.const 125
+  call $rt.putchar
+  get_local $block
+  get_local $block
+  i32.load
+  i32.const -5
+  i32.and
+  i32.store)
+
+;; the repr() of a str is quoted with single quotes, unless it only has
+;; double quotes in it. backslashes, the quote, and bytes that aren't
+;; printable ascii are escaped.
+(func $rt.write_str (param $s i64) (param $repr i32)
+  (local $address i32)
+  (local $end i32)
+  (local $quote i32)
+  (local $single i32)
+  (local $double i32)
+  (local $c i32)
+  get_local $s
+  call $rt.bytes
+  tee_local $address
+  get_local $s
+  call $rt.len
+  i32.add
+  set_local $end
+  get_local $repr
+  i32.eqz
+  if
+    get_local $address
+    get_local $s
+    call $rt.len
+    call $rt.write_bytes
+    return
+  end
+  block $done
+    loop $next
+      get_local $address
+      get_local $end
+      i32.ge_u
+      br_if $done
+      get_local $address
+      i32.load8_u
+      tee_local $c
+      i32.const 39
+      i32.eq
+      get_local $single
+      i32.or
+      set_local $single
+      get_local $c
+      i32.const 34
+      i32.eq
+      get_local $double
+      i32.or
+      set_local $double
+      get_local $address
+      i32.const 1
+      i32.add
+      set_local $address
+      br $next
+    end
+  end
+  i32.const 34
+  i32.const 39
+  get_local $single
+  get_local $double
+  i32.eqz
+  i32.and
+  select
+  tee_local $quote
+  call $rt.putchar
+  get_local $s
+  call $rt.bytes
+  set_local $address
+  block $done
+    loop $next
+      get_local $address
+      get_local $end
+      i32.ge_u
+      br_if $done
+      get_local $address
+      i32.load8_u
+      tee_local $c
+      get_local $quote
+      i32.eq
+      get_local $c
+      i32.const 92
+      i32.eq
+      i32.or
+      if
+        i32.const 92
+        call $rt.putchar
+        get_local $c
+        call $rt.putchar
+      else
+        get_local $c
+        i32.const 9
+        i32.eq
+        if
+          i32.const 92
+          call $rt.putchar
+          i32.const 116
+          call $rt.putchar
+        else
+          get_local $c
+          i32.const 10
+          i32.eq
+          if
+            i32.const 92
+            call $rt.putchar
+            i32.const 110
+            call $rt.putchar
+          else
+            get_local $c
+            i32.const 13
+            i32.eq
+            if
+              i32.const 92
+              call $rt.putchar
+              i32.const 114
+              call $rt.putchar
+            else
+              get_local $c
+              i32.const 32
+              i32.lt_u
+              get_local $c
+              i32.const 127
+              i32.ge_u
+              i32.or
+              if
+                i32.const 92
+                call $rt.putchar
+                i32.const 120
+                call $rt.putchar
+                get_local $c
+                i32.const 4
+                i32.shr_u
+                i32.const 163
+                i32.add
+                i32.load8_u
+                call $rt.putchar
+                get_local $c
+                i32.const 15
+                i32.and
+                i32.const 163
+                i32.add
+                i32.load8_u
+                call $rt.putchar
+              else
+                get_local $c
+                call $rt.putchar
+              end
+            end
+          end
+        end
+      end
+      get_local $address
+      i32.const 1
+      i32.add
+      set_local $address
+      br $next
+    end
+  end
+  get_local $quote
+  call $rt.putchar)
+
+;; writes `value` the way python's str() shows it, or its repr() if `repr`
+;; is 1. containers show their items with repr().
+(func $rt.write (param $value i64) (param $repr i32)
+  get_local $value
+  call $rt.is_float
+  if
+    get_global $stream
+    get_local $value
+    f64.reinterpret_i64
+    get_local $repr
+    call $put_float
+    return
+  end
+  get_local $value
+  call $rt.is_sequence
+  if
+    get_local $value
+    call $rt.write_sequence
+    return
+  end
+  get_local $value
+  call $rt.is_dict
+  if
+    get_local $value
+    call $rt.write_dict
+    return
+  end
+  get_local $value
+  call $rt.is_str
+  if
+    get_local $value
+    get_local $repr
+    call $rt.write_str
+    return
+  end
+  get_local $value
+  call $rt.is_long
+  if
+    get_local $value
+    get_local $repr
+    call $rt.write_long
+    return
+  end
+  get_local $value
+  call $rt.is_exception
+  if
+    get_local $value
+    get_local $repr
+    call $rt.write_exception
+    return
+  end
+  get_local $value
+  call $rt.is_object
+  if
+    get_local $value
+    call $rt.write_object
+    return
+  end
+  block $none
+    block $bool
+      block $int
+        get_local $value
+        call $rt.tag
+        i32.const 0xfff9
+        i32.sub
+        br_table $int $bool $none
+      end
+      get_local $value
+      i32.wrap_i64
+      call $rt.write_int
+      return
+    end
+    ;; True or False.
+    i32.const 136
+    i32.const 140
+    get_local $value
+    i32.wrap_i64
+    select
+    i32.const 4
+    i32.const 5
+    get_local $value
+    i32.wrap_i64
+    select
+    call $rt.write_bytes
+    return
+  end
+  ;; None or NotImplemented.
+  i32.const 179
+  i32.const 145
+  get_local $value
+  i32.wrap_i64
+  select
+  i32.const 14
+  i32.const 4
+  get_local $value
+  i32.wrap_i64
+  select
+  call $rt.write_bytes)
+
+;; writes out a class or an instance as python 2 does, where an instance's
+;; address is its address on the heap. an instance whose class has a
+;; __repr__ is written as the str it gives back, which $special.repr gets.
+(func $rt.write_object (param $value i64)
+  (local $class i32)
+  (local $repr i64)
+  get_local $value
+  call $rt.is_instance
+  if
+    get_local $value
+    call $special.repr
+    tee_local $repr
+    get_local $value
+    i64.ne
+    if
+      get_local $repr
+      i32.const 0
+      call $rt.write_str
+      return
+    end
+  end
+  get_local $value
+  i32.wrap_i64
+  set_local $class
+  get_local $value
+  call $rt.is_instance
+  if
+    get_local $class
+    i32.load
+    set_local $class
+  end
+  get_local $value
+  call $rt.is_instance
+  if
+    i32.const 60
+    call $rt.putchar
+  else
+    get_local $class
+    i32.load offset=32
+    if
+      i32.const 49220
+      i32.const 8
+      call $rt.write_bytes
+    end
+  end
+  i32.const 49228
+  i32.const 9
+  call $rt.write_bytes
+  get_local $class
+  i32.load offset=24
+  call $rt.write_name
+  get_local $value
+  call $rt.is_instance
+  if
+    i32.const 49254
+    i32.const 49239
+    get_local $class
+    i32.load offset=32
+    select
+    i32.const 13
+    i32.const 15
+    get_local $class
+    i32.load offset=32
+    select
+    call $rt.write_bytes
+    get_local $value
+    i32.wrap_i64
+    call $rt.write_hex
+    i32.const 62
+    call $rt.putchar
+  else
+    get_local $class
+    i32.load offset=32
+    if
+      i32.const 49237
+      i32.const 2
+      call $rt.write_bytes
+    end
+  end)
+
+;; writes an item of a print statement to `stream`, after a space if the
+;; stream is owed one, as python's softspace does. every item but a str
+;; ending in whitespace other than a space leaves the stream owed a space.
+;; writing out a long allocates, so the value is rooted while it is written.
+(func $rt.print_item (param $value i64) (param $stream i32)
+  (local $top i32)
+  (local $owed i32)
+  get_local $stream
+  set_global $stream
+  get_local $stream
+  i32.load8_u offset=132
+  if
+    i32.const 32
+    call $rt.putchar
+  end
+  get_global $shadow.top
+  set_local $top
+  get_local $value
+  call $rt.root
+  i32.const 0
+  call $rt.write
+  get_local $top
+  set_global $shadow.top
+  i32.const 1
+  set_local $owed
+  get_local $value
+  call $rt.is_str
+  if
+    get_local $value
+    call $rt.len
+    if
+      ;; tab, newline, vertical tab, form feed or carriage return.
+      get_local $value
+      call $rt.bytes
+      get_local $value
+      call $rt.len
+      i32.add
+      i32.const 1
+      i32.sub
+      i32.load8_u
+      i32.const 9
+      i32.sub
+      i32.const 5
+      i32.ge_u
+      set_local $owed
+    end
+  end
+  get_local $stream
+  get_local $owed
+  i32.store8 offset=132)
+
+;; ends the line of a print statement.
+(func $rt.print_newline (param $stream i32)
+  get_local $stream
+  set_global $stream
+  i32.const 10
+  call $rt.putchar
+  get_local $stream
+  i32.const 0
+  i32.store8 offset=132)
+
+(func $rt.print (param $value i64)
+  get_local $value
+  i32.const 1
+  call $rt.print_item
+  i32.const 1
+  call $rt.print_newline)
+
+;; python ends the line that a print to stdout left open when the program
+;; exits.
+(func $rt.exit
+  i32.const 133
+  i32.load8_u
+  if
+    i32.const 1
+    call $rt.print_newline
+  end)
+
+;; an exception of the builtin class `class`, raised at the line being run
+;; unless it is raised again somewhere else. `args` is a tuple.
+(func $rt.new_exception (param $args i64) (param $class i32) (result i64)
+  (local $top i32)
+  (local $traceback i64)
+  (local $address i32)
+  get_global $shadow.top
+  set_local $top
+  get_local $args
+  call $rt.root
+  drop
+  call $rt.traceback
+  call $rt.root
+  set_local $traceback
+  i32.const 32
+  call $rt.alloc
+  tee_local $address
+  get_local $args
+  i64.store
+  get_local $address
+  get_local $class
+  call $rt.int
+  i64.store offset=8
+  get_local $address
+  get_global $line
+  call $rt.int
+  i64.store offset=16
+  get_local $address
+  get_local $traceback
+  i64.store offset=24
+  get_local $top
+  set_global $shadow.top
+  get_local $address
+  i64.extend_i32_u
+  i64.const 0x7ffe_000000000000
+  i64.or)
+
+(func $rt.is_exception (param $value i64) (result i32)
+  get_local $value
+  call $rt.tag
+  i32.const 0x7ffe
+  i32.eq)
+
+(func $rt.exception_class (param $exception i64) (result i32)
+  get_local $exception
+  i32.wrap_i64
+  i32.load offset=8)
+
+;; whether `exception` is of the class `class`, or of one derived from it.
+;; anything else isn't.
+(func $rt.matches (param $exception i64) (param $class i32) (result i32)
+  (local $c i32)
+  get_local $exception
+  call $rt.is_exception
+  i32.eqz
+  if
+    i32.const 0
+    return
+  end
+  get_local $exception
+  call $rt.exception_class
+  set_local $c
+  loop $next
+    get_local $c
+    get_local $class
+    i32.eq
+    if
+      i32.const 1
+      return
+    end
+    get_local $c
+    i32.const 2
+    i32.shl
+    i32.load8_u offset=208
+    tee_local $c
+    br_if $next
+  end
+  i32.const 0)
+
+;; the raise statement, which raises `value` from the line being run.
+;; anything but an exception raises a TypeError instead.
+(func $rt.raise_exception (param $value i64)
+  get_local $value
+  call $rt.is_exception
+  i32.eqz
+  if
+    i32.const 48459
+    i32.const 72
+    call $rt.say
+    get_local $value
+    i32.const 0
+    call $rt.say_type
+    i32.const 2
+    call $rt.raise_said
+  end
+  get_local $value
+  call $rt.root
+  i32.wrap_i64
+  call $rt.traceback
+  i64.store offset=24
+  get_global $shadow.top
+  i32.const 8
+  i32.sub
+  set_global $shadow.top
+  get_local $value
+  i32.wrap_i64
+  get_global $line
+  call $rt.int
+  i64.store offset=16
+  get_local $value
+  call $rt.throw)
+
+;; a copy of the call stack as it is now, for the exception being raised.
+(func $rt.traceback (result i64)
+  (local $s i64)
+  (local $address i32)
+  get_global $calls.top
+  i32.const 32768
+  i32.sub
+  call $rt.new_str
+  tee_local $s
+  call $rt.bytes
+  set_local $address
+  i32.const 32768
+  get_local $address
+  get_local $s
+  call $rt.len
+  call $rt.copy_bytes
+  get_local $s)
+
+;; pushes an entry for a call to the function whose name is at `name`.
+(func $rt.call (param $name i32)
+  get_global $calls.top
+  i32.const 47104
+  i32.ge_u
+  if
+    call $rt.out_of_memory
+  end
+  get_global $calls.top
+  get_global $line
+  i32.store16
+  get_global $calls.top
+  get_local $name
+  i32.store16 offset=2
+  get_global $calls.top
+  i32.const 4
+  i32.add
+  set_global $calls.top)
+
+;; pops the entry of the function that is returning, and goes back to the
+;; line that called it.
+(func $rt.return
+  get_global $calls.top
+  i32.const 4
+  i32.sub
+  set_global $calls.top
+  get_global $calls.top
+  i32.load16_u
+  set_global $line)
+
+(func $rt.write_name (param $name i32)
+  get_local $name
+  i32.const 1
+  i32.add
+  get_local $name
+  i32.load8_u
+  call $rt.write_bytes)
+
+;; reports the exception in $exception, which nothing caught, the way
+;; python does: its traceback on stderr, from the outermost call to where
+;; it was raised, and then its class and what str() shows of it. the host
+;; is told its class and line.
+(func $rt.uncaught
+  (local $exception i64)
+  (local $traceback i64)
+  (local $entry i32)
+  (local $end i32)
+  (local $name i32)
+  ;; nothing is being raised while the report is written.
+  get_global $exception
+  call $rt.root
+  set_local $exception
+  i64.const 0
+  set_global $exception
+  call $rt.exit
+  i32.const 134
+  i32.load8_u
+  if
+    i32.const 2
+    call $rt.print_newline
+  end
+  i32.const 2
+  set_global $stream
+  i32.const 49152
+  i32.const 35
+  call $rt.write_bytes
+  get_local $exception
+  i32.wrap_i64
+  i64.load offset=24
+  tee_local $traceback
+  call $rt.bytes
+  tee_local $entry
+  get_local $traceback
+  call $rt.len
+  i32.add
+  set_local $end
+  ;; each entry names a function, and the line it was called from belongs
+  ;; to the frame before it.
+  i32.const 49208
+  set_local $name
+  loop $frames
+    i32.const 49187
+    i32.const 8
+    call $rt.write_bytes
+    i32.const 49280
+    call $rt.write_name
+    i32.const 49195
+    i32.const 8
+    call $rt.write_bytes
+    get_local $entry
+    get_local $end
+    i32.lt_u
+    if (result i32)
+      get_local $entry
+      i32.load16_u
+    else
+      get_local $exception
+      i32.wrap_i64
+      i32.load offset=16
+    end
+    call $rt.write_int
+    i32.const 49203
+    i32.const 5
+    call $rt.write_bytes
+    get_local $name
+    call $rt.write_name
+    i32.const 10
+    call $rt.putchar
+    get_local $entry
+    get_local $end
+    i32.lt_u
+    if
+      get_local $entry
+      i32.load16_u offset=2
+      set_local $name
+      get_local $entry
+      i32.const 4
+      i32.add
+      set_local $entry
+      br $frames
+    end
+  end
+  get_local $exception
+  call $rt.exception_class
+  i32.const 2
+  i32.shl
+  tee_local $entry
+  i32.load16_u offset=210
+  get_local $entry
+  i32.load8_u offset=209
+  call $rt.write_bytes
+  get_local $exception
+  i32.wrap_i64
+  i64.load
+  call $rt.len
+  if
+    i32.const 161
+    i32.const 2
+    call $rt.write_bytes
+    get_local $exception
+    i32.const 0
+    call $rt.write_exception
+  end
+  i32.const 10
+  call $rt.putchar
+  get_local $exception
+  call $rt.exception_class
+  get_local $exception
+  i32.wrap_i64
+  i32.load offset=16
+  call $raise
+  unreachable)
+
+;; an exception shows its args, or nothing if there are none, or the only
+;; one by itself. a KeyError shows its key the way repr() does. its repr()
+;; is its class's name and then its args.
+(func $rt.write_exception (param $exception i64) (param $repr i32)
+  (local $args i64)
+  (local $entry i32)
+  get_local $exception
+  i32.wrap_i64
+  i64.load
+  set_local $args
+  get_local $repr
+  if
+    get_local $exception
+    call $rt.exception_class
+    i32.const 2
+    i32.shl
+    tee_local $entry
+    i32.load16_u offset=210
+    get_local $entry
+    i32.load8_u offset=209
+    call $rt.write_bytes
+    get_local $args
+    i32.const 1
+    call $rt.write
+    return
+  end
+  get_local $args
+  call $rt.len
+  i32.eqz
+  if
+    return
+  end
+  get_local $args
+  call $rt.len
+  i32.const 1
+  i32.gt_u
+  if
+    get_local $args
+    i32.const 0
+    call $rt.write
+    return
+  end
+  get_local $args
+  call $rt.elements
+  i64.load
+  get_local $exception
+  call $rt.exception_class
+  i32.const 7
+  i32.eq
+  call $rt.write)
@@ -0,0 +1,625 @@
+use error::Error;
+use exception::Exception;
+use program::*;
+use std::collections::{HashMap, HashSet};
+
+// names that are available without the program binding them, besides the
+// exception classes.
+const BUILTINS: &[&str] = &[
+    "True",
+    "False",
+    "None",
+    "NotImplemented",
+    "len",
+    "isinstance",
+    "object",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scope {
+    Local,   // bound in the function that uses it
+    Global,  // bound at the top level of the module
+    Builtin, // never bound by the program
+    Free,    // bound in an enclosing function
+}
+
+// the names used or bound in one function, or in the module itself.
+#[derive(Debug, PartialEq)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Scope>,
+    // parameters and assigned names, in the order they are first bound
+    variables: Vec<String>,
+    // tables for the functions defined directly in this one, by name
+    children: HashMap<String, SymbolTable>,
+}
+
+impl SymbolTable {
+    pub fn scope(&self, name: &str) -> Option<Scope> {
+        self.symbols.get(name).cloned()
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    pub fn child(&self, name: &str) -> Option<&SymbolTable> {
+        self.children.get(name)
+    }
+}
+
+// what a single scope binds and uses, not counting the bodies of the
+// functions defined inside it.
+#[derive(Default)]
+struct Names<'a> {
+    bound: Vec<String>,
+    variables: Vec<String>,
+    used: Vec<(String, usize)>,
+    // names declared with a global statement
+    globals: Vec<String>,
+    // the functions defined here, with the methods of the classes defined
+    // here named after their class
+    defs: Vec<(String, &'a [Parameter], &'a Body)>,
+}
+
+impl<'a> Names<'a> {
+    fn bind(&mut self, name: &str) {
+        if !self.bound.iter().any(|bound| bound == name) {
+            self.bound.push(name.to_owned());
+        }
+    }
+
+    fn assign(&mut self, name: &str) {
+        self.bind(name);
+        if !self.variables.iter().any(|variable| variable == name) {
+            self.variables.push(name.to_owned());
+        }
+    }
+
+    // moves the names declared global out of this scope, returning the ones
+    // that are assigned to.
+    fn take_globals(&mut self) -> Vec<String> {
+        let globals = &self.globals;
+        let assigned = self
+            .variables
+            .iter()
+            .filter(|name| globals.contains(name))
+            .cloned()
+            .collect();
+        self.bound.retain(|name| !globals.contains(name));
+        self.variables.retain(|name| !globals.contains(name));
+        assigned
+    }
+
+    fn collect_body(&mut self, body: &'a Body) {
+        for stmt in body.statements.iter() {
+            match stmt {
+                Statement::Return(e) | Statement::Expression(e) => self.collect_expression(e),
+                Statement::Print { items, .. } => {
+                    for item in items.iter() {
+                        self.collect_expression(item);
+                    }
+                }
+                // deleting a name makes it local, like assigning to it.
+                Statement::Del(Expression::Simple(Value::Variable(name, _))) => self.assign(name),
+                Statement::Del(e) => self.collect_expression(e),
+                Statement::Assert(condition, message) => {
+                    self.collect_expression(condition);
+                    if let Some(message) = message {
+                        self.collect_expression(message);
+                    }
+                }
+                Statement::Import(_) | Statement::Pass => (),
+                Statement::Raise(e) => {
+                    if let Some(e) = e {
+                        self.collect_expression(e);
+                    }
+                }
+                Statement::Try {
+                    body,
+                    handlers,
+                    else_body,
+                    finally,
+                } => {
+                    self.collect_body(body);
+                    for handler in handlers.iter() {
+                        if let Some(exception) = &handler.exception {
+                            self.collect_expression(exception);
+                        }
+                        if let Some(name) = &handler.name {
+                            self.assign(name);
+                        }
+                        self.collect_body(&handler.body);
+                    }
+                    for body in else_body.iter().chain(finally.iter()) {
+                        self.collect_body(body);
+                    }
+                }
+                Statement::Assign(name, e) => {
+                    self.collect_expression(e);
+                    self.assign(name);
+                }
+                Statement::AugAssign(e) => {
+                    self.collect_expression(e);
+                    if let Some(name) = stmt.augmented_name() {
+                        self.assign(name);
+                    }
+                }
+                Statement::SetItem {
+                    object,
+                    index,
+                    value,
+                } => {
+                    self.collect_expression(value);
+                    self.collect_expression(object);
+                    self.collect_expression(index);
+                }
+                Statement::SetAttr { object, value, .. } => {
+                    self.collect_expression(value);
+                    self.collect_expression(object);
+                }
+                Statement::Unpack(targets, value) => {
+                    self.collect_expression(value);
+                    for target in targets.iter() {
+                        self.collect_target(target);
+                    }
+                }
+                Statement::Global(names) => {
+                    for name in names.iter() {
+                        if !self.globals.contains(name) {
+                            self.globals.push(name.clone());
+                        }
+                    }
+                }
+                Statement::If {
+                    condition,
+                    body,
+                    elif,
+                    else_body,
+                } => {
+                    self.collect_expression(condition);
+                    self.collect_body(body);
+                    for (condition, body) in elif.iter() {
+                        self.collect_expression(condition);
+                        self.collect_body(body);
+                    }
+                    if let Some(body) = else_body {
+                        self.collect_body(body);
+                    }
+                }
+                Statement::Def { name, params, body } => {
+                    // defaults are evaluated where the function is defined.
+                    for param in params.iter() {
+                        if let Parameter::Default(_, default) = param {
+                            self.collect_expression(default);
+                        }
+                    }
+                    self.bind(name);
+                    self.defs.push((name.clone(), params, body));
+                }
+                // the names a class's body uses are looked up where the
+                // class is defined, but the attributes it sets and the
+                // methods it defines only belong to the class, and are read
+                // from it once they are.
+                Statement::Class { name, base, body } => {
+                    if let Some(base) = base {
+                        self.collect_expression(base);
+                    }
+                    let mut attributes: Vec<&str> = vec![];
+                    for stmt in body.statements.iter() {
+                        let used = self.used.len();
+                        match stmt {
+                            Statement::Def {
+                                name: method,
+                                params,
+                                body,
+                            } => {
+                                for param in params.iter() {
+                                    if let Parameter::Default(_, default) = param {
+                                        self.collect_expression(default);
+                                    }
+                                }
+                                self.defs
+                                    .push((format!("{}.{}", name, method), params, body));
+                            }
+                            _ => {
+                                for e in stmt.expressions() {
+                                    self.collect_expression(e);
+                                }
+                            }
+                        }
+                        let reads = self.used.split_off(used);
+                        self.used.extend(
+                            reads
+                                .into_iter()
+                                .filter(|(read, _)| !attributes.contains(&read.as_str())),
+                        );
+                        match stmt {
+                            Statement::Assign(attribute, _) => attributes.push(attribute),
+                            Statement::Def { name: method, .. } => attributes.push(method),
+                            _ => (),
+                        }
+                    }
+                    self.assign(name);
+                }
+            }
+        }
+    }
+
+    fn collect_target(&mut self, target: &Target) {
+        match target {
+            Target::Name(name) => self.assign(name),
+            Target::Item(object, index) => {
+                self.collect_expression(object);
+                self.collect_expression(index);
+            }
+            Target::Attribute(object, _) => self.collect_expression(object),
+            Target::Tuple(targets) => {
+                for target in targets.iter() {
+                    self.collect_target(target);
+                }
+            }
+        }
+    }
+
+    fn collect_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Call { name, line, .. } => self.used.push((name.clone(), *line)),
+            Expression::Simple(Value::Variable(name, line)) => {
+                self.used.push((name.clone(), *line))
+            }
+            _ => (),
+        }
+        for operand in expr.operands() {
+            self.collect_expression(operand);
+        }
+    }
+}
+
+// builds the symbol table of the module, following python's rules: a name
+// bound anywhere in a function is local to the whole function unless it is
+// declared global, and any other name is looked up in the enclosing
+// functions, then the module, then the builtins. names found in none of them
+// are reported as NameErrors.
+pub fn analyze(program: &Program) -> Result<SymbolTable, Error> {
+    let mut names = Names::default();
+    names.collect_body(&program.body);
+    // functions can create module variables by assigning to them.
+    let mut assigned = Vec::new();
+    global_assignments(&names.defs, &mut assigned);
+    for name in assigned.iter() {
+        names.assign(name);
+    }
+    // a def is called directly, so its name can't be bound to anything else.
+    for (stmt, line) in program
+        .body
+        .statements
+        .iter()
+        .zip(program.body.lines.iter())
+    {
+        if let Statement::Def { name, .. } = stmt {
+            if names.variables.contains(name) {
+                return Err(Error::Unsupported {
+                    message: format!("rebinding the function '{}'", name),
+                    line: *line,
+                });
+            }
+        }
+    }
+    let module: HashSet<&str> = names.bound.iter().map(|name| name.as_str()).collect();
+
+    let mut symbols = HashMap::new();
+    for name in names.bound.iter() {
+        symbols.insert(name.clone(), Scope::Global);
+    }
+    for (name, line) in names.used.iter() {
+        if !symbols.contains_key(name) {
+            symbols.insert(name.clone(), builtin(name, "name", *line)?);
+        }
+    }
+
+    let mut children = HashMap::new();
+    for (name, params, body) in names.defs.iter() {
+        let table = analyze_function(params, body, &HashSet::new(), &module)?;
+        children.insert(name.clone(), table);
+    }
+
+    Ok(SymbolTable {
+        symbols,
+        variables: names.variables,
+        children,
+    })
+}
+
+fn analyze_function(
+    params: &[Parameter],
+    body: &Body,
+    enclosing: &HashSet<&str>,
+    module: &HashSet<&str>,
+) -> Result<SymbolTable, Error> {
+    let mut names = Names::default();
+    for param in params.iter() {
+        names.assign(param.name());
+    }
+    names.collect_body(body);
+    if let Some(param) = params
+        .iter()
+        .find(|param| names.globals.iter().any(|name| name == param.name()))
+    {
+        return Err(Error::LocalAndGlobal(param.name().to_owned()));
+    }
+    names.take_globals();
+
+    let mut symbols = HashMap::new();
+    for name in names.bound.iter() {
+        symbols.insert(name.clone(), Scope::Local);
+    }
+    for (name, line) in names.used.iter() {
+        if symbols.contains_key(name) {
+            continue;
+        }
+        let scope = if enclosing.contains(name.as_str()) && !names.globals.contains(name) {
+            Scope::Free
+        } else if module.contains(name.as_str()) {
+            Scope::Global
+        } else {
+            builtin(name, "global name", *line)?
+        };
+        symbols.insert(name.clone(), scope);
+    }
+    for name in names.globals.iter() {
+        symbols.entry(name.clone()).or_insert(Scope::Global);
+    }
+
+    let mut inner = enclosing.clone();
+    inner.extend(names.bound.iter().map(|name| name.as_str()));
+    let mut children = HashMap::new();
+    for (name, params, body) in names.defs.iter() {
+        let table = analyze_function(params, body, &inner, module)?;
+        children.insert(name.clone(), table);
+    }
+
+    Ok(SymbolTable {
+        symbols,
+        variables: names.variables,
+        children,
+    })
+}
+
+// collects the names that `defs`, and the functions inside them, declare
+// global and assign to.
+fn global_assignments(defs: &[(String, &[Parameter], &Body)], assigned: &mut Vec<String>) {
+    for (_, _, body) in defs.iter() {
+        let mut names = Names::default();
+        names.collect_body(body);
+        for name in names.take_globals() {
+            if !assigned.contains(&name) {
+                assigned.push(name);
+            }
+        }
+        global_assignments(&names.defs, assigned);
+    }
+}
+
+fn builtin(name: &str, kind: &str, line: usize) -> Result<Scope, Error> {
+    if BUILTINS.contains(&name) || Exception::from_name(name).is_some() {
+        Ok(Scope::Builtin)
+    } else {
+        Err(Error::NameError {
+            message: format!("{} '{}' is not defined", kind, name),
+            line,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing::*;
+
+    // the table of the function reached by following `path` from the module.
+    fn table(text: &str, path: &[&str]) -> SymbolTable {
+        let program = parse(text).unwrap();
+        let mut table = analyze(&program).unwrap();
+        for name in path.iter() {
+            table = table.children.remove(*name).unwrap();
+        }
+        table
+    }
+
+    macro_rules! scope_test {
+        (name: $name:ident,text: $text:expr,path: $path:expr,scopes: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let table = table($text, &$path);
+                for (name, scope) in $expected.iter() {
+                    assert_eq!(table.scope(name), Some(*scope), "scope of {}", name);
+                }
+            }
+        };
+    }
+
+    macro_rules! error_test {
+        (name: $name:ident,text: $text:expr,error: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let program = parse($text).unwrap();
+                assert_eq!(analyze(&program).unwrap_err(), $expected);
+            }
+        };
+    }
+
+    scope_test! {
+        name: module_names,
+        text: "x = 1\ndef f(a):\n  return a\nprint f(x)",
+        path: [],
+        scopes: [("x", Scope::Global), ("f", Scope::Global)],
+    }
+
+    scope_test! {
+        name: parameters_are_local,
+        text: "def f(a, b=1, *c, **d):\n  return a",
+        path: ["f"],
+        scopes: [
+            ("a", Scope::Local),
+            ("b", Scope::Local),
+            ("c", Scope::Local),
+            ("d", Scope::Local),
+        ],
+    }
+
+    scope_test! {
+        name: module_names_in_function,
+        text: "x = 1\ndef f():\n  return x + g()\ndef g():\n  return 2",
+        path: ["f"],
+        scopes: [("x", Scope::Global), ("g", Scope::Global)],
+    }
+
+    scope_test! {
+        name: assigned_anywhere_is_local,
+        text: "x = 1\ndef f():\n  print x\n  if True:\n    x = 2\n  return x",
+        path: ["f"],
+        scopes: [("x", Scope::Local), ("True", Scope::Builtin)],
+    }
+
+    scope_test! {
+        name: enclosing_names_are_free,
+        text: "def f(a):\n  def g():\n    return a + b\n  b = 1\n  return 0",
+        path: ["f", "g"],
+        scopes: [("a", Scope::Free), ("b", Scope::Free)],
+    }
+
+    scope_test! {
+        name: builtins,
+        text: "print True\nprint None, NotImplemented",
+        path: [],
+        scopes: [
+            ("True", Scope::Builtin),
+            ("None", Scope::Builtin),
+            ("NotImplemented", Scope::Builtin),
+        ],
+    }
+
+    scope_test! {
+        name: builtins_can_be_shadowed,
+        text: "True = 0\nprint True",
+        path: [],
+        scopes: [("True", Scope::Global)],
+    }
+
+    scope_test! {
+        name: exception_classes_and_handler_names,
+        text: "def f():\n  try: pass\n  except ValueError, e: raise KeyError(e)\n  return 0",
+        path: ["f"],
+        scopes: [
+            ("ValueError", Scope::Builtin),
+            ("KeyError", Scope::Builtin),
+            ("e", Scope::Local),
+        ],
+    }
+
+    scope_test! {
+        name: declared_global,
+        text: "x = 1\ndef f(a):\n  global x\n  x = a\n  return x",
+        path: ["f"],
+        scopes: [("x", Scope::Global), ("a", Scope::Local)],
+    }
+
+    scope_test! {
+        name: global_created_by_function,
+        text: "def f():\n  global y\n  y = 1\n  return 0\ndef g():\n  return y",
+        path: ["g"],
+        scopes: [("y", Scope::Global)],
+    }
+
+    scope_test! {
+        name: global_skips_enclosing_function,
+        text: "x = 1\ndef f():\n  x = 2\n  def g():\n    global x\n    return x\n  return x",
+        path: ["f", "g"],
+        scopes: [("x", Scope::Global)],
+    }
+
+    scope_test! {
+        name: method,
+        text: "x = 1\nclass A:\n  y = x\n  def f(self):\n    return A",
+        path: ["A.f"],
+        scopes: [("self", Scope::Local), ("A", Scope::Global)],
+    }
+
+    #[test]
+    fn module_variables() {
+        let table = table("a = 1\ndef f():\n  global b\n  b = 2\n  return 0", &[]);
+        assert_eq!(table.variables(), ["a", "b"]);
+    }
+
+    #[test]
+    fn variables_in_binding_order() {
+        let table = table("def f(a):\n  b = a\n  c = b\n  b = c\n  return b", &["f"]);
+        assert_eq!(table.variables(), ["a", "b", "c"]);
+    }
+
+    error_test! {
+        name: undefined_module_name,
+        text: "print 1\nprint x",
+        error: Error::NameError {
+            message: "name 'x' is not defined".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: undefined_global_name,
+        text: "def f():\n  return y",
+        error: Error::NameError {
+            message: "global name 'y' is not defined".to_owned(),
+            line: 2,
+        },
+    }
+
+    error_test! {
+        name: undefined_function,
+        text: "print g(1)",
+        error: Error::NameError {
+            message: "name 'g' is not defined".to_owned(),
+            line: 1,
+        },
+    }
+
+    error_test! {
+        name: local_of_other_function,
+        text: "def f():\n  z = 1\n  return z\ndef g():\n  return z",
+        error: Error::NameError {
+            message: "global name 'z' is not defined".to_owned(),
+            line: 5,
+        },
+    }
+
+    error_test! {
+        name: parameter_declared_global,
+        text: "def f(a):\n  global a\n  return a",
+        error: Error::LocalAndGlobal("a".to_owned()),
+    }
+
+    error_test! {
+        name: function_rebound,
+        text: "def f():\n  return 1\ndef g():\n  global f\n  f = 2\n  return 0",
+        error: Error::Unsupported {
+            message: "rebinding the function 'f'".to_owned(),
+            line: 1,
+        },
+    }
+
+    error_test! {
+        name: class_attribute_is_not_a_variable,
+        text: "class A:\n  y = 1\n  def f(self):\n    return y",
+        error: Error::NameError {
+            message: "global name 'y' is not defined".to_owned(),
+            line: 4,
+        },
+    }
+
+    #[test]
+    fn class_attribute_read_in_class() {
+        let table = table("class A:\n  y = 1\n  z = y", &[]);
+        assert_eq!(table.scope("y"), None);
+    }
+}
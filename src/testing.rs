@@ -1,4 +1,17 @@
 use common::*;
+use exception::{Exception, Handling};
+use program::Program;
+use regex::Regex;
+use scope;
+use std::process::Command;
+use std::{fs, str};
+use tempfile::Builder;
+use token::Token;
+use types;
+
+lazy_static! {
+    static ref HOST_RE: Regex = Regex::new(r"^called host host[.](\w+)[(]([^)]*)[)] =>$").unwrap();
+}
 
 pub fn lex(text: &str) -> Vec<Token> {
     let lexer = Lexer::new(text);
@@ -11,7 +24,228 @@ pub fn parse(text: &str) -> Result<Program, Error> {
 }
 
 pub fn codegen(text: &str) -> String {
+    codegen_handling(text, Handling::Status)
+}
+
+// the module for `text`, raising exceptions the way `handling` says.
+pub fn codegen_handling(text: &str, handling: Handling) -> String {
     let program = parse(text).unwrap();
-    let codegenerator = CodeGenerator::new(program);
-    codegenerator.codegen().to_string()
+    let symbols = scope::analyze(&program).unwrap();
+    let types = types::infer(&program, &symbols);
+    let codegenerator = CodeGenerator::new(program, symbols, types, handling);
+    codegenerator.codegen().unwrap().to_string()
+}
+
+// assembles and runs a module, returning the lines it wrote to stdout, then
+// those it wrote to stderr marked with "stderr: ". a raised exception ends
+// the output with its name and line.
+pub fn run_wat(wat: &str) -> Vec<String> {
+    println!("compiled wat: {}", wat);
+
+    let watfile = Builder::new()
+        .prefix("compyler-test-input")
+        .suffix(".wat")
+        .tempfile()
+        .expect("Failed to create tempfile");
+
+    fs::write(&watfile, wat).expect("Failed to write WAT.");
+
+    let wasm = Builder::new()
+        .prefix("compyler-test-wasm")
+        .suffix(".wasm")
+        .tempfile()
+        .expect("Failed to create tempfile");
+
+    let wat2wasm_status = Command::new("wat2wasm")
+        .arg("-o")
+        .arg(wasm.path())
+        .arg(watfile.path())
+        .status()
+        .expect("wat2wasm failed.");
+
+    assert!(wat2wasm_status.success());
+
+    let wasm_interp_output = Command::new("wasm-interp")
+        .arg("--host-print")
+        .arg("--run-all-exports")
+        .arg(wasm.path())
+        .output()
+        .expect("Failed to execute wasm-interp");
+
+    let stdout =
+        str::from_utf8(&wasm_interp_output.stdout).expect("wasm-interp output was not UTF-8");
+
+    println!("wasm-interp:  {:?}", stdout.trim());
+
+    let (output, raised) = host_output(stdout);
+    if !raised && !wasm_interp_output.status.success() {
+        let stderr = str::from_utf8(&wasm_interp_output.stderr)
+            .unwrap()
+            .to_string();
+        println!("{}", stderr);
+        panic!();
+    }
+    output
+}
+
+// runs a module that handles exceptions with wasm's own, which wasm-interp
+// can't, in wasmtime. its host calls are shown the way wasm-interp shows
+// them, so that they are read the same way.
+pub fn run_wat_exceptions(wat: &str) -> Vec<String> {
+    println!("compiled wat: {}", wat);
+
+    let mut config = wasmtime::Config::new();
+    config.wasm_exceptions(true);
+    let engine = wasmtime::Engine::new(&config).unwrap();
+    let module = wasmtime::Module::new(&engine, assemble(wat)).expect("Failed to compile wasm");
+    let mut store = wasmtime::Store::new(&engine, String::new());
+    let mut linker = wasmtime::Linker::new(&engine);
+    for import in module.imports() {
+        let ty = match import.ty() {
+            wasmtime::ExternType::Func(ty) => ty,
+            _ => panic!("unexpected import: {}", import.name()),
+        };
+        let name = format!("{}.{}", import.module(), import.name());
+        let host = wasmtime::Func::new(&mut store, ty, move |mut caller, params, _| {
+            let args: Vec<String> = params
+                .iter()
+                .map(|param| match param {
+                    wasmtime::Val::I32(i) => format!("i32:{}", *i as u32),
+                    wasmtime::Val::F64(bits) => format!("f64:{}", f64::from_bits(*bits)),
+                    param => panic!("unexpected argument: {:?}", param),
+                })
+                .collect();
+            let calls = caller.data_mut();
+            calls.push_str(&format!("called host {}({}) =>\n", name, args.join(", ")));
+            Ok(())
+        });
+        linker
+            .define(&store, import.module(), import.name(), host)
+            .unwrap();
+    }
+    let instance = linker.instantiate(&mut store, &module).unwrap();
+    let main = instance
+        .get_typed_func::<(), ()>(&mut store, "main")
+        .unwrap();
+    let result = main.call(&mut store, ());
+    let (output, raised) = host_output(store.data());
+    if !raised {
+        result.expect("wasmtime failed");
+    }
+    output
+}
+
+// `wat` in the instruction names wasmtime knows, rather than those from
+// before wasm 1.0 that wabt still accepts.
+fn assemble(wat: &str) -> String {
+    let mut text = wat.to_owned();
+    for (old, new) in [
+        ("get_local", "local.get"),
+        ("set_local", "local.set"),
+        ("tee_local", "local.tee"),
+        ("get_global", "global.get"),
+        ("set_global", "global.set"),
+        ("current_memory", "memory.size"),
+        ("grow_memory", "memory.grow"),
+    ] {
+        text = text.replace(old, new);
+    }
+    text
+}
+
+// the output of a run from the host calls it made, and whether it raised.
+fn host_output(calls: &str) -> (Vec<String>, bool) {
+    // the bytes written to stdout and stderr.
+    let mut written = [vec![], vec![]];
+    for line in calls.lines() {
+        let captures = match HOST_RE.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let args: Vec<&str> = captures[2].split(", ").collect();
+        match &captures[1] {
+            "putchar" => written[stream(args[0])].push(int(args[1]) as u8),
+            "put_float" => {
+                let f = args[1].trim_start_matches("f64:").parse().unwrap();
+                let repr = int(args[2]) == 1;
+                written[stream(args[0])].extend(format_float(f, repr).bytes());
+            }
+            "raise" => {
+                // the program traps right after raising.
+                let exception =
+                    Exception::from_code(int(args[0]) as u32).expect("unknown exception code");
+                let mut output = output(&written);
+                output.push(format!("{:?}: line {}", exception, int(args[1])));
+                return (output, true);
+            }
+            name => panic!("unexpected host call: {}", name),
+        }
+    }
+    (output(&written), false)
+}
+
+// wasm-interp shows i32s as unsigned.
+fn int(arg: &str) -> i32 {
+    arg.trim_start_matches("i32:").parse::<u32>().unwrap() as i32
+}
+
+fn stream(arg: &str) -> usize {
+    int(arg) as usize - 1
+}
+
+fn output(written: &[Vec<u8>; 2]) -> Vec<String> {
+    let mut output = lines(&written[0]);
+    for line in lines(&written[1]) {
+        output.push(format!("stderr: {}", line));
+    }
+    output
+}
+
+fn lines(written: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(written);
+    let mut lines: Vec<String> = text.split('\n').map(str::to_owned).collect();
+    if lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines
+}
+
+// what a host has to write for put_float. python 2.5 shows floats with 12
+// significant digits, or 17 in their repr, in the shortest of the fixed and
+// exponent forms, and adds ".0" to anything that would look like an int.
+pub fn format_float(f: f64, repr: bool) -> String {
+    if f.is_nan() {
+        return "nan".to_owned();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "inf" } else { "-inf" }.to_owned();
+    }
+    let precision: i32 = if repr { 17 } else { 12 };
+    let scientific = format!("{:.*e}", precision as usize - 1, f);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    let trim = |digits: &str| {
+        if digits.contains('.') {
+            digits
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_owned()
+        } else {
+            digits.to_owned()
+        }
+    };
+    let mut text = if exponent < -4 || exponent >= precision {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim(mantissa), sign, exponent.abs())
+    } else {
+        trim(&format!("{:.*}", (precision - 1 - exponent) as usize, f))
+    };
+    if text
+        .trim_start_matches('-')
+        .bytes()
+        .all(|b| b.is_ascii_digit())
+    {
+        text.push_str(".0");
+    }
+    text
 }
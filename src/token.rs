@@ -6,16 +6,38 @@ pub enum TokenKind {
     Else,
     Print,
     Def,
+    Class,
     Return,
+    Global,
+    Import,
+    Del,
+    Pass,
+    Assert,
+    Try,
+    Except,
+    Finally,
+    Raise,
+    In,
+    Not,
+    Is,
+    Str,
     Integer,
+    Float,
     Newline,
     Indent,
     Dedent,
     Eof,
     ParenL,
     ParenR,
+    BracketL,
+    BracketR,
+    BraceL,
+    BraceR,
+    Dot,
     Colon,
+    Semicolon,
     Comma,
+    Equals,
     EqEq,
     Ne,
     Lt,
@@ -25,14 +47,33 @@ pub enum TokenKind {
     Plus,
     Minus,
     Mult,
+    Power,
     Div,
     Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Invert,
+    LShift,
+    RShift,
     And,
     Or,
+    PlusEquals,
+    MinusEquals,
+    MultEquals,
+    PowerEquals,
+    DivEquals,
+    ModEquals,
+    BitAndEquals,
+    BitOrEquals,
+    BitXorEquals,
+    LShiftEquals,
+    RShiftEquals,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub lexeme: String,
+    pub line: usize,
 }
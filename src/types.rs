@@ -0,0 +1,866 @@
+use binding::{self, Binding};
+use program::*;
+use scope::SymbolTable;
+use std::collections::HashMap;
+use std::fmt;
+
+// what is known about a value before the program runs. values of a known
+// type are kept unboxed, as raw wasm numbers, and everything else is boxed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Type {
+    Unknown, // nothing has been inferred yet
+    Int,
+    Float,
+    Bool,
+    Str,     // boxed like any object, but known to be a str
+    Dynamic, // any kind of value, only known at runtime
+}
+
+impl Type {
+    // the type of a value that can come from either side.
+    pub fn join(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Unknown, other) => other,
+            (this, Type::Unknown) => this,
+            (this, other) if this == other => this,
+            _ => Type::Dynamic,
+        }
+    }
+
+    // an int that doesn't fit in 32 bits becomes a long, which has to be
+    // boxed, so an int result that might overflow is only known at runtime.
+    pub fn promoted(self) -> Type {
+        match self {
+            Type::Int => Type::Dynamic,
+            t => t,
+        }
+    }
+
+    // the bitwise operators only work on ints and bools, and give a bool if
+    // both operands are bools. anything else is checked at runtime.
+    pub fn bitwise(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Dynamic, _)
+            | (_, Type::Dynamic)
+            | (Type::Float, _)
+            | (_, Type::Float)
+            | (Type::Str, _)
+            | (_, Type::Str) => Type::Dynamic,
+            (Type::Unknown, _) | (_, Type::Unknown) => Type::Unknown,
+            (Type::Bool, Type::Bool) => Type::Bool,
+            _ => Type::Int,
+        }
+    }
+
+    // the wasm type that values of this type are kept in.
+    pub fn wasm(self) -> &'static str {
+        match self {
+            Type::Int | Type::Bool => "i32",
+            Type::Float => "f64",
+            Type::Unknown | Type::Str | Type::Dynamic => "i64",
+        }
+    }
+
+    // whether values of this type are kept boxed, where the collector can
+    // see them.
+    pub fn boxed(self) -> bool {
+        matches!(self, Type::Str | Type::Dynamic)
+    }
+
+    // bools count as integers in arithmetic. what it does to a str depends
+    // on the other operand, so it is only known at runtime.
+    pub fn arithmetic(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Dynamic, _) | (_, Type::Dynamic) | (Type::Str, _) | (_, Type::Str) => {
+                Type::Dynamic
+            }
+            (Type::Unknown, _) | (_, Type::Unknown) => Type::Unknown,
+            (Type::Float, _) | (_, Type::Float) => Type::Float,
+            _ => Type::Int,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::Unknown => "unknown",
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Bool => "bool",
+            Type::Str => "str",
+            Type::Dynamic => "object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// the inferred types of the variables of one function, or of the module,
+// and of what the function returns.
+#[derive(Debug, PartialEq)]
+pub struct TypeTable {
+    // in the same order as the symbol table's variables
+    variables: Vec<(String, Type)>,
+    result: Type,
+    // the type of each value, for a function that returns several of them
+    // as separate wasm results
+    results: Vec<Type>,
+    // tables for the top-level functions, in the order they are defined
+    children: Vec<(String, TypeTable)>,
+}
+
+impl TypeTable {
+    fn new(symbols: &SymbolTable) -> TypeTable {
+        TypeTable {
+            variables: symbols
+                .variables()
+                .iter()
+                .map(|name| (name.clone(), Type::Unknown))
+                .collect(),
+            result: Type::Unknown,
+            results: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn variable(&self, name: &str) -> Option<Type> {
+        self.variables
+            .iter()
+            .find(|(variable, _)| variable == name)
+            .map(|(_, t)| *t)
+    }
+
+    pub fn result(&self) -> Type {
+        self.result
+    }
+
+    pub fn results(&self) -> &[Type] {
+        &self.results
+    }
+
+    pub fn child(&self, name: &str) -> Option<&TypeTable> {
+        self.children
+            .iter()
+            .find(|(child, _)| child == name)
+            .map(|(_, table)| table)
+    }
+
+    fn child_mut(&mut self, name: &str) -> &mut TypeTable {
+        self.children
+            .iter_mut()
+            .find(|(child, _)| child == name)
+            .map(|(_, table)| table)
+            .unwrap()
+    }
+
+    // the type of `name` where it is used in `function`, or in the module
+    // itself if that is None.
+    pub fn lookup(&self, function: Option<&str>, name: &str) -> Type {
+        let local = function.and_then(|function| self.child(function)?.variable(name));
+        match local.or_else(|| self.variable(name)) {
+            Some(t) => t,
+            None if name == "True" || name == "False" => Type::Bool,
+            None => Type::Dynamic,
+        }
+    }
+
+    pub fn expression(&self, function: Option<&str>, expr: &Expression) -> Type {
+        match expr {
+            Expression::EqEq(..)
+            | Expression::Ne(..)
+            | Expression::Lt(..)
+            | Expression::Gt(..)
+            | Expression::Leq(..)
+            | Expression::Geq(..)
+            | Expression::In(..)
+            | Expression::NotIn(..)
+            | Expression::Is(..)
+            | Expression::IsNot(..)
+            | Expression::Chain(..) => Type::Bool,
+            Expression::Add(l, r)
+            | Expression::Sub(l, r)
+            | Expression::Mult(l, r)
+            | Expression::Div(l, r)
+            | Expression::Power(l, r) => self
+                .expression(function, l)
+                .arithmetic(self.expression(function, r))
+                .promoted(),
+            // a remainder is never bigger than what it is a remainder of.
+            Expression::Mod(l, r) => self
+                .expression(function, l)
+                .arithmetic(self.expression(function, r)),
+            // a negated literal always fits.
+            Expression::Neg(e) => match **e {
+                Expression::Simple(Value::Integer(_)) => Type::Int,
+                _ => self
+                    .expression(function, e)
+                    .arithmetic(Type::Int)
+                    .promoted(),
+            },
+            Expression::BitAnd(l, r) | Expression::BitOr(l, r) | Expression::BitXor(l, r) => self
+                .expression(function, l)
+                .bitwise(self.expression(function, r)),
+            // shifting a bool gives an int.
+            Expression::LShift(l, r) => self
+                .expression(function, l)
+                .bitwise(self.expression(function, r))
+                .arithmetic(Type::Int)
+                .promoted(),
+            Expression::RShift(l, r) => self
+                .expression(function, l)
+                .bitwise(self.expression(function, r))
+                .arithmetic(Type::Int),
+            Expression::Invert(e) => self.expression(function, e).bitwise(Type::Int),
+            // `and` and `or` evaluate to one of their operands, and so does a
+            // conditional expression.
+            Expression::And(l, r) | Expression::Or(l, r) | Expression::Conditional(_, l, r) => self
+                .expression(function, l)
+                .join(self.expression(function, r)),
+            Expression::Call { name, .. } => match self.child(name) {
+                Some(table) => table.result,
+                None if name == "len" => Type::Int,
+                None if name == "isinstance" => Type::Bool,
+                None => Type::Dynamic,
+            },
+            Expression::MethodCall { .. }
+            | Expression::Attribute(..)
+            | Expression::List(_)
+            | Expression::Tuple(_)
+            | Expression::Dict(_)
+            | Expression::Subscript(..)
+            | Expression::Slice(..) => Type::Dynamic,
+            Expression::Simple(Value::Integer(_)) => Type::Int,
+            Expression::Simple(Value::Float(_)) => Type::Float,
+            Expression::Simple(Value::Long(_)) => Type::Dynamic,
+            Expression::Simple(Value::String(_)) => Type::Str,
+            Expression::Simple(Value::Variable(name, _)) => self.lookup(function, name),
+            Expression::Simple(Value::Complex(e)) => self.expression(function, e),
+        }
+    }
+
+    // the types of the values that unpacking `value` into `n` targets gives.
+    // a tuple display, or a call to a function with that many results, is
+    // unpacked without making a tuple, so each value keeps its own type.
+    pub fn unpacked(&self, function: Option<&str>, value: &Expression, n: usize) -> Vec<Type> {
+        match value {
+            Expression::Tuple(items) if items.len() == n => items
+                .iter()
+                .map(|item| self.expression(function, item))
+                .collect(),
+            Expression::Call { name, .. }
+                if self
+                    .child(name)
+                    .is_some_and(|table| table.results.len() == n) =>
+            {
+                self.child(name).unwrap().results.clone()
+            }
+            _ => vec![Type::Dynamic; n],
+        }
+    }
+
+    // the expressions `stmt` evaluates when it runs in `function`. a tuple
+    // that is returned as several results, or unpacked as soon as it is
+    // made, is never made, so only its items are evaluated.
+    pub fn evaluated<'b>(
+        &self,
+        function: Option<&str>,
+        stmt: &'b Statement,
+    ) -> Vec<&'b Expression> {
+        let results = function
+            .and_then(|function| self.child(function))
+            .map_or(0, |table| table.results.len());
+        match stmt {
+            Statement::Return(Expression::Tuple(items)) if results > 0 => items.iter().collect(),
+            Statement::Unpack(targets, Expression::Tuple(items))
+                if items.len() == targets.len() =>
+            {
+                let mut expressions: Vec<&Expression> = items.iter().collect();
+                for target in targets.iter() {
+                    expressions.extend(target.expressions());
+                }
+                expressions
+            }
+            _ => stmt.expressions(),
+        }
+    }
+
+    // whether any expression in `body`, run in `function`, has a value that
+    // could be anything.
+    pub fn body_dynamic(&self, function: Option<&str>, body: &Body) -> bool {
+        body.statements.iter().any(|stmt| {
+            self.evaluated(function, stmt)
+                .iter()
+                .any(|e| self.expression_dynamic(function, e))
+                || stmt
+                    .bodies()
+                    .iter()
+                    .any(|body| self.body_dynamic(function, body))
+        })
+    }
+
+    fn expression_dynamic(&self, function: Option<&str>, expr: &Expression) -> bool {
+        self.expression(function, expr).boxed()
+            || expr
+                .operands()
+                .iter()
+                .any(|operand| self.expression_dynamic(function, operand))
+    }
+
+    // widens the type of `name`, returning whether it changed.
+    fn refine(&mut self, name: &str, t: Type) -> bool {
+        match self
+            .variables
+            .iter_mut()
+            .find(|(variable, _)| variable == name)
+        {
+            Some((_, old)) if old.join(t) != *old => {
+                *old = old.join(t);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn refine_result(&mut self, t: Type) -> bool {
+        let result = self.result.join(t);
+        let changed = result != self.result;
+        self.result = result;
+        changed
+    }
+
+    fn refine_results(&mut self, types: &[Type]) -> bool {
+        let mut changed = false;
+        for (result, t) in self.results.iter_mut().zip(types.iter()) {
+            changed |= result.join(*t) != *result;
+            *result = result.join(*t);
+        }
+        changed
+    }
+
+    // whatever is still unknown can be anything.
+    fn finish(&mut self) {
+        for (_, t) in self.variables.iter_mut() {
+            if *t == Type::Unknown {
+                *t = Type::Dynamic;
+            }
+        }
+        for t in Some(&mut self.result)
+            .into_iter()
+            .chain(self.results.iter_mut())
+        {
+            if *t == Type::Unknown {
+                *t = Type::Dynamic;
+            }
+        }
+        for (_, table) in self.children.iter_mut() {
+            table.finish();
+        }
+    }
+}
+
+impl fmt::Display for TypeTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, t) in self.variables.iter() {
+            writeln!(f, "{}: {}", name, t)?;
+        }
+        for (name, table) in self.children.iter() {
+            if table.results.is_empty() {
+                writeln!(f, "{} -> {}", name, table.result)?;
+            } else {
+                let results: Vec<String> = table.results.iter().map(Type::to_string).collect();
+                writeln!(f, "{} -> ({})", name, results.join(", "))?;
+            }
+            for (name, t) in table.variables.iter() {
+                writeln!(f, "  {}: {}", name, t)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// works out the types of the module's variables and of the variables,
+// parameters and results of its top-level functions. every assignment,
+// argument and return widens a type, and the program is walked until
+// nothing changes. a variable that is given values of different types, or
+// of types that aren't tracked, is dynamic.
+pub fn infer(program: &Program, symbols: &SymbolTable) -> TypeTable {
+    let mut table = TypeTable::new(symbols);
+    let mut defs = HashMap::new();
+    let functions = program.body.functions();
+    for function in functions.iter() {
+        let mut child = TypeTable::new(symbols.child(&function.name).unwrap());
+        // a method is called on whatever its caller's object turns out to
+        // be, so nothing is known about what it is given or gives back.
+        if function.method {
+            for param in function.params.iter() {
+                child.refine(param.name(), Type::Dynamic);
+            }
+            child.refine_result(Type::Dynamic);
+            table.children.push((function.name.clone(), child));
+            continue;
+        }
+        if let Some(n) = returned_arity(function.body) {
+            child.results = vec![Type::Unknown; n];
+        }
+        // surplus arguments are packed in a tuple and a dict.
+        for param in function.params.iter() {
+            if let Parameter::Star(param) | Parameter::DoubleStar(param) = param {
+                child.refine(param, Type::Dynamic);
+            }
+        }
+        table.children.push((function.name.clone(), child));
+        defs.insert(function.name.as_str(), function.params);
+    }
+    let mut inference = Inference {
+        table,
+        defs,
+        function: None,
+        changed: true,
+    };
+    while inference.changed {
+        inference.changed = false;
+        inference.function = None;
+        inference.visit_body(&program.body);
+        for function in functions.iter() {
+            inference.function = Some(&function.name);
+            inference.visit_body(function.body);
+            if can_fall_off(function.body) {
+                inference.changed |= inference
+                    .table
+                    .child_mut(&function.name)
+                    .refine_result(Type::Dynamic);
+            }
+        }
+    }
+    inference.table.finish();
+    inference.table
+}
+
+// whether running `body` can reach its end without returning.
+pub fn can_fall_off(body: &Body) -> bool {
+    match body.statements.last() {
+        Some(Statement::Return(_)) | Some(Statement::Raise(_)) => false,
+        // the end of a try statement is reached through its body and else
+        // clause, or through any handler, and then only if its finally
+        // clause can fall off too.
+        Some(Statement::Try {
+            body,
+            handlers,
+            else_body,
+            finally,
+        }) => {
+            let through = can_fall_off(body) && else_body.as_ref().is_none_or(can_fall_off);
+            (through || handlers.iter().any(|handler| can_fall_off(&handler.body)))
+                && finally.as_ref().is_none_or(can_fall_off)
+        }
+        Some(Statement::If {
+            body,
+            elif,
+            else_body: Some(else_body),
+            ..
+        }) => {
+            can_fall_off(body)
+                || elif.iter().any(|(_, body)| can_fall_off(body))
+                || can_fall_off(else_body)
+        }
+        _ => true,
+    }
+}
+
+// how many values every return in `body` gives, if each of them returns a
+// tuple display of the same length, and that is at least 2. such a function
+// gives its values back as separate wasm results instead of in a tuple.
+pub fn returned_arity(body: &Body) -> Option<usize> {
+    fn collect<'a>(body: &'a Body, returns: &mut Vec<&'a Expression>) {
+        for stmt in body.statements.iter() {
+            if let Statement::Return(e) = stmt {
+                returns.push(e);
+            }
+            for body in stmt.bodies() {
+                collect(body, returns);
+            }
+        }
+    }
+    if can_fall_off(body) {
+        return None;
+    }
+    let mut returns = vec![];
+    collect(body, &mut returns);
+    let arity = match returns.first() {
+        Some(Expression::Tuple(items)) if items.len() >= 2 => items.len(),
+        _ => return None,
+    };
+    let same = returns
+        .iter()
+        .all(|e| matches!(e, Expression::Tuple(items) if items.len() == arity));
+    if same {
+        Some(arity)
+    } else {
+        None
+    }
+}
+
+struct Inference<'a> {
+    table: TypeTable,
+    // parameters of each top-level function, by name
+    defs: HashMap<&'a str, &'a [Parameter]>,
+    // the function being walked, or None for the module itself
+    function: Option<&'a str>,
+    changed: bool,
+}
+
+impl<'a> Inference<'a> {
+    fn visit_body(&mut self, body: &'a Body) {
+        for stmt in body.statements.iter() {
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &'a Statement) {
+        match stmt {
+            Statement::Expression(e) | Statement::Del(e) => self.visit_expression(e),
+            Statement::Print { items, .. } => {
+                for item in items.iter() {
+                    self.visit_expression(item);
+                }
+            }
+            Statement::SetItem {
+                object,
+                index,
+                value,
+            } => {
+                self.visit_expression(value);
+                self.visit_expression(object);
+                self.visit_expression(index);
+            }
+            Statement::SetAttr { object, value, .. } => {
+                self.visit_expression(value);
+                self.visit_expression(object);
+            }
+            Statement::Return(e) => {
+                self.visit_expression(e);
+                let t = self.table.expression(self.function, e);
+                if let Some(function) = self.function {
+                    self.changed |= self.table.child_mut(function).refine_result(t);
+                    if let Expression::Tuple(items) = e {
+                        let types: Vec<Type> = items
+                            .iter()
+                            .map(|item| self.table.expression(self.function, item))
+                            .collect();
+                        self.changed |= self.table.child_mut(function).refine_results(&types);
+                    }
+                }
+            }
+            Statement::Unpack(targets, value) => {
+                for e in stmt.expressions() {
+                    self.visit_expression(e);
+                }
+                let types = self.table.unpacked(self.function, value, targets.len());
+                for (target, t) in targets.iter().zip(types) {
+                    self.assign_target(target, t);
+                }
+            }
+            Statement::Assign(name, e) => {
+                self.visit_expression(e);
+                let t = self.table.expression(self.function, e);
+                self.assign(name, t);
+            }
+            Statement::AugAssign(e) => {
+                self.visit_expression(e);
+                if let Some(name) = stmt.augmented_name() {
+                    let t = self.table.expression(self.function, e);
+                    self.assign(name, t);
+                }
+            }
+            Statement::Assert(..) | Statement::Raise(_) => {
+                for e in stmt.expressions() {
+                    self.visit_expression(e);
+                }
+            }
+            // a handler's name can be given any exception.
+            Statement::Try { handlers, .. } => {
+                for e in stmt.expressions() {
+                    self.visit_expression(e);
+                }
+                for name in handlers.iter().filter_map(|handler| handler.name.as_ref()) {
+                    self.assign(name, Type::Dynamic);
+                }
+                for body in stmt.bodies() {
+                    self.visit_body(body);
+                }
+            }
+            Statement::Global(_) | Statement::Import(_) | Statement::Pass => (),
+            Statement::If {
+                condition,
+                body,
+                elif,
+                else_body,
+            } => {
+                self.visit_expression(condition);
+                self.visit_body(body);
+                for (condition, body) in elif.iter() {
+                    self.visit_expression(condition);
+                    self.visit_body(body);
+                }
+                if let Some(body) = else_body {
+                    self.visit_body(body);
+                }
+            }
+            Statement::Def { name, params, .. } => {
+                for param in params.iter() {
+                    if let Parameter::Default(param, default) = param {
+                        self.visit_expression(default);
+                        let t = self.table.expression(self.function, default);
+                        if self.defs.contains_key(name.as_str()) {
+                            self.changed |= self.table.child_mut(name).refine(param, t);
+                        }
+                    }
+                }
+            }
+            // what the class's body assigns are attributes of the class,
+            // not variables. once it has, reading one gets whatever the
+            // class holds, so a module variable of the same name is read
+            // boxed too.
+            Statement::Class { name, base, body } => {
+                if let Some(base) = base {
+                    self.visit_expression(base);
+                }
+                let mut attributes: Vec<&str> = vec![];
+                for stmt in body.statements.iter() {
+                    for e in stmt.expressions() {
+                        self.box_attributes(e, &attributes);
+                    }
+                    if let Statement::Assign(attribute, _) = stmt {
+                        attributes.push(attribute);
+                    }
+                    match stmt {
+                        Statement::Def { params, .. } => {
+                            for param in params.iter() {
+                                if let Parameter::Default(_, default) = param {
+                                    self.visit_expression(default);
+                                }
+                            }
+                        }
+                        _ => {
+                            for e in stmt.expressions() {
+                                self.visit_expression(e);
+                            }
+                        }
+                    }
+                }
+                self.assign(name, Type::Dynamic);
+            }
+        }
+    }
+
+    // gives `t` to the names in `target`. the values a nested target
+    // unpacks could be anything.
+    fn assign_target(&mut self, target: &Target, t: Type) {
+        match target {
+            Target::Name(name) => self.assign(name, t),
+            Target::Item(..) | Target::Attribute(..) => (),
+            Target::Tuple(targets) => {
+                for target in targets.iter() {
+                    self.assign_target(target, Type::Dynamic);
+                }
+            }
+        }
+    }
+
+    // gives `t` to the variable `name` in the current function, which is
+    // either one of its locals or a module variable.
+    fn assign(&mut self, name: &str, t: Type) {
+        let table = &mut self.table;
+        let local = self
+            .function
+            .filter(|function| table.child(function).unwrap().variable(name).is_some());
+        let table = match local {
+            Some(function) => table.child_mut(function),
+            None => table,
+        };
+        self.changed |= table.refine(name, t);
+    }
+
+    fn box_attributes(&mut self, expr: &Expression, attributes: &[&str]) {
+        if let Expression::Simple(Value::Variable(name, _)) = expr {
+            if attributes.contains(&name.as_str()) {
+                self.changed |= self.table.refine(name, Type::Dynamic);
+            }
+        }
+        for operand in expr.operands() {
+            self.box_attributes(operand, attributes);
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &'a Expression) {
+        for operand in expr.operands() {
+            self.visit_expression(operand);
+        }
+        if let Expression::Call { name, params, line } = expr {
+            let def_params = match self.defs.get(name.as_str()) {
+                Some(def_params) => *def_params,
+                None => return,
+            };
+            // the checker has already reported calls that don't bind.
+            let bindings = match binding::bind(name, def_params, params, *line) {
+                Ok(bindings) => bindings,
+                Err(_) => return,
+            };
+            for (param, binding) in def_params.iter().zip(bindings.iter()) {
+                if let Binding::Argument(i) = binding {
+                    let t = self
+                        .table
+                        .expression(self.function, params[*i].expression());
+                    self.changed |= self.table.child_mut(name).refine(param.name(), t);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scope;
+    use testing::*;
+
+    macro_rules! types_test {
+        (name: $name:ident,text: $text:expr,types: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let program = parse($text).unwrap();
+                let symbols = scope::analyze(&program).unwrap();
+                let types = infer(&program, &symbols);
+                assert_eq!(types.to_string(), $expected);
+            }
+        };
+    }
+
+    types_test! {
+        name: constants,
+        text: "a = 1\nb = 1.5\nc = True\nd = None",
+        types: "a: int\nb: float\nc: bool\nd: object\n",
+    }
+
+    types_test! {
+        name: strings,
+        text: "a = 'a'\nb = a + 'b'\nc = 'c' if a else 1\ndef f(s):\n  return s\nx = f('x')",
+        types: "a: str\nb: object\nc: object\nx: str\nf -> str\n  s: str\n",
+    }
+
+    types_test! {
+        name: arithmetic,
+        text: "a = 1 + 2\nb = 2 * 1.5\nc = True + True\nd = a < b",
+        types: "a: object\nb: float\nc: object\nd: bool\n",
+    }
+
+    types_test! {
+        name: mixed_assignments,
+        text: "a = 1\na = 2\nb = 1\nb = 1.5\nc = b + 1",
+        types: "a: int\nb: object\nc: object\n",
+    }
+
+    types_test! {
+        name: and_or,
+        text: "a = 1 and 2\nb = 1 or True",
+        types: "a: int\nb: object\n",
+    }
+
+    types_test! {
+        name: parameters_from_calls,
+        text: "def f(a, b):\n  return a + b\nx = f(1, 2)\ny = f(3, 4)",
+        types: "x: object\ny: object\nf -> object\n  a: int\n  b: int\n",
+    }
+
+    types_test! {
+        name: parameters_from_keywords_and_defaults,
+        text: "def f(a, b=2.5):\n  return a\nx = f(b=1.5, a=True)",
+        types: "x: bool\nf -> bool\n  a: bool\n  b: float\n",
+    }
+
+    types_test! {
+        name: uncalled_function,
+        text: "def f(a):\n  b = a\n  return 1",
+        types: "f -> int\n  a: object\n  b: object\n",
+    }
+
+    types_test! {
+        name: recursion,
+        text: "def fib(n):\n  if n < 2:\n    return n\n  else:\n    return fib(n - 1) + fib(n - 2)\
+               \nprint fib(10)",
+        types: "fib -> object\n  n: object\n",
+    }
+
+    types_test! {
+        name: falling_off,
+        text: "def f(n):\n  if n:\n    return 1\nprint f(1)",
+        types: "f -> object\n  n: int\n",
+    }
+
+    types_test! {
+        name: global_assignment,
+        text: "x = 1\ndef f():\n  global x\n  x = 1.5\n  return x\ny = f()",
+        types: "x: object\ny: object\nf -> object\n",
+    }
+
+    types_test! {
+        name: lists,
+        text: "xs = [1]\nn = len(xs)\nx = xs[0]",
+        types: "xs: object\nn: int\nx: object\n",
+    }
+
+    types_test! {
+        name: multiple_results,
+        text: "def f(a):\n  if a:\n    return a, 1.5\n  return 0, a\nx, y = f(1)\nt = f(2)\na, b = 1, x",
+        types: "x: int\ny: object\nt: object\na: int\nb: int\nf -> (int, object)\n  a: int\n",
+    }
+
+    types_test! {
+        name: unpacking_lists,
+        text: "a, (b, c) = [1, [2, 3]]\nd, e = 1, 2.5",
+        types: "a: object\nb: object\nc: object\nd: int\ne: float\n",
+    }
+
+    types_test! {
+        name: bitwise_operators,
+        text: "a = True & False\nb = True << 1\nc = ~True\nd = 1 | a\ne = 1.5 ^ 2",
+        types: "a: bool\nb: object\nc: int\nd: int\ne: object\n",
+    }
+
+    types_test! {
+        name: power,
+        text: "a = 2 ** 3\nb = 2 ** -1\nc = 2 ** a\nd = 1.5 ** 2",
+        types: "a: object\nb: object\nc: object\nd: float\n",
+    }
+
+    types_test! {
+        name: promotion,
+        text: "a = 7 % 2\nb = -1\nc = a >> 1\nd = 5L\ne = -a\nf = a << 1",
+        types: "a: int\nb: int\nc: int\nd: object\ne: object\nf: object\n",
+    }
+
+    types_test! {
+        name: augmented_assignment,
+        text: "a = 1\na += 1.5\nb = 1.5\nb *= 2\nc = 7\nc %= 2\nd = [1]\nd[0] += 1",
+        types: "a: object\nb: float\nc: int\nd: object\n",
+    }
+
+    types_test! {
+        name: conditional_expression,
+        text: "a = 1 if 0 else 2\nb = 1 if a else 2.5\nc = 1.5 if a > 1 else 'c'",
+        types: "a: int\nb: object\nc: object\n",
+    }
+
+    types_test! {
+        name: exceptions,
+        text: "def f(a):\n  try:\n    return a\n  except KeyError, e:\n    raise\nx = f(1)",
+        types: "x: int\nf -> int\n  a: int\n  e: object\n",
+    }
+
+    types_test! {
+        name: classes,
+        text: "class A:\n  n = 1\n  def f(self, x):\n    y = 2\n    return x\n\
+               a = A()\nb = a.f(1)\nc = isinstance(a, A)",
+        types: "A: object\na: object\nb: object\nc: bool\nA.f -> object\n  self: object\n  x: object\n  y: int\n",
+    }
+}
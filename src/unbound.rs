@@ -0,0 +1,411 @@
+use program::*;
+use scope::{Scope, SymbolTable};
+use std::collections::{HashMap, HashSet};
+
+// the variables that can be read before they are assigned. they get a flag
+// that records whether they are, so that reading them raises the error
+// python does instead of giving whatever wasm starts them out as.
+#[derive(Debug, Default)]
+pub struct Unbound {
+    globals: HashSet<String>,
+    // the locals of each function that can be
+    locals: HashMap<String, HashSet<String>>,
+}
+
+impl Unbound {
+    pub fn global(&self, name: &str) -> bool {
+        self.globals.contains(name)
+    }
+
+    pub fn globals(&self) -> Vec<&String> {
+        let mut globals: Vec<&String> = self.globals.iter().collect();
+        globals.sort();
+        globals
+    }
+
+    pub fn local(&self, function: &str, name: &str) -> bool {
+        self.locals
+            .get(function)
+            .is_some_and(|locals| locals.contains(name))
+    }
+
+    pub fn locals(&self, function: &str) -> Vec<&String> {
+        let mut locals: Vec<&String> = self
+            .locals
+            .get(function)
+            .map_or(vec![], |locals| locals.iter().collect());
+        locals.sort();
+        locals
+    }
+}
+
+pub fn analyze(program: &Program, symbols: &SymbolTable) -> Unbound {
+    let mut module = Flow::new(symbols, symbols);
+    // no function can run before the first top-level statement that calls
+    // something, so what is assigned by then always is in functions.
+    let mut assigned = Some(HashSet::new());
+    let mut before_calls = None;
+    for stmt in program.body.statements.iter() {
+        if before_calls.is_none() && calls(stmt) {
+            before_calls = assigned.clone();
+        }
+        assigned = module.statement(stmt, assigned);
+    }
+    let before_calls = before_calls.or(assigned).unwrap_or_default();
+
+    // a function can delete a global at any time.
+    let mut unbound = Unbound {
+        globals: deleted(&program.body),
+        locals: HashMap::new(),
+    };
+    unbound.globals.extend(module.globals);
+    for function in program.body.functions() {
+        let table = symbols.child(&function.name).unwrap();
+        let mut flow = Flow::new(table, symbols);
+        let mut assigned: HashSet<String> = before_calls
+            .iter()
+            .filter(|name| table.scope(name) != Some(Scope::Local))
+            .cloned()
+            .collect();
+        assigned.extend(function.params.iter().map(|param| param.name().to_owned()));
+        flow.body(function.body, Some(assigned));
+        unbound.globals.extend(flow.globals);
+        unbound.globals.extend(
+            deleted(function.body)
+                .into_iter()
+                .filter(|name| table.scope(name) == Some(Scope::Global)),
+        );
+        unbound.locals.insert(function.name, flow.locals);
+    }
+    unbound
+}
+
+// whether running `stmt` can call a function.
+fn calls(stmt: &Statement) -> bool {
+    fn any_call(expr: &Expression) -> bool {
+        matches!(
+            expr,
+            Expression::Call { .. } | Expression::MethodCall { .. }
+        ) || expr.operands().into_iter().any(any_call)
+    }
+    stmt.expressions().into_iter().any(any_call)
+        || stmt
+            .bodies()
+            .iter()
+            .any(|body| body.statements.iter().any(calls))
+}
+
+// the names that `body` deletes, in any statement that runs with it.
+fn deleted(body: &Body) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in body.statements.iter() {
+        if let Statement::Del(Expression::Simple(Value::Variable(name, _))) = stmt {
+            names.insert(name.clone());
+        }
+        for body in stmt.bodies() {
+            names.extend(deleted(body));
+        }
+    }
+    names
+}
+
+// the variables that are sure to have been assigned at some point of a
+// body, or None where it can't be reached.
+type Assigned = Option<HashSet<String>>;
+
+// what is sure to be assigned where either of two paths join.
+fn join(a: Assigned, b: Assigned) -> Assigned {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.intersection(&b).cloned().collect()),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+// follows the paths through the module or one function, noting the
+// variables it reads where they might not be assigned.
+struct Flow<'a> {
+    table: &'a SymbolTable,
+    module: &'a SymbolTable,
+    globals: HashSet<String>,
+    locals: HashSet<String>,
+}
+
+impl<'a> Flow<'a> {
+    fn new(table: &'a SymbolTable, module: &'a SymbolTable) -> Flow<'a> {
+        Flow {
+            table,
+            module,
+            globals: HashSet::new(),
+            locals: HashSet::new(),
+        }
+    }
+
+    fn body(&mut self, body: &Body, mut assigned: Assigned) -> Assigned {
+        for stmt in body.statements.iter() {
+            assigned = self.statement(stmt, assigned);
+        }
+        assigned
+    }
+
+    fn statement(&mut self, stmt: &Statement, assigned: Assigned) -> Assigned {
+        let mut assigned = assigned?;
+        match stmt {
+            Statement::If {
+                condition,
+                body,
+                elif,
+                else_body,
+            } => {
+                self.expression(condition, &assigned);
+                let mut after = self.body(body, Some(assigned.clone()));
+                for (condition, body) in elif.iter() {
+                    self.expression(condition, &assigned);
+                    after = join(after, self.body(body, Some(assigned.clone())));
+                }
+                let last = match else_body {
+                    Some(body) => self.body(body, Some(assigned)),
+                    None => Some(assigned),
+                };
+                join(after, last)
+            }
+            // the body can raise before any of its assignments, or after
+            // any of the dels in the statement, and the finally clause also
+            // runs after a return, or an exception that nothing catches.
+            Statement::Try {
+                body,
+                handlers,
+                else_body,
+                finally,
+            } => {
+                let mut before = assigned.clone();
+                for body in stmt.bodies() {
+                    for name in deleted(body) {
+                        before.remove(&name);
+                    }
+                }
+                let mut after = self.body(body, Some(assigned));
+                if let Some(body) = else_body {
+                    after = self.body(body, after);
+                }
+                for handler in handlers.iter() {
+                    let mut assigned = before.clone();
+                    if let Some(exception) = &handler.exception {
+                        self.expression(exception, &assigned);
+                    }
+                    if let Some(name) = &handler.name {
+                        assigned.insert(name.clone());
+                    }
+                    after = join(after, self.body(&handler.body, Some(assigned)));
+                }
+                match finally {
+                    Some(finally) => {
+                        self.body(finally, Some(before));
+                        self.body(finally, after)
+                    }
+                    None => after,
+                }
+            }
+            Statement::Return(e) => {
+                self.expression(e, &assigned);
+                None
+            }
+            Statement::Raise(e) => {
+                if let Some(e) = e {
+                    self.expression(e, &assigned);
+                }
+                None
+            }
+            Statement::Assign(name, e) => {
+                self.expression(e, &assigned);
+                assigned.insert(name.clone());
+                Some(assigned)
+            }
+            Statement::Del(e) => {
+                self.expression(e, &assigned);
+                if let Expression::Simple(Value::Variable(name, _)) = e {
+                    assigned.remove(name);
+                }
+                Some(assigned)
+            }
+            Statement::AugAssign(e) => {
+                self.expression(e, &assigned);
+                if let Some(name) = stmt.augmented_name() {
+                    assigned.insert(name.to_owned());
+                }
+                Some(assigned)
+            }
+            Statement::Unpack(targets, _) => {
+                for e in stmt.expressions() {
+                    self.expression(e, &assigned);
+                }
+                for target in targets.iter() {
+                    Self::target(target, &mut assigned);
+                }
+                Some(assigned)
+            }
+            // what a class's body assigns are its attributes.
+            Statement::Class { name, body, .. } => {
+                for e in stmt.expressions() {
+                    self.expression(e, &assigned);
+                }
+                self.body(body, Some(assigned.clone()));
+                assigned.insert(name.clone());
+                Some(assigned)
+            }
+            _ => {
+                for e in stmt.expressions() {
+                    self.expression(e, &assigned);
+                }
+                Some(assigned)
+            }
+        }
+    }
+
+    fn target(target: &Target, assigned: &mut HashSet<String>) {
+        match target {
+            Target::Name(name) => {
+                assigned.insert(name.clone());
+            }
+            Target::Tuple(targets) => {
+                for target in targets.iter() {
+                    Self::target(target, assigned);
+                }
+            }
+            Target::Item(..) | Target::Attribute(..) => (),
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression, assigned: &HashSet<String>) {
+        if let Expression::Simple(Value::Variable(name, _)) | Expression::Call { name, .. } = expr {
+            if !assigned.contains(name) {
+                match self.table.scope(name) {
+                    Some(Scope::Local) => {
+                        self.locals.insert(name.clone());
+                    }
+                    // the name of a def is no variable.
+                    Some(Scope::Global) if self.module.variables().contains(name) => {
+                        self.globals.insert(name.clone());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for operand in expr.operands() {
+            self.expression(operand, assigned);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scope;
+    use testing::*;
+
+    macro_rules! unbound_test {
+        (name: $name:ident,text: $text:expr,function: $function:expr,locals: $expected:expr,) => {
+            #[test]
+            fn $name() {
+                let program = parse($text).unwrap();
+                let symbols = scope::analyze(&program).unwrap();
+                let unbound = analyze(&program, &symbols);
+                let expected: &[&str] = &$expected;
+                assert_eq!(unbound.locals($function), expected);
+            }
+        };
+    }
+
+    unbound_test! {
+        name: assigned_before_read,
+        text: "def f(a):\n  b = a\n  return a + b",
+        function: "f",
+        locals: [],
+    }
+
+    unbound_test! {
+        name: read_before_assigned,
+        text: "x = 1\ndef f():\n  print x\n  x = 2\n  return x",
+        function: "f",
+        locals: ["x"],
+    }
+
+    unbound_test! {
+        name: assigned_on_one_branch,
+        text: "def f(a):\n  if a:\n    b = 1\n    c = 1\n  else:\n    c = 2\n  return b + c",
+        function: "f",
+        locals: ["b"],
+    }
+
+    unbound_test! {
+        name: branch_that_returns,
+        text: "def f(a):\n  if a:\n    return 0\n  else:\n    b = 1\n  return b",
+        function: "f",
+        locals: [],
+    }
+
+    unbound_test! {
+        name: assigned_in_try,
+        text: "def f():\n  try:\n    a = 1\n    b = 2\n  except KeyError, e:\n    a = 3\n  print e\
+               \n  return a + b",
+        function: "f",
+        locals: ["b", "e"],
+    }
+
+    unbound_test! {
+        name: method,
+        text: "class A:\n  def m(self):\n    return n\n    n = 1",
+        function: "A.m",
+        locals: ["n"],
+    }
+
+    fn globals(text: &str) -> Vec<String> {
+        let program = parse(text).unwrap();
+        let symbols = scope::analyze(&program).unwrap();
+        let unbound = analyze(&program, &symbols);
+        unbound.globals().into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn globals_assigned_before_calls() {
+        assert_eq!(
+            globals("x = 1\ndef f():\n  return x + y\nprint f()\ny = 2"),
+            ["y"]
+        );
+    }
+
+    #[test]
+    fn global_assigned_on_one_branch() {
+        assert_eq!(globals("if 0:\n  y = 5\nprint y"), ["y"]);
+    }
+
+    #[test]
+    fn global_assigned_in_function() {
+        assert_eq!(
+            globals("def f():\n  global z\n  z = 1\n  return z\nprint f()\nprint z"),
+            ["z"]
+        );
+    }
+
+    unbound_test! {
+        name: deleted,
+        text: "def f(a, b):\n  del a\n  if b:\n    del b\n  return b",
+        function: "f",
+        locals: ["b"],
+    }
+
+    unbound_test! {
+        name: deleted_in_try,
+        text: "def f(a):\n  try:\n    del a\n    a = 1\n  except KeyError:\n    return a\n  return a",
+        function: "f",
+        locals: ["a"],
+    }
+
+    #[test]
+    fn global_deleted_in_function() {
+        assert_eq!(
+            globals("x = 1\ndef d():\n  global x\n  del x\n  return 0\nprint d()"),
+            ["x"]
+        );
+    }
+}
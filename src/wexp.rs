@@ -5,6 +5,64 @@ pub enum Wexp {
     Atom(String),
 }
 
+impl Wexp {
+    // reads wat text back into wexps, for the parts of a module that are
+    // written by hand. comments are dropped. panics on unbalanced parens,
+    // since the text is never user input.
+    pub fn parse(text: &str) -> Vec<Wexp> {
+        let mut stack: Vec<Vec<Wexp>> = vec![vec![]];
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' if chars.peek() == Some(&';') => {
+                    let mut previous = c;
+                    for c in chars.by_ref() {
+                        if previous == ';' && c == ')' {
+                            break;
+                        }
+                        previous = c;
+                    }
+                }
+                '(' => stack.push(vec![]),
+                ')' => {
+                    let list = stack.pop().expect("unbalanced parens");
+                    stack
+                        .last_mut()
+                        .expect("unbalanced parens")
+                        .push(Wexp::List(list));
+                }
+                ';' if chars.peek() == Some(&';') => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                c if c.is_whitespace() => (),
+                c => {
+                    let mut atom = c.to_string();
+                    let mut quoted = c == '"';
+                    while let Some(&c) = chars.peek() {
+                        if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                            break;
+                        }
+                        atom.push(c);
+                        chars.next();
+                        if quoted && c == '\\' {
+                            atom.extend(chars.next());
+                        } else if c == '"' {
+                            quoted = !quoted;
+                        }
+                    }
+                    stack.last_mut().unwrap().push(Wexp::Atom(atom));
+                }
+            }
+        }
+        assert_eq!(stack.len(), 1, "unbalanced parens");
+        stack.pop().unwrap()
+    }
+}
+
 impl Display for Wexp {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use self::Wexp::*;
@@ -48,4 +106,18 @@ mod test {
         ]);
         assert_eq!(w.to_string(), "(hmm (??!))");
     }
+
+    #[test]
+    fn parse() {
+        let text = "(func $f (param i32) ;; comment\n  get_local 0 (; block ;) drop)\n\
+                    (data \"a (\\\"b\\\")\")";
+        let wexps: Vec<String> = Wexp::parse(text).iter().map(Wexp::to_string).collect();
+        assert_eq!(
+            wexps,
+            [
+                "(func $f (param i32) get_local 0 drop)",
+                "(data \"a (\\\"b\\\")\")",
+            ]
+        );
+    }
 }